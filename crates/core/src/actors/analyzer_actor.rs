@@ -6,7 +6,6 @@ use chrono::Utc;
 use chrono::Utc as UtcTime;
 use serde::Deserialize;
 use serde::Serialize;
-use solana_client::rpc_client::RpcClient;
 use solana_program::pubkey::Pubkey;
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
@@ -22,31 +21,94 @@ use crate::ai::chains::IslamicAnalysisChain as IslamicChain;
 use crate::ai::chains::IslamicChainConfig;
 use crate::ai::embeddings::VectorDatabase;
 use crate::ai::embeddings::VectorDbConfig;
+use crate::ai::prompt_guard::UNTRUSTED_DATA_NOTICE;
+use crate::ai::prompt_guard::neutralize_untrusted;
+use crate::ai::prompt_guard::untrusted_data_block;
+use crate::http::HttpClient;
+use crate::islamic::DocumentIngester;
+use crate::islamic::DocumentStore;
+use crate::islamic::FatwaKnowledgeBase;
+use crate::islamic::FatwaQuery;
+use crate::islamic::IslamicAnalyzer;
+use crate::islamic::LIKELY_MAYSIR;
+use crate::islamic::POSSIBLE_MAYSIR;
+use crate::islamic::ReviewLog;
+use crate::islamic::apply_override;
+use crate::islamic::mint_key;
+use crate::islamic::ratios::screen_project;
+use crate::islamic::token_facts;
 use crate::models::AnalysisStatus;
 use crate::models::BacktestResult;
+use crate::models::CategoryLabel;
 use crate::models::ConfidenceBreakdown;
+use crate::models::DeterminationState;
+use crate::models::DocumentExcerpt;
+use crate::models::Fatwa;
+use crate::models::GhararAssessment;
+use crate::models::GhararLevel;
+use crate::models::IkhtilafMap;
+use crate::models::IkhtilafPosition;
+use crate::models::MaysirAssessment;
+use crate::models::PegMechanism;
+use crate::models::RibaAnalysis;
+use crate::models::RibaKind;
+use crate::models::RulingEvidence;
+use crate::models::ScriptureReference;
 use crate::models::SolanaError;
+use crate::models::StablecoinAnalysis;
+use crate::models::TokenExtension;
 use crate::models::TokenStandard;
+use crate::models::analysis::InjectionFinding;
+use crate::models::analysis::RuleHit;
 use crate::models::analysis::ScrapedData;
 use crate::models::analysis::TokenAnalysis;
 use crate::models::fatwa::FatwaReference;
 use crate::models::fatwa::IslamicAnalysis;
 use crate::models::fatwa::IslamicPrinciple;
+use crate::models::fatwa::RatioScreening;
+use crate::models::fatwa::RatioStatus;
 use crate::models::messages::AnalyzerError;
 use crate::models::messages::AnalyzerMessage;
 use crate::models::query::Query;
 use crate::models::query::QueryType;
 use crate::models::token::SolanaTokenInfo;
 use crate::models::token::UniversalTokenInfo;
+use crate::solana::CENTRALIZED_CONTROL;
+use crate::solana::TokenMetadataResolver;
+
+/// Knowledge base relevance at and above which a fatwa is taken to speak about the token
+const RELEVANT_FATWA_SCORE: f64 = 0.5;
 
 // Data structures used by the analyzer actor and external APIs
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct IslamicAnalysisResult {
     pub is_halal: bool,
     pub compliance_score: f64,
     pub confidence: f64,
     pub reasoning: Vec<String>,
     pub scholar_references: Vec<String>,
+    /// Ruling from the screening rules, when they produced one
+    #[serde(default)]
+    pub ruling: Option<IslamicPrinciple>,
+    /// Screening rules that fired
+    #[serde(default)]
+    pub rule_trace: Vec<RuleHit>,
+    #[serde(default)]
+    pub ratio_screening: Option<RatioScreening>,
+    #[serde(default)]
+    pub gharar: Option<GhararAssessment>,
+    #[serde(default)]
+    pub maysir: Option<MaysirAssessment>,
+    #[serde(default)]
+    pub riba: Option<RibaAnalysis>,
+    #[serde(default)]
+    pub stablecoin: Option<StablecoinAnalysis>,
+    /// Quran and hadith among the scholar references, with their text
+    #[serde(default)]
+    pub scripture_references: Vec<ScriptureReference>,
+    /// Categories of the token taxonomy, most confident first
+    #[serde(default)]
+    pub categories: Vec<CategoryLabel>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,7 +125,8 @@ pub struct AnalyzerActor {
 
     #[allow(dead_code)]
     solana_rpc_url: Option<String>, // Store URL instead of client
-    solana_client: Arc<Mutex<Option<Arc<RpcClient>>>>, // Use Arc<RpcClient> for sharing
+    metadata_resolver: Arc<TokenMetadataResolver>, // Caches resolved token metadata across analyses
+    document_http: HttpClient,                     // Fetches whitepapers linked from token websites
     analysis_cache: Arc<RwLock<HashMap<Uuid, TokenAnalysis>>>,
     islamic_chain: Arc<Mutex<Option<IslamicChain>>>,
     backtest_chain: Arc<Mutex<Option<BacktestChain>>>,
@@ -109,8 +172,9 @@ impl AnalyzerActor {
 
         Self {
             receiver,
+            metadata_resolver: Arc::new(TokenMetadataResolver::new(&rpc_url)),
+            document_http: HttpClient::from_env(reqwest::Client::new(), "documents"),
             solana_rpc_url: Some(rpc_url),
-            solana_client: Arc::new(Mutex::new(None)), // Client will be created lazily when needed
             analysis_cache: Arc::new(RwLock::new(HashMap::new())),
             islamic_chain: Arc::new(Mutex::new(None)),
             backtest_chain: Arc::new(Mutex::new(None)),
@@ -120,24 +184,6 @@ impl AnalyzerActor {
         }
     }
 
-    /// Get or create Solana RPC client lazily
-    async fn get_solana_client(&self) -> Result<Arc<RpcClient>, AnalyzerError> {
-        let mut client_guard = self.solana_client.lock().await;
-
-        if client_guard.is_none() {
-            if let Some(ref url) = self.solana_rpc_url {
-                info!("Creating Solana RPC client for: {}", url);
-                let client = RpcClient::new(url.clone());
-                *client_guard = Some(Arc::new(client));
-            } else {
-                return Err(AnalyzerError::InitializationFailed("No Solana RPC URL provided".to_string()));
-            }
-        }
-
-        // Clone the Arc for use
-        Ok(client_guard.as_ref().unwrap().clone())
-    }
-
     pub async fn run(&mut self) {
         info!("AnalyzerActor starting up");
 
@@ -164,13 +210,21 @@ impl AnalyzerActor {
                     let _ = respond_to.send(result);
                 },
                 AnalyzerMessage::SearchFatwas {
-                    keywords: _keywords,
-                    language: _language,
-                    limit: _limit,
+                    keywords,
+                    language,
+                    limit,
                     respond_to,
                 } => {
-                    // Mock implementation for search_fatwas
-                    let fatwas = vec![]; // Empty for now
+                    let query = FatwaQuery {
+                        terms: keywords,
+                        language: Some(language),
+                        limit,
+                        ..FatwaQuery::default()
+                    };
+                    let fatwas = FatwaKnowledgeBase::shared().search(&query).unwrap_or_else(|e| {
+                        error!("Fatwa search failed: {}", e);
+                        vec![]
+                    });
                     let _ = respond_to.send(fatwas);
                 },
                 AnalyzerMessage::RunBacktest {
@@ -190,6 +244,18 @@ impl AnalyzerActor {
                             risk_factors: vec![],
                             recommendations: vec![],
                             maqashid_assessment: vec![],
+                            ratio_screening: None,
+                            methodology_rulings: vec![],
+                            gharar: None,
+                            maysir: None,
+                            riba: None,
+                            stablecoin: None,
+                            scripture_references: vec![],
+                            ikhtilaf: None,
+                            determination: None,
+                            review: None,
+                            categories: vec![],
+                            document_excerpts: vec![],
                         },
                         scraped_data: vec![],
                         status: AnalysisStatus::Completed,
@@ -203,9 +269,13 @@ impl AnalyzerActor {
                             consensus_level: 0.6,
                             data_freshness: 0.8,
                             overall_confidence: 0.75,
+                            gharar_score: None,
                         },
                         backtest_results: vec![],
                         user_feedback: None,
+                        injection_detected: false,
+                        injection_findings: vec![],
+                        reasoning_trace: None,
                     };
                     let _ = respond_to.send(Ok(mock_analysis));
                 },
@@ -336,24 +406,361 @@ Before answering the question, please analyze the following sources:
             token_info.cloned()
         };
 
-        // Perform Islamic analysis using AI service directly
-        let islamic_analysis = match self
-            .ai_service
-            .analyze_islamic_compliance(&self.create_analysis_prompt(query, solana_token_info.as_ref(), scraped_data))
-            .await
-        {
-            Ok(analysis_result) => {
-                info!("AI service analysis completed successfully");
-                self.parse_ai_analysis_result(&analysis_result).await
+        // Positions of known Sharia sources and of fatwas naming the token, weighed before the model sees them
+        let fatwas = solana_token_info
+            .as_ref()
+            .map(|token| self.token_fatwas(&token.metadata.symbol, &token.metadata.name))
+            .unwrap_or_default();
+        let analyzer = IslamicAnalyzer::new();
+        let ikhtilaf = analyzer.ikhtilaf(scraped_data, &fatwas);
+
+        // Tokenomics, revenue model and use of funds from the project's whitepaper
+        let document_excerpts = match &solana_token_info {
+            Some(token) => self.project_documents(token).await,
+            None => Vec::new(),
+        };
+
+        let (prompt, injection_findings) = self.create_analysis_prompt(
+            query,
+            solana_token_info.as_ref(),
+            scraped_data,
+            ikhtilaf.as_ref(),
+            &document_excerpts,
+        );
+        if !injection_findings.is_empty() {
+            warn!(
+                "Neutralized {} prompt-injection attempt(s) in analysis input for query: {}",
+                injection_findings.len(),
+                query.id
+            );
+        }
+
+        // Perform Islamic analysis using AI service directly; only the answer is parsed, never the reasoning
+        let mut reasoning_trace = None;
+        let mut evidence = Vec::new();
+        let mut islamic_analysis = match self.ai_service.analyze_with_reasoning(&prompt).await {
+            Ok(output) => {
+                info!(
+                    "AI service analysis completed successfully ({} completion tokens, {} of them reasoning)",
+                    output.usage.completion_tokens(),
+                    output.usage.reasoning_tokens
+                );
+                reasoning_trace = output.retained_reasoning();
+                let parsed = self.parse_ai_analysis_result(&output.answer).await;
+                // A Syubhat answer is the model abstaining, not evidence
+                if parsed.ruling != IslamicPrinciple::Syubhat {
+                    evidence.push(RulingEvidence {
+                        source: "model".to_owned(),
+                        ruling: parsed.ruling.clone(),
+                        confidence: parsed.confidence,
+                        decisive: false,
+                    });
+                }
+                parsed
             },
             Err(e) => {
                 error!("AI service analysis failed: {}", e);
                 // Fallback to basic analysis
-                self.create_fallback_analysis(&e.clone()).await
+                self.create_fallback_analysis(&prompt, &e).await
             },
         };
 
+        if !injection_findings.is_empty() {
+            islamic_analysis
+                .risk_factors
+                .push("Sumber data mengandung upaya manipulasi instruksi AI (prompt injection)".to_owned());
+            islamic_analysis
+                .recommendations
+                .push("Verifikasi manual diperlukan karena sumber data mencoba mengarahkan hasil analisis".to_owned());
+        }
+
+        // Treasury and revenue ratios when the project's financials are on file, a ruling per standard and Maqashid
+        if let Some(token) = &solana_token_info {
+            let market_cap = token.price_data.as_ref().map(|price_data| price_data.market_cap as f64);
+            islamic_analysis.ratio_screening = screen_project(&token.pubkey, &token.metadata.symbol, market_cap);
+
+            let universal = token.to_universal();
+            let preferences = query.preferences.clone().unwrap_or_default();
+            islamic_analysis.categories = analyzer.categories(&universal, scraped_data);
+            islamic_analysis.methodology_rulings =
+                analyzer.methodology_rulings(&universal, &preferences, &islamic_analysis.categories);
+            islamic_analysis.maqashid_assessment = analyzer.maqashid_assessment(&token_facts(&universal));
+            islamic_analysis.gharar = Some(analyzer.gharar_assessment(&universal));
+            islamic_analysis.maysir = Some(analyzer.maysir_assessment(&universal));
+            islamic_analysis.riba = Some(analyzer.riba_analysis(&universal));
+            islamic_analysis.stablecoin = analyzer.stablecoin_analysis(&universal);
+        }
+        if let Some(stablecoin) = &islamic_analysis.stablecoin {
+            let mechanism = match stablecoin.mechanism {
+                PegMechanism::FiatBacked => "berbasis fiat",
+                PegMechanism::CryptoCollateralized => "berjaminan kripto",
+                PegMechanism::Algorithmic => "algoritmik",
+                PegMechanism::CommodityBacked => "berbasis emas/komoditas",
+            };
+            if stablecoin.mechanism == PegMechanism::Algorithmic {
+                islamic_analysis
+                    .risk_factors
+                    .push("Stablecoin algoritmik tanpa cadangan memadai mengandung gharar".to_owned());
+            }
+            islamic_analysis
+                .recommendations
+                .push(format!("Stablecoin {mechanism}: halal selama {}", stablecoin.conditions.join("; ")));
+            islamic_analysis
+                .recommendations
+                .extend(stablecoin.caveats.iter().map(|caveat| format!("Catatan penggunaan: {caveat}")));
+        }
+        if let Some(riba) = islamic_analysis.riba.as_ref().filter(|riba| !riba.findings.is_empty()) {
+            // Riba in the token's own mechanics settles the ruling whatever the model concluded
+            evidence.push(RulingEvidence {
+                source: "riba".to_owned(),
+                ruling: IslamicPrinciple::Riba,
+                confidence: 1.0,
+                decisive: true,
+            });
+            for finding in &riba.findings {
+                let kind = match finding.kind {
+                    RibaKind::LendingReceipt => "token bukti setoran protokol pinjaman",
+                    RibaKind::InterestBearingMint => "ekstensi bunga Token-2022",
+                    RibaKind::RebasingLendingYield => "imbal hasil rebasing dari pinjaman",
+                    RibaKind::StakingFromLending => "imbalan staking dari pinjaman",
+                };
+                islamic_analysis
+                    .risk_factors
+                    .push(format!("Riba ({kind}): {}", finding.observed));
+            }
+        }
+        if let Some(maysir) = &islamic_analysis.maysir {
+            if maysir.probability >= POSSIBLE_MAYSIR {
+                let observed = maysir
+                    .evidence
+                    .iter()
+                    .map(|item| item.observed.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                if maysir.probability >= LIKELY_MAYSIR {
+                    // Tokens used for gambling are maysir whatever the model concluded
+                    evidence.push(RulingEvidence {
+                        source: "maysir".to_owned(),
+                        ruling: IslamicPrinciple::Maysir,
+                        confidence: maysir.probability,
+                        decisive: true,
+                    });
+                    islamic_analysis.risk_factors.push(format!(
+                        "Kemungkinan besar digunakan untuk perjudian (maysir), probabilitas {:.0}%: {observed}",
+                        maysir.probability * 100.0
+                    ));
+                } else {
+                    islamic_analysis.risk_factors.push(format!(
+                        "Terdapat indikasi unsur perjudian (maysir), probabilitas {:.0}%: {observed}",
+                        maysir.probability * 100.0
+                    ));
+                }
+            }
+        }
+        if let Some(gharar) = &islamic_analysis.gharar {
+            let measured = gharar
+                .factors
+                .iter()
+                .filter(|factor| factor.risk.is_some_and(|risk| risk >= 0.5))
+                .map(|factor| factor.explanation.as_str())
+                .collect::<Vec<_>>()
+                .join("; ");
+            match gharar.level {
+                GhararLevel::Fahish => {
+                    // Excessive gharar invalidates the sale whatever the model concluded
+                    evidence.push(RulingEvidence {
+                        source: "gharar".to_owned(),
+                        ruling: IslamicPrinciple::Gharar,
+                        confidence: gharar.score,
+                        decisive: true,
+                    });
+                    islamic_analysis
+                        .risk_factors
+                        .push(format!("Gharar berlebihan (fahisy) terukur, skor {:.2}: {measured}", gharar.score));
+                },
+                GhararLevel::Mutawassit => {
+                    islamic_analysis
+                        .risk_factors
+                        .push(format!("Gharar sedang terukur, skor {:.2}: {measured}", gharar.score));
+                },
+                GhararLevel::Yasir | GhararLevel::Undetermined => {},
+            }
+        }
+        // Authorities left with a single key can dilute or freeze holders whatever the ruling
+        if let Some(control) = solana_token_info
+            .as_ref()
+            .and_then(|token| token.control.as_ref())
+            .filter(|control| control.score >= CENTRALIZED_CONTROL)
+        {
+            let held = control
+                .factors
+                .iter()
+                .filter(|factor| factor.risk.is_some_and(|risk| risk >= 0.5))
+                .map(|factor| factor.explanation.as_str())
+                .collect::<Vec<_>>()
+                .join("; ");
+            islamic_analysis
+                .risk_factors
+                .push(format!("Kendali token terpusat, skor {:.2}: {held}", control.score));
+        }
+        // The standards are one evaluation of the same facts, so each ruling class they reach is one piece of
+        // evidence at its strongest standard's confidence; each source that stated a ruling is a further one
+        let mut standards: Vec<RulingEvidence> = Vec::new();
+        for ruling in islamic_analysis
+            .methodology_rulings
+            .iter()
+            .filter(|ruling| ruling.ruling != IslamicPrinciple::Syubhat)
+        {
+            let confidence = if ruling.ruling.permits() {
+                ruling.compliance_score
+            } else {
+                1.0 - ruling.compliance_score
+            };
+            let standard = format!("{:?}", ruling.standard);
+            match standards.iter_mut().find(|item| item.ruling.class() == ruling.ruling.class()) {
+                Some(item) => {
+                    item.source = format!("{},{standard}", item.source);
+                    if confidence > item.confidence {
+                        item.ruling = ruling.ruling.clone();
+                        item.confidence = confidence;
+                    }
+                },
+                None => standards.push(RulingEvidence {
+                    source: format!("standard:{standard}"),
+                    ruling: ruling.ruling.clone(),
+                    confidence,
+                    decisive: false,
+                }),
+            }
+        }
+        evidence.extend(standards);
+        evidence.extend(
+            ikhtilaf
+                .iter()
+                .flat_map(|map| std::iter::once(&map.majority_view).chain(&map.minority_views))
+                .flat_map(|position| position.opinions.iter())
+                .map(|opinion| RulingEvidence {
+                    source: format!("source:{}", opinion.source.label()),
+                    ruling: opinion.ruling.clone(),
+                    confidence: opinion.weight,
+                    decisive: false,
+                }),
+        );
+        let mut determination = analyzer.determine_ruling(evidence);
+
+        // A scholar's override takes precedence over everything the analysis found
+        // Keyed by mint only: anyone can mint a token under a reviewed token's symbol
+        let review = match solana_token_info.as_ref().and_then(|token| mint_key(&token.pubkey)) {
+            Some(mint) => ReviewLog::shared().override_for(&mint).unwrap_or_else(|e| {
+                error!("Failed to read scholar reviews: {}", e);
+                None
+            }),
+            None => None,
+        };
+        if let Some(review) = &review {
+            apply_override(&mut determination, review);
+        }
+
+        islamic_analysis.ruling = determination.ruling.clone();
+        islamic_analysis.confidence = if determination.state == DeterminationState::Determined {
+            determination.confidence
+        } else {
+            0.0
+        };
+        if determination.state != DeterminationState::Determined {
+            islamic_analysis.recommendations.push(format!(
+                "Status hukum belum dapat dipastikan (syubhat), sebaiknya dihindari sampai jelas: {}",
+                determination.abstentions.join("; ")
+            ));
+        }
+        islamic_analysis.determination = Some(determination);
+        if let Some(review) = review {
+            islamic_analysis.recommendations.push(format!(
+                "Hukum ditetapkan oleh peninjau syariah {}: {} (rujukan: {})",
+                review.scholar_name,
+                review.rationale,
+                review.references.join("; ")
+            ));
+            islamic_analysis.review = Some(review);
+        }
+
+        // Quran and hadith behind the findings and rulings, in the user's language
+        let citations = islamic_analysis
+            .riba
+            .iter()
+            .flat_map(|riba| riba.findings.iter().flat_map(|finding| finding.references.iter()))
+            .chain(
+                islamic_analysis
+                    .maysir
+                    .iter()
+                    .flat_map(|maysir| maysir.evidence.iter().flat_map(|item| item.references.iter())),
+            )
+            .chain(
+                islamic_analysis
+                    .stablecoin
+                    .iter()
+                    .flat_map(|stablecoin| stablecoin.references.iter()),
+            )
+            .chain(
+                islamic_analysis
+                    .methodology_rulings
+                    .iter()
+                    .flat_map(|ruling| ruling.basis.iter()),
+            )
+            .cloned()
+            .collect::<Vec<_>>();
+        islamic_analysis.scripture_references = analyzer.scripture_references(&citations, &query.language);
+
+        if let Some(map) = ikhtilaf.as_ref().filter(|map| !map.is_consensus()) {
+            let view = |position: &IkhtilafPosition| {
+                let sources = position
+                    .opinions
+                    .iter()
+                    .map(|opinion| opinion.source.label())
+                    .collect::<Vec<_>>();
+                format!("{:?} ({:.0}%, {})", position.ruling, position.weight_share * 100.0, sources.join(", "))
+            };
+            islamic_analysis.recommendations.push(format!(
+                "Terdapat perbedaan pendapat (ikhtilaf): pendapat mayoritas {}, pendapat minoritas {}",
+                view(&map.majority_view),
+                map.minority_views.iter().map(view).collect::<Vec<_>>().join("; ")
+            ));
+        }
+        islamic_analysis.ikhtilaf = ikhtilaf;
+        islamic_analysis.document_excerpts = document_excerpts;
+
+        let divergent = islamic_analysis
+            .methodology_rulings
+            .iter()
+            .filter(|ruling| ruling.diverges)
+            .map(|ruling| format!("{:?}: {:?}", ruling.standard, ruling.ruling))
+            .collect::<Vec<_>>();
+        if !divergent.is_empty() {
+            islamic_analysis.recommendations.push(format!(
+                "Standar syariah berbeda pendapat ({}); periksa dasar masing-masing sesuai standar yang Anda ikuti",
+                divergent.join(", ")
+            ));
+        }
+        if let Some(screening) = &islamic_analysis.ratio_screening {
+            if screening.status == RatioStatus::Fail {
+                let failed = screening
+                    .ratios
+                    .iter()
+                    .filter(|ratio| ratio.status == RatioStatus::Fail)
+                    .map(|ratio| ratio.description.as_str())
+                    .collect::<Vec<_>>();
+                islamic_analysis.risk_factors.push(format!(
+                    "Rasio keuangan tidak lolos screening {}: {}",
+                    screening.methodology,
+                    failed.join(", ")
+                ));
+            }
+        }
+
         let processing_time = start_time.elapsed();
+        let confidence_breakdown = self
+            .calculate_confidence_breakdown(scraped_data, islamic_analysis.gharar.as_ref(), processing_time)
+            .await;
 
         // Create comprehensive token analysis
         let analysis = TokenAnalysis {
@@ -366,9 +773,12 @@ Before answering the question, please analyze the following sources:
             created_at: Utc::now().timestamp_millis() as u64,
             completed_at: Some(Utc::now().timestamp_millis() as u64),
             processing_time_ms: Some(processing_time.as_millis() as u64),
-            confidence_breakdown: self.calculate_confidence_breakdown(scraped_data, processing_time).await,
+            confidence_breakdown,
             backtest_results: Vec::new(),
             user_feedback: None,
+            injection_detected: !injection_findings.is_empty(),
+            injection_findings,
+            reasoning_trace,
         };
 
         // Store analysis in cache
@@ -401,30 +811,10 @@ Before answering the question, please analyze the following sources:
             .parse::<Pubkey>()
             .map_err(|e| SolanaError::InvalidAddress(e.to_string()))?;
 
-        // In a real implementation, this would fetch actual token metadata from Solana
-        // For now, return mock data
-        let token_info = SolanaTokenInfo {
-            pubkey: pubkey.to_string(),
-            metadata: crate::models::TokenMetadata {
-                name: "Unknown Token".to_owned(),
-                symbol: "UNK".to_owned(),
-                contract_address: address.to_owned(),
-                decimals: 9,
-                description: Some("Token metadata fetched from blockchain".to_owned()),
-                image_url: None,
-                creator: None,
-                verified: false,
-                token_standard: TokenStandard::SPL,
-                blockchain: crate::models::token::BlockchainNetwork::Solana,
-            },
-            price_data: None,
-            holders: None,
-            liquidity_pools: Vec::new(),
-            is_verified: false,
-            risk_score: Some(0.5),
-        };
+        // The risk score is the token control score the resolver computed from the mint's authorities
+        let resolved = self.metadata_resolver.resolve(&pubkey).await?;
 
-        Ok(token_info)
+        Ok(SolanaTokenInfo::from_internal(resolved))
     }
 
     #[allow(dead_code)]
@@ -515,6 +905,75 @@ Before answering the question, please analyze the following sources:
 
     // Helper methods
 
+    /// Current fatwas in the knowledge base that name the token
+    fn token_fatwas(
+        &self,
+        symbol: &str,
+        name: &str,
+    ) -> Vec<Fatwa> {
+        let query = FatwaQuery {
+            terms: vec![symbol.to_owned(), name.to_owned()],
+            ..FatwaQuery::default()
+        };
+        match FatwaKnowledgeBase::shared().search(&query) {
+            Ok(fatwas) => fatwas
+                .into_iter()
+                .filter(|fatwa| fatwa.confidence_score.unwrap_or_default() >= RELEVANT_FATWA_SCORE)
+                .collect(),
+            Err(e) => {
+                error!("Fatwa search failed: {}", e);
+                vec![]
+            },
+        }
+    }
+
+    /// Key sections of the token's documents, first ingesting the whitepaper its website links to when
+    /// none is on file
+    async fn project_documents(
+        &self,
+        token: &SolanaTokenInfo,
+    ) -> Vec<DocumentExcerpt> {
+        // Documents are keyed by mint; tokens without a known one carry the default pubkey and have none
+        let Some(mint) = token.pubkey.parse::<Pubkey>().ok().filter(|mint| *mint != Pubkey::default()) else {
+            return Vec::new();
+        };
+        let store = DocumentStore::shared();
+        let on_file = store
+            .documents(&token.pubkey)
+            .map(|documents| !documents.is_empty())
+            .unwrap_or_else(|e| {
+                error!("Failed to read project documents: {}", e);
+                true
+            });
+
+        // Only mints read from the chain carry a website; the metadata is still cached from this analysis
+        if !on_file {
+            if let Some(metadata) = self
+                .metadata_resolver
+                .resolve(&mint)
+                .await
+                .ok()
+                .map(|resolved| resolved.metadata)
+                .filter(|metadata| metadata.website.is_some())
+            {
+                match DocumentIngester::shared().fetch_linked(&self.document_http, &metadata).await {
+                    Ok(document) => {
+                        info!("Ingested {} for {} from its website", document.source, mint);
+                        if let Err(e) = store.save(&document) {
+                            error!("Failed to save project document: {}", e);
+                        }
+                    },
+                    Err(e) => warn!("No project document fetched for {}: {}", mint, e),
+                }
+            }
+        }
+
+        store.excerpts_for(&token.pubkey).unwrap_or_else(|e| {
+            error!("Failed to read project documents: {}", e);
+            Vec::new()
+        })
+    }
+
     async fn extract_and_fetch_token_info(
         &self,
         query: &Query,
@@ -551,12 +1010,14 @@ Before answering the question, please analyze the following sources:
                     verified: false,
                     token_standard: TokenStandard::SPL,
                     blockchain: crate::models::token::BlockchainNetwork::Solana,
+                    extensions: vec![],
                 },
                 price_data: None,
                 holders: None,
                 liquidity_pools: Vec::new(),
                 is_verified: false,
-                risk_score: Some(0.5),
+                risk_score: None, // Nothing was read from the chain to score
+                control: None,
             };
 
             Ok(token_info)
@@ -591,35 +1052,45 @@ Before answering the question, please analyze the following sources:
         None
     }
 
+    /// Ask the AI service once more with the original `prompt`, after the first attempt failed with `error_context`
     async fn create_fallback_analysis(
         &self,
+        prompt: &str,
         error_context: &str,
     ) -> IslamicAnalysis {
         warn!("Creating fallback Islamic analysis due to: {}", error_context);
 
         // Try to use AI service for fallback analysis
-        match self
-            .ai_service
-            .analyze_islamic_compliance(&format!("Analisis Islam: {error_context}"))
-            .await
-        {
+        match self.ai_service.analyze_islamic_compliance(prompt).await {
             Ok(ai_response) => {
                 info!("AI service provided fallback analysis");
                 IslamicAnalysis {
-                    ruling: IslamicPrinciple::Mubah, // Default to permissible
-                    confidence: 0.6,                 // Moderate confidence for AI fallback
+                    ruling: IslamicPrinciple::Syubhat, // The fallback answer is not parsed for a ruling
+                    confidence: 0.6,                   // Moderate confidence for AI fallback
                     reasoning: ai_response,
                     supporting_fatwas: vec![],
                     risk_factors: vec!["Analisis menggunakan AI fallback".to_owned()],
                     recommendations: vec!["Verifikasi dengan ahli fiqh".to_owned()],
                     maqashid_assessment: vec![],
+                    ratio_screening: None,
+                    methodology_rulings: vec![],
+                    gharar: None,
+                    maysir: None,
+                    riba: None,
+                    stablecoin: None,
+                    scripture_references: vec![],
+                    ikhtilaf: None,
+                    determination: None,
+                    review: None,
+                    categories: vec![],
+                    document_excerpts: vec![],
                 }
             },
             Err(e) => {
                 warn!("AI service also failed: {}, using basic fallback", e);
                 IslamicAnalysis {
-                    ruling: IslamicPrinciple::Mubah, // Default to permissible with low confidence
-                    confidence: 0.3,                 // Low confidence for basic fallback
+                    ruling: IslamicPrinciple::Syubhat, // Nothing is known about the token
+                    confidence: 0.3,                   // Low confidence for basic fallback
                     reasoning: format!(
                         "Analisis dasar berdasarkan prinsip umum Islam. {error_context}. Direkomendasikan untuk \
                          konsultasi lebih lanjut dengan ahli fiqh."
@@ -628,6 +1099,18 @@ Before answering the question, please analyze the following sources:
                     risk_factors: vec!["Analisis terbatas".to_owned()],
                     recommendations: vec!["Konsultasi dengan ahli fiqh".to_owned()],
                     maqashid_assessment: vec![],
+                    ratio_screening: None,
+                    methodology_rulings: vec![],
+                    gharar: None,
+                    maysir: None,
+                    riba: None,
+                    stablecoin: None,
+                    scripture_references: vec![],
+                    ikhtilaf: None,
+                    determination: None,
+                    review: None,
+                    categories: vec![],
+                    document_excerpts: vec![],
                 }
             },
         }
@@ -636,15 +1119,21 @@ Before answering the question, please analyze the following sources:
     async fn calculate_confidence_breakdown(
         &self,
         scraped_data: &[ScrapedData],
+        gharar: Option<&GhararAssessment>,
         processing_time: std::time::Duration,
     ) -> ConfidenceBreakdown {
-        let data_quality_score = if scraped_data.is_empty() {
+        let scraped_quality = if scraped_data.is_empty() {
             0.2
         } else {
             let avg_relevance: f64 =
                 scraped_data.iter().map(|d| d.relevance_score).sum::<f64>() / scraped_data.len() as f64;
             avg_relevance.min(1.0)
         };
+        // How much of the market and on-chain data the gharar score needs was actually available
+        let data_quality_score = match gharar {
+            Some(gharar) => (scraped_quality + gharar.coverage) / 2.0,
+            None => scraped_quality,
+        };
 
         let processing_speed_score = if processing_time.as_millis() < 500 {
             1.0 // Fast processing gets full score
@@ -679,6 +1168,7 @@ Before answering the question, please analyze the following sources:
             data_freshness: 0.7,
             overall_confidence: (data_quality_score + source_reliability_score + processing_speed_score + 0.7 + 0.7)
                 / 5.0,
+            gharar_score: gharar.map(|gharar| gharar.score),
         }
     }
 
@@ -737,54 +1227,129 @@ pub async fn spawn_analyzer_actor(
 
 impl AnalyzerActor {
     /// Create analysis prompt for AI service
+    /// Build the analysis prompt, quoting scraped and on-chain text as untrusted data
+    ///
+    /// Returns the prompt together with any injection attempts found in that text.
     fn create_analysis_prompt(
         &self,
         query: &Query,
         token_info: Option<&SolanaTokenInfo>,
         scraped_data: &[ScrapedData],
-    ) -> String {
+        ikhtilaf: Option<&IkhtilafMap>,
+        document_excerpts: &[DocumentExcerpt],
+    ) -> (String, Vec<InjectionFinding>) {
         let mut prompt =
             String::from("You are an Islamic finance expert. Analyze the following for Sharia compliance:\n\n");
+        let mut findings = Vec::new();
 
-        // Add query information
+        // Add query information; whatever the user typed, links included, is quoted and neutralized like page text
         match &query.query_type {
             QueryType::Text {
                 text,
+            }
+            | QueryType::FollowUp {
+                question: text, ..
             } => {
-                prompt.push_str(&format!("Query: {text}\n\n"));
+                let block = untrusted_data_block(0, "user_query", text, 1000);
+                prompt.push_str("Query:\n");
+                prompt.push_str(UNTRUSTED_DATA_NOTICE);
+                prompt.push('\n');
+                prompt.push_str(&block.text);
+                prompt.push('\n');
+                findings.extend(block.findings);
             },
             QueryType::TokenTicker {
                 ticker,
             } => {
-                prompt.push_str(&format!("Token: {ticker}\n\n"));
+                let ticker = neutralize_untrusted("user_query", ticker);
+                prompt.push_str(&format!("Token: {}\n\n", ticker.text));
+                findings.extend(ticker.findings);
             },
             QueryType::ContractAddress {
                 address,
             } => {
-                prompt.push_str(&format!("Contract Address: {address}\n\n"));
+                let address = neutralize_untrusted("user_query", address);
+                prompt.push_str(&format!("Contract Address: {}\n\n", address.text));
+                findings.extend(address.findings);
             },
-            _ => {},
+            // Raw audio carries no text to quote
+            QueryType::Audio {
+                ..
+            } => {},
         }
 
-        // Add token information if available
+        // Add token information if available; name and symbol are set by the token creator
         if let Some(token) = token_info {
+            let name = neutralize_untrusted("token_metadata", &token.metadata.name);
+            let symbol = neutralize_untrusted("token_metadata", &token.metadata.symbol);
+
             prompt.push_str("Token Details:\n");
-            prompt.push_str(&format!("- Name: {}\n", token.metadata.name));
-            prompt.push_str(&format!("- Symbol: {}\n", token.metadata.symbol));
+            prompt.push_str(&format!("- Name: {}\n", name.text));
+            prompt.push_str(&format!("- Symbol: {}\n", symbol.text));
             prompt.push_str(&format!("- Decimals: {}\n", token.metadata.decimals));
+            if !token.metadata.extensions.is_empty() {
+                let extensions = token.metadata.extensions.iter().map(TokenExtension::name).collect::<Vec<_>>();
+                prompt.push_str(&format!("- Token-2022 Extensions: {}\n", extensions.join(", ")));
+            }
             if let Some(price_data) = &token.price_data {
                 prompt.push_str(&format!("- Price: ${}\n", price_data.price_usd));
                 prompt.push_str(&format!("- Market Cap: ${}\n", price_data.market_cap));
             }
             prompt.push('\n');
+
+            findings.extend(name.findings);
+            findings.extend(symbol.findings);
         }
 
-        // Add scraped data
+        // Add scraped data as delimited blocks
         if !scraped_data.is_empty() {
             prompt.push_str("Additional Information:\n");
-            for data in scraped_data.iter().take(3) {
+            prompt.push_str(UNTRUSTED_DATA_NOTICE);
+            prompt.push('\n');
+            for (index, data) in scraped_data.iter().take(3).enumerate() {
                 // Limit to avoid token limits
-                prompt.push_str(&format!("- {}\n", data.content.chars().take(200).collect::<String>()));
+                let block = untrusted_data_block(index + 1, &data.source_url, &data.content, 200);
+                prompt.push_str(&block.text);
+                findings.extend(block.findings);
+            }
+            prompt.push('\n');
+        }
+
+        // Whitepaper text is written by the project itself; quoted like scraped pages and labelled with its pages
+        if !document_excerpts.is_empty() {
+            prompt.push_str("Project Documents (cite the page given with each excerpt when relying on it):\n");
+            prompt.push_str(UNTRUSTED_DATA_NOTICE);
+            prompt.push('\n');
+            let first_index = scraped_data.len().min(3) + 1;
+            for (offset, excerpt) in document_excerpts.iter().enumerate() {
+                let source = format!("{:?} - {}", excerpt.kind, excerpt.citation());
+                let block = untrusted_data_block(first_index + offset, &source, &excerpt.text, 800);
+                prompt.push_str(&block.text);
+                findings.extend(block.findings);
+            }
+            prompt.push('\n');
+        }
+
+        // Positions are built from configured source labels and matched rulings only, never from page text
+        if let Some(map) = ikhtilaf {
+            prompt.push_str("Scholarly Positions (weighed by source credibility and recency):\n");
+            for (label, position) in std::iter::once(("Majority", &map.majority_view))
+                .chain(map.minority_views.iter().map(|position| ("Minority", position)))
+            {
+                let sources = position
+                    .opinions
+                    .iter()
+                    .map(|opinion| format!("{} ({:?})", opinion.source.label(), opinion.ruling))
+                    .collect::<Vec<_>>();
+                prompt.push_str(&format!(
+                    "- {label} view, {:.0}% of weight: {:?} - {}\n",
+                    position.weight_share * 100.0,
+                    position.ruling,
+                    sources.join(", ")
+                ));
+            }
+            for basis in &map.basis_of_difference {
+                prompt.push_str(&format!("- Basis of difference: {basis}\n"));
             }
             prompt.push('\n');
         }
@@ -795,7 +1360,7 @@ impl AnalyzerActor {
         prompt.push_str("3. REASONING: Detailed explanation with Islamic principles\n");
         prompt.push_str("4. SOURCES: Relevant Islamic finance sources\n");
 
-        prompt
+        (prompt, findings)
     }
 
     /// Parse AI analysis result into `IslamicAnalysis`
@@ -816,6 +1381,18 @@ impl AnalyzerActor {
                 risk_factors: vec!["Unable to complete automated analysis".to_owned()],
                 recommendations: vec!["Seek guidance from Islamic finance scholars".to_owned()],
                 maqashid_assessment: vec![],
+                ratio_screening: None,
+                methodology_rulings: vec![],
+                gharar: None,
+                maysir: None,
+                riba: None,
+                stablecoin: None,
+                scripture_references: vec![],
+                ikhtilaf: None,
+                determination: None,
+                review: None,
+                categories: vec![],
+                document_excerpts: vec![],
             };
         }
 
@@ -853,8 +1430,8 @@ impl AnalyzerActor {
         } else if analysis_lower.contains("haram") {
             IslamicPrinciple::Haram
         } else {
-            // Default to neutral if unclear
-            IslamicPrinciple::Mubah
+            // Unclear answers leave the ruling undecided
+            IslamicPrinciple::Syubhat
         };
 
         let confidence = if analysis_result.contains("CONFIDENCE:") {
@@ -909,6 +1486,18 @@ impl AnalyzerActor {
             risk_factors,
             recommendations: vec![],
             maqashid_assessment: vec![],
+            ratio_screening: None,
+            methodology_rulings: vec![],
+            gharar: None,
+            maysir: None,
+            riba: None,
+            stablecoin: None,
+            scripture_references: vec![],
+            ikhtilaf: None,
+            determination: None,
+            review: None,
+            categories: vec![],
+            document_excerpts: vec![],
         }
     }
 }
@@ -922,8 +1511,6 @@ mod tests {
         // Create a mock AI service for testing
         let config = crate::AverroesConfig {
             groq_api_key: "test_key".to_owned(),
-            grok_api_key: "".to_owned(),
-            openai_api_key: "".to_owned(),
             preferred_model: "groq".to_owned(),
             ..Default::default()
         };
@@ -938,8 +1525,6 @@ mod tests {
         // Create a mock AI service for testing
         let config = crate::AverroesConfig {
             groq_api_key: "test_key".to_owned(),
-            grok_api_key: "".to_owned(),
-            openai_api_key: "".to_owned(),
             preferred_model: "groq".to_owned(),
             ..Default::default()
         };
@@ -960,8 +1545,6 @@ mod tests {
         // Create a mock AI service for testing
         let config = crate::AverroesConfig {
             groq_api_key: "test_key".to_owned(),
-            grok_api_key: "".to_owned(),
-            openai_api_key: "".to_owned(),
             preferred_model: "groq".to_owned(),
             ..Default::default()
         };
@@ -21,6 +21,7 @@ use crate::models::HistoryMessage;
 use crate::models::HistoryQuery;
 use crate::models::IslamicPrinciple;
 use crate::models::Query;
+use crate::models::ShariaPreferences;
 use crate::models::TokenAnalysis;
 use crate::models::UserAnalysisStats;
 
@@ -30,6 +31,7 @@ pub struct HistoryActor {
     analyses_tree: Tree,
     histories_tree: Tree,
     stats_tree: Tree,
+    preferences_tree: Tree,
     cache: HashMap<String, AnalysisHistory>, // token_identifier -> history
 }
 
@@ -67,12 +69,17 @@ impl HistoryActor {
 
         let stats_tree = db.open_tree("stats").map_err(|e| HistoryError::DatabaseError(e.to_string()))?;
 
+        let preferences_tree = db
+            .open_tree("preferences")
+            .map_err(|e| HistoryError::DatabaseError(e.to_string()))?;
+
         Ok(Self {
             receiver,
             db,
             analyses_tree,
             histories_tree,
             stats_tree,
+            preferences_tree,
             cache: HashMap::new(),
         })
     }
@@ -124,6 +131,25 @@ impl HistoryActor {
                         error!("Failed to send cleanup result: {:?}", e);
                     }
                 },
+                HistoryMessage::SavePreferences {
+                    user_id,
+                    preferences,
+                    respond_to,
+                } => {
+                    let result = self.save_preferences(&user_id, &preferences).await;
+                    if let Err(e) = respond_to.send(result) {
+                        error!("Failed to send save preferences result: {:?}", e);
+                    }
+                },
+                HistoryMessage::GetPreferences {
+                    user_id,
+                    respond_to,
+                } => {
+                    let result = self.get_preferences(&user_id).await;
+                    if let Err(e) = respond_to.send(result) {
+                        error!("Failed to send preferences: {:?}", e);
+                    }
+                },
             }
         }
 
@@ -455,6 +481,39 @@ impl HistoryActor {
         Ok(deleted_count)
     }
 
+    async fn save_preferences(
+        &self,
+        user_id: &str,
+        preferences: &ShariaPreferences,
+    ) -> Result<(), HistoryError> {
+        info!("Saving Sharia preferences for user: {}", user_id);
+
+        let data = serde_json::to_vec(preferences).map_err(|e| HistoryError::SerializationError(e.to_string()))?;
+        self.preferences_tree
+            .insert(user_id, data)
+            .map_err(|e| HistoryError::DatabaseError(e.to_string()))?;
+
+        self.db.flush().map_err(|e| HistoryError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn get_preferences(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<ShariaPreferences>, HistoryError> {
+        let Some(data) = self
+            .preferences_tree
+            .get(user_id)
+            .map_err(|e| HistoryError::DatabaseError(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| HistoryError::SerializationError(e.to_string()))
+    }
+
     async fn update_user_stats(
         &self,
         _user_id: &str,
@@ -630,6 +689,22 @@ mod tests {
         assert_eq!(result, Some("BTC".to_owned()));
     }
 
+    #[tokio::test]
+    async fn test_preferences_round_trip() {
+        let (_sender, receiver) = mpsc::channel(10);
+        let actor = HistoryActor::new(receiver, None).unwrap();
+
+        assert_eq!(actor.get_preferences("user_1").await.unwrap(), None);
+
+        let preferences = ShariaPreferences {
+            madhab: Some(crate::models::Madhab::Shafii),
+            standards: vec![crate::models::ShariahStandard::SacMalaysia],
+        };
+        actor.save_preferences("user_1", &preferences).await.unwrap();
+
+        assert_eq!(actor.get_preferences("user_1").await.unwrap(), Some(preferences));
+    }
+
     #[tokio::test]
     async fn test_frequency_calculation() {
         let (_sender, receiver) = mpsc::channel(10);
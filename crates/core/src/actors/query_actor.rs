@@ -84,11 +84,21 @@ impl QueryActor {
 
     async fn handle_query(
         &mut self,
-        query: Query,
+        mut query: Query,
     ) -> QueryResponse {
         info!("Processing query: {:?}", query.query_type);
         let start_time = std::time::Instant::now();
 
+        // Rule by the user's saved madhab and standards unless the query names its own
+        if query.preferences.is_none() {
+            if let (Some(user_id), Some(history_handle)) = (&query.user_id, &self.history_handle) {
+                match history_handle.get_preferences(user_id.clone()).await {
+                    Ok(preferences) => query.preferences = preferences,
+                    Err(e) => warn!("Failed to load Sharia preferences for {}: {:?}", user_id, e),
+                }
+            }
+        }
+
         // Store query in cache
         if let Some(user_id) = &query.user_id {
             self.query_cache.entry(user_id.clone()).or_default().push(query.clone());
@@ -117,6 +127,7 @@ impl QueryActor {
                     follow_up_questions: vec![],
                     timestamp: Utc::now().timestamp_millis() as u64,
                     analysis_id: None,
+                    ruling: None,
                 },
             },
             QueryType::Audio {
@@ -131,6 +142,7 @@ impl QueryActor {
                     follow_up_questions: vec![],
                     timestamp: Utc::now().timestamp_millis() as u64,
                     analysis_id: None,
+                    ruling: None,
                 }
             },
         };
@@ -201,6 +213,7 @@ impl QueryActor {
                     follow_up_questions: self.generate_follow_up_questions(&analysis.islamic_analysis.ruling),
                     timestamp: Utc::now().timestamp_millis() as u64,
                     analysis_id: Some(analysis.id),
+                    ruling: Some(analysis.islamic_analysis.ruling.clone()),
                 }
             },
             Err(e) => {
@@ -214,6 +227,7 @@ impl QueryActor {
                     follow_up_questions: Vec::new(),
                     timestamp: Utc::now().timestamp_millis() as u64,
                     analysis_id: None,
+                    ruling: None,
                 }
             },
         }
@@ -272,6 +286,7 @@ impl QueryActor {
                     follow_up_questions: self.generate_follow_up_questions(&analysis.islamic_analysis.ruling),
                     timestamp: Utc::now().timestamp_millis() as u64,
                     analysis_id: Some(analysis.id),
+                    ruling: Some(analysis.islamic_analysis.ruling.clone()),
                 }
             },
             Err(e) => {
@@ -289,6 +304,7 @@ impl QueryActor {
                     ],
                     timestamp: Utc::now().timestamp_millis() as u64,
                     analysis_id: None,
+                    ruling: None,
                 }
             },
         }
@@ -366,6 +382,7 @@ impl QueryActor {
                     follow_up_questions: self.generate_follow_up_questions(&analysis.islamic_analysis.ruling),
                     timestamp: Utc::now().timestamp_millis() as u64,
                     analysis_id: Some(analysis.id),
+                    ruling: Some(analysis.islamic_analysis.ruling.clone()),
                 }
             },
             Err(e) => {
@@ -381,6 +398,7 @@ impl QueryActor {
                     ],
                     timestamp: Utc::now().timestamp_millis() as u64,
                     analysis_id: None,
+                    ruling: None,
                 }
             },
         }
@@ -414,6 +432,7 @@ impl QueryActor {
             follow_up_questions: vec!["Any other questions?".to_owned()],
             timestamp: Utc::now().timestamp_millis() as u64,
             analysis_id: None,
+            ruling: None,
         }
     }
 
@@ -510,22 +529,29 @@ impl QueryActor {
         &self,
         text: &str,
     ) -> Option<String> {
-        // Look for potential token symbols (2-10 chars, alphanumeric)
+        // A `$`-prefixed symbol is the strongest signal, then an address, then a ticker written in capitals
         let words: Vec<&str> = text.split_whitespace().collect();
-        for word in &words {
-            let clean_word = word.trim_matches(|c: char| !c.is_alphanumeric());
-            if clean_word.len() >= 2 && clean_word.len() <= 50 && clean_word.chars().all(|c| c.is_alphanumeric()) {
-                // Check if it looks like a Solana address (base58, ~44 chars)
-                if clean_word.len() > 30 {
-                    return Some(clean_word.to_owned());
-                }
-                // Or a typical token symbol
-                if clean_word.len() <= 10 {
-                    return Some(clean_word.to_uppercase());
-                }
-            }
+        let clean = |word: &&str| word.trim_matches(|c: char| !c.is_alphanumeric()).to_owned();
+        let is_symbol = |word: &String| {
+            (2..=10).contains(&word.len())
+                && word.chars().all(|c| c.is_alphanumeric())
+                && word.chars().any(|c| c.is_alphabetic())
+        };
+
+        if let Some(symbol) = words.iter().filter(|w| w.starts_with('$')).map(clean).find(is_symbol) {
+            return Some(symbol.to_uppercase());
+        }
+        if let Some(address) = words
+            .iter()
+            .map(clean)
+            .find(|w| (31..=50).contains(&w.len()) && w.chars().all(|c| c.is_alphanumeric()))
+        {
+            return Some(address);
         }
-        None
+        words
+            .iter()
+            .map(clean)
+            .find(|w| is_symbol(w) && w.chars().all(|c| !c.is_lowercase()))
     }
 
     fn format_islamic_ruling(
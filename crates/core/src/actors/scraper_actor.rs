@@ -4,7 +4,6 @@ use std::time::Duration;
 use std::time::Instant;
 
 use chrono::Utc;
-use reqwest::Client;
 use scraper::Html;
 use scraper::Selector;
 use tokio::sync::Semaphore;
@@ -14,6 +13,7 @@ use tracing::error;
 use tracing::info;
 use tracing::warn;
 
+use crate::http::HttpClient;
 use crate::models::ScrapedData;
 use crate::models::ScrapedDataType;
 use crate::models::ScraperError;
@@ -22,19 +22,27 @@ use crate::models::ScrapingStats;
 
 pub struct ScraperActor {
     receiver: mpsc::Receiver<ScraperMessage>,
-    client: Client,
+    client: HttpClient,
     stats: Arc<Mutex<ScrapingStats>>,
     rate_limiter: Arc<Semaphore>, // Max concurrent requests
 }
 
 impl ScraperActor {
     pub fn new(receiver: mpsc::Receiver<ScraperMessage>) -> Self {
-        let client = Client::builder()
+        let client = reqwest::Client::builder()
             .user_agent("Averroes/1.0 Islamic Token Analyzer")
             .timeout(Duration::from_secs(30))
             .build()
             .expect("Failed to create HTTP client");
 
+        Self::with_http_client(receiver, HttpClient::from_env(client, "scraper"))
+    }
+
+    /// Create the actor with a shared HTTP client, e.g. one replaying a cassette in tests
+    pub fn with_http_client(
+        receiver: mpsc::Receiver<ScraperMessage>,
+        client: HttpClient,
+    ) -> Self {
         let stats = Arc::new(Mutex::new(ScrapingStats {
             total_requests: 0,
             successful_scrapes: 0,
@@ -127,7 +135,7 @@ impl ScraperActor {
         // Add small delay to be respectful
         tokio::time::sleep(Duration::from_millis(100)).await;
 
-        let response = match self.client.get(&url).send().await {
+        let response = match self.client.send(self.client.get(&url)).await {
             Ok(resp) => {
                 if resp.status().is_success() {
                     resp
@@ -142,20 +150,12 @@ impl ScraperActor {
             },
         };
 
-        let content = match response.text().await {
-            Ok(text) => {
-                if text.len() > 1_000_000 {
-                    // 1MB limit
-                    self.increment_failed_scrapes();
-                    return Err(ScraperError::ContentTooLarge);
-                }
-                text
-            },
-            Err(e) => {
-                self.increment_failed_scrapes();
-                return Err(ScraperError::NetworkError(e.to_string()));
-            },
-        };
+        let content = response.text();
+        if content.len() > 1_000_000 {
+            // 1MB limit
+            self.increment_failed_scrapes();
+            return Err(ScraperError::ContentTooLarge);
+        }
 
         // Parse and extract relevant content
         let parsed_content = self.extract_relevant_content(&content, &keywords, &url);
@@ -449,6 +449,21 @@ pub async fn spawn_scraper_actor() -> crate::models::ScraperActorHandle {
     }
 }
 
+/// Spawn a scraper actor that fetches through the given HTTP client
+pub async fn spawn_scraper_actor_with_http_client(client: HttpClient) -> crate::models::ScraperActorHandle {
+    let (sender, receiver) = mpsc::channel(100);
+
+    let mut actor = ScraperActor::with_http_client(receiver, client);
+
+    tokio::spawn(async move {
+        actor.run().await;
+    });
+
+    crate::models::ScraperActorHandle {
+        sender,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     // use tokio_test;
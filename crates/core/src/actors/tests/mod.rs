@@ -1,3 +1,5 @@
+mod gherkin_scenarios;
+
 use std::time::Duration;
 
 use uuid::Uuid;
@@ -25,8 +27,6 @@ mod integration_tests {
         // Create a mock AI service for testing
         let config = crate::AverroesConfig {
             groq_api_key: "test_key".to_owned(),
-            grok_api_key: "".to_owned(),
-            openai_api_key: "".to_owned(),
             preferred_model: "groq".to_owned(),
             ..Default::default()
         };
@@ -282,7 +282,12 @@ mod integration_tests {
             "Expected Riba, Haram, or Syubhat ruling, got {:?}",
             analysis.islamic_analysis.ruling
         );
-        assert!(analysis.islamic_analysis.confidence > 0.2);
+        // Abstaining carries no confidence in a ruling
+        if analysis.islamic_analysis.ruling == IslamicPrinciple::Syubhat {
+            assert_eq!(analysis.islamic_analysis.confidence, 0.0);
+        } else {
+            assert!(analysis.islamic_analysis.confidence > 0.2);
+        }
         assert!(!analysis.islamic_analysis.risk_factors.is_empty());
     }
 
@@ -374,7 +379,7 @@ mod unit_tests {
     async fn test_islamic_analysis_defaults() {
         let analysis = IslamicAnalysis::default();
 
-        assert!(matches!(analysis.ruling, IslamicPrinciple::Mubah));
+        assert!(matches!(analysis.ruling, IslamicPrinciple::Syubhat));
         assert_eq!(analysis.confidence, 0.0);
         assert!(analysis.reasoning.is_empty());
         assert!(analysis.supporting_fatwas.is_empty());
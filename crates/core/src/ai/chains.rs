@@ -1,13 +1,12 @@
-use std::sync::Arc;
-
 use chrono::Utc;
-use tokio::sync::RwLock;
+use tracing::info;
 use uuid::Uuid;
 
+use crate::islamic::FatwaKnowledgeBase;
+use crate::islamic::FatwaQuery;
 use crate::models::analysis::BacktestResult;
 use crate::models::analysis::ScrapedData;
 use crate::models::fatwa::Fatwa;
-use crate::models::fatwa::FatwaSource;
 use crate::models::fatwa::IslamicAnalysis;
 use crate::models::fatwa::IslamicPrinciple;
 use crate::models::messages::ActorError;
@@ -40,12 +39,12 @@ pub struct AnalysisContext {
 pub struct IslamicAnalysisChain {
     #[allow(dead_code)]
     config: IslamicChainConfig,
-    fatwa_database: Arc<RwLock<Vec<Fatwa>>>,
+    fatwa_database: FatwaKnowledgeBase,
 }
 
 impl IslamicAnalysisChain {
     pub async fn new(config: IslamicChainConfig) -> Result<Self, ActorError> {
-        let fatwa_database = Arc::new(RwLock::new(Vec::new()));
+        let fatwa_database = FatwaKnowledgeBase::shared();
 
         Ok(Self {
             config,
@@ -60,31 +59,7 @@ impl IslamicAnalysisChain {
     }
 
     async fn load_fatwa_database(&self) -> Result<(), ActorError> {
-        let mut db = self.fatwa_database.write().await;
-
-        // Add sample fatwas for Islamic finance principles
-        let sample_fatwas = vec![
-            Fatwa {
-                id: Uuid::new_v4(),
-                title: "Prohibition of Riba in Financial Instruments".to_owned(),
-                content: "Interest-based transactions are prohibited in Islamic finance...".to_owned(),
-                source: FatwaSource::MUI,
-                principles_addressed: vec![IslamicPrinciple::Riba],
-                maqashid_relevance: vec![],
-                keywords: vec!["riba".to_owned(), "interest".to_owned(), "finance".to_owned()],
-                language: "en".to_owned(),
-                issued_date: Some(Utc::now()),
-                created_at: Utc::now(),
-                vector_embedding: None,
-                confidence_score: Some(0.9),
-            },
-            // Add more sample fatwas...
-        ];
-
-        for fatwa in sample_fatwas {
-            db.push(fatwa);
-        }
-
+        info!("Fatwa knowledge base holds {} fatwas in force", self.fatwa_database.len());
         Ok(())
     }
 
@@ -165,7 +140,7 @@ impl IslamicAnalysisChain {
                 vec!["High uncertainty detected".to_owned()],
             )
         } else {
-            (IslamicPrinciple::Mubah, 0.6, "No obvious Islamic law violations detected".to_owned(), vec![
+            (IslamicPrinciple::Syubhat, 0.3, "No indicators either way; the ruling is undecided".to_owned(), vec![
                 "Limited data available".to_owned(),
             ])
         };
@@ -178,18 +153,36 @@ impl IslamicAnalysisChain {
             risk_factors,
             recommendations: vec!["Consult with Islamic scholars for detailed analysis".to_owned()],
             maqashid_assessment: vec![],
+            ratio_screening: None,
+            methodology_rulings: vec![],
+            gharar: None,
+            maysir: None,
+            riba: None,
+            stablecoin: None,
+            scripture_references: vec![],
+            ikhtilaf: None,
+            determination: None,
+            review: None,
+            categories: vec![],
+            document_excerpts: vec![],
         })
     }
 
-    /// Mock `search_fatwas` method
+    /// Fatwas in force in `language` that best match the keywords
     pub async fn search_fatwas(
         &self,
-        _keywords: Vec<String>,
-        _language: String,
-        _limit: usize,
+        keywords: Vec<String>,
+        language: String,
+        limit: usize,
     ) -> Result<Vec<Fatwa>, ActorError> {
-        // Mock implementation
-        Ok(vec![])
+        self.fatwa_database
+            .search(&FatwaQuery {
+                terms: keywords,
+                language: Some(language),
+                limit: Some(limit),
+                ..FatwaQuery::default()
+            })
+            .map_err(ActorError::DatabaseError)
     }
 
     pub async fn build_context(
@@ -0,0 +1,825 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use serde_json::json;
+use tracing::debug;
+use tracing::error;
+
+use crate::http::HttpClient;
+use crate::http::HttpError;
+use crate::islamic::rules::load_from_env;
+use crate::reasoning::ModelOutput;
+use crate::reasoning::ReasoningStream;
+use crate::reasoning::TokenUsage;
+use crate::reasoning::completion_budget;
+
+/// Environment variable pointing at a TOML file of extra `[[providers]]` entries
+pub const PROVIDERS_FILE_ENV: &str = "AVERROES_PROVIDERS_FILE";
+
+const DEFAULT_TIMEOUT_MS: u64 = 60_000;
+
+/// Sources the model is asked to consult before ruling
+const REFERENCE_SOURCES: &[&str] = &[
+    "https://www.cryptohalal.cc/currencies/4",
+    "https://sharlife.my/crypto-shariah/crypto/bitcoin",
+    "https://www.islamicfinanceguru.com/crypto",
+    "https://app.practicalislamicfinance.com/reports/crypto/",
+];
+
+// ============================================================================
+// PROVIDER CONFIGURATION
+// ============================================================================
+
+/// One OpenAI-compatible endpoint: a hosted API or a self-hosted server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    /// Name used to select the provider, e.g. as `preferred_model`
+    pub name: String,
+    /// Base URL including the API version, e.g. `https://api.groq.com/openai/v1`
+    pub base_url: String,
+    pub model: String,
+    /// Sent as a bearer token; self-hosted servers usually need none
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Environment variable holding the key, used when `api_key` is unset
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+    /// Extra headers sent with every request
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProvidersFile {
+    #[serde(default)]
+    providers: Vec<ProviderConfig>,
+}
+
+fn default_timeout_ms() -> u64 {
+    DEFAULT_TIMEOUT_MS
+}
+
+impl ProviderConfig {
+    pub fn new(
+        name: &str,
+        base_url: &str,
+        model: &str,
+    ) -> Self {
+        Self {
+            name: name.to_owned(),
+            base_url: base_url.to_owned(),
+            model: model.to_owned(),
+            api_key: None,
+            api_key_env: None,
+            headers: BTreeMap::new(),
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+        }
+    }
+
+    pub fn groq(api_key: String) -> Self {
+        Self::new("groq", "https://api.groq.com/openai/v1", "llama3-8b-8192").with_api_key(api_key)
+    }
+
+    /// xAI's Grok, registered as "grok"
+    pub fn xai(api_key: String) -> Self {
+        Self::new("grok", "https://api.x.ai/v1", "grok-beta").with_api_key(api_key)
+    }
+
+    pub fn openai(api_key: String) -> Self {
+        Self::new("openai", "https://api.openai.com/v1", "gpt-3.5-turbo").with_api_key(api_key)
+    }
+
+    /// Local Ollama server
+    pub fn ollama(model: &str) -> Self {
+        Self::new("ollama", "http://localhost:11434/v1", model)
+    }
+
+    /// vLLM's OpenAI-compatible server
+    pub fn vllm(
+        base_url: &str,
+        model: &str,
+    ) -> Self {
+        Self::new("vllm", base_url, model)
+    }
+
+    /// llama.cpp's `llama-server`, which serves whatever model it was started with
+    pub fn llama_cpp(base_url: &str) -> Self {
+        Self::new("llama_cpp", base_url, "default")
+    }
+
+    pub fn with_api_key(
+        mut self,
+        api_key: String,
+    ) -> Self {
+        self.api_key = Some(api_key);
+        self
+    }
+
+    pub fn with_header(
+        mut self,
+        name: &str,
+        value: &str,
+    ) -> Self {
+        self.headers.insert(name.to_owned(), value.to_owned());
+        self
+    }
+
+    pub fn with_timeout(
+        mut self,
+        timeout: Duration,
+    ) -> Self {
+        self.timeout_ms = timeout.as_millis() as u64;
+        self
+    }
+
+    /// Parse `[[providers]]` entries from TOML
+    pub fn parse_all(raw: &str) -> Result<Vec<Self>, String> {
+        let file: ProvidersFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+        Ok(file.providers)
+    }
+
+    pub fn load_all(path: &Path) -> Result<Vec<Self>, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::parse_all(&raw).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Entries from the file named by `AVERROES_PROVIDERS_FILE`, or none when it is unset
+    pub fn from_env() -> Result<Vec<Self>, String> {
+        load_from_env(PROVIDERS_FILE_ENV, Self::parse_all, Vec::new)
+    }
+
+    fn resolved_api_key(&self) -> Option<String> {
+        self.api_key
+            .clone()
+            .or_else(|| self.api_key_env.as_ref().and_then(|name| std::env::var(name).ok()))
+            .filter(|key| !key.is_empty())
+    }
+
+    fn completions_url(&self) -> String {
+        format!("{}/chat/completions", self.base_url.trim_end_matches('/'))
+    }
+}
+
+// ============================================================================
+// CHAT TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    fn with_role(
+        role: &str,
+        content: &str,
+    ) -> Self {
+        Self {
+            role: role.to_owned(),
+            content: Some(content.to_owned()),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+
+    pub fn system(content: &str) -> Self {
+        Self::with_role("system", content)
+    }
+
+    pub fn user(content: &str) -> Self {
+        Self::with_role("user", content)
+    }
+
+    /// The model's own turn, including any tool calls it made, to send back with tool results
+    pub fn assistant(completion: &ChatCompletion) -> Self {
+        Self {
+            role: "assistant".to_owned(),
+            content: Some(completion.output.answer.clone()).filter(|answer| !answer.is_empty()),
+            tool_calls: completion.tool_calls.clone(),
+            tool_call_id: None,
+        }
+    }
+
+    /// Result of running the tool call `call_id`
+    pub fn tool(
+        call_id: &str,
+        content: &str,
+    ) -> Self {
+        Self {
+            tool_call_id: Some(call_id.to_owned()),
+            ..Self::with_role("tool", content)
+        }
+    }
+}
+
+/// A function the model may call
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    /// JSON Schema of the arguments object
+    pub parameters: Value,
+}
+
+impl ToolDefinition {
+    fn to_json(&self) -> Value {
+        json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.parameters,
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type", default = "function_type")]
+    pub kind: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    /// JSON-encoded arguments, exactly as the model produced them
+    pub arguments: String,
+}
+
+fn function_type() -> String {
+    "function".to_owned()
+}
+
+impl ToolCall {
+    pub fn arguments(&self) -> Result<Value, HttpError> {
+        serde_json::from_str(&self.function.arguments).map_err(|e| HttpError::Body(e.to_string()))
+    }
+}
+
+/// Everything needed for one completion, independent of the provider
+#[derive(Debug, Clone)]
+pub struct ChatRequest {
+    pub messages: Vec<ChatMessage>,
+    pub temperature: f64,
+    /// Budget for the answer; reasoning models get extra room on top, see [`completion_budget`]
+    pub max_tokens: u32,
+    pub tools: Vec<ToolDefinition>,
+}
+
+impl ChatRequest {
+    pub fn new(messages: Vec<ChatMessage>) -> Self {
+        Self {
+            messages,
+            temperature: 0.3,
+            max_tokens: 1000,
+            tools: Vec::new(),
+        }
+    }
+
+    pub fn with_temperature(
+        mut self,
+        temperature: f64,
+    ) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    pub fn with_max_tokens(
+        mut self,
+        max_tokens: u32,
+    ) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    pub fn with_tools(
+        mut self,
+        tools: Vec<ToolDefinition>,
+    ) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    fn body(
+        &self,
+        model: &str,
+        stream: bool,
+    ) -> Value {
+        let mut body = json!({
+            "model": model,
+            "messages": self.messages,
+            "temperature": self.temperature,
+            "max_tokens": completion_budget(model, self.max_tokens),
+            "stream": stream,
+        });
+
+        if !self.tools.is_empty() {
+            body["tools"] = self.tools.iter().map(ToolDefinition::to_json).collect();
+        }
+
+        body
+    }
+}
+
+/// A finished completion: the split answer/reasoning plus any tool calls
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatCompletion {
+    pub output: ModelOutput,
+    pub tool_calls: Vec<ToolCall>,
+    pub finish_reason: Option<String>,
+}
+
+impl ChatCompletion {
+    fn from_response(response: &Value) -> Result<Self, HttpError> {
+        let choice = &response["choices"][0];
+        let tool_calls = match &choice["message"]["tool_calls"] {
+            Value::Null => Vec::new(),
+            calls => serde_json::from_value(calls.clone()).map_err(|e| HttpError::Body(e.to_string()))?,
+        };
+
+        Ok(Self {
+            output: ModelOutput::from_chat_completion(response),
+            tool_calls,
+            finish_reason: choice["finish_reason"].as_str().map(str::to_owned),
+        })
+    }
+}
+
+// ============================================================================
+// CLIENT
+// ============================================================================
+
+/// Chat client for any OpenAI-compatible `/chat/completions` endpoint
+#[derive(Debug, Clone)]
+pub struct ChatClient {
+    config: ProviderConfig,
+    client: HttpClient,
+}
+
+impl ChatClient {
+    pub fn new(config: ProviderConfig) -> Self {
+        Self::with_http_client(config, HttpClient::default())
+    }
+
+    /// Create a client that sends requests through the given (possibly cassette-backed) HTTP client
+    pub fn with_http_client(
+        config: ProviderConfig,
+        client: HttpClient,
+    ) -> Self {
+        Self {
+            config,
+            client,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    pub fn config(&self) -> &ProviderConfig {
+        &self.config
+    }
+
+    /// Run one completion
+    pub async fn chat(
+        &self,
+        request: &ChatRequest,
+    ) -> Result<ChatCompletion, HttpError> {
+        let response = self.client.send(self.request(request, false)).await?.error_for_status()?;
+        let completion = ChatCompletion::from_response(&response.json()?)?;
+
+        debug!(
+            "{} completion: {} answer + {} reasoning tokens",
+            self.config.name, completion.output.usage.answer_tokens, completion.output.usage.reasoning_tokens
+        );
+        Ok(completion)
+    }
+
+    /// Run one completion as a stream, calling `on_delta` with each piece of answer text
+    ///
+    /// Reasoning deltas are collected into the result but never passed to `on_delta`.
+    pub async fn chat_stream<F>(
+        &self,
+        request: &ChatRequest,
+        mut on_delta: F,
+    ) -> Result<ChatCompletion, HttpError>
+    where
+        F: FnMut(&str), {
+        let mut events = StreamAccumulator::default();
+        self.client
+            .send_streaming(self.request(request, true), |bytes| events.feed(bytes, &mut on_delta))
+            .await?
+            .error_for_status()?;
+
+        Ok(events.finish(&mut on_delta))
+    }
+
+    fn request(
+        &self,
+        request: &ChatRequest,
+        stream: bool,
+    ) -> reqwest::RequestBuilder {
+        let mut builder = self
+            .client
+            .post(self.config.completions_url())
+            .timeout(Duration::from_millis(self.config.timeout_ms))
+            .json(&request.body(&self.config.model, stream));
+
+        if let Some(api_key) = self.config.resolved_api_key() {
+            builder = builder.header("Authorization", format!("Bearer {api_key}"));
+        }
+        for (name, value) in &self.config.headers {
+            builder = builder.header(name, value);
+        }
+
+        builder
+    }
+
+    // ========================================================================
+    // ISLAMIC FINANCE PROMPTS
+    // ========================================================================
+
+    /// Analyze Islamic compliance; provider errors come back as an "Error:" answer for the parser to catch
+    pub async fn analyze_islamic_compliance(
+        &self,
+        prompt: &str,
+    ) -> Result<ModelOutput, HttpError> {
+        debug!("Analyzing Islamic compliance with {}: {}", self.config.name, &prompt[..50.min(prompt.len())]);
+
+        let system_prompt = format!(
+            "You are an expert in Islamic finance and Sharia compliance.\n\nThe user will ask the following: \
+             {prompt}\n\nBefore answering the question, please analyze the following sources:\n{}\n\nAnalyze \
+             cryptocurrency and financial instruments based on Islamic principles: no riba (interest), no gharar \
+             (excessive uncertainty), no maysir (gambling), and adherence to maqashid shariah (objectives of Islamic \
+             law). Provide clear, scholarly analysis with references to Islamic sources when possible.",
+            REFERENCE_SOURCES
+                .iter()
+                .enumerate()
+                .map(|(index, url)| format!("{}. {url}", index + 1))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        let request = ChatRequest::new(vec![
+            ChatMessage::system(&system_prompt),
+            ChatMessage::user("Please provide your analysis based on the sources mentioned in the system prompt."),
+        ]);
+
+        match self.chat(&request).await {
+            Ok(completion) => Ok(completion.output.or_answer("Analysis unavailable")),
+            Err(HttpError::Status {
+                status,
+                body,
+            }) => {
+                error!("{} API error: {}", self.config.name, status);
+                error!("Error response: {}", body);
+                Ok(ModelOutput::answer_only(format!(
+                    "Error: Unable to analyze with {}. Status: {status}",
+                    self.config.name
+                )))
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Generate follow-up questions based on analysis
+    pub async fn generate_follow_up_questions(
+        &self,
+        analysis: &str,
+    ) -> Result<Vec<String>, HttpError> {
+        let prompt = format!(
+            "Based on this Islamic finance analysis, generate 3 relevant follow-up questions that would help users \
+             understand the topic better:\n\n{analysis}\n\nProvide only the questions, one per line, without \
+             numbering."
+        );
+        let request = ChatRequest::new(vec![
+            ChatMessage::system(
+                "You are an Islamic finance expert. Generate relevant follow-up questions that help users understand \
+                 Islamic finance concepts better.",
+            ),
+            ChatMessage::user(&prompt),
+        ])
+        .with_temperature(0.5)
+        .with_max_tokens(200);
+
+        let questions = match self.chat(&request).await {
+            Ok(completion) => completion
+                .output
+                .answer
+                .lines()
+                .map(|line| line.trim().to_owned())
+                .filter(|line| !line.is_empty())
+                .take(3)
+                .collect(),
+            Err(HttpError::Status {
+                ..
+            }) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        if questions.is_empty() {
+            Ok(default_follow_up_questions())
+        } else {
+            Ok(questions)
+        }
+    }
+
+    /// Test the API connection
+    pub async fn test_connection(&self) -> Result<bool, HttpError> {
+        let request = ChatRequest::new(vec![ChatMessage::user("Test connection")])
+            .with_temperature(0.1)
+            .with_max_tokens(10);
+
+        match self.chat(&request).await {
+            Ok(_) => Ok(true),
+            Err(HttpError::Status {
+                ..
+            }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn default_follow_up_questions() -> Vec<String> {
+    vec![
+        "Would you like to know more about the Islamic principles involved?".to_owned(),
+        "Are there any specific aspects of this analysis you'd like clarified?".to_owned(),
+        "Would you like to explore alternative Sharia-compliant options?".to_owned(),
+    ]
+}
+
+// ============================================================================
+// STREAMING
+// ============================================================================
+
+/// Reassembles a server-sent event stream of `chat.completion.chunk` objects
+#[derive(Debug, Default)]
+struct StreamAccumulator {
+    /// Bytes of an incomplete line carried over to the next chunk
+    partial: Vec<u8>,
+    content: ReasoningStream,
+    reasoning: String,
+    tool_calls: Vec<ToolCall>,
+    finish_reason: Option<String>,
+    usage: Option<Value>,
+}
+
+impl StreamAccumulator {
+    fn feed(
+        &mut self,
+        bytes: &[u8],
+        on_delta: &mut impl FnMut(&str),
+    ) {
+        self.partial.extend_from_slice(bytes);
+
+        while let Some(end) = self.partial.iter().position(|byte| *byte == b'\n') {
+            let line = self.partial.drain(..=end).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                self.event(data.trim(), on_delta);
+            }
+        }
+    }
+
+    fn event(
+        &mut self,
+        data: &str,
+        on_delta: &mut impl FnMut(&str),
+    ) {
+        if data == "[DONE]" {
+            return;
+        }
+        let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+            debug!("Skipping malformed stream event: {data}");
+            return;
+        };
+
+        if !chunk["usage"].is_null() {
+            self.usage = Some(chunk["usage"].clone());
+        }
+
+        let choice = &chunk["choices"][0];
+        let delta = &choice["delta"];
+
+        if let Some(text) = delta["reasoning_content"].as_str().or_else(|| delta["reasoning"].as_str()) {
+            self.reasoning.push_str(text);
+        }
+
+        if let Some(text) = delta["content"].as_str() {
+            let visible = self.content.push(text);
+            if !visible.is_empty() {
+                on_delta(&visible);
+            }
+        }
+
+        // Tool calls arrive in pieces keyed by index; arguments are concatenated
+        for call in delta["tool_calls"].as_array().into_iter().flatten() {
+            let index = call["index"].as_u64().unwrap_or(0) as usize;
+            while self.tool_calls.len() <= index {
+                self.tool_calls.push(ToolCall {
+                    id: String::new(),
+                    kind: function_type(),
+                    function: FunctionCall {
+                        name: String::new(),
+                        arguments: String::new(),
+                    },
+                });
+            }
+
+            let entry = &mut self.tool_calls[index];
+            if let Some(id) = call["id"].as_str() {
+                id.clone_into(&mut entry.id);
+            }
+            if let Some(name) = call["function"]["name"].as_str() {
+                entry.function.name.push_str(name);
+            }
+            if let Some(arguments) = call["function"]["arguments"].as_str() {
+                entry.function.arguments.push_str(arguments);
+            }
+        }
+
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            self.finish_reason = Some(reason.to_owned());
+        }
+    }
+
+    fn finish(
+        mut self,
+        on_delta: &mut impl FnMut(&str),
+    ) -> ChatCompletion {
+        // A final event without a trailing newline
+        if !self.partial.is_empty() {
+            self.feed(b"\n", on_delta);
+        }
+
+        let (rest, mut output) = self.content.finish();
+        if !rest.is_empty() {
+            on_delta(&rest);
+        }
+
+        let separate = self.reasoning.trim();
+        if !separate.is_empty() {
+            output.reasoning = Some(match output.reasoning {
+                Some(inline) => format!("{separate}\n\n{inline}"),
+                None => separate.to_owned(),
+            });
+        }
+        output.usage = match &self.usage {
+            Some(usage) => TokenUsage::from_usage_json(usage, &output.answer, output.reasoning.as_deref()),
+            None => TokenUsage::estimate(&output.answer, output.reasoning.as_deref()),
+        };
+
+        ChatCompletion {
+            output,
+            tool_calls: self.tool_calls,
+            finish_reason: self.finish_reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::body_partial_json;
+    use wiremock::matchers::header;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+
+    use super::*;
+
+    fn local_provider(server: &MockServer) -> ProviderConfig {
+        ProviderConfig::new("local", &format!("{}/v1", server.uri()), "deepseek-r1:8b")
+            .with_api_key("local-key".to_owned())
+            .with_header("X-Tenant", "averroes")
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_tool_calls() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("Authorization", "Bearer local-key"))
+            .and(header("X-Tenant", "averroes"))
+            .and(body_partial_json(json!({
+                "model": "deepseek-r1:8b",
+                "stream": false,
+                "tools": [{ "type": "function", "function": { "name": "get_token_info" } }]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{
+                    "message": {
+                        "role": "assistant",
+                        "content": "<think>Need market data first.</think>",
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": { "name": "get_token_info", "arguments": "{\"symbol\":\"SOL\"}" }
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ChatClient::new(local_provider(&server));
+        let request = ChatRequest::new(vec![ChatMessage::user("Is SOL halal?")]).with_tools(vec![ToolDefinition {
+            name: "get_token_info".to_owned(),
+            description: "Look up a token".to_owned(),
+            parameters: json!({ "type": "object", "properties": { "symbol": { "type": "string" } } }),
+        }]);
+        let completion = client.chat(&request).await.unwrap();
+
+        assert_eq!(completion.finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(completion.output.answer, "");
+        assert_eq!(completion.output.reasoning.as_deref(), Some("Need market data first."));
+        assert_eq!(completion.tool_calls[0].function.name, "get_token_info");
+        assert_eq!(completion.tool_calls[0].arguments().unwrap(), json!({ "symbol": "SOL" }));
+
+        // The assistant turn round-trips with its tool calls and no empty content
+        let turn = serde_json::to_value(ChatMessage::assistant(&completion)).unwrap();
+        assert_eq!(turn["content"], Value::Null);
+        assert_eq!(turn["tool_calls"][0]["id"], "call_1");
+    }
+
+    #[tokio::test]
+    async fn test_stream_hides_reasoning_and_joins_tool_calls() {
+        let events = [
+            r#"{"choices":[{"delta":{"content":"<think>Check riba"}}]}"#,
+            r#"{"choices":[{"delta":{"content":" first</think>RULING: "}}]}"#,
+            r#"{"choices":[{"delta":{"content":"HALAL"}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_9","function":{"name":"cite","arguments":"{\"n\":"}}]}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"1}"}}]},"finish_reason":"stop"}]}"#,
+        ];
+        let body = events
+            .iter()
+            .chain(&["[DONE]"])
+            .map(|event| format!("data: {event}\n\n"))
+            .collect::<String>();
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({ "stream": true })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let client = ChatClient::new(local_provider(&server));
+        let mut shown = String::new();
+        let completion = client
+            .chat_stream(&ChatRequest::new(vec![ChatMessage::user("SOL?")]), |delta| shown.push_str(delta))
+            .await
+            .unwrap();
+
+        assert_eq!(shown, "RULING: HALAL");
+        assert_eq!(completion.output.answer, "RULING: HALAL");
+        assert_eq!(completion.output.reasoning.as_deref(), Some("Check riba first"));
+        assert_eq!(completion.tool_calls[0].id, "call_9");
+        assert_eq!(completion.tool_calls[0].function.arguments, "{\"n\":1}");
+        assert_eq!(completion.finish_reason.as_deref(), Some("stop"));
+    }
+
+    #[tokio::test]
+    async fn test_provider_error_becomes_error_answer() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("overloaded"))
+            .mount(&server)
+            .await;
+
+        let client = ChatClient::new(local_provider(&server));
+        let output = client.analyze_islamic_compliance("Token: PEPE").await.unwrap();
+
+        assert_eq!(output.answer, "Error: Unable to analyze with local. Status: 503");
+        assert!(!client.test_connection().await.unwrap());
+    }
+
+    #[test]
+    fn test_example_providers_file_parses() {
+        let providers = ProviderConfig::parse_all(include_str!("../../providers.example.toml")).unwrap();
+        let names = providers.iter().map(|p| p.name.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["groq", "grok", "openai", "ollama", "vllm", "llama_cpp"]);
+        assert_eq!(providers[0].api_key_env.as_deref(), Some("GROQ_API_KEY"));
+        assert_eq!(providers[3], ProviderConfig::ollama("deepseek-r1:8b"));
+    }
+}
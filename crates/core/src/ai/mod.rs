@@ -7,17 +7,15 @@
 // - Fine-tuned Islamic finance models
 
 pub mod chains;
+pub mod chat_client;
 pub mod embeddings;
-pub mod grok_client;
-pub mod groq_client;
 pub mod models;
-pub mod openai_client;
+pub mod prompt_guard;
 pub mod service;
 
 pub use chains::*;
+pub use chat_client::*;
 pub use embeddings::*;
-pub use grok_client::*;
-pub use groq_client::*;
 pub use models::*;
-pub use openai_client::*;
+pub use prompt_guard::*;
 pub use service::*;
@@ -6,10 +6,13 @@ use tokio::sync::RwLock;
 use tracing::debug;
 use tracing::info;
 
+use crate::islamic::IslamicAnalyzer;
+use crate::islamic::TokenFacts;
+use crate::mock::ScriptedModel;
 use crate::models::analysis::ScrapedData;
 use crate::models::fatwa::IslamicAnalysis;
 use crate::models::fatwa::IslamicPrinciple;
-use crate::models::fatwa::MaqashidPrinciple;
+use crate::reasoning::split_reasoning;
 
 /// Trait for language models used in Islamic finance analysis
 #[async_trait]
@@ -113,10 +116,37 @@ impl LanguageModel for OpenAIModel {
     }
 }
 
+/// Scenario-driven mock, see [`crate::mock`]
+#[async_trait]
+impl LanguageModel for ScriptedModel {
+    async fn complete(
+        &self,
+        prompt: &str,
+    ) -> Result<String, String> {
+        // Scenarios may script a reasoning trace; callers of this trait only see the answer
+        ScriptedModel::complete(self, prompt)
+            .await
+            .map(|text| split_reasoning(&text).0)
+            .map_err(|e| e.to_string())
+    }
+
+    async fn generate_follow_up_questions(
+        &self,
+        _response: &str,
+    ) -> Result<Vec<String>, String> {
+        let questions = self.follow_up_questions();
+        if questions.is_empty() {
+            Err(format!("Mock scenario '{}' has no follow-up questions", self.name()))
+        } else {
+            Ok(questions)
+        }
+    }
+}
+
 /// Parse AI model response into structured Islamic analysis
 pub async fn parse_islamic_analysis(
     response: &str,
-    _scraped_data: &[ScrapedData],
+    scraped_data: &[ScrapedData],
     _model: &dyn LanguageModel,
 ) -> Result<IslamicAnalysis, String> {
     // Extract ruling
@@ -133,7 +163,8 @@ pub async fn parse_islamic_analysis(
     } else if response.to_lowercase().contains("maysir") {
         IslamicPrinciple::Maysir
     } else {
-        IslamicPrinciple::Mubah
+        // No ruling stated; leave it undecided rather than permissible
+        IslamicPrinciple::Syubhat
     };
 
     // Extract confidence (look for decimal numbers)
@@ -164,8 +195,17 @@ pub async fn parse_islamic_analysis(
     let risk_factors = vec!["Analysis requires further review".to_owned()];
     let recommendations = vec!["Consult with Islamic scholars for detailed guidance".to_owned()];
 
+    // Maqashid evidence comes from the project's own material, not from the model's answer
+    let project_text = scraped_data
+        .iter()
+        .map(|data| data.content.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let maqashid_assessment =
+        IslamicAnalyzer::new().maqashid_assessment(&TokenFacts::new().text("text", &project_text));
+
     Ok(IslamicAnalysis {
-        ruling: ruling.clone(),
+        ruling,
         confidence: confidence.min(1.0),
         reasoning: if reasoning.is_empty() {
             "Analisis berdasarkan prinsip-prinsip Islam dan Maqashid Syariah".to_owned()
@@ -175,16 +215,19 @@ pub async fn parse_islamic_analysis(
         supporting_fatwas: Vec::new(),
         risk_factors,
         recommendations,
-        maqashid_assessment: vec![MaqashidPrinciple {
-            name: "Hifz al-Mal (Preservation of Wealth)".to_owned(),
-            category: "Financial Security".to_owned(),
-            description: "Protecting wealth from harmful activities".to_owned(),
-            relevance_score: match ruling {
-                IslamicPrinciple::Halal => 0.9,
-                IslamicPrinciple::Haram => 0.1,
-                _ => 0.5,
-            },
-        }],
+        maqashid_assessment,
+        ratio_screening: None,
+        methodology_rulings: vec![],
+        gharar: None,
+        maysir: None,
+        riba: None,
+        stablecoin: None,
+        scripture_references: vec![],
+        ikhtilaf: None,
+        determination: None,
+        review: None,
+        categories: vec![],
+        document_excerpts: vec![],
     })
 }
 
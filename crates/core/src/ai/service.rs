@@ -1,132 +1,140 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 use async_trait::async_trait;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
 
 use crate::AverroesConfig;
-use crate::ai::grok_client::GrokClient;
-use crate::ai::groq_client::GroqClient;
+use crate::ai::chat_client::ChatClient;
+use crate::ai::chat_client::ProviderConfig;
 use crate::ai::models::LanguageModel;
-use crate::ai::openai_client::OpenAIClient;
-use crate::models::token::BlockchainNetwork;
-use crate::models::token::TokenMetadata;
-use crate::models::token::TokenPriceData;
-use crate::models::token::TokenStandard;
-use crate::models::token::UniversalTokenInfo;
+use crate::http::HttpClient;
+use crate::mock::MockModelError;
+use crate::mock::ScriptedModel;
+use crate::reasoning::ModelOutput;
+
+/// Retries after a rate limit before the call is reported as failed
+const MAX_RATE_LIMIT_RETRIES: u32 = 2;
+/// Longest `retry_after` honoured; a model asking for more is treated as failed
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(10);
 
 /// Unified AI service that can use different models
 pub struct AIService {
-    groq_client: Option<GroqClient>,
-    grok_client: Option<GrokClient>,
-    openai_client: Option<OpenAIClient>,
+    /// OpenAI-compatible providers, in fallback order
+    providers: Vec<ChatClient>,
+    http_client: HttpClient,
+    scripted_model: Option<Arc<ScriptedModel>>,
     preferred_model: String,
 }
 
 impl AIService {
     pub async fn new(config: &AverroesConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::with_http_client(config, HttpClient::from_env(reqwest::Client::new(), "ai_service")).await
+    }
+
+    /// Build the service with a shared HTTP client, e.g. one replaying a cassette in tests
+    ///
+    /// Providers come from the Groq key in `config` and the `GROK_API_KEY`/`OPENAI_API_KEY` environment variables, then
+    /// from the `AVERROES_PROVIDERS_FILE` entries, which replace a keyed provider of the same name.
+    pub async fn with_http_client(
+        config: &AverroesConfig,
+        http_client: HttpClient,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         info!("Initializing AI service with preferred model: {}", config.preferred_model);
 
-        let groq_client = if !config.groq_api_key.is_empty() {
-            Some(GroqClient::new(config.groq_api_key.clone()))
-        } else {
-            None
+        // A scenario file always wins; "mock" without one uses the bundled scenario
+        let scripted_model = match ScriptedModel::from_env()? {
+            Some(model) => Some(Arc::new(model)),
+            None if config.preferred_model == "mock" => Some(Arc::new(ScriptedModel::builtin())),
+            None => None,
         };
 
-        let grok_client = if !config.grok_api_key.is_empty() {
-            Some(GrokClient::new(config.grok_api_key.clone()))
-        } else {
-            None
+        let mut service = Self {
+            providers: Vec::new(),
+            http_client,
+            scripted_model,
+            preferred_model: config.preferred_model.clone(),
         };
+        if !config.groq_api_key.is_empty() {
+            service = service.with_provider(ProviderConfig::groq(config.groq_api_key.clone()));
+        }
+        // Only the Groq key is part of the exported config; the others come from the environment
+        if let Some(api_key) = env_key("GROK_API_KEY") {
+            service = service.with_provider(ProviderConfig::xai(api_key));
+        }
+        if let Some(api_key) = env_key("OPENAI_API_KEY") {
+            service = service.with_provider(ProviderConfig::openai(api_key));
+        }
+        for provider in ProviderConfig::from_env()? {
+            service = service.with_provider(provider);
+        }
 
-        let openai_client = if !config.openai_api_key.is_empty() {
-            Some(OpenAIClient::new(config.openai_api_key.clone()))
-        } else {
-            None
-        };
+        Ok(service)
+    }
 
-        Ok(Self {
-            groq_client,
-            grok_client,
-            openai_client,
-            preferred_model: config.preferred_model.clone(),
-        })
+    /// Add an OpenAI-compatible provider, replacing any existing one with the same name
+    pub fn with_provider(
+        mut self,
+        config: ProviderConfig,
+    ) -> Self {
+        let client = ChatClient::with_http_client(config, self.http_client.clone());
+        match self.providers.iter_mut().find(|existing| existing.name() == client.name()) {
+            Some(existing) => *existing = client,
+            None => self.providers.push(client),
+        }
+        self
+    }
+
+    /// Answer from a scripted scenario, used when `preferred_model` is "mock" and as the last fallback
+    pub fn with_scripted_model(
+        mut self,
+        model: Arc<ScriptedModel>,
+    ) -> Self {
+        self.scripted_model = Some(model);
+        self
     }
 
-    /// Analyze Islamic compliance using the preferred model
+    fn provider(
+        &self,
+        name: &str,
+    ) -> Option<&ChatClient> {
+        self.providers.iter().find(|client| client.name() == name)
+    }
+
+    /// Analyze Islamic compliance using the preferred model, returning the answer only
     pub async fn analyze_islamic_compliance(
         &self,
         prompt: &str,
     ) -> Result<String, String> {
+        self.analyze_with_reasoning(prompt).await.map(|output| output.answer)
+    }
+
+    /// Analyze Islamic compliance, keeping the model's reasoning trace apart from its answer
+    pub async fn analyze_with_reasoning(
+        &self,
+        prompt: &str,
+    ) -> Result<ModelOutput, String> {
         debug!("Analyzing Islamic compliance with preferred model: {}", self.preferred_model);
 
-        match self.preferred_model.as_str() {
-            "groq" => {
-                if let Some(client) = &self.groq_client {
-                    client
-                        .analyze_islamic_compliance(prompt)
-                        .await
-                        .map_err(|e| format!("Groq analysis failed: {e}"))
-                } else {
-                    self.fallback_analysis(prompt).await
-                }
-            },
-            "grok" => {
-                if let Some(client) = &self.grok_client {
-                    client
-                        .analyze_islamic_compliance(prompt)
-                        .await
-                        .map_err(|e| format!("Grok analysis failed: {e}"))
-                } else {
-                    self.fallback_analysis(prompt).await
-                }
-            },
-            "openai" => {
-                if let Some(client) = &self.openai_client {
-                    // Create a dummy token info for analysis
-                    let token_info = UniversalTokenInfo {
-                        address: "QUERY".to_owned(),
-                        metadata: TokenMetadata {
-                            name: "General Query".to_owned(),
-                            symbol: "QUERY".to_owned(),
-                            contract_address: "QUERY".to_owned(),
-                            decimals: 0,
-                            description: Some(prompt.to_owned()),
-                            image_url: None,
-                            creator: None,
-                            verified: false,
-                            token_standard: TokenStandard::Other {
-                                name: "General".to_owned(),
-                            },
-                            blockchain: BlockchainNetwork::Other {
-                                name: "Unknown".to_owned(),
-                            },
-                        },
-                        price_data: Some(TokenPriceData {
-                            price_usd: 0.0,
-                            price_change_24h: 0.0,
-                            volume_24h: 0,
-                            market_cap: 0,
-                            total_supply: Some(0.0),
-                            last_updated: 0,
-                        }),
-                        holders: None,
-                        liquidity_pools: vec![],
-                        is_verified: false,
-                        risk_score: None,
-                        blockchain: BlockchainNetwork::Other {
-                            name: "Unknown".to_owned(),
-                        },
-                    };
-                    client
-                        .analyze_islamic_compliance(&token_info)
-                        .await
-                        .map_err(|e| format!("OpenAI analysis failed: {e}"))
-                } else {
-                    self.fallback_analysis(prompt).await
-                }
-            },
-            _ => {
-                error!("Unknown preferred model: {}", self.preferred_model);
+        if self.preferred_model == "mock" {
+            return match &self.scripted_model {
+                Some(model) => complete_with_retry(model, prompt)
+                    .await
+                    .map(|text| ModelOutput::from_text(&text))
+                    .map_err(|e| format!("Mock analysis failed: {e}")),
+                None => self.fallback_analysis(prompt).await,
+            };
+        }
+
+        match self.provider(&self.preferred_model) {
+            Some(client) => client
+                .analyze_islamic_compliance(prompt)
+                .await
+                .map_err(|e| format!("{} analysis failed: {e}", client.name())),
+            None => {
+                error!("Preferred model '{}' is not configured", self.preferred_model);
                 self.fallback_analysis(prompt).await
             },
         }
@@ -137,82 +145,43 @@ impl AIService {
         &self,
         analysis: &str,
     ) -> Result<Vec<String>, String> {
-        match self.preferred_model.as_str() {
-            "groq" => {
-                if let Some(client) = &self.groq_client {
-                    client
-                        .generate_follow_up_questions(analysis)
-                        .await
-                        .map_err(|e| format!("Groq follow-up generation failed: {e}"))
-                } else {
-                    Ok(self.default_follow_up_questions())
-                }
-            },
-            "grok" => {
-                if let Some(client) = &self.grok_client {
-                    client
-                        .generate_follow_up_questions(analysis)
-                        .await
-                        .map_err(|e| format!("Grok follow-up generation failed: {e}"))
-                } else {
-                    Ok(self.default_follow_up_questions())
-                }
-            },
-            "openai" => {
-                if let Some(_client) = &self.openai_client {
-                    // OpenAI client doesn't have follow-up questions method, use default
-                    Ok(self.default_follow_up_questions())
-                } else {
-                    Ok(self.default_follow_up_questions())
-                }
-            },
-            _ => Ok(self.default_follow_up_questions()),
+        if self.preferred_model == "mock" {
+            return match &self.scripted_model {
+                Some(model) => LanguageModel::generate_follow_up_questions(model.as_ref(), analysis).await,
+                None => Ok(self.default_follow_up_questions()),
+            };
+        }
+
+        match self.provider(&self.preferred_model) {
+            Some(client) => client
+                .generate_follow_up_questions(analysis)
+                .await
+                .map_err(|e| format!("{} follow-up generation failed: {e}", client.name())),
+            None => Ok(self.default_follow_up_questions()),
         }
     }
 
     /// Test connection to the preferred model
     pub async fn test_connection(&self) -> Result<bool, String> {
-        match self.preferred_model.as_str() {
-            "groq" => {
-                if let Some(client) = &self.groq_client {
-                    client
-                        .test_connection()
-                        .await
-                        .map_err(|e| format!("Groq connection test failed: {e}"))
-                } else {
-                    Ok(false)
-                }
-            },
-            "grok" => {
-                if let Some(client) = &self.grok_client {
-                    client
-                        .test_connection()
-                        .await
-                        .map_err(|e| format!("Grok connection test failed: {e}"))
-                } else {
-                    Ok(false)
-                }
-            },
-            "openai" => {
-                // OpenAI client doesn't have a test method, assume it works if initialized
-                Ok(self.openai_client.is_some())
-            },
-            _ => Ok(false),
+        if self.preferred_model == "mock" {
+            return Ok(self.scripted_model.is_some());
+        }
+
+        match self.provider(&self.preferred_model) {
+            Some(client) => client
+                .test_connection()
+                .await
+                .map_err(|e| format!("{} connection test failed: {e}", client.name())),
+            None => Ok(false),
         }
     }
 
     /// Get available models
     pub fn get_available_models(&self) -> Vec<String> {
-        let mut models = Vec::new();
+        let mut models = self.providers.iter().map(|client| client.name().to_owned()).collect::<Vec<_>>();
 
-        if self.groq_client.is_some() {
-            models.push("groq".to_owned());
-        }
-        if self.grok_client.is_some() {
-            models.push("grok".to_owned());
-        }
-        if self.openai_client.is_some() {
-            models.push("openai".to_owned());
+        if self.scripted_model.is_some() {
+            models.push("mock".to_owned());
         }
 
         models
@@ -222,71 +191,30 @@ impl AIService {
     async fn fallback_analysis(
         &self,
         prompt: &str,
-    ) -> Result<String, String> {
+    ) -> Result<ModelOutput, String> {
         info!("Attempting fallback analysis");
 
-        // Try Groq first
-        if let Some(client) = &self.groq_client {
-            match client.analyze_islamic_compliance(prompt).await {
-                Ok(result) => return Ok(result),
-                Err(e) => error!("Groq fallback failed: {}", e),
-            }
-        }
-
-        // Try Grok second
-        if let Some(client) = &self.grok_client {
+        // Providers in configuration order
+        for client in &self.providers {
             match client.analyze_islamic_compliance(prompt).await {
                 Ok(result) => return Ok(result),
-                Err(e) => error!("Grok fallback failed: {}", e),
+                Err(e) => error!("{} fallback failed: {}", client.name(), e),
             }
         }
 
-        // Try OpenAI last
-        if let Some(client) = &self.openai_client {
-            let token_info = UniversalTokenInfo {
-                address: "QUERY".to_owned(),
-                metadata: TokenMetadata {
-                    name: "General Query".to_owned(),
-                    symbol: "QUERY".to_owned(),
-                    contract_address: "QUERY".to_owned(),
-                    decimals: 0,
-                    description: Some(prompt.to_owned()),
-                    image_url: None,
-                    creator: None,
-                    verified: false,
-                    token_standard: TokenStandard::Other {
-                        name: "General".to_owned(),
-                    },
-                    blockchain: BlockchainNetwork::Other {
-                        name: "Unknown".to_owned(),
-                    },
-                },
-                price_data: Some(TokenPriceData {
-                    price_usd: 0.0,
-                    price_change_24h: 0.0,
-                    volume_24h: 0,
-                    market_cap: 0,
-                    total_supply: Some(0.0),
-                    last_updated: 0,
-                }),
-                holders: None,
-                liquidity_pools: vec![],
-                is_verified: false,
-                risk_score: None,
-                blockchain: BlockchainNetwork::Other {
-                    name: "Unknown".to_owned(),
-                },
-            };
-            match client.analyze_islamic_compliance(&token_info).await {
-                Ok(result) => return Ok(result),
-                Err(e) => error!("OpenAI fallback failed: {}", e),
-            }
+        // Scripted scenario, so tests can decide what the last resort says
+        if let Some(model) = &self.scripted_model {
+            return complete_with_retry(model, prompt)
+                .await
+                .map(|text| ModelOutput::from_text(&text))
+                .map_err(|e| format!("Mock fallback failed: {e}"));
         }
 
         // If all fail, return a default response
-        Ok("I apologize, but I'm currently unable to analyze this request due to service limitations. Please try \
-            again later or contact support."
-            .to_owned())
+        Ok(ModelOutput::answer_only(
+            "I apologize, but I'm currently unable to analyze this request due to service limitations. Please try \
+             again later or contact support.",
+        ))
     }
 
     /// Default follow-up questions when AI generation fails
@@ -299,6 +227,31 @@ impl AIService {
     }
 }
 
+/// Complete `prompt`, waiting out up to [`MAX_RATE_LIMIT_RETRIES`] rate limits
+async fn complete_with_retry(
+    model: &ScriptedModel,
+    prompt: &str,
+) -> Result<String, MockModelError> {
+    let mut retries = 0;
+    loop {
+        match model.complete(prompt).await {
+            Err(MockModelError::RateLimited {
+                retry_after_ms,
+            }) if retries < MAX_RATE_LIMIT_RETRIES && Duration::from_millis(retry_after_ms) <= MAX_RETRY_WAIT => {
+                retries += 1;
+                info!("Model rate limited, retrying in {}ms ({}/{})", retry_after_ms, retries, MAX_RATE_LIMIT_RETRIES);
+                tokio::time::sleep(Duration::from_millis(retry_after_ms)).await;
+            },
+            result => return result,
+        }
+    }
+}
+
+/// A non-empty API key from the environment
+fn env_key(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|api_key| !api_key.is_empty())
+}
+
 #[async_trait]
 impl LanguageModel for AIService {
     async fn complete(
@@ -1,11 +1,22 @@
+use crate::actors::analyzer_actor::ComprehensiveAnalysis;
+use crate::ai::ChatClient;
+use crate::islamic::IslamicAnalyzer;
+use crate::models::CategoryLabel;
+use crate::models::SolanaError;
+use crate::models::UniversalTokenInfo;
+use crate::solana::SolanaTokenAnalyzer;
+
 pub struct EnhancedAnalyzer {
     islamic_analyzer: IslamicAnalyzer,
-    openai_client: Option<OpenAIClient>,
+    ai_client: Option<ChatClient>,
     solana_analyzer: SolanaTokenAnalyzer,
 }
 
 impl EnhancedAnalyzer {
-    pub async fn comprehensive_analysis(&self, token_identifier: &str) -> Result<ComprehensiveAnalysis, AnalysisError> {
+    pub async fn comprehensive_analysis(
+        &self,
+        token_identifier: &str,
+    ) -> Result<ComprehensiveAnalysis, SolanaError> {
         // 1. Get token info from Solana/CoinGecko
         let token_info = self.solana_analyzer.get_token_info(token_identifier).await?;
 
@@ -13,8 +24,12 @@ impl EnhancedAnalyzer {
         let islamic_analysis = self.islamic_analyzer.analyze_token(&token_info).await;
 
         // 3. AI enhancement (if available)
-        let ai_reasoning = if let Some(ai_client) = &self.openai_client {
-            ai_client.analyze_islamic_compliance(&token_info).await.ok()
+        let ai_reasoning = if let Some(ai_client) = &self.ai_client {
+            ai_client
+                .analyze_islamic_compliance(&token_prompt(&token_info, &islamic_analysis.categories))
+                .await
+                .ok()
+                .map(|output| output.answer)
         } else {
             None
         };
@@ -28,4 +43,31 @@ impl EnhancedAnalyzer {
             analysis_id: uuid::Uuid::new_v4(),
         })
     }
-}
\ No newline at end of file
+}
+/// Ask the model about one token's metadata, category and price
+fn token_prompt(
+    token_info: &UniversalTokenInfo,
+    categories: &[CategoryLabel],
+) -> String {
+    let category = if categories.is_empty() {
+        "Unclassified".to_owned()
+    } else {
+        categories
+            .iter()
+            .map(|label| format!("{:?} ({:.0}%)", label.category, label.confidence * 100.0))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        "As an Islamic finance expert, analyze this cryptocurrency token for Sharia compliance:\n\nToken: {} \
+         ({})\nDescription: {}\nCategory: {}\nCurrent Price: ${:.4}\n\nPlease provide:\n1. Halal/Haram \
+         determination\n2. Islamic reasoning based on Quran and Hadith\n3. Risk factors from Islamic perspective\n4. \
+         Confidence level (0-100%)\n\nBe concise but thorough. Focus on Islamic principles like avoiding riba, \
+         gharar, and maysir.",
+        token_info.metadata.name,
+        token_info.metadata.symbol,
+        token_info.metadata.description.as_deref().unwrap_or("No description available"),
+        category,
+        token_info.price_data.as_ref().map(|p| p.price_usd).unwrap_or(0.0)
+    )
+}
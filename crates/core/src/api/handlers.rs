@@ -343,6 +343,7 @@ pub async fn handle_follow_up_query(
         timestamp: chrono::Utc::now().timestamp_millis() as u64,
         language: request.language.unwrap_or_else(|| "id".to_owned()),
         context: Some(request.context.unwrap_or_default()),
+        preferences: None,
     };
 
     match state.query_actor.process_query(query).await {
@@ -1,13 +1,16 @@
 use std::collections::HashMap;
 
 use axum::Router;
+use axum::body::Bytes;
 use axum::extract::Path;
 use axum::extract::Query;
 use axum::extract::State;
+use axum::http::HeaderMap;
 use axum::http::StatusCode;
 use axum::response::Json;
 use axum::routing::get;
 use axum::routing::post;
+use axum::routing::put;
 use serde::Deserialize;
 use serde::Serialize;
 use tracing::error;
@@ -15,13 +18,47 @@ use tracing::info;
 use uuid::Uuid;
 
 use super::AppState;
+use crate::http::HttpClient;
+use crate::islamic::DocumentError;
+use crate::islamic::DocumentIngester;
+use crate::islamic::DocumentMatch;
+use crate::islamic::DocumentStore;
+use crate::islamic::FatwaKnowledgeBase;
+use crate::islamic::FatwaQuery;
+use crate::islamic::FatwaRecord;
+use crate::islamic::ImportFormat;
+use crate::islamic::ImportReport;
+use crate::islamic::IslamicAnalyzer;
+use crate::islamic::ReviewError;
+use crate::islamic::ReviewLog;
+use crate::islamic::ReviewSubmission;
+use crate::islamic::ReviewerAccount;
+use crate::islamic::ReviewerRegistry;
+use crate::islamic::ZakatConfig;
+use crate::islamic::detect_format;
+use crate::islamic::mint_key;
+use crate::islamic::purify_from_env;
 use crate::models::BacktestResult;
+use crate::models::CategoryLabel;
+use crate::models::DocumentExcerpt;
+use crate::models::DocumentFormat;
+use crate::models::Fatwa;
 use crate::models::HistoryQuery;
 use crate::models::IslamicPrinciple;
+use crate::models::ProjectDocument;
+use crate::models::PurificationRequest;
+use crate::models::PurificationResult;
 use crate::models::Query as FiqhQuery;
+use crate::models::QueryResponse;
+use crate::models::ReviewDecision;
+use crate::models::ScriptureReference;
+use crate::models::ShariaPreferences;
 use crate::models::SolanaTokenInfo;
 use crate::models::TokenAnalysis;
+use crate::models::TokenCategory;
 use crate::models::UserAnalysisStats;
+use crate::models::ZakatRequest;
+use crate::models::ZakatResult;
 
 /// Shared application state
 /// Create the main API router
@@ -38,6 +75,30 @@ pub fn create_router(state: AppState) -> Router {
         .route("/history/user/:user_id", get(get_user_history))
         .route("/history/token/:token", get(get_token_history))
         .route("/history/stats/:user_id", get(get_user_stats))
+        // Madhab and screening standards to rule by
+        .route("/preferences/:user_id", get(get_preferences))
+        .route("/preferences/:user_id", put(save_preferences))
+        // Islamic finance tools
+        .route("/purification", post(purify_holding))
+        .route("/zakat", post(calculate_zakat))
+        .route("/scripture", get(lookup_scripture))
+        .route("/scripture/search", get(search_scripture))
+        // Fatwa knowledge base
+        .route("/fatwas", get(search_fatwas))
+        .route("/fatwas", post(add_fatwa))
+        .route("/fatwas/import", post(import_fatwas))
+        .route("/fatwas/:id", get(get_fatwa))
+        .route("/fatwas/:id", put(revise_fatwa))
+        .route("/fatwas/:id/versions", get(get_fatwa_versions))
+        // Scholar review of rulings
+        .route("/reviews", get(list_reviews))
+        .route("/reviews", post(submit_review))
+        .route("/reviews/:token", get(get_token_reviews))
+        // Project documents
+        .route("/documents", post(upload_document))
+        .route("/documents/fetch", post(fetch_document))
+        .route("/documents/:token", get(get_token_documents))
+        .route("/documents/:token/search", get(search_documents))
         // Audio analysis endpoint
         .route("/analyze/audio", post(analyze_audio))
         // Contract address analysis
@@ -73,14 +134,7 @@ async fn analyze_token(
         Ok(response) => {
             let analysis_response = AnalysisResponse {
                 token,
-                ruling: format!(
-                    "{:?}",
-                    response
-                        .analysis_id
-                        .as_ref()
-                        .map(|_| IslamicPrinciple::Halal)
-                        .unwrap_or(IslamicPrinciple::Mubah)
-                ),
+                ruling: response_ruling(&response),
                 confidence: response.confidence,
                 reasoning: response.response,
                 sources: response.sources,
@@ -143,14 +197,7 @@ async fn analyze_token_detailed(
             let detailed_response = DetailedAnalysisResponse {
                 basic: AnalysisResponse {
                     token: request.token,
-                    ruling: format!(
-                        "{:?}",
-                        response
-                            .analysis_id
-                            .as_ref()
-                            .map(|_| IslamicPrinciple::Halal)
-                            .unwrap_or(IslamicPrinciple::Mubah)
-                    ),
+                    ruling: response_ruling(&response),
                     confidence: response.confidence,
                     reasoning: response.response,
                     sources: response.sources,
@@ -207,11 +254,18 @@ async fn get_token_index(
     match state.history_actor.query_analyses(history_query).await {
         Ok(history) => {
             let latest_analysis = history.entries.first();
+            let pubkey = latest_analysis
+                .and_then(|entry| entry.token_info.as_ref())
+                .map(|info| info.pubkey.as_str());
+            let review = scholar_override(pubkey.unwrap_or(token.as_str()));
 
             let index_response = TokenIndexResponse {
                 token: token.clone(),
-                halal_status: latest_analysis
-                    .map(|entry| format!("{:?}", entry.ruling))
+                halal_status: review
+                    .as_ref()
+                    .and_then(|review| review.ruling.as_ref())
+                    .or(latest_analysis.map(|entry| &entry.ruling))
+                    .map(|ruling| format!("{ruling:?}"))
                     .unwrap_or_else(|| "UNKNOWN".to_owned()),
                 confidence: latest_analysis.map(|entry| entry.confidence).unwrap_or(0.0),
                 last_updated: latest_analysis
@@ -219,6 +273,8 @@ async fn get_token_index(
                     .unwrap_or_else(chrono::Utc::now),
                 analysis_count: history.entries.len() as u32,
                 consensus_score: calculate_consensus_score(&history.entries),
+                review,
+                categories: latest_analysis.map(|entry| entry.categories.clone()).unwrap_or_default(),
             };
 
             Ok(Json(index_response))
@@ -263,15 +319,23 @@ async fn list_halal_index(
                 .into_iter()
                 .map(|(token, analyses)| {
                     let latest = analyses.first().unwrap();
+                    let pubkey = latest.token_info.as_ref().map(|t| t.pubkey.as_str()).unwrap_or_default();
+                    let review = scholar_override(pubkey);
+                    let ruling = review
+                        .as_ref()
+                        .and_then(|review| review.ruling.as_ref())
+                        .unwrap_or(&latest.ruling);
                     IndexEntry {
                         token: token.clone(),
                         symbol: latest.token_info.as_ref().map(|t| t.metadata.symbol.clone()).unwrap_or(token),
-                        halal_status: format!("{:?}", latest.ruling),
+                        halal_status: format!("{ruling:?}"),
                         confidence: latest.confidence,
                         last_updated: chrono::DateTime::from_timestamp_millis(latest.analyzed_at as i64)
                             .unwrap_or_else(chrono::Utc::now),
                         analysis_count: analyses.len() as u32,
                         consensus_score: calculate_consensus_score(&analyses),
+                        review,
+                        categories: latest.categories.clone(),
                     }
                 })
                 .collect();
@@ -285,6 +349,10 @@ async fn list_halal_index(
                 index_entries.retain(|entry| entry.confidence >= min_confidence);
             }
 
+            if let Some(category) = params.category {
+                index_entries.retain(|entry| entry.categories.iter().any(|label| label.category == category));
+            }
+
             // Sort by confidence or last updated
             match params.sort_by.as_deref() {
                 Some("confidence") => index_entries.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap()),
@@ -330,14 +398,7 @@ async fn update_token_index(
                 token: request.token,
                 updated: true,
                 analysis_id: response.analysis_id.clone().and_then(|id| Uuid::parse_str(&id).ok()),
-                new_status: format!(
-                    "{:?}",
-                    response
-                        .analysis_id
-                        .as_ref()
-                        .map(|_| IslamicPrinciple::Halal)
-                        .unwrap_or(IslamicPrinciple::Mubah)
-                ),
+                new_status: response_ruling(&response),
                 timestamp: chrono::DateTime::from_timestamp_millis(response.timestamp as i64)
                     .unwrap_or_else(chrono::Utc::now),
             };
@@ -378,14 +439,7 @@ async fn analyze_audio(
         Ok(response) => {
             let analysis_response = AnalysisResponse {
                 token: "Audio Query".to_owned(),
-                ruling: format!(
-                    "{:?}",
-                    response
-                        .analysis_id
-                        .as_ref()
-                        .map(|_| IslamicPrinciple::Halal)
-                        .unwrap_or(IslamicPrinciple::Mubah)
-                ),
+                ruling: response_ruling(&response),
                 confidence: response.confidence,
                 reasoning: response.response,
                 sources: response.sources,
@@ -429,14 +483,7 @@ async fn analyze_contract_address(
             let contract_response = ContractAnalysisResponse {
                 contract_address: address,
                 token_info: None, // Would be populated from blockchain data
-                ruling: format!(
-                    "{:?}",
-                    response
-                        .analysis_id
-                        .as_ref()
-                        .map(|_| IslamicPrinciple::Halal)
-                        .unwrap_or(IslamicPrinciple::Mubah)
-                ),
+                ruling: response_ruling(&response),
                 confidence: response.confidence,
                 reasoning: response.response,
                 sources: response.sources,
@@ -619,6 +666,426 @@ async fn get_user_stats(
     }
 }
 
+/// Get the madhab and standards a user rules by, or the defaults when none are saved
+async fn get_preferences(
+    Path(user_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ShariaPreferences>, (StatusCode, Json<ErrorResponse>)> {
+    match state.history_actor.get_preferences(user_id.clone()).await {
+        Ok(preferences) => Ok(Json(preferences.unwrap_or_default())),
+        Err(e) => {
+            error!("Failed to get preferences for {}: {}", user_id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: "preferences_retrieval_failed".to_owned(),
+                    message: e.to_string(),
+                    details: None,
+                }),
+            ))
+        },
+    }
+}
+
+/// Save the madhab and standards a user rules by
+async fn save_preferences(
+    Path(user_id): Path<String>,
+    State(state): State<AppState>,
+    Json(preferences): Json<ShariaPreferences>,
+) -> Result<Json<ShariaPreferences>, (StatusCode, Json<ErrorResponse>)> {
+    info!("Saving preferences for user: {}", user_id);
+
+    if preferences.standards.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "invalid_preferences".to_owned(),
+                message: "Select at least one screening standard".to_owned(),
+                details: None,
+            }),
+        ));
+    }
+
+    match state.history_actor.save_preferences(user_id.clone(), preferences.clone()).await {
+        Ok(()) => Ok(Json(preferences)),
+        Err(e) => {
+            error!("Failed to save preferences for {}: {}", user_id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: "preferences_save_failed".to_owned(),
+                    message: e.to_string(),
+                    details: None,
+                }),
+            ))
+        },
+    }
+}
+
+// ============================================================================
+// ISLAMIC FINANCE TOOLS
+// ============================================================================
+
+/// Amount to donate per period to purify a holding's impermissible income
+async fn purify_holding(
+    State(_state): State<AppState>,
+    Json(request): Json<PurificationRequest>,
+) -> Result<Json<PurificationResult>, (StatusCode, Json<ErrorResponse>)> {
+    info!("Calculating purification with {:?} per {:?}", request.method, request.period);
+
+    purify_from_env(&request).map(Json).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "invalid_purification_request".to_owned(),
+                message: e,
+                details: None,
+            }),
+        )
+    })
+}
+
+/// Zakat due on a portfolio, with a breakdown per asset
+async fn calculate_zakat(
+    State(_state): State<AppState>,
+    Json(request): Json<ZakatRequest>,
+) -> Result<Json<ZakatResult>, (StatusCode, Json<ErrorResponse>)> {
+    info!("Calculating zakat on {} holdings with {:?}", request.holdings.len(), request.method);
+
+    ZakatConfig::from_env()
+        .and_then(|config| config.calculate(&request))
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "invalid_zakat_request".to_owned(),
+                    message: e,
+                    details: None,
+                }),
+            )
+        })
+}
+
+/// Arabic text and translation of a Quran verse or hadith, e.g. `?citation=Quran 2:275&language=id`
+async fn lookup_scripture(
+    Query(params): Query<ScriptureLookupParams>,
+    State(_state): State<AppState>,
+) -> Result<Json<ScriptureReference>, (StatusCode, Json<ErrorResponse>)> {
+    let language = params.language.as_deref().unwrap_or("id");
+
+    IslamicAnalyzer::new()
+        .scripture_library()
+        .resolve(&params.citation, language)
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "invalid_scripture_reference".to_owned(),
+                    message: e,
+                    details: None,
+                }),
+            )
+        })
+}
+
+/// Quran verses and hadith matching the query's words, best matches first
+async fn search_scripture(
+    Query(params): Query<ScriptureSearchParams>,
+    State(_state): State<AppState>,
+) -> Json<Vec<ScriptureReference>> {
+    let language = params.language.as_deref().unwrap_or("id");
+    let mut results = IslamicAnalyzer::new().scripture_library().search(&params.q, language);
+    results.truncate(params.limit.unwrap_or(20));
+
+    Json(results)
+}
+
+// ============================================================================
+// FATWA KNOWLEDGE BASE
+// ============================================================================
+
+/// Fatwas in force matching the filters, best matches first
+async fn search_fatwas(
+    Query(params): Query<FatwaSearchParams>,
+    State(_state): State<AppState>,
+) -> Result<Json<Vec<Fatwa>>, (StatusCode, Json<ErrorResponse>)> {
+    let query = FatwaQuery {
+        terms: params.q.into_iter().collect(),
+        principle: params.principle,
+        source: params.source,
+        language: params.language,
+        limit: params.limit,
+    };
+
+    FatwaKnowledgeBase::shared()
+        .search(&query)
+        .map(Json)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, "fatwa_search_failed", e))
+}
+
+/// Add a fatwa, or a new version of the one with the same source and title; needs a scholar's token
+async fn add_fatwa(
+    State(_state): State<AppState>,
+    headers: HeaderMap,
+    Json(record): Json<FatwaRecord>,
+) -> Result<Json<ImportReport>, (StatusCode, Json<ErrorResponse>)> {
+    let account = scholar_account(&headers)?;
+    info!("Adding fatwa '{}' from {} by {}", record.title, record.source, account.id);
+
+    FatwaKnowledgeBase::shared()
+        .insert(record)
+        .map(Json)
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, "invalid_fatwa", e))
+}
+
+/// Import a JSON, Markdown or CSV bundle sent as the request body; needs a scholar's token
+async fn import_fatwas(
+    Query(params): Query<FatwaImportParams>,
+    State(_state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ImportReport>, (StatusCode, Json<ErrorResponse>)> {
+    let account = scholar_account(&headers)?;
+    info!("Importing {:?} fatwa bundle of {} bytes by {}", params.format, body.len(), account.id);
+
+    FatwaKnowledgeBase::shared()
+        .import(params.format, &body)
+        .map(Json)
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, "invalid_fatwa_bundle", e))
+}
+
+async fn get_fatwa(
+    Path(id): Path<Uuid>,
+    State(_state): State<AppState>,
+) -> Result<Json<Fatwa>, (StatusCode, Json<ErrorResponse>)> {
+    match FatwaKnowledgeBase::shared().get(id) {
+        Ok(Some(fatwa)) => Ok(Json(fatwa)),
+        Ok(None) => Err(api_error(StatusCode::NOT_FOUND, "fatwa_not_found", format!("Fatwa {id} does not exist"))),
+        Err(e) => Err(api_error(StatusCode::INTERNAL_SERVER_ERROR, "fatwa_retrieval_failed", e)),
+    }
+}
+
+/// Store a new version of a fatwa, superseding the one in force; needs a scholar's token
+async fn revise_fatwa(
+    Path(id): Path<Uuid>,
+    State(_state): State<AppState>,
+    headers: HeaderMap,
+    Json(record): Json<FatwaRecord>,
+) -> Result<Json<Fatwa>, (StatusCode, Json<ErrorResponse>)> {
+    let account = scholar_account(&headers)?;
+    info!("Revising fatwa {} by {}", id, account.id);
+
+    FatwaKnowledgeBase::shared()
+        .revise(id, record)
+        .map(Json)
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, "invalid_fatwa_revision", e))
+}
+
+/// Every version of a fatwa, oldest first
+async fn get_fatwa_versions(
+    Path(id): Path<Uuid>,
+    State(_state): State<AppState>,
+) -> Result<Json<Vec<Fatwa>>, (StatusCode, Json<ErrorResponse>)> {
+    match FatwaKnowledgeBase::shared().versions(id) {
+        Ok(versions) if versions.is_empty() => {
+            Err(api_error(StatusCode::NOT_FOUND, "fatwa_not_found", format!("Fatwa {id} does not exist")))
+        },
+        Ok(versions) => Ok(Json(versions)),
+        Err(e) => Err(api_error(StatusCode::INTERNAL_SERVER_ERROR, "fatwa_retrieval_failed", e)),
+    }
+}
+
+fn api_error(
+    status: StatusCode,
+    error: &str,
+    message: String,
+) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_owned(),
+            message,
+            details: None,
+        }),
+    )
+}
+
+// ============================================================================
+// SCHOLAR REVIEW
+// ============================================================================
+
+/// Approve, annotate or override a token's ruling; needs a scholar's `Authorization: Bearer` token
+async fn submit_review(
+    State(_state): State<AppState>,
+    headers: HeaderMap,
+    Json(submission): Json<ReviewSubmission>,
+) -> Result<Json<ReviewDecision>, (StatusCode, Json<ErrorResponse>)> {
+    let account = reviewer_account(&headers)?;
+    info!("Review of {} by {}: {:?}", submission.token, account.id, submission.action);
+
+    ReviewLog::shared().record(&account, submission).map(Json).map_err(|e| {
+        let (status, error) = match e {
+            ReviewError::NotScholar(_) => (StatusCode::FORBIDDEN, "scholar_role_required"),
+            ReviewError::Invalid(_) => (StatusCode::BAD_REQUEST, "invalid_review"),
+            ReviewError::Storage(_) => (StatusCode::INTERNAL_SERVER_ERROR, "review_storage_failed"),
+        };
+        api_error(status, error, e.to_string())
+    })
+}
+
+/// The reviewer whose `Authorization: Bearer` token came with the request
+fn reviewer_account(headers: &HeaderMap) -> Result<ReviewerAccount, (StatusCode, Json<ErrorResponse>)> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|access_token| ReviewerRegistry::shared().authenticate(access_token.trim()).cloned())
+        .ok_or_else(|| {
+            api_error(StatusCode::UNAUTHORIZED, "unauthorized", "A reviewer access token is required".to_owned())
+        })
+}
+
+/// The reviewer behind the request, who must be a scholar
+fn scholar_account(headers: &HeaderMap) -> Result<ReviewerAccount, (StatusCode, Json<ErrorResponse>)> {
+    let account = reviewer_account(headers)?;
+    if !account.is_scholar() {
+        let e = ReviewError::NotScholar(account.id);
+        return Err(api_error(StatusCode::FORBIDDEN, "scholar_role_required", e.to_string()));
+    }
+    Ok(account)
+}
+
+/// Review decisions about a mint, oldest first
+async fn get_token_reviews(
+    Path(token): Path<String>,
+    State(_state): State<AppState>,
+) -> Result<Json<Vec<ReviewDecision>>, (StatusCode, Json<ErrorResponse>)> {
+    ReviewLog::shared()
+        .history(&token)
+        .map(Json)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, "review_retrieval_failed", e))
+}
+
+/// The audit log of review decisions, oldest first
+async fn list_reviews(
+    Query(params): Query<ReviewLogParams>,
+    State(_state): State<AppState>,
+) -> Result<Json<Vec<ReviewDecision>>, (StatusCode, Json<ErrorResponse>)> {
+    ReviewLog::shared()
+        .log(params.after, params.limit.unwrap_or(100))
+        .map(Json)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, "review_retrieval_failed", e))
+}
+
+/// Ruling of the analysis behind a response, a scholar override already applied; Syubhat when nothing was analyzed
+fn response_ruling(response: &QueryResponse) -> String {
+    format!("{:?}", response.ruling.as_ref().unwrap_or(&IslamicPrinciple::Syubhat))
+}
+
+/// The scholar override in force for a mint; the index still serves when reviews can't be read
+fn scholar_override(mint: &str) -> Option<ReviewDecision> {
+    // Tokens without a known mint carry the default pubkey, which no review may be keyed by
+    let mint = mint_key(mint)?;
+    ReviewLog::shared().override_for(&mint).unwrap_or_else(|e| {
+        error!("Failed to read scholar reviews: {}", e);
+        None
+    })
+}
+
+// ============================================================================
+// PROJECT DOCUMENTS
+// ============================================================================
+
+/// Ingest a whitepaper sent as the request body, the format detected when not given; needs a scholar's token
+async fn upload_document(
+    Query(params): Query<DocumentUploadParams>,
+    State(_state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<DocumentSummary>, (StatusCode, Json<ErrorResponse>)> {
+    let account = scholar_account(&headers)?;
+    let name = params.name.unwrap_or_else(|| "upload".to_owned());
+    let content_type = headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    let format = params
+        .format
+        .or_else(|| detect_format(content_type, &name, &body))
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::BAD_REQUEST,
+                "unsupported_document",
+                format!("{name} is not a PDF, Markdown or HTML document"),
+            )
+        })?;
+    info!("Ingesting {:?} document '{}' for {} ({} bytes) by {}", format, name, params.token, body.len(), account.id);
+
+    let document = DocumentIngester::shared()
+        .ingest(&params.token, &name, format, &body)
+        .map_err(document_error)?;
+    save_document(document)
+}
+
+/// Ingest a whitepaper from a URL, following a project website's link to its whitepaper; needs a scholar's token
+async fn fetch_document(
+    State(_state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<DocumentFetchRequest>,
+) -> Result<Json<DocumentSummary>, (StatusCode, Json<ErrorResponse>)> {
+    let account = scholar_account(&headers)?;
+    info!("Fetching project document for {} from {} by {}", request.token, request.url, account.id);
+
+    let http = HttpClient::from_env(reqwest::Client::new(), "documents");
+    let document = DocumentIngester::shared()
+        .fetch(&http, &request.token, &request.url)
+        .await
+        .map_err(document_error)?;
+    save_document(document)
+}
+
+/// Documents ingested for a token, oldest first
+async fn get_token_documents(
+    Path(token): Path<String>,
+    State(_state): State<AppState>,
+) -> Result<Json<Vec<DocumentSummary>>, (StatusCode, Json<ErrorResponse>)> {
+    DocumentStore::shared()
+        .documents(&token)
+        .map(|documents| Json(documents.iter().map(DocumentSummary::from).collect()))
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, "document_retrieval_failed", e))
+}
+
+/// Passages of a token's documents closest to a question, with their pages
+async fn search_documents(
+    Path(token): Path<String>,
+    Query(params): Query<DocumentSearchParams>,
+    State(_state): State<AppState>,
+) -> Result<Json<Vec<DocumentMatch>>, (StatusCode, Json<ErrorResponse>)> {
+    let embedding = DocumentIngester::shared().embed(&params.q);
+
+    DocumentStore::shared()
+        .search(&token, &embedding, params.limit.unwrap_or(5))
+        .map(Json)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, "document_search_failed", e))
+}
+
+fn save_document(document: ProjectDocument) -> Result<Json<DocumentSummary>, (StatusCode, Json<ErrorResponse>)> {
+    DocumentStore::shared()
+        .save(&document)
+        .map_err(|e| document_error(DocumentError::Storage(e)))?;
+    Ok(Json(DocumentSummary::from(&document)))
+}
+
+fn document_error(e: DocumentError) -> (StatusCode, Json<ErrorResponse>) {
+    let (status, error) = match e {
+        DocumentError::Invalid(_) => (StatusCode::BAD_REQUEST, "invalid_document"),
+        DocumentError::Fetch(_) => (StatusCode::BAD_GATEWAY, "document_fetch_failed"),
+        DocumentError::Storage(_) => (StatusCode::INTERNAL_SERVER_ERROR, "document_storage_failed"),
+    };
+    api_error(status, error, e.to_string())
+}
+
 // ============================================================================
 // HEALTH AND STATUS ENDPOINTS
 // ============================================================================
@@ -741,10 +1208,63 @@ pub struct AudioAnalysisRequest {
 pub struct IndexListParams {
     pub status: Option<String>,
     pub min_confidence: Option<f64>,
+    pub category: Option<TokenCategory>,
     pub limit: Option<usize>,
     pub sort_by: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ScriptureLookupParams {
+    pub citation: String,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScriptureSearchParams {
+    pub q: String,
+    pub language: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FatwaSearchParams {
+    pub q: Option<String>,
+    pub principle: Option<IslamicPrinciple>,
+    pub source: Option<String>,
+    pub language: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FatwaImportParams {
+    pub format: ImportFormat,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewLogParams {
+    pub after: Option<u64>, // Sequence of the last decision already seen
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DocumentUploadParams {
+    pub token: String,        // Mint address
+    pub name: Option<String>, // File name, cited with the excerpts
+    pub format: Option<DocumentFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DocumentFetchRequest {
+    pub token: String,
+    pub url: String, // The whitepaper, or a project page linking to it
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DocumentSearchParams {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct IndexUpdateRequest {
     pub token: String,
@@ -807,6 +1327,8 @@ pub struct TokenIndexResponse {
     pub last_updated: chrono::DateTime<chrono::Utc>,
     pub analysis_count: u32,
     pub consensus_score: f64,
+    pub review: Option<ReviewDecision>, // Scholar override that set `halal_status`
+    pub categories: Vec<CategoryLabel>, // Taxonomy categories of the latest analysis
 }
 
 #[derive(Debug, Serialize)]
@@ -825,6 +1347,40 @@ pub struct IndexEntry {
     pub last_updated: chrono::DateTime<chrono::Utc>,
     pub analysis_count: u32,
     pub consensus_score: f64,
+    pub review: Option<ReviewDecision>, // Scholar override that set `halal_status`
+    pub categories: Vec<CategoryLabel>, // Taxonomy categories of the latest analysis
+}
+
+/// An ingested document without its text and embeddings
+#[derive(Debug, Serialize)]
+pub struct DocumentSummary {
+    pub id: String,
+    pub token: String,
+    pub source: String,
+    pub format: DocumentFormat,
+    pub title: Option<String>,
+    pub page_count: Option<u32>,
+    pub sections: usize,
+    pub chunks: usize,
+    pub key_sections: Vec<DocumentExcerpt>,
+    pub ingested_at: u64,
+}
+
+impl From<&ProjectDocument> for DocumentSummary {
+    fn from(document: &ProjectDocument) -> Self {
+        Self {
+            id: document.id.clone(),
+            token: document.token.clone(),
+            source: document.source.clone(),
+            format: document.format,
+            title: document.title.clone(),
+            page_count: document.page_count,
+            sections: document.sections.len(),
+            chunks: document.chunks.len(),
+            key_sections: document.key_sections.clone(),
+            ingested_at: document.ingested_at,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -1,8 +1,7 @@
 use thiserror::Error;
 
 /// Core `Averroes` system errors
-#[derive(Debug, Error, uniffi::Error)]
-#[uniffi(flat_error)]
+#[derive(Debug, Error)]
 pub enum AverroesError {
     #[error("Initialization failed: {0}")]
     InitializationError(String),
@@ -1,26 +1,29 @@
 use std::collections::HashMap;
 
 use lazy_static::lazy_static;
+
+use crate::actors::analyzer_actor::ComprehensiveAnalysis;
+use crate::actors::analyzer_actor::IslamicAnalysisResult;
 use crate::models::token::UniversalTokenInfo as TokenInfo;
-use crate::models::analysis::IslamicAnalysis;
 
 lazy_static! {
     pub static ref FALLBACK_TOKENS: HashMap<&'static str, TokenInfo> = {
         let mut m = HashMap::new();
 
         m.insert("SOL", TokenInfo {
-            address: "11111111111111111111111111111112".to_string(),
+            address: "11111111111111111111111111111112".to_owned(),
             metadata: crate::models::token::TokenMetadata {
-                name: "Solana".to_string(),
-                symbol: "SOL".to_string(),
-                contract_address: "11111111111111111111111111111112".to_string(),
+                name: "Solana".to_owned(),
+                symbol: "SOL".to_owned(),
+                contract_address: "11111111111111111111111111111112".to_owned(),
                 decimals: 9,
-                description: Some("Solana is a high-performance blockchain supporting builders around the world creating crypto apps that scale today.".to_string()),
+                description: Some("Solana is a high-performance blockchain supporting builders around the world creating crypto apps that scale today.".to_owned()),
                 image_url: None,
                 creator: None,
                 verified: true,
                 token_standard: crate::models::token::TokenStandard::SPL,
                 blockchain: crate::models::token::BlockchainNetwork::Solana,
+                extensions: vec![],
             },
             price_data: Some(crate::models::token::TokenPriceData {
                 price_usd: 23.45,
@@ -35,21 +38,23 @@ lazy_static! {
             is_verified: true,
             risk_score: Some(0.2),
             blockchain: crate::models::token::BlockchainNetwork::Solana,
+            control: None,
         });
 
         m.insert("BTC", TokenInfo {
-            address: "bitcoin".to_string(),
+            address: "bitcoin".to_owned(),
             metadata: crate::models::token::TokenMetadata {
-                name: "Bitcoin".to_string(),
-                symbol: "BTC".to_string(),
-                contract_address: "bitcoin".to_string(),
+                name: "Bitcoin".to_owned(),
+                symbol: "BTC".to_owned(),
+                contract_address: "bitcoin".to_owned(),
                 decimals: 8,
-                description: Some("Bitcoin is a decentralized digital currency, without a central bank or single administrator.".to_string()),
+                description: Some("Bitcoin is a decentralized digital currency, without a central bank or single administrator.".to_owned()),
                 image_url: None,
                 creator: None,
                 verified: true,
-                token_standard: crate::models::token::TokenStandard::Other { name: "Bitcoin".to_string() },
-                blockchain: crate::models::token::BlockchainNetwork::Other { name: "Bitcoin".to_string() },
+                token_standard: crate::models::token::TokenStandard::Other { name: "Bitcoin".to_owned() },
+                blockchain: crate::models::token::BlockchainNetwork::Other { name: "Bitcoin".to_owned() },
+                extensions: vec![],
             },
             price_data: Some(crate::models::token::TokenPriceData {
                 price_usd: 43_200.0,
@@ -63,22 +68,24 @@ lazy_static! {
             liquidity_pools: vec![],
             is_verified: true,
             risk_score: Some(0.8),
-            blockchain: crate::models::token::BlockchainNetwork::Other { name: "Bitcoin".to_string() },
+            blockchain: crate::models::token::BlockchainNetwork::Other { name: "Bitcoin".to_owned() },
+            control: None,
         });
 
         m.insert("USDC", TokenInfo {
-            address: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+            address: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_owned(),
             metadata: crate::models::token::TokenMetadata {
-                name: "USD Coin".to_string(),
-                symbol: "USDC".to_string(),
-                contract_address: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+                name: "USD Coin".to_owned(),
+                symbol: "USDC".to_owned(),
+                contract_address: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_owned(),
                 decimals: 6,
-                description: Some("USDC is a fully collateralized US dollar stablecoin.".to_string()),
+                description: Some("USDC is a fully collateralized US dollar stablecoin.".to_owned()),
                 image_url: None,
                 creator: None,
                 verified: true,
                 token_standard: crate::models::token::TokenStandard::SPL,
                 blockchain: crate::models::token::BlockchainNetwork::Solana,
+                extensions: vec![],
             },
             price_data: Some(crate::models::token::TokenPriceData {
                 price_usd: 1.0,
@@ -93,59 +100,77 @@ lazy_static! {
             is_verified: true,
             risk_score: Some(0.1),
             blockchain: crate::models::token::BlockchainNetwork::Solana,
+            control: None,
         });
 
         m
     };
-    pub static ref FALLBACK_ANALYSES: HashMap<&'static str, IslamicAnalysis> = {
+    // Rule-based analysis and the model's reasoning for each fallback token
+    pub static ref FALLBACK_ANALYSES: HashMap<&'static str, (IslamicAnalysisResult, &'static str)> = {
         let mut m = HashMap::new();
 
-        m.insert("SOL", IslamicAnalysis {
+        m.insert("SOL", (IslamicAnalysisResult {
             is_halal: true,
             compliance_score: 0.85,
             confidence: 0.8,
             reasoning: vec![
-                "Utility token for blockchain infrastructure".to_string(),
-                "No direct involvement in riba-based activities".to_string(),
-                "Supports halal applications and services".to_string(),
+                "Utility token for blockchain infrastructure".to_owned(),
+                "No direct involvement in riba-based activities".to_owned(),
+                "Supports halal applications and services".to_owned(),
             ],
             scholar_references: vec![
-                "AAOIFI Sharia Standard No. 17".to_string(),
-                "Islamic Finance Council guidance on utility tokens".to_string(),
+                "AAOIFI Sharia Standard No. 17".to_owned(),
+                "Islamic Finance Council guidance on utility tokens".to_owned(),
             ],
-            ai_reasoning: Some(
-                "HALAL - Solana is primarily a utility token for blockchain infrastructure. It doesn't   
-involve riba (interest), excessive gharar (uncertainty), or maysir (gambling). Confidence: 85%"
-                    .to_string(),
-            ),
-        });
+            ..Default::default()
+        }, "HALAL - Solana is primarily a utility token for blockchain infrastructure. It doesn't
+involve riba (interest), excessive gharar (uncertainty), or maysir (gambling). Confidence: 85%"));
 
-        m.insert("BTC", IslamicAnalysis {
+        m.insert("BTC", (IslamicAnalysisResult {
             is_halal: false,
             compliance_score: 0.3,
             confidence: 0.7,
             reasoning: vec![
-                "Highly speculative nature (gharar)".to_string(),
-                "Often used for gambling and speculation".to_string(),
-                "No intrinsic utility or backing".to_string(),
+                "Highly speculative nature (gharar)".to_owned(),
+                "Often used for gambling and speculation".to_owned(),
+                "No intrinsic utility or backing".to_owned(),
             ],
             scholar_references: vec![
-                "Fatwa by Grand Mufti of Egypt".to_string(),
-                "Islamic Finance Council concerns on speculation".to_string(),
+                "Fatwa by Grand Mufti of Egypt".to_owned(),
+                "Islamic Finance Council concerns on speculation".to_owned(),
             ],
-            ai_reasoning: Some(
-                "HARAM - Bitcoin's extreme volatility and speculative nature constitute excessive gharar 
-(uncertainty). Widely used for gambling and speculation. Confidence: 70%"
-                    .to_string(),
-            ),
-        });
+            ..Default::default()
+        }, "HARAM - Bitcoin's extreme volatility and speculative nature constitute excessive gharar
+(uncertainty). Widely used for gambling and speculation. Confidence: 70%"));
+
+        m.insert("USDC", (IslamicAnalysisResult {
+            is_halal: true,
+            compliance_score: 0.75,
+            confidence: 0.75,
+            reasoning: vec![
+                "Fiat-backed stablecoin redeemable one-to-one for US dollars".to_owned(),
+                "Reserves earn interest in T-bills, but holders receive none of it".to_owned(),
+                "Lending it on interest-bearing platforms is riba".to_owned(),
+            ],
+            scholar_references: vec![
+                "AAOIFI Sharia Standard No. 1 (Trading in Currencies)".to_owned(),
+            ],
+            ..Default::default()
+        }, "HALAL - USDC is a fiat-backed stablecoin and involves no riba directly. Using it in interest-based
+lending or yield products is not permissible. Confidence: 75%"));
 
         m
     };
 }
 
-pub fn get_fallback_analysis(symbol: &str) -> Option<(TokenInfo, IslamicAnalysis)> {
+pub fn get_fallback_analysis(symbol: &str) -> Option<ComprehensiveAnalysis> {
     let token = FALLBACK_TOKENS.get(symbol)?;
-    let analysis = FALLBACK_ANALYSES.get(symbol)?;
-    Some((token.clone(), analysis.clone()))
+    let (analysis, ai_reasoning) = FALLBACK_ANALYSES.get(symbol)?;
+    Some(ComprehensiveAnalysis {
+        analysis_id: uuid::Uuid::new_v4(),
+        token_info: token.clone(),
+        islamic_analysis: analysis.clone(),
+        ai_reasoning: Some((*ai_reasoning).to_owned()),
+        timestamp: chrono::Utc::now(),
+    })
 }
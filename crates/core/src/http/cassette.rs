@@ -0,0 +1,388 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::scrubber::Scrubber;
+
+/// Current on-disk cassette format
+pub const CASSETTE_VERSION: u32 = 1;
+
+/// How the client treats outbound requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CassetteMode {
+    /// Hit the network and append every exchange to the cassette
+    Record,
+    /// Serve responses from the cassette, never touching the network
+    Replay,
+    /// Plain HTTP, no cassette involved
+    Passthrough,
+}
+
+impl CassetteMode {
+    /// Parse a mode name, case-insensitively
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "record" => Some(Self::Record),
+            "replay" => Some(Self::Replay),
+            "passthrough" | "off" | "" => Some(Self::Passthrough),
+            _ => None,
+        }
+    }
+}
+
+/// Request fields used to pick a recorded interaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchOn {
+    Method,
+    Url,
+    Body,
+}
+
+/// Controls how live requests are matched against recorded ones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchConfig {
+    pub match_on: Vec<MatchOn>,
+    /// Reuse the last matching interaction once all matches have been played
+    pub allow_repeats: bool,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            match_on: vec![MatchOn::Method, MatchOn::Url],
+            allow_repeats: false,
+        }
+    }
+}
+
+/// A request as stored on disk, already scrubbed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+impl RecordedRequest {
+    /// Build a scrubbed request record
+    pub fn new(
+        method: &str,
+        url: &str,
+        headers: impl IntoIterator<Item = (String, String)>,
+        body: Option<&[u8]>,
+        scrubber: &Scrubber,
+    ) -> Self {
+        Self {
+            method: method.to_uppercase(),
+            url: scrubber.scrub_url(url),
+            headers: headers
+                .into_iter()
+                .map(|(name, value)| {
+                    let value = scrubber.scrub_header(&name, &value);
+                    (name.to_lowercase(), value)
+                })
+                .collect(),
+            body: body.map(|bytes| scrubber.scrub_text(&String::from_utf8_lossy(bytes))),
+        }
+    }
+
+    /// Whether this recorded request satisfies `other` under the given rules
+    pub fn matches(
+        &self,
+        other: &RecordedRequest,
+        config: &MatchConfig,
+    ) -> bool {
+        config.match_on.iter().all(|field| match field {
+            MatchOn::Method => self.method == other.method,
+            MatchOn::Url => self.url == other.url,
+            MatchOn::Body => bodies_equal(self.body.as_deref(), other.body.as_deref()),
+        })
+    }
+}
+
+/// How a recorded body is written in the cassette
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyEncoding {
+    /// UTF-8 text, scrubbed of secrets
+    #[default]
+    Text,
+    /// Raw bytes, e.g. a PDF, stored verbatim as base64
+    Base64,
+}
+
+/// A response as stored on disk, already scrubbed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub encoding: BodyEncoding,
+}
+
+impl RecordedResponse {
+    /// Build a response record, scrubbing headers and text bodies
+    ///
+    /// Bodies whose content type isn't textual, or that aren't valid UTF-8, are kept byte for byte.
+    pub fn new(
+        status: u16,
+        headers: &BTreeMap<String, String>,
+        body: &[u8],
+        scrubber: &Scrubber,
+    ) -> Self {
+        let textual = headers.get("content-type").is_none_or(|content_type| is_textual(content_type));
+        let (body, encoding) = match std::str::from_utf8(body) {
+            Ok(text) if textual => (scrubber.scrub_text(text), BodyEncoding::Text),
+            _ => (BASE64.encode(body), BodyEncoding::Base64),
+        };
+
+        Self {
+            status,
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.clone(), scrubber.scrub_header(name, value)))
+                .collect(),
+            body,
+            encoding,
+        }
+    }
+
+    /// The body as it was received
+    pub fn body_bytes(&self) -> Result<Vec<u8>, String> {
+        match self.encoding {
+            BodyEncoding::Text => Ok(self.body.clone().into_bytes()),
+            BodyEncoding::Base64 => BASE64.decode(&self.body).map_err(|e| format!("invalid base64 body: {e}")),
+        }
+    }
+}
+
+/// Whether a content type carries text that may hold secrets worth scrubbing
+fn is_textual(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    mime.starts_with("text/")
+        || mime.ends_with("json")
+        || mime.ends_with("xml")
+        || mime.ends_with("javascript")
+        || mime == "application/x-www-form-urlencoded"
+}
+
+/// One request/response exchange
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// An ordered list of recorded exchanges plus replay bookkeeping
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cassette {
+    pub version: u32,
+    pub interactions: Vec<Interaction>,
+    #[serde(skip)]
+    played: Vec<bool>,
+}
+
+impl Default for Cassette {
+    fn default() -> Self {
+        Self {
+            version: CASSETTE_VERSION,
+            interactions: Vec::new(),
+            played: Vec::new(),
+        }
+    }
+}
+
+impl Cassette {
+    /// Load a cassette from disk
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let mut cassette: Cassette = serde_json::from_str(&raw).map_err(|e| format!("{}: {e}", path.display()))?;
+
+        if cassette.version != CASSETTE_VERSION {
+            return Err(format!(
+                "{}: unsupported cassette version {} (expected {CASSETTE_VERSION})",
+                path.display(),
+                cassette.version
+            ));
+        }
+
+        cassette.played = vec![false; cassette.interactions.len()];
+        Ok(cassette)
+    }
+
+    /// Write the cassette to disk as pretty JSON
+    pub fn save(
+        &self,
+        path: &Path,
+    ) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+        }
+
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, format!("{json}\n")).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Append a freshly recorded exchange
+    pub fn record(
+        &mut self,
+        interaction: Interaction,
+    ) {
+        self.interactions.push(interaction);
+        self.played.push(true);
+    }
+
+    /// Find the next unplayed interaction matching `request` and mark it played
+    pub fn next_match(
+        &mut self,
+        request: &RecordedRequest,
+        config: &MatchConfig,
+    ) -> Option<RecordedResponse> {
+        let mut last_match = None;
+
+        for (index, interaction) in self.interactions.iter().enumerate() {
+            if !interaction.request.matches(request, config) {
+                continue;
+            }
+
+            if !self.played[index] {
+                self.played[index] = true;
+                return Some(interaction.response.clone());
+            }
+
+            last_match = Some(index);
+        }
+
+        if config.allow_repeats {
+            last_match.map(|index| self.interactions[index].response.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Number of recorded interactions not yet replayed
+    pub fn remaining(&self) -> usize {
+        self.played.iter().filter(|played| !**played).count()
+    }
+}
+
+/// Compare bodies structurally when both are JSON, textually otherwise
+fn bodies_equal(
+    left: Option<&str>,
+    right: Option<&str>,
+) -> bool {
+    match (left, right) {
+        (Some(left), Some(right)) => {
+            match (serde_json::from_str::<serde_json::Value>(left), serde_json::from_str::<serde_json::Value>(right)) {
+                (Ok(left), Ok(right)) => left == right,
+                _ => left == right,
+            }
+        },
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interaction(
+        method: &str,
+        url: &str,
+        body: &str,
+    ) -> Interaction {
+        Interaction {
+            request: RecordedRequest {
+                method: method.to_owned(),
+                url: url.to_owned(),
+                headers: BTreeMap::new(),
+                body: None,
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers: BTreeMap::new(),
+                body: body.to_owned(),
+                encoding: BodyEncoding::Text,
+            },
+        }
+    }
+
+    fn request(
+        method: &str,
+        url: &str,
+    ) -> RecordedRequest {
+        RecordedRequest {
+            method: method.to_owned(),
+            url: url.to_owned(),
+            headers: BTreeMap::new(),
+            body: None,
+        }
+    }
+
+    #[test]
+    fn test_replays_matching_interactions_in_order() {
+        let mut cassette = Cassette::default();
+        cassette.interactions.push(interaction("GET", "https://a.test/", "first"));
+        cassette.interactions.push(interaction("GET", "https://b.test/", "other"));
+        cassette.interactions.push(interaction("GET", "https://a.test/", "second"));
+        cassette.played = vec![false; 3];
+
+        let config = MatchConfig::default();
+        let get_a = request("GET", "https://a.test/");
+
+        assert_eq!(cassette.next_match(&get_a, &config).unwrap().body, "first");
+        assert_eq!(cassette.next_match(&get_a, &config).unwrap().body, "second");
+        assert!(cassette.next_match(&get_a, &config).is_none());
+        assert!(cassette.next_match(&request("POST", "https://a.test/"), &config).is_none());
+        assert_eq!(cassette.remaining(), 1);
+    }
+
+    #[test]
+    fn test_allow_repeats_reuses_last_match() {
+        let mut cassette = Cassette::default();
+        cassette.interactions.push(interaction("GET", "https://a.test/", "only"));
+        cassette.played = vec![false];
+
+        let config = MatchConfig {
+            match_on: vec![MatchOn::Url],
+            allow_repeats: true,
+        };
+        let get_a = request("GET", "https://a.test/");
+
+        assert_eq!(cassette.next_match(&get_a, &config).unwrap().body, "only");
+        assert_eq!(cassette.next_match(&get_a, &config).unwrap().body, "only");
+    }
+
+    #[test]
+    fn test_binary_bodies_survive_recording() {
+        let pdf = b"%PDF-1.7\n\xe2\xe3\xcf\xd3\n1 0 obj\n".to_vec();
+        let headers = BTreeMap::from([("content-type".to_owned(), "application/pdf".to_owned())]);
+        let recorded = RecordedResponse::new(200, &headers, &pdf, &Scrubber::default());
+        assert_eq!(recorded.encoding, BodyEncoding::Base64);
+        assert_eq!(recorded.body_bytes().unwrap(), pdf);
+
+        let headers = BTreeMap::from([("content-type".to_owned(), "application/json; charset=utf-8".to_owned())]);
+        let recorded =
+            RecordedResponse::new(200, &headers, br#"{"key":"gsk_0123456789abcdefABCDEF"}"#, &Scrubber::default());
+        assert_eq!(recorded.encoding, BodyEncoding::Text);
+        assert_eq!(recorded.body, r#"{"key":"<REDACTED>"}"#);
+    }
+
+    #[test]
+    fn test_json_bodies_match_structurally() {
+        assert!(bodies_equal(Some(r#"{"a":1,"b":2}"#), Some(r#"{ "b": 2, "a": 1 }"#)));
+        assert!(!bodies_equal(Some(r#"{"a":1}"#), Some(r#"{"a":2}"#)));
+        assert!(!bodies_equal(Some("x"), None));
+    }
+}
@@ -0,0 +1,141 @@
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::net::SocketAddr;
+
+use url::Host;
+use url::Url;
+
+use super::client::HttpError;
+
+/// Redirects followed for an untrusted URL before giving up
+pub const MAX_UNTRUSTED_REDIRECTS: usize = 5;
+
+/// Parse a URL from untrusted input, refusing anything but http(s) to a host that could be public
+///
+/// Domain names are only refused here when they name the local machine or network; their addresses are
+/// checked by [`resolve_public`] right before connecting.
+pub fn public_url(url: &str) -> Result<Url, HttpError> {
+    let parsed = Url::parse(url.trim()).map_err(|e| HttpError::Refused(format!("{url}: {e}")))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(HttpError::Refused(format!("{parsed}: only http and https are fetched")));
+    }
+
+    let public = match parsed.host() {
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_lowercase();
+            !(domain == "localhost"
+                || domain.ends_with(".localhost")
+                || domain.ends_with(".local")
+                || domain.ends_with(".internal"))
+        },
+        Some(Host::Ipv4(ip)) => is_public(IpAddr::V4(ip)),
+        Some(Host::Ipv6(ip)) => is_public(IpAddr::V6(ip)),
+        None => false,
+    };
+    if !public {
+        return Err(HttpError::Refused(format!("{parsed}: host is not public")));
+    }
+    Ok(parsed)
+}
+
+/// Addresses of the URL's host, refused when any of them is not public
+///
+/// Connections are pinned to these addresses so the name can't be re-resolved somewhere private.
+pub async fn resolve_public(url: &Url) -> Result<Vec<SocketAddr>, HttpError> {
+    let host = url.host_str().ok_or_else(|| HttpError::Refused(format!("{url}: no host")))?;
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addrs = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| HttpError::Request(format!("{host}: {e}")))?
+        .collect::<Vec<_>>();
+
+    if addrs.is_empty() {
+        return Err(HttpError::Request(format!("{host}: no addresses")));
+    }
+    if let Some(private) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+        return Err(HttpError::Refused(format!("{url}: {host} resolves to {}", private.ip())));
+    }
+    Ok(addrs)
+}
+
+/// Whether an address is reachable on the public internet rather than this machine or its networks
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_v4(mapped),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local() // Includes the 169.254.169.254 cloud metadata service
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        || a >= 240
+        || (a == 100 && (64..128).contains(&b)) // Carrier-grade NAT
+        || (a == 192 && b == 0 && c == 0)
+        || (a == 198 && (b == 18 || b == 19)))
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    // NAT64 addresses reach whatever IPv4 address they embed
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let [_, _, _, _, _, _, high, low] = segments;
+        return is_public_v4(Ipv4Addr::from((u32::from(high) << 16) | u32::from(low)));
+    }
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || (segments[0] & 0xfe00) == 0xfc00 // Unique local
+        || (segments[0] & 0xffc0) == 0xfe80 // Link local
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_private_and_local_hosts_are_refused() {
+        for url in [
+            "http://localhost:8899/",
+            "http://api.localhost/",
+            "http://127.0.0.1/",
+            "http://10.1.2.3/",
+            "http://172.16.0.1/",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://100.64.0.1/",
+            "http://0.0.0.0/",
+            "http://[::1]/",
+            "http://[fd00::1]/",
+            "http://[fe80::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://[64:ff9b::a00:1]/",
+            "file:///etc/passwd",
+            "ftp://example.com/",
+            "not a url",
+        ] {
+            assert!(matches!(public_url(url), Err(HttpError::Refused(_))), "{url} should be refused");
+        }
+
+        for url in [
+            "https://arweave.net/hsw",
+            "https://ipfs.io/ipfs/bafyhsw",
+            "http://8.8.8.8/",
+            "http://[2606:4700::1111]/",
+        ] {
+            assert!(public_url(url).is_ok(), "{url} should be allowed");
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! Outbound HTTP with record/replay cassettes.
+//!
+//! Every external call (LLM providers, Solana RPC, scraped pages, market data) goes through [`HttpClient`] so that
+//! real exchanges can be recorded once, scrubbed of secrets, and replayed offline in tests.
+//!
+//! The mode is picked from the environment by [`HttpClient::from_env`]:
+//!
+//! - `AVERROES_CASSETTE_MODE` = `record` | `replay` | `passthrough` (default)
+//! - `AVERROES_CASSETTE_DIR` = directory holding `<name>.json` cassettes (default `tests/fixtures/cassettes`)
+//!
+//! URLs taken from untrusted input go through [`HttpClient::get_untrusted`], which only reaches public hosts.
+
+pub mod cassette;
+pub mod client;
+pub mod guard;
+pub mod scrubber;
+
+pub use cassette::*;
+pub use client::*;
+pub use guard::*;
+pub use scrubber::*;
@@ -0,0 +1,187 @@
+use regex::Regex;
+use url::Url;
+
+/// Placeholder written in place of every scrubbed secret
+pub const REDACTED: &str = "<REDACTED>";
+
+/// Environment variables whose values are always treated as secrets
+const SECRET_ENV_VARS: &[&str] = &[
+    "GROQ_API_KEY",
+    "GROK_API_KEY",
+    "XAI_API_KEY",
+    "OPENAI_API_KEY",
+    "COINGECKO_API_KEY",
+    "CMC_API_KEY",
+];
+
+/// Removes credentials from recorded requests and responses before they reach disk
+#[derive(Debug, Clone)]
+pub struct Scrubber {
+    headers: Vec<String>,
+    query_params: Vec<String>,
+    secret_values: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl Default for Scrubber {
+    fn default() -> Self {
+        let secret_values = SECRET_ENV_VARS
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .filter(|value| !value.is_empty())
+            .collect();
+
+        Self {
+            headers: vec![
+                "authorization".to_owned(),
+                "proxy-authorization".to_owned(),
+                "x-api-key".to_owned(),
+                "api-key".to_owned(),
+                "x-cg-pro-api-key".to_owned(),
+                "x-cmc_pro_api_key".to_owned(),
+                "cookie".to_owned(),
+                "set-cookie".to_owned(),
+            ],
+            query_params: vec![
+                "key".to_owned(),
+                "api_key".to_owned(),
+                "apikey".to_owned(),
+                "token".to_owned(),
+                "access_token".to_owned(),
+            ],
+            secret_values,
+            patterns: vec![
+                // Groq, OpenAI and xAI key formats
+                Regex::new(r"gsk_[A-Za-z0-9]{16,}").unwrap(),
+                Regex::new(r"sk-[A-Za-z0-9_\-]{16,}").unwrap(),
+                Regex::new(r"xai-[A-Za-z0-9]{16,}").unwrap(),
+            ],
+        }
+    }
+}
+
+impl Scrubber {
+    /// Treat an additional literal value as a secret
+    pub fn with_secret(
+        mut self,
+        value: impl Into<String>,
+    ) -> Self {
+        let value = value.into();
+        if !value.is_empty() {
+            self.secret_values.push(value);
+        }
+        self
+    }
+
+    /// Treat an additional header name as sensitive
+    pub fn with_header(
+        mut self,
+        name: impl Into<String>,
+    ) -> Self {
+        self.headers.push(name.into().to_lowercase());
+        self
+    }
+
+    /// Whether a header's value must be redacted
+    pub fn is_sensitive_header(
+        &self,
+        name: &str,
+    ) -> bool {
+        let name = name.to_lowercase();
+        self.headers.contains(&name)
+    }
+
+    /// Redact a header value, keeping the auth scheme so recordings stay readable
+    pub fn scrub_header(
+        &self,
+        name: &str,
+        value: &str,
+    ) -> String {
+        if !self.is_sensitive_header(name) {
+            return self.scrub_text(value);
+        }
+
+        match value.split_once(' ') {
+            Some((scheme, _)) if scheme.eq_ignore_ascii_case("bearer") || scheme.eq_ignore_ascii_case("basic") => {
+                format!("{scheme} {REDACTED}")
+            },
+            _ => REDACTED.to_owned(),
+        }
+    }
+
+    /// Redact secret query parameters and any embedded secret values in a URL
+    pub fn scrub_url(
+        &self,
+        raw: &str,
+    ) -> String {
+        let Ok(mut url) = Url::parse(raw) else {
+            return self.scrub_text(raw);
+        };
+
+        if url.query().is_some() {
+            let pairs: Vec<(String, String)> = url
+                .query_pairs()
+                .map(|(key, value)| {
+                    let value = if self.query_params.contains(&key.to_lowercase()) {
+                        REDACTED.to_owned()
+                    } else {
+                        value.into_owned()
+                    };
+                    (key.into_owned(), value)
+                })
+                .collect();
+
+            url.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+
+        self.scrub_text(url.as_str())
+    }
+
+    /// Redact known secret values and key-shaped tokens from free text
+    pub fn scrub_text(
+        &self,
+        text: &str,
+    ) -> String {
+        let mut scrubbed = text.to_owned();
+
+        for secret in &self.secret_values {
+            scrubbed = scrubbed.replace(secret.as_str(), REDACTED);
+        }
+
+        for pattern in &self.patterns {
+            scrubbed = pattern.replace_all(&scrubbed, REDACTED).into_owned();
+        }
+
+        scrubbed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrub_authorization_header() {
+        let scrubber = Scrubber::default();
+
+        assert_eq!(scrubber.scrub_header("Authorization", "Bearer gsk_abc"), "Bearer <REDACTED>");
+        assert_eq!(scrubber.scrub_header("x-api-key", "abc"), "<REDACTED>");
+        assert_eq!(scrubber.scrub_header("Content-Type", "application/json"), "application/json");
+    }
+
+    #[test]
+    fn test_scrub_query_params() {
+        let scrubber = Scrubber::default();
+        let url = scrubber.scrub_url("https://api.example.com/v1/price?ids=solana&api_key=secret123");
+
+        assert_eq!(url, "https://api.example.com/v1/price?ids=solana&api_key=%3CREDACTED%3E");
+    }
+
+    #[test]
+    fn test_scrub_key_patterns_and_literals() {
+        let scrubber = Scrubber::default().with_secret("hunter2");
+        let text = scrubber.scrub_text("key=gsk_0123456789abcdefABCDEF pass=hunter2");
+
+        assert_eq!(text, "key=<REDACTED> pass=<REDACTED>");
+    }
+}
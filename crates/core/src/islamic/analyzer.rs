@@ -1,85 +1,518 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use tracing::error;
+use tracing::warn;
+
+use super::determination::RulingResolver;
+use super::gharar::GhararInputs;
+use super::ikhtilaf::IkhtilafMapper;
+use super::maqashid::MaqashidAssessor;
+use super::maysir::LIKELY_MAYSIR;
+use super::maysir::MaysirDetector;
+use super::maysir::POSSIBLE_MAYSIR;
+use super::maysir::program_interactions_from_env;
+use super::ratios::ProjectFinancials;
+use super::ratios::screen_project;
+use super::riba::RibaDetector;
+use super::riba::RibaInputs;
+use super::rules::RuleEngine;
+use super::rules::TokenFacts;
+use super::rules::load_or_else;
+use super::schools::SchoolPositions;
+use super::scripture::DEFAULT_LANGUAGE;
+use super::scripture::ResolvedEvidence;
+use super::scripture::ScriptureLibrary;
+use super::stablecoin::StablecoinClassifier;
+use super::stablecoin::StablecoinInputs;
+use super::taxonomy::TokenClassifier;
+use super::taxonomy::category_fact;
+use crate::actors::analyzer_actor::IslamicAnalysisResult;
+use crate::models::CategoryLabel;
+use crate::models::Fatwa;
+use crate::models::GhararAssessment;
+use crate::models::GhararLevel;
+use crate::models::IkhtilafMap;
+use crate::models::IslamicPrinciple;
+use crate::models::MaqashidPrinciple;
+use crate::models::MaysirAssessment;
+use crate::models::MethodologyRuling;
+use crate::models::PegMechanism;
+use crate::models::RatioScreening;
+use crate::models::RatioStatus;
+use crate::models::RibaAnalysis;
+use crate::models::RulingDetermination;
+use crate::models::RulingEvidence;
+use crate::models::ScriptureReference;
+use crate::models::ShariaPreferences;
+use crate::models::StablecoinAnalysis;
+use crate::models::TokenExtension;
+use crate::models::analysis::ScrapedData;
+use crate::models::token::UniversalTokenInfo as TokenInfo;
+
+lazy_static! {
+    /// Rules from the environment, loaded once; a broken pack falls back to the bundled rules
+    static ref DEFAULT_ENGINE: Arc<RuleEngine> =
+        Arc::new(load_or_else("rule packs", RuleEngine::from_env, RuleEngine::builtin));
+    /// Standard and madhab positions from the environment, loaded once
+    static ref DEFAULT_SCHOOLS: Arc<SchoolPositions> =
+        Arc::new(load_or_else("school positions", SchoolPositions::from_env, SchoolPositions::builtin));
+    /// Maqashid signals from the environment, loaded once
+    static ref DEFAULT_MAQASHID: Arc<MaqashidAssessor> =
+        Arc::new(load_or_else("Maqashid signals", MaqashidAssessor::from_env, MaqashidAssessor::builtin));
+    /// Gambling programs and signals from the environment, loaded once
+    static ref DEFAULT_MAYSIR: Arc<MaysirDetector> =
+        Arc::new(load_or_else("maysir detector", MaysirDetector::from_env, MaysirDetector::builtin));
+    /// Lending protocols and riba signals from the environment, loaded once
+    static ref DEFAULT_RIBA: Arc<RibaDetector> =
+        Arc::new(load_or_else("riba detector", RibaDetector::from_env, RibaDetector::builtin));
+    /// Stablecoin pegs and guidance from the environment, loaded once
+    static ref DEFAULT_STABLECOIN: Arc<StablecoinClassifier> =
+        Arc::new(load_or_else("stablecoin classifier", StablecoinClassifier::from_env, StablecoinClassifier::builtin));
+    /// Quran and hadith text from the environment, loaded once
+    static ref DEFAULT_SCRIPTURE: Arc<ScriptureLibrary> =
+        Arc::new(load_or_else("scripture library", ScriptureLibrary::from_env, ScriptureLibrary::builtin));
+    /// Position sources, ruling phrases and bases of difference from the environment, loaded once
+    static ref DEFAULT_IKHTILAF: Arc<IkhtilafMapper> =
+        Arc::new(load_or_else("ikhtilaf mapper", IkhtilafMapper::from_env, IkhtilafMapper::builtin));
+    /// Evidence and confidence thresholds per ruling class from the environment, loaded once
+    static ref DEFAULT_DETERMINATION: Arc<RulingResolver> =
+        Arc::new(load_or_else("ruling thresholds", RulingResolver::from_env, RulingResolver::builtin));
+    /// Token category taxonomy from the environment, loaded once
+    static ref DEFAULT_TAXONOMY: Arc<TokenClassifier> =
+        Arc::new(load_or_else("token taxonomy", TokenClassifier::from_env, TokenClassifier::builtin));
+}
+
+/// Rule-based screening of token data, without any model call
 pub struct IslamicAnalyzer {
-    prohibited_keywords: Vec<String>,
-    halal_categories: Vec<String>,
-    haram_categories: Vec<String>,
+    engine: Arc<RuleEngine>,
+    schools: Arc<SchoolPositions>,
+    maqashid: Arc<MaqashidAssessor>,
+    maysir: Arc<MaysirDetector>,
+    riba: Arc<RibaDetector>,
+    stablecoin: Arc<StablecoinClassifier>,
+    scripture: Arc<ScriptureLibrary>,
+    ikhtilaf: Arc<IkhtilafMapper>,
+    determination: Arc<RulingResolver>,
+    taxonomy: Arc<TokenClassifier>,
+}
+
+impl Default for IslamicAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl IslamicAnalyzer {
     pub fn new() -> Self {
+        Self::with_engine(DEFAULT_ENGINE.clone())
+    }
+
+    pub fn with_engine(engine: Arc<RuleEngine>) -> Self {
         Self {
-            prohibited_keywords: vec![
-                "gambling".to_string(), "casino".to_string(), "lottery".to_string(),
-                "alcohol".to_string(), "pork".to_string(), "interest".to_string(),
-                "usury".to_string(), "riba".to_string(), "lending".to_string(),
-                "borrowing".to_string(), "leverage".to_string(), "margin".to_string(),
-            ],
-            halal_categories: vec![
-                "utility".to_string(), "infrastructure".to_string(), "gaming".to_string(),
-                "social".to_string(), "education".to_string(), "healthcare".to_string(),
-            ],
-            haram_categories: vec![
-                "defi".to_string(), "lending".to_string(), "gambling".to_string(),
-                "adult".to_string(), "alcohol".to_string(),
-            ],
+            engine,
+            schools: DEFAULT_SCHOOLS.clone(),
+            maqashid: DEFAULT_MAQASHID.clone(),
+            maysir: DEFAULT_MAYSIR.clone(),
+            riba: DEFAULT_RIBA.clone(),
+            stablecoin: DEFAULT_STABLECOIN.clone(),
+            scripture: DEFAULT_SCRIPTURE.clone(),
+            ikhtilaf: DEFAULT_IKHTILAF.clone(),
+            determination: DEFAULT_DETERMINATION.clone(),
+            taxonomy: DEFAULT_TAXONOMY.clone(),
         }
     }
 
-    pub async fn analyze_token(&self, token_info: &TokenInfo) -> IslamicAnalysis {
-        let mut compliance_score = 0.5; // Start neutral
-        let mut reasoning = Vec::new();
-        let mut confidence = 0.7;
+    pub async fn analyze_token(
+        &self,
+        token_info: &TokenInfo,
+    ) -> IslamicAnalysisResult {
+        let gharar = self.gharar_assessment(token_info);
+        let maysir = self.maysir_assessment(token_info);
+        let riba = self.riba_analysis(token_info);
+        let stablecoin = self.stablecoin_analysis(token_info);
+        let categories = self.categories(token_info, &[]);
+        let result = self.engine.evaluate(&measured_facts(
+            token_info,
+            &gharar,
+            &maysir,
+            &riba,
+            stablecoin.as_ref(),
+            &categories,
+        ));
 
-        // Check description for prohibited content
-        let description_lower = token_info.description.to_lowercase();
+        // Nothing fired means the ruling rests on the base score alone
+        let confidence = if result.hits.is_empty() {
+            0.4
+        } else {
+            0.7
+        };
 
-        for keyword in &self.prohibited_keywords {
-            if description_lower.contains(keyword) {
-                compliance_score -= 0.2;
-                reasoning.push(format!("Contains prohibited keyword: {}", keyword));
-            }
-        }
+        let ratio_screening = screen_project(
+            &token_info.address,
+            &token_info.metadata.symbol,
+            token_info.price_data.as_ref().map(|price_data| price_data.market_cap as f64),
+        );
 
-        // Check for DeFi/lending protocols (high risk)
-        if description_lower.contains("defi") || description_lower.contains("yield") {
-            compliance_score -= 0.3;
-            reasoning.push("DeFi protocols may involve riba (interest)".to_string());
-        }
+        let mut reasoning = result.explain();
+        reasoning.extend(self.identify_risk_factors(
+            token_info,
+            ratio_screening.as_ref(),
+            &gharar,
+            &maysir,
+            &riba,
+            stablecoin.as_ref(),
+        ));
+
+        // Scripture that is malformed or missing from the library is not passed on as a reference
+        let evidence = result.evidence();
+        let resolved = self.resolve_scripture(&evidence, DEFAULT_LANGUAGE);
+        let scholar_references = evidence
+            .into_iter()
+            .filter(|line| !resolved.rejected.iter().any(|(rejected, _)| rejected == line))
+            .collect();
 
-        // Check for utility tokens (generally acceptable)
-        if description_lower.contains("utility") || description_lower.contains("infrastructure") {
-            compliance_score += 0.2;
-            reasoning.push("Utility tokens are generally permissible".to_string());
+        IslamicAnalysisResult {
+            is_halal: result.ruling == IslamicPrinciple::Halal,
+            compliance_score: result.score,
+            confidence,
+            reasoning,
+            scholar_references,
+            ruling: Some(result.ruling),
+            rule_trace: result.hits,
+            ratio_screening,
+            gharar: Some(gharar),
+            maysir: Some(maysir),
+            riba: Some(riba),
+            stablecoin,
+            scripture_references: resolved.references,
+            categories,
         }
+    }
 
-        // Ensure score is between 0 and 1
-        compliance_score = compliance_score.max(0.0).min(1.0);
+    /// A ruling per standard the user follows, under their madhab when one is chosen
+    pub fn methodology_rulings(
+        &self,
+        token_info: &TokenInfo,
+        preferences: &ShariaPreferences,
+        categories: &[CategoryLabel],
+    ) -> Vec<MethodologyRuling> {
+        let financials = match ProjectFinancials::from_env(&token_info.address, &token_info.metadata.symbol) {
+            Ok(financials) => financials.map(|financials| {
+                financials.with_market_cap_fallback(
+                    token_info.price_data.as_ref().map(|price_data| price_data.market_cap as f64),
+                )
+            }),
+            Err(e) => {
+                error!("Failed to load project financials for {}: {}", token_info.metadata.symbol, e);
+                None
+            },
+        };
 
-        let is_halal = compliance_score >= 0.6;
+        let facts = measured_facts(
+            token_info,
+            &self.gharar_assessment(token_info),
+            &self.maysir_assessment(token_info),
+            &self.riba_analysis(token_info),
+            self.stablecoin_analysis(token_info).as_ref(),
+            categories,
+        );
+        self.schools.rulings(&self.engine, &facts, financials.as_ref(), preferences)
+    }
 
-        IslamicAnalysis {
-            is_halal,
-            compliance_score,
-            confidence,
-            reasoning,
-            scholar_references: vec![
-                "Quran 2:275 - Allah has permitted trade and forbidden riba".to_string(),
-                "AAOIFI Sharia Standard No. 17".to_string(),
-            ],
-            risk_factors: self.identify_risk_factors(&token_info),
+    /// Gharar measured from the project file, completed with the token's market data
+    pub fn gharar_assessment(
+        &self,
+        token_info: &TokenInfo,
+    ) -> GhararAssessment {
+        let inputs = match GhararInputs::from_env(&token_info.address, &token_info.metadata.symbol) {
+            Ok(inputs) => inputs.unwrap_or_default(),
+            Err(e) => {
+                error!("Failed to load gharar inputs for {}: {}", token_info.metadata.symbol, e);
+                GhararInputs::default()
+            },
+        };
+
+        inputs.with_token(token_info).assess(Utc::now().timestamp() as u64)
+    }
+
+    /// Taxonomy categories from the token's metadata, its program interactions and the pages scraped about it
+    pub fn categories(
+        &self,
+        token_info: &TokenInfo,
+        scraped: &[ScrapedData],
+    ) -> Vec<CategoryLabel> {
+        let interactions = program_interactions_from_env(&token_info.address, &token_info.metadata.symbol)
+            .unwrap_or_else(|e| {
+                error!("Failed to load program interactions for {}: {}", token_info.metadata.symbol, e);
+                Vec::new()
+            });
+
+        self.taxonomy
+            .classify(&token_info.address, &token_facts(token_info), &interactions, scraped)
+    }
+
+    /// Likelihood of gambling use from the token's program interactions, tokenomics and trading
+    pub fn maysir_assessment(
+        &self,
+        token_info: &TokenInfo,
+    ) -> MaysirAssessment {
+        let interactions = program_interactions_from_env(&token_info.address, &token_info.metadata.symbol)
+            .unwrap_or_else(|e| {
+                error!("Failed to load program interactions for {}: {}", token_info.metadata.symbol, e);
+                Vec::new()
+            });
+
+        self.maysir.detect(&token_facts(token_info), &interactions)
+    }
+
+    /// Typed riba findings from the token's mint and its extensions, yield sources and description
+    pub fn riba_analysis(
+        &self,
+        token_info: &TokenInfo,
+    ) -> RibaAnalysis {
+        let inputs = RibaInputs::from_env(&token_info.address, &token_info.metadata.symbol).unwrap_or_else(|e| {
+            error!("Failed to load riba inputs for {}: {}", token_info.metadata.symbol, e);
+            None
+        });
+
+        self.riba
+            .detect(&token_facts(token_info), &inputs.unwrap_or_default().with_token(token_info))
+    }
+
+    /// Peg mechanism, attested reserves and usage conditions, or `None` when the token is not a stablecoin
+    pub fn stablecoin_analysis(
+        &self,
+        token_info: &TokenInfo,
+    ) -> Option<StablecoinAnalysis> {
+        let inputs = StablecoinInputs::from_env(&token_info.address, &token_info.metadata.symbol).unwrap_or_else(|e| {
+            error!("Failed to load stablecoin inputs for {}: {}", token_info.metadata.symbol, e);
+            None
+        });
+
+        self.stablecoin
+            .analyze(&token_facts(token_info), &inputs.unwrap_or_default(), Utc::now().timestamp() as u64)
+    }
+
+    /// The Quran and hadith among `citations`, with their text in `language`; malformed and unknown citations are
+    /// logged and left out
+    pub fn scripture_references(
+        &self,
+        citations: &[String],
+        language: &str,
+    ) -> Vec<ScriptureReference> {
+        self.resolve_scripture(citations, language).references
+    }
+
+    /// The bundled or configured scripture library, for lookup and search
+    pub fn scripture_library(&self) -> &ScriptureLibrary {
+        &self.scripture
+    }
+
+    /// Majority and minority positions of the known sources among `scraped` and of `fatwas`, weighed by
+    /// credibility and recency; `None` when none of them states a ruling
+    pub fn ikhtilaf(
+        &self,
+        scraped: &[ScrapedData],
+        fatwas: &[Fatwa],
+    ) -> Option<IkhtilafMap> {
+        self.ikhtilaf.map(scraped, fatwas, Utc::now().timestamp() as u64)
+    }
+
+    /// The ruling the evidence supports under the configured thresholds, or Syubhat with the reasons for abstaining
+    pub fn determine_ruling(
+        &self,
+        evidence: Vec<RulingEvidence>,
+    ) -> RulingDetermination {
+        self.determination.resolve(evidence)
+    }
+
+    /// Scores for the five objectives and the benefit/harm balance, each with its rationale
+    pub fn maqashid_assessment(
+        &self,
+        facts: &TokenFacts,
+    ) -> Vec<MaqashidPrinciple> {
+        self.maqashid.assess(facts)
+    }
+
+    fn resolve_scripture(
+        &self,
+        citations: &[String],
+        language: &str,
+    ) -> ResolvedEvidence {
+        let resolved = self.scripture.resolve_evidence(citations, language);
+        for (line, reason) in &resolved.rejected {
+            warn!("Dropping scripture reference '{}': {}", line, reason);
         }
+        resolved
     }
 
-    fn identify_risk_factors(&self, token_info: &TokenInfo) -> Vec<String> {
+    fn identify_risk_factors(
+        &self,
+        token_info: &TokenInfo,
+        ratio_screening: Option<&RatioScreening>,
+        gharar: &GhararAssessment,
+        maysir: &MaysirAssessment,
+        riba: &RibaAnalysis,
+        stablecoin: Option<&StablecoinAnalysis>,
+    ) -> Vec<String> {
         let mut risks = Vec::new();
 
-        if token_info.total_supply > 1_000_000_000.0 {
-            risks.push("High token supply may indicate inflationary pressure".to_string());
+        if let Some(price_data) = &token_info.price_data {
+            if price_data.total_supply.unwrap_or(0.0) > 1_000_000_000.0 {
+                risks.push("High token supply may indicate inflationary pressure".to_owned());
+            }
+
+            if price_data.price_usd < 0.01 {
+                risks.push("Very low price may indicate speculative nature".to_owned());
+            }
+        }
+
+        for ratio in ratio_screening.iter().flat_map(|screening| &screening.ratios) {
+            if ratio.status == RatioStatus::Fail {
+                risks.push(format!(
+                    "{} of {:.1}% exceeds the {:.1}% limit",
+                    ratio.description,
+                    ratio.value.unwrap_or_default() * 100.0,
+                    ratio.threshold * 100.0
+                ));
+            }
+        }
+
+        if matches!(gharar.level, GhararLevel::Mutawassit | GhararLevel::Fahish) {
+            let factors = gharar
+                .factors
+                .iter()
+                .filter(|factor| factor.risk.is_some_and(|risk| risk >= 0.5))
+                .map(|factor| factor.explanation.as_str())
+                .collect::<Vec<_>>();
+            risks.push(format!("Measured gharar of {:.2} ({:?}): {}", gharar.score, gharar.level, factors.join("; ")));
+        }
+
+        if maysir.probability >= POSSIBLE_MAYSIR {
+            let observed = maysir
+                .evidence
+                .iter()
+                .map(|item| format!("[{}] {}", item.source, item.observed))
+                .collect::<Vec<_>>();
+            risks.push(format!(
+                "Maysir probability of {:.0}%{}: {}",
+                maysir.probability * 100.0,
+                if maysir.probability >= LIKELY_MAYSIR {
+                    " (likely gambling)"
+                } else {
+                    ""
+                },
+                observed.join("; ")
+            ));
+        }
+
+        for finding in &riba.findings {
+            risks.push(format!("Riba ({:?}): {}", finding.kind, finding.observed));
         }
 
-        if token_info.current_price < 0.01 {
-            risks.push("Very low price may indicate speculative nature".to_string());
+        if let Some(stablecoin) = stablecoin {
+            if stablecoin.mechanism == PegMechanism::Algorithmic {
+                risks.push(format!("Algorithmic stablecoin peg ({})", stablecoin.observed));
+            }
+            risks.push(format!(
+                "{:?} stablecoin, permissible while: {}",
+                stablecoin.mechanism,
+                stablecoin.conditions.join("; ")
+            ));
+            risks.push(format!("Stablecoin caveats: {}", stablecoin.caveats.join("; ")));
         }
 
         risks
     }
-}
\ No newline at end of file
+}
+
+/// Token facts plus the measured maysir probability, riba findings, stablecoin peg and taxonomy categories, and the
+/// gharar score once enough inputs were available to grade it
+pub fn measured_facts(
+    token_info: &TokenInfo,
+    gharar: &GhararAssessment,
+    maysir: &MaysirAssessment,
+    riba: &RibaAnalysis,
+    stablecoin: Option<&StablecoinAnalysis>,
+    categories: &[CategoryLabel],
+) -> TokenFacts {
+    let mut facts = token_facts(token_info)
+        .number("maysir_probability", maysir.probability)
+        .number("riba_findings", riba.findings.len() as f64);
+    if !categories.is_empty() {
+        let names = categories
+            .iter()
+            .map(|label| format!("{:?}", label.category))
+            .collect::<Vec<_>>();
+        facts = facts.text("categories", &names.join(" "));
+    }
+    for label in categories {
+        facts = facts.number(&category_fact(label.category), label.confidence);
+    }
+    if let Some(stablecoin) = stablecoin {
+        facts = facts.text("peg_mechanism", &format!("{:?}", stablecoin.mechanism));
+        if let Some(reserves) = &stablecoin.reserves {
+            facts = facts
+                .number("reserve_interest_bearing_share", reserves.interest_bearing_share())
+                .maybe_number("reserve_backing_ratio", reserves.backing_ratio);
+        }
+    }
+    if gharar.level == GhararLevel::Undetermined {
+        facts
+    } else {
+        facts.number("gharar_score", gharar.score)
+    }
+}
+
+/// Facts the rule packs can test, named as documented in the bundled `core.toml`
+pub fn token_facts(token_info: &TokenInfo) -> TokenFacts {
+    let metadata = &token_info.metadata;
+    let description = metadata.description.clone().unwrap_or_default();
+    let text = format!("{} {} {}", metadata.name, metadata.symbol, description);
+
+    let mut facts = TokenFacts::new()
+        .text("text", &text)
+        .text("name", &metadata.name)
+        .text("symbol", &metadata.symbol)
+        .text("description", &description)
+        .text("blockchain", &format!("{:?}", token_info.blockchain))
+        .text("token_standard", &format!("{:?}", metadata.token_standard))
+        .flag("verified", token_info.is_verified || metadata.verified)
+        .maybe_number("holders", token_info.holders.map(|holders| holders as f64));
+
+    if let Some(control) = &token_info.control {
+        facts = facts
+            .flag("mint_authority_active", control.mint_authority.is_some())
+            .flag("freeze_authority_active", control.freeze_authority.is_some())
+            .number("control_score", control.score);
+        if let Some(mutable) = control.metadata_mutable {
+            facts = facts.flag("metadata_mutable", mutable);
+        }
+    }
+
+    if !metadata.extensions.is_empty() {
+        let names = metadata.extensions.iter().map(TokenExtension::name).collect::<Vec<_>>();
+        facts = facts.text("extensions", &names.join(" "));
+    }
+
+    if !token_info.liquidity_pools.is_empty() {
+        facts = facts.number("liquidity_usd", token_info.liquidity_pools.iter().map(|pool| pool.liquidity_usd).sum());
+    }
+
+    if let Some(price_data) = &token_info.price_data {
+        facts = facts
+            .number("price_usd", price_data.price_usd)
+            .number("price_change_24h", price_data.price_change_24h)
+            .number("market_cap", price_data.market_cap as f64)
+            .number("volume_24h", price_data.volume_24h as f64)
+            .maybe_number("total_supply", price_data.total_supply);
+
+        if price_data.market_cap > 0 {
+            facts = facts.number("turnover_24h", price_data.volume_24h as f64 / price_data.market_cap as f64);
+        }
+    }
+
+    facts
+}
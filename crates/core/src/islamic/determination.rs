@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::rules::load_from_env;
+use crate::models::DeterminationState;
+use crate::models::IslamicPrinciple;
+use crate::models::RulingClass;
+use crate::models::RulingDetermination;
+use crate::models::RulingEvidence;
+
+/// Environment variable pointing at a TOML file that replaces the bundled thresholds
+pub const DETERMINATION_FILE_ENV: &str = "AVERROES_DETERMINATION_FILE";
+
+const BUILTIN_THRESHOLDS: &str = include_str!("determination.toml");
+
+/// What a ruling of one class needs before it is adopted
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ClassThreshold {
+    pub min_evidence: usize,
+    pub min_confidence: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThresholdFile {
+    thresholds: HashMap<RulingClass, ClassThreshold>,
+}
+
+/// Reaches a ruling from the evidence, or abstains with Syubhat when the evidence is thin or conflicting
+#[derive(Debug, Clone)]
+pub struct RulingResolver {
+    thresholds: HashMap<RulingClass, ClassThreshold>,
+}
+
+impl RulingResolver {
+    /// Parse and validate `[thresholds.<class>]` for every ruling class from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let file: ThresholdFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        for class in [RulingClass::Permissible, RulingClass::Doubtful, RulingClass::Prohibited] {
+            let threshold = file
+                .thresholds
+                .get(&class)
+                .ok_or_else(|| format!("Missing thresholds for {class:?}"))?;
+            if !(0.0..=1.0).contains(&threshold.min_confidence) {
+                return Err(format!("{class:?}: min_confidence must be between 0 and 1"));
+            }
+            if threshold.min_evidence == 0 {
+                return Err(format!("{class:?}: min_evidence must be at least 1"));
+            }
+        }
+
+        Ok(Self {
+            thresholds: file.thresholds,
+        })
+    }
+
+    /// The bundled thresholds
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_THRESHOLDS).expect("Bundled ruling thresholds must be valid")
+    }
+
+    /// Thresholds from `AVERROES_DETERMINATION_FILE`, or the bundled ones when unset
+    pub fn from_env() -> Result<Self, String> {
+        load_from_env(DETERMINATION_FILE_ENV, Self::from_toml, Self::builtin)
+    }
+
+    pub fn threshold(
+        &self,
+        class: RulingClass,
+    ) -> ClassThreshold {
+        self.thresholds[&class]
+    }
+
+    /// Undetermined without evidence; Determined by the first decisive evidence, or by the class with the most
+    /// confidence behind it when that class meets its thresholds unopposed; Syubhat otherwise
+    pub fn resolve(
+        &self,
+        evidence: Vec<RulingEvidence>,
+    ) -> RulingDetermination {
+        if evidence.is_empty() {
+            return RulingDetermination {
+                state: DeterminationState::Undetermined,
+                ruling: IslamicPrinciple::Syubhat,
+                proposed: None,
+                confidence: 0.0,
+                evidence,
+                abstentions: vec!["No evidence about the token was available".to_owned()],
+            };
+        }
+
+        let decisive = evidence.iter().filter(|item| item.decisive).collect::<Vec<_>>();
+        if let Some(first) = decisive.first() {
+            let ruling = first.ruling.clone();
+            let confidence = combined(&decisive);
+            return RulingDetermination {
+                state: DeterminationState::Determined,
+                ruling: ruling.clone(),
+                proposed: Some(ruling),
+                confidence,
+                evidence,
+                abstentions: vec![],
+            };
+        }
+
+        // The class with the most confidence behind it; on a tie the stricter one
+        let mut classes: Vec<(RulingClass, Vec<&RulingEvidence>)> = Vec::new();
+        for item in &evidence {
+            let class = item.ruling.class();
+            match classes.iter_mut().find(|(existing, _)| *existing == class) {
+                Some((_, items)) => items.push(item),
+                None => classes.push((class, vec![item])),
+            }
+        }
+        let total = |items: &[&RulingEvidence]| items.iter().map(|item| item.confidence).sum::<f64>();
+        let (class, support) = classes
+            .iter()
+            .max_by(|(class_a, a), (class_b, b)| total(a).total_cmp(&total(b)).then_with(|| class_a.cmp(class_b)))
+            .expect("Evidence is not empty");
+        let proposed = support
+            .iter()
+            .copied()
+            .reduce(|best, next| {
+                if next.confidence > best.confidence {
+                    next
+                } else {
+                    best
+                }
+            })
+            .map(|item| item.ruling.clone())
+            .expect("A class has at least one piece of evidence");
+        let confidence = combined(support);
+
+        let threshold = self.threshold(*class);
+        let mut abstentions = Vec::new();
+        if support.len() < threshold.min_evidence {
+            abstentions.push(format!(
+                "Only {} of the {} pieces of evidence a {class:?} ruling needs",
+                support.len(),
+                threshold.min_evidence
+            ));
+        }
+        if confidence < threshold.min_confidence {
+            abstentions.push(format!(
+                "Confidence {confidence:.2} is below the {:.2} a {class:?} ruling needs",
+                threshold.min_confidence
+            ));
+        }
+        if let Some(opposite) = opposite(*class) {
+            let min_confidence = self.threshold(opposite).min_confidence;
+            abstentions.extend(
+                evidence
+                    .iter()
+                    .filter(|item| item.ruling.class() == opposite && item.confidence >= min_confidence)
+                    .map(|item| {
+                        format!(
+                            "Conflicting evidence: {} holds {:?} with confidence {:.2}",
+                            item.source, item.ruling, item.confidence
+                        )
+                    }),
+            );
+        }
+
+        let (state, ruling) = if abstentions.is_empty() {
+            (DeterminationState::Determined, proposed.clone())
+        } else {
+            (DeterminationState::Syubhat, IslamicPrinciple::Syubhat)
+        };
+        RulingDetermination {
+            state,
+            ruling,
+            proposed: Some(proposed),
+            confidence,
+            evidence,
+            abstentions,
+        }
+    }
+}
+
+/// Confidence that at least one of several independent pieces of evidence is right, so corroboration never
+/// weakens a ruling
+fn combined(items: &[&RulingEvidence]) -> f64 {
+    1.0 - items.iter().map(|item| 1.0 - item.confidence).product::<f64>()
+}
+
+fn opposite(class: RulingClass) -> Option<RulingClass> {
+    match class {
+        RulingClass::Permissible => Some(RulingClass::Prohibited),
+        RulingClass::Prohibited => Some(RulingClass::Permissible),
+        RulingClass::Doubtful => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evidence(
+        source: &str,
+        ruling: IslamicPrinciple,
+        confidence: f64,
+    ) -> RulingEvidence {
+        RulingEvidence {
+            source: source.to_owned(),
+            ruling,
+            confidence,
+            decisive: false,
+        }
+    }
+
+    #[test]
+    fn test_abstains_when_evidence_is_missing_thin_or_conflicting() {
+        let resolver = RulingResolver::builtin();
+
+        let nothing = resolver.resolve(vec![]);
+        assert_eq!(nothing.state, DeterminationState::Undetermined);
+        assert_eq!(nothing.ruling, IslamicPrinciple::Syubhat);
+
+        // A lukewarm "halal" does not make a token permissible
+        let thin = resolver.resolve(vec![evidence("model", IslamicPrinciple::Halal, 0.7)]);
+        assert_eq!(thin.state, DeterminationState::Syubhat);
+        assert_eq!(thin.ruling, IslamicPrinciple::Syubhat);
+        assert_eq!(thin.proposed, Some(IslamicPrinciple::Halal));
+        assert_eq!(thin.abstentions, vec!["Confidence 0.70 is below the 0.75 a Permissible ruling needs".to_owned()]);
+
+        let strict = RulingResolver::from_toml(
+            r#"
+            thresholds.Permissible = { min_evidence = 2, min_confidence = 0.75 }
+            thresholds.Doubtful = { min_evidence = 1, min_confidence = 0.0 }
+            thresholds.Prohibited = { min_evidence = 1, min_confidence = 0.6 }
+            "#,
+        )
+        .unwrap();
+        let single = strict.resolve(vec![evidence("model", IslamicPrinciple::Halal, 0.9)]);
+        assert_eq!(single.ruling, IslamicPrinciple::Syubhat);
+        assert_eq!(single.abstentions, vec![
+            "Only 1 of the 2 pieces of evidence a Permissible ruling needs".to_owned()
+        ]);
+
+        let conflicting = resolver.resolve(vec![
+            evidence("model", IslamicPrinciple::Halal, 0.9),
+            evidence("standard:Aaoifi", IslamicPrinciple::Halal, 0.8),
+            evidence("standard:MuiDsn", IslamicPrinciple::Haram, 0.7),
+        ]);
+        assert_eq!(conflicting.state, DeterminationState::Syubhat);
+        assert_eq!(conflicting.abstentions, vec![
+            "Conflicting evidence: standard:MuiDsn holds Haram with confidence 0.70".to_owned()
+        ]);
+    }
+
+    #[test]
+    fn test_determines_rulings_that_meet_their_thresholds() {
+        let resolver = RulingResolver::builtin();
+
+        let halal = resolver.resolve(vec![
+            evidence("model", IslamicPrinciple::Halal, 0.8),
+            evidence("standard:Aaoifi", IslamicPrinciple::Mubah, 0.75),
+            evidence("standard:MuiDsn", IslamicPrinciple::Haram, 0.4),
+        ]);
+        assert_eq!(halal.state, DeterminationState::Determined);
+        assert_eq!(halal.ruling, IslamicPrinciple::Halal);
+        assert!(halal.abstentions.is_empty());
+
+        // A weakly credited source that agrees adds to the confidence instead of diluting it
+        let corroborated = resolver.resolve(vec![
+            evidence("model", IslamicPrinciple::Halal, 0.85),
+            evidence("source:CryptoHalal", IslamicPrinciple::Halal, 0.3),
+        ]);
+        assert_eq!(corroborated.ruling, IslamicPrinciple::Halal);
+        assert!(corroborated.confidence > 0.85);
+
+        let haram = resolver.resolve(vec![evidence("model", IslamicPrinciple::Haram, 0.8)]);
+        assert_eq!(haram.ruling, IslamicPrinciple::Haram);
+
+        // Decisive evidence settles the prohibition whatever else was said
+        let riba = resolver.resolve(vec![evidence("model", IslamicPrinciple::Halal, 0.9), RulingEvidence {
+            decisive: true,
+            ..evidence("riba", IslamicPrinciple::Riba, 1.0)
+        }]);
+        assert_eq!(riba.state, DeterminationState::Determined);
+        assert_eq!(riba.ruling, IslamicPrinciple::Riba);
+    }
+}
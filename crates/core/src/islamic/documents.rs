@@ -0,0 +1,1021 @@
+//! Whitepaper ingestion: text and section structure from PDF, Markdown and HTML documents,
+//! chunked and embedded for retrieval, with the tokenomics, revenue model and use-of-funds sections
+//! picked out for the analysis.
+//!
+//! Documents are stored per token in sled; the analysis quotes the latest key sections on file with
+//! the pages they were found on.
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use scraper::ElementRef;
+use scraper::Html;
+use scraper::Selector;
+use serde::Deserialize;
+use serde::Serialize;
+use sled::Db;
+use sled::Tree;
+use tracing::error;
+use tracing::info;
+use tracing::warn;
+use url::Url;
+use uuid::Uuid;
+
+use super::rules::contains_word;
+use super::rules::load_from_env;
+use super::rules::load_or_else;
+use super::storage::db_path;
+use super::storage::fnv1a;
+use super::storage::mint_key;
+use super::storage::open_db;
+use super::storage::open_or_in_memory;
+use crate::http::HttpClient;
+use crate::http::HttpError;
+use crate::models::DocumentChunk;
+use crate::models::DocumentExcerpt;
+use crate::models::DocumentFormat;
+use crate::models::DocumentSection;
+use crate::models::DocumentSectionKind;
+use crate::models::ProjectDocument;
+use crate::models::token::TokenMetadataInternal;
+
+/// Environment variable pointing at a TOML file that replaces the bundled ingestion settings
+pub const DOCUMENTS_FILE_ENV: &str = "AVERROES_DOCUMENTS_FILE";
+
+/// Environment variable with the path of the document database; an in-memory one is used when unset
+pub const DOCUMENTS_DB_ENV: &str = "AVERROES_DOCUMENTS_DB";
+
+const BUILTIN_DOCUMENTS: &str = include_str!("documents.toml");
+
+/// Longest line of a PDF that can be taken for a heading
+const MAX_HEADING_CHARS: usize = 80;
+
+lazy_static! {
+    /// Settings from `AVERROES_DOCUMENTS_FILE`, loaded once
+    static ref DEFAULT_INGESTER: DocumentIngester =
+        load_or_else("document settings", DocumentIngester::from_env, DocumentIngester::builtin);
+
+    /// Documents at `AVERROES_DOCUMENTS_DB`, opened once
+    static ref SHARED: DocumentStore =
+        open_or_in_memory("document", DocumentStore::from_env, || DocumentStore::open(None));
+
+    static ref TITLE: Selector = Selector::parse("title").unwrap();
+    static ref BLOCKS: Selector = Selector::parse("h1, h2, h3, h4, h5, h6, p, li, td, pre, blockquote").unwrap();
+    static ref LINKS: Selector = Selector::parse("a[href]").unwrap();
+}
+
+/// Why a document was not ingested
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum DocumentError {
+    #[error("Invalid document: {0}")]
+    Invalid(String),
+
+    #[error("Failed to fetch document: {0}")]
+    Fetch(String),
+
+    #[error("Document storage error: {0}")]
+    Storage(String),
+}
+
+/// How one key section is recognised
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionProfile {
+    pub kind: DocumentSectionKind,
+    /// Matched on whole words in section headings
+    #[serde(default)]
+    pub headings: Vec<String>,
+    /// Embedded and compared with the chunks when no heading matches
+    #[serde(default)]
+    pub terms: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DocumentsFile {
+    max_bytes: usize,
+    chunk_words: usize,
+    chunk_overlap: usize,
+    embedding_dimension: usize,
+    min_similarity: f32,
+    excerpt_chars: usize,
+    #[serde(default)]
+    link_terms: Vec<String>,
+    #[serde(default)]
+    sections: Vec<SectionProfile>,
+}
+
+/// Turns project documents into sections, embedded chunks and key-section excerpts
+#[derive(Debug, Clone)]
+pub struct DocumentIngester {
+    config: DocumentsFile,
+}
+
+impl DocumentIngester {
+    /// Parse and validate the settings and `[[sections]]` from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let config: DocumentsFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        for (name, value) in [
+            ("max_bytes", config.max_bytes),
+            ("chunk_words", config.chunk_words),
+            ("embedding_dimension", config.embedding_dimension),
+            ("excerpt_chars", config.excerpt_chars),
+        ] {
+            if value == 0 {
+                return Err(format!("{name} must be above 0"));
+            }
+        }
+        if config.chunk_overlap >= config.chunk_words {
+            return Err("chunk_overlap must be below chunk_words".to_owned());
+        }
+        if !(0.0..=1.0).contains(&config.min_similarity) {
+            return Err("min_similarity must be between 0 and 1".to_owned());
+        }
+        for (index, profile) in config.sections.iter().enumerate() {
+            if config.sections[..index].iter().any(|other| other.kind == profile.kind) {
+                return Err(format!("{:?} is listed more than once", profile.kind));
+            }
+            if profile.headings.is_empty() && profile.terms.is_empty() {
+                return Err(format!("{:?} needs at least one heading or term", profile.kind));
+            }
+        }
+
+        Ok(Self {
+            config,
+        })
+    }
+
+    /// The bundled settings
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_DOCUMENTS).expect("Bundled document settings must be valid")
+    }
+
+    /// Settings from `AVERROES_DOCUMENTS_FILE`, or the bundled ones when unset
+    pub fn from_env() -> Result<Self, String> {
+        load_from_env(DOCUMENTS_FILE_ENV, Self::from_toml, Self::builtin)
+    }
+
+    /// The process-wide ingester
+    pub fn shared() -> Self {
+        DEFAULT_INGESTER.clone()
+    }
+
+    /// Extract, chunk and embed a document; `source` is its URL or file name
+    pub fn ingest(
+        &self,
+        token: &str,
+        source: &str,
+        format: DocumentFormat,
+        bytes: &[u8],
+    ) -> Result<ProjectDocument, DocumentError> {
+        let token = mint_key(token).ok_or_else(|| DocumentError::Invalid("token must be a mint address".to_owned()))?;
+        if bytes.len() > self.config.max_bytes {
+            return Err(DocumentError::Invalid(format!(
+                "{source} is {} bytes, above the limit of {}",
+                bytes.len(),
+                self.config.max_bytes
+            )));
+        }
+
+        let extracted = match format {
+            DocumentFormat::Pdf => pdf_sections(bytes)?,
+            DocumentFormat::Markdown => markdown_sections(&String::from_utf8_lossy(bytes)),
+            DocumentFormat::Html => html_sections(&String::from_utf8_lossy(bytes)),
+        };
+        if extracted.sections.iter().all(|section| section.text.is_empty()) {
+            return Err(DocumentError::Invalid(format!("{source} has no extractable text")));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let chunks = self.chunks(&extracted.sections);
+        let key_sections = self.key_sections(&id, source, &extracted.sections, &chunks);
+        Ok(ProjectDocument {
+            title: extracted
+                .title
+                .or_else(|| extracted.sections.iter().find_map(|section| section.heading.clone())),
+            id,
+            token,
+            source: source.to_owned(),
+            format,
+            page_count: extracted.page_count,
+            sections: extracted.sections,
+            chunks,
+            key_sections,
+            ingested_at: Utc::now().timestamp() as u64,
+        })
+    }
+
+    /// Download and ingest a document; an HTML page linking to a whitepaper is followed to it
+    pub async fn fetch(
+        &self,
+        http: &HttpClient,
+        token: &str,
+        url: &str,
+    ) -> Result<ProjectDocument, DocumentError> {
+        let (format, bytes) = self.download(http, url).await?;
+        if format == DocumentFormat::Html {
+            let page = String::from_utf8_lossy(&bytes);
+            if let Some(link) = whitepaper_link(&page, url, &self.config.link_terms).filter(|link| link != url) {
+                info!("Following whitepaper link {} from {}", link, url);
+                match self.download(http, &link).await {
+                    Ok((format, bytes)) => return self.ingest(token, &link, format, &bytes),
+                    Err(e) => warn!("Failed to fetch linked whitepaper, ingesting {} instead: {}", url, e),
+                }
+            }
+        }
+
+        self.ingest(token, url, format, &bytes)
+    }
+
+    /// Ingest the whitepaper found through a token's website
+    pub async fn fetch_linked(
+        &self,
+        http: &HttpClient,
+        metadata: &TokenMetadataInternal,
+    ) -> Result<ProjectDocument, DocumentError> {
+        let website = metadata
+            .website
+            .as_deref()
+            .filter(|website| !website.trim().is_empty())
+            .ok_or_else(|| DocumentError::Invalid(format!("{} has no website", metadata.symbol)))?;
+
+        self.fetch(http, &metadata.mint_address, website.trim()).await
+    }
+
+    /// Embedding comparable with the stored chunks
+    pub fn embed(
+        &self,
+        text: &str,
+    ) -> Vec<f32> {
+        embed_text(text, self.config.embedding_dimension)
+    }
+
+    /// Links come from users and token websites, so only public hosts are fetched and the size is capped
+    async fn download(
+        &self,
+        http: &HttpClient,
+        url: &str,
+    ) -> Result<(DocumentFormat, Vec<u8>), DocumentError> {
+        let response = http
+            .get_untrusted(url, self.config.max_bytes)
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| match e {
+                HttpError::Refused(_)
+                | HttpError::TooLarge {
+                    ..
+                } => DocumentError::Invalid(format!("{url}: {e}")),
+                e => DocumentError::Fetch(format!("{url}: {e}")),
+            })?;
+
+        let format = detect_format(response.header("content-type"), url, response.bytes())
+            .ok_or_else(|| DocumentError::Invalid(format!("{url} is not a PDF, Markdown or HTML document")))?;
+        Ok((format, response.bytes().to_vec()))
+    }
+
+    /// Overlapping runs of words within each section, so every chunk keeps one heading and page
+    fn chunks(
+        &self,
+        sections: &[DocumentSection],
+    ) -> Vec<DocumentChunk> {
+        let step = self.config.chunk_words - self.config.chunk_overlap;
+        let mut chunks = Vec::new();
+        for section in sections {
+            let words = section.text.split_whitespace().collect::<Vec<_>>();
+            let mut start = 0;
+            while start < words.len() {
+                let end = (start + self.config.chunk_words).min(words.len());
+                let text = words[start..end].join(" ");
+                let heading = section.heading.as_deref().unwrap_or_default();
+                chunks.push(DocumentChunk {
+                    index: chunks.len(),
+                    heading: section.heading.clone(),
+                    page: section.page,
+                    embedding: self.embed(&format!("{heading} {text}")),
+                    text,
+                });
+                if end == words.len() {
+                    break;
+                }
+                start += step;
+            }
+        }
+        chunks
+    }
+
+    fn key_sections(
+        &self,
+        document_id: &str,
+        source: &str,
+        sections: &[DocumentSection],
+        chunks: &[DocumentChunk],
+    ) -> Vec<DocumentExcerpt> {
+        self.config
+            .sections
+            .iter()
+            .filter_map(|profile| {
+                let (heading, pages, text) = self
+                    .headed_excerpt(profile, sections)
+                    .or_else(|| self.similar_excerpt(profile, chunks))?;
+                Some(DocumentExcerpt {
+                    kind: profile.kind,
+                    document_id: document_id.to_owned(),
+                    source: source.to_owned(),
+                    heading,
+                    pages,
+                    text,
+                })
+            })
+            .collect()
+    }
+
+    /// The first section headed like the profile, with its subsections and its continuation on later pages
+    fn headed_excerpt(
+        &self,
+        profile: &SectionProfile,
+        sections: &[DocumentSection],
+    ) -> Option<(Option<String>, Vec<u32>, String)> {
+        let start = sections.iter().position(|section| {
+            !section.continued
+                && section
+                    .heading
+                    .as_deref()
+                    .is_some_and(|heading| profile.headings.iter().any(|term| contains_word(heading, term)))
+        })?;
+        let level = sections[start].level;
+        let following = sections[start + 1..]
+            .iter()
+            .take_while(|section| section.continued || section.level > level);
+
+        let (mut pages, mut text) = (Vec::new(), String::new());
+        for (index, section) in std::iter::once(&sections[start]).chain(following).enumerate() {
+            if text.chars().count() >= self.config.excerpt_chars {
+                break;
+            }
+            if section.text.is_empty() {
+                continue;
+            }
+            if let Some(page) = section.page.filter(|page| !pages.contains(page)) {
+                pages.push(page);
+            }
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            if let Some(heading) = section.heading.as_deref().filter(|_| index > 0 && !section.continued) {
+                text.push_str(&format!("{heading}: "));
+            }
+            text.push_str(&section.text);
+        }
+        if text.is_empty() {
+            return None;
+        }
+
+        Some((sections[start].heading.clone(), pages, truncate_chars(&text, self.config.excerpt_chars)))
+    }
+
+    /// The chunk closest to the profile's terms, when it is close enough
+    fn similar_excerpt(
+        &self,
+        profile: &SectionProfile,
+        chunks: &[DocumentChunk],
+    ) -> Option<(Option<String>, Vec<u32>, String)> {
+        if profile.terms.is_empty() {
+            return None;
+        }
+        let query = self.embed(&profile.terms.join(" "));
+        let (score, chunk) = chunks
+            .iter()
+            .map(|chunk| (similarity(&query, &chunk.embedding), chunk))
+            .max_by(|a, b| a.0.total_cmp(&b.0))?;
+        if score < self.config.min_similarity {
+            return None;
+        }
+
+        Some((
+            chunk.heading.clone(),
+            chunk.page.into_iter().collect(),
+            truncate_chars(&chunk.text, self.config.excerpt_chars),
+        ))
+    }
+}
+
+/// A stored chunk matching a search, without its embedding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentMatch {
+    pub document_id: String,
+    pub source: String,
+    pub heading: Option<String>,
+    pub page: Option<u32>,
+    pub text: String,
+    pub score: f32, // Cosine similarity
+}
+
+/// Ingested documents persisted in sled, indexed by token
+#[derive(Clone)]
+pub struct DocumentStore {
+    db: Db,
+    documents: Tree, // id -> document JSON
+    by_token: Tree,  // "<token>\0" and big-endian sequence -> id
+}
+
+impl DocumentStore {
+    /// Database at `path`, or an in-memory one
+    pub fn open(path: Option<&str>) -> Result<Self, String> {
+        let db = open_db(path)?;
+        let tree = |name: &str| db.open_tree(name).map_err(|e| e.to_string());
+
+        Ok(Self {
+            documents: tree("documents")?,
+            by_token: tree("documents_by_token")?,
+            db,
+        })
+    }
+
+    /// Database at `AVERROES_DOCUMENTS_DB`, or an in-memory one when unset
+    pub fn from_env() -> Result<Self, String> {
+        Self::open(db_path(DOCUMENTS_DB_ENV).as_deref())
+    }
+
+    /// The process-wide document store
+    pub fn shared() -> Self {
+        SHARED.clone()
+    }
+
+    pub fn save(
+        &self,
+        document: &ProjectDocument,
+    ) -> Result<(), String> {
+        let json = serde_json::to_vec(document).map_err(|e| e.to_string())?;
+        let sequence = self.db.generate_id().map_err(|e| e.to_string())?;
+        let mut key = format!("{}\0", document.token).into_bytes();
+        key.extend_from_slice(&sequence.to_be_bytes());
+
+        self.documents.insert(document.id.as_bytes(), json).map_err(|e| e.to_string())?;
+        self.by_token.insert(key, document.id.as_bytes()).map_err(|e| e.to_string())?;
+        self.db.flush().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    pub fn get(
+        &self,
+        id: &str,
+    ) -> Result<Option<ProjectDocument>, String> {
+        match self.documents.get(id.as_bytes()).map_err(|e| e.to_string())? {
+            Some(value) => serde_json::from_slice(&value).map(Some).map_err(|e| e.to_string()),
+            None => Ok(None),
+        }
+    }
+
+    /// Documents ingested for a mint, oldest first
+    pub fn documents(
+        &self,
+        mint: &str,
+    ) -> Result<Vec<ProjectDocument>, String> {
+        Ok(self.indexed(mint)?.into_iter().map(|(_, document)| document).collect())
+    }
+
+    /// The most recently ingested excerpt of each kind in a mint's documents
+    pub fn excerpts_for(
+        &self,
+        mint: &str,
+    ) -> Result<Vec<DocumentExcerpt>, String> {
+        let mut documents = self.indexed(mint)?;
+        documents.sort_by_key(|(sequence, _)| std::cmp::Reverse(*sequence));
+
+        let mut excerpts: Vec<DocumentExcerpt> = Vec::new();
+        for excerpt in documents.into_iter().flat_map(|(_, document)| document.key_sections) {
+            if !excerpts.iter().any(|known| known.kind == excerpt.kind) {
+                excerpts.push(excerpt);
+            }
+        }
+        excerpts.sort_by_key(|excerpt| excerpt.kind);
+        Ok(excerpts)
+    }
+
+    /// Chunks of a mint's documents most similar to `embedding`, best first
+    pub fn search(
+        &self,
+        mint: &str,
+        embedding: &[f32],
+        limit: usize,
+    ) -> Result<Vec<DocumentMatch>, String> {
+        let mut matches = self
+            .indexed(mint)?
+            .into_iter()
+            .flat_map(|(_, document)| {
+                document.chunks.into_iter().map(move |chunk| DocumentMatch {
+                    document_id: document.id.clone(),
+                    source: document.source.clone(),
+                    score: similarity(embedding, &chunk.embedding),
+                    heading: chunk.heading,
+                    page: chunk.page,
+                    text: chunk.text,
+                })
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        Ok(matches)
+    }
+
+    /// Documents under a mint with the sequence they were saved under, oldest first; none for anything else
+    fn indexed(
+        &self,
+        mint: &str,
+    ) -> Result<Vec<(u64, ProjectDocument)>, String> {
+        let Some(mint) = mint_key(mint) else {
+            return Ok(Vec::new());
+        };
+        let prefix = format!("{mint}\0");
+        self.by_token
+            .scan_prefix(prefix.as_bytes())
+            .map(|entry| {
+                let (key, id) = entry.map_err(|e| e.to_string())?;
+                let sequence = key[prefix.len()..]
+                    .try_into()
+                    .map(u64::from_be_bytes)
+                    .map_err(|_| "Malformed document index key".to_owned())?;
+                let document = self
+                    .get(&String::from_utf8_lossy(&id))?
+                    .ok_or_else(|| "Document index points at a missing document".to_owned())?;
+                Ok((sequence, document))
+            })
+            .collect()
+    }
+}
+
+/// Format of a document from its content type, its file name or URL, and its first bytes
+pub fn detect_format(
+    content_type: Option<&str>,
+    location: &str,
+    bytes: &[u8],
+) -> Option<DocumentFormat> {
+    let content_type = content_type.unwrap_or_default().to_lowercase();
+    let path = location.split(['?', '#']).next().unwrap_or_default().to_lowercase();
+    let has_extension = |extensions: &[&str]| extensions.iter().any(|extension| path.ends_with(extension));
+
+    if bytes.starts_with(b"%PDF-") || content_type.contains("pdf") || has_extension(&[".pdf"]) {
+        return Some(DocumentFormat::Pdf);
+    }
+    if content_type.contains("html") || has_extension(&[".html", ".htm"]) {
+        return Some(DocumentFormat::Html);
+    }
+    if content_type.contains("markdown")
+        || content_type.starts_with("text/plain")
+        || has_extension(&[".md", ".markdown", ".txt"])
+    {
+        return Some(DocumentFormat::Markdown);
+    }
+
+    // Nothing to go on but the content, which must at least be text
+    let text = std::str::from_utf8(bytes).ok()?;
+    if text.trim_start().starts_with('<') {
+        Some(DocumentFormat::Html)
+    } else {
+        Some(DocumentFormat::Markdown)
+    }
+}
+
+/// Hashed bag-of-words embedding of unit length; the same text always gets the same vector
+pub fn embed_text(
+    text: &str,
+    dimension: usize,
+) -> Vec<f32> {
+    let mut embedding = vec![0.0_f32; dimension];
+    if dimension == 0 {
+        return embedding;
+    }
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+    {
+        let hash = fnv1a(word.to_lowercase().as_bytes());
+        // The top bit picks a sign so that colliding words tend to cancel out rather than add up
+        let sign = if hash >> 63 == 0 {
+            1.0
+        } else {
+            -1.0
+        };
+        embedding[(hash % dimension as u64) as usize] += sign;
+    }
+
+    let norm = embedding.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for value in &mut embedding {
+            *value /= norm;
+        }
+    }
+    embedding
+}
+
+/// Cosine similarity of two unit-length embeddings
+fn similarity(
+    a: &[f32],
+    b: &[f32],
+) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Text and structure pulled out of a document
+struct Extracted {
+    title: Option<String>,
+    page_count: Option<u32>,
+    sections: Vec<DocumentSection>,
+}
+
+/// Collects lines into sections, starting a continuation whenever the page changes
+#[derive(Default)]
+struct SectionBuilder {
+    sections: Vec<DocumentSection>,
+}
+
+impl SectionBuilder {
+    fn heading(
+        &mut self,
+        heading: String,
+        level: u8,
+        page: Option<u32>,
+    ) {
+        self.sections.push(DocumentSection {
+            heading: Some(heading),
+            level,
+            page,
+            continued: false,
+            text: String::new(),
+        });
+    }
+
+    fn text(
+        &mut self,
+        text: &str,
+        page: Option<u32>,
+    ) {
+        let text = squash(text);
+        if text.is_empty() {
+            return;
+        }
+        if let Some(section) = self.sections.last_mut().filter(|section| section.page == page) {
+            if !section.text.is_empty() {
+                section.text.push(' ');
+            }
+            section.text.push_str(&text);
+            return;
+        }
+
+        let (heading, level) = self
+            .sections
+            .last()
+            .map(|section| (section.heading.clone(), section.level))
+            .unwrap_or((None, 0));
+        self.sections.push(DocumentSection {
+            continued: !self.sections.is_empty(),
+            heading,
+            level,
+            page,
+            text,
+        });
+    }
+}
+
+/// Text per page; PDFs carry no heading markup, so headings are recognised by [`pdf_heading`]
+fn pdf_sections(bytes: &[u8]) -> Result<Extracted, DocumentError> {
+    let document =
+        lopdf::Document::load_mem(bytes).map_err(|e| DocumentError::Invalid(format!("Unreadable PDF: {e}")))?;
+    let pages = document.get_pages();
+
+    let mut builder = SectionBuilder::default();
+    for &number in pages.keys() {
+        let text = match document.extract_text(&[number]) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("Skipping PDF page {} without extractable text: {}", number, e);
+                continue;
+            },
+        };
+        for line in text.lines() {
+            match pdf_heading(line) {
+                Some((heading, level)) => builder.heading(heading, level, Some(number)),
+                None => builder.text(line, Some(number)),
+            }
+        }
+    }
+
+    Ok(Extracted {
+        title: None,
+        page_count: Some(pages.len() as u32),
+        sections: builder.sections,
+    })
+}
+
+/// A short line that is numbered ("2.1 Token Allocation") or title-cased and doesn't end like a
+/// sentence, with its level: the depth of its numbering, or 1
+fn pdf_heading(line: &str) -> Option<(String, u8)> {
+    let line = squash(line);
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() || line.chars().count() > MAX_HEADING_CHARS || line.ends_with(['.', ',', ';', ':']) {
+        return None;
+    }
+
+    let numbering = words[0].trim_end_matches('.');
+    let depth = numbering.split('.').count();
+    let numbered = numbering
+        .split('.')
+        .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    let titles = words
+        .iter()
+        .skip(usize::from(numbered))
+        .filter(|word| word.chars().any(char::is_alphabetic))
+        .collect::<Vec<_>>();
+    // Short words are allowed in lower case: "Use of Funds"
+    let title_cased = titles
+        .iter()
+        .all(|word| word.chars().count() <= 3 || word.chars().next().is_some_and(|c| !c.is_lowercase()));
+    let wordy = titles
+        .iter()
+        .any(|word| word.chars().filter(|c| c.is_alphabetic()).count() >= 3);
+    if !title_cased || !wordy || titles.len() > 10 {
+        return None;
+    }
+
+    let level = if numbered {
+        depth.min(6) as u8
+    } else {
+        1
+    };
+    Some((line, level))
+}
+
+fn markdown_sections(text: &str) -> Extracted {
+    let mut builder = SectionBuilder::default();
+    let mut fenced = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fenced = !fenced;
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        let heading = trimmed[level..].trim().trim_end_matches('#').trim();
+        let is_heading = !fenced
+            && (1..=6).contains(&level)
+            && trimmed[level..].starts_with(char::is_whitespace)
+            && !heading.is_empty();
+        if is_heading {
+            builder.heading(squash(heading), level as u8, None);
+        } else {
+            builder.text(line, None);
+        }
+    }
+
+    Extracted {
+        title: None,
+        page_count: None,
+        sections: builder.sections,
+    }
+}
+
+fn html_sections(html: &str) -> Extracted {
+    let document = Html::parse_document(html);
+    let title = document
+        .select(&TITLE)
+        .next()
+        .map(|title| squash(&title.text().collect::<String>()))
+        .filter(|title| !title.is_empty());
+
+    let mut builder = SectionBuilder::default();
+    for element in document.select(&BLOCKS) {
+        // Blocks nested in another block were read with it
+        let nested = element
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(|ancestor| matches!(ancestor.value().name(), "p" | "li" | "td" | "pre" | "blockquote"));
+        if nested {
+            continue;
+        }
+
+        let text = element.text().collect::<Vec<_>>().join(" ");
+        let name = element.value().name();
+        match name.strip_prefix('h').and_then(|level| level.parse::<u8>().ok()) {
+            Some(level) if !squash(&text).is_empty() => builder.heading(squash(&text), level, None),
+            Some(_) => {},
+            None => builder.text(&text, None),
+        }
+    }
+
+    Extracted {
+        title,
+        page_count: None,
+        sections: builder.sections,
+    }
+}
+
+/// First link on a page whose address or text mentions one of `terms`, made absolute against `base`
+fn whitepaper_link(
+    html: &str,
+    base: &str,
+    terms: &[String],
+) -> Option<String> {
+    let base = Url::parse(base).ok()?;
+    let document = Html::parse_document(html);
+
+    document.select(&LINKS).find_map(|link| {
+        let href = link.value().attr("href")?.trim();
+        let label = format!("{href} {}", link.text().collect::<String>()).to_lowercase();
+        if !terms.iter().any(|term| label.contains(&term.to_lowercase())) {
+            return None;
+        }
+        let url = base.join(href).ok()?;
+        matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+    })
+}
+
+fn squash(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn truncate_chars(
+    text: &str,
+    max_chars: usize,
+) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_owned();
+    }
+    let mut truncated = text.chars().take(max_chars).collect::<String>();
+    truncated.push_str("...");
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::Object;
+    use lopdf::Stream;
+    use lopdf::content::Content;
+    use lopdf::content::Operation;
+    use lopdf::dictionary;
+
+    use super::*;
+
+    const MINT: &str = "So11111111111111111111111111111111111111112";
+
+    /// A PDF with one text object per line, in Helvetica
+    fn pdf(pages: &[&[&str]]) -> Vec<u8> {
+        let mut document = lopdf::Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+        let font_id = document.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let resources_id = document.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+
+        let kids = pages
+            .iter()
+            .map(|lines| {
+                let operations = lines
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(index, line)| {
+                        [
+                            Operation::new("BT", vec![]),
+                            Operation::new("Tf", vec!["F1".into(), 11.into()]),
+                            Operation::new("Td", vec![50.into(), (750 - 20 * index as i64).into()]),
+                            Operation::new("Tj", vec![Object::string_literal(*line)]),
+                            Operation::new("ET", vec![]),
+                        ]
+                    })
+                    .collect::<Vec<_>>();
+                let content = Content {
+                    operations,
+                };
+                let content_id = document.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+                document
+                    .add_object(dictionary! {
+                        "Type" => "Page",
+                        "Parent" => pages_id,
+                        "Contents" => content_id,
+                    })
+                    .into()
+            })
+            .collect::<Vec<Object>>();
+        document.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => kids.len() as i64,
+                "Kids" => kids,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        document.trailer.set("Root", catalog_id);
+
+        let mut bytes = Vec::new();
+        document.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_pdf_key_sections_are_cited_by_page() {
+        let ingester = DocumentIngester::builtin();
+        let bytes = pdf(&[
+            &["Introduction", "Halal Swap is a payment network for merchants."],
+            &[
+                "3 Tokenomics",
+                "The total supply is fixed at 100 million HSW.",
+                "3.1 Team Allocation",
+                "Fifteen percent goes to the team, vesting over four years.",
+            ],
+            &[
+                "Ten percent is kept by the foundation treasury.",
+                "4 Roadmap",
+                "Mainnet launches next year.",
+            ],
+        ]);
+        assert_eq!(detect_format(None, "upload", &bytes), Some(DocumentFormat::Pdf));
+
+        let document = ingester.ingest(MINT, "halal-swap.pdf", DocumentFormat::Pdf, &bytes).unwrap();
+
+        assert_eq!(document.page_count, Some(3));
+        assert_eq!(document.title.as_deref(), Some("Introduction"));
+        let headings = document
+            .sections
+            .iter()
+            .map(|section| (section.heading.as_deref().unwrap_or_default(), section.level, section.page))
+            .collect::<Vec<_>>();
+        assert_eq!(headings, vec![
+            ("Introduction", 1, Some(1)),
+            ("3 Tokenomics", 1, Some(2)),
+            ("3.1 Team Allocation", 2, Some(2)),
+            ("3.1 Team Allocation", 2, Some(3)),
+            ("4 Roadmap", 1, Some(3)),
+        ]);
+        assert!(document.sections[3].continued);
+
+        let tokenomics = &document.key_sections[0];
+        assert_eq!(tokenomics.kind, DocumentSectionKind::Tokenomics);
+        assert_eq!(tokenomics.heading.as_deref(), Some("3 Tokenomics"));
+        assert_eq!(tokenomics.pages, vec![2, 3]);
+        assert_eq!(
+            tokenomics.text,
+            "The total supply is fixed at 100 million HSW. 3.1 Team Allocation: Fifteen percent goes to the team, \
+             vesting over four years. Ten percent is kept by the foundation treasury."
+        );
+        assert_eq!(tokenomics.citation(), "halal-swap.pdf, pp. 2-3");
+        assert!(document.chunks.iter().all(|chunk| chunk.embedding.len() == 384));
+    }
+
+    #[test]
+    fn test_markdown_and_html_sections_are_found_by_heading_or_similarity() {
+        let ingester = DocumentIngester::builtin();
+        let markdown = "# Halal Swap\n\nA payment network.\n\n## Business Model\n\nMerchants pay a 0.3% fee per \
+                        payment.\n\n```\n# not a heading\n```\n\n## Use of Funds\n\nDevelopment 60%, legal 15%.\n";
+        let document = ingester
+            .ingest(&format!(" {MINT} "), "README.md", DocumentFormat::Markdown, markdown.as_bytes())
+            .unwrap();
+        assert_eq!(document.token, MINT);
+        // A symbol names every token minted under it, so documents are never filed under one
+        assert!(matches!(
+            ingester.ingest("HSW", "README.md", DocumentFormat::Markdown, markdown.as_bytes()),
+            Err(DocumentError::Invalid(_))
+        ));
+        let kinds = document.key_sections.iter().map(|excerpt| excerpt.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![DocumentSectionKind::RevenueModel, DocumentSectionKind::UseOfFunds]);
+        assert_eq!(document.key_sections[1].citation(), "README.md, \"Use of Funds\"");
+        assert!(
+            document
+                .sections
+                .iter()
+                .all(|section| section.heading.as_deref() != Some("not a heading"))
+        );
+
+        // No heading names the tokenomics, but the chunk about supply and vesting is close enough
+        let html = "<html><head><title>HSW Docs</title></head><body><h1>Overview</h1><p>Payments for \
+                    merchants.</p><h2>The Token</h2><ul><li><p>Supply: 100 million, with team allocation vesting and \
+                    monthly unlock after a cliff.</p></li></ul><a href=\"/files/whitepaper-v2.pdf\">Read \
+                    more</a></body></html>";
+        let document = ingester
+            .ingest(MINT, "https://hsw.example/docs", DocumentFormat::Html, html.as_bytes())
+            .unwrap();
+        assert_eq!(document.title.as_deref(), Some("HSW Docs"));
+        assert_eq!(document.key_sections.len(), 1);
+        assert_eq!(document.key_sections[0].kind, DocumentSectionKind::Tokenomics);
+        assert_eq!(document.key_sections[0].heading.as_deref(), Some("The Token"));
+        assert_eq!(
+            whitepaper_link(html, "https://hsw.example/docs", &["whitepaper".to_owned()]).as_deref(),
+            Some("https://hsw.example/files/whitepaper-v2.pdf")
+        );
+
+        let store = DocumentStore::open(None).unwrap();
+        store.save(&document).unwrap();
+        assert_eq!(store.excerpts_for(MINT).unwrap().len(), 1);
+        assert!(store.excerpts_for("HSW").unwrap().is_empty());
+        let hits = store.search(MINT, &ingester.embed("team vesting cliff"), 1).unwrap();
+        assert_eq!(hits[0].heading.as_deref(), Some("The Token"));
+    }
+}
@@ -0,0 +1,832 @@
+//! Persistent fatwa knowledge base: import, deduplication, versioning, indexes and ranked search.
+//!
+//! Fatwas live in sled trees next to indexes by principle, source, language and keyword. Only the
+//! version in force is indexed; revising a fatwa stores a new version linked to the one it supersedes.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::IntoDeserializer;
+use sled::Db;
+use sled::Transactional;
+use sled::Tree;
+use sled::transaction::TransactionError;
+use sled::transaction::abort;
+use tracing::error;
+use tracing::info;
+use uuid::Uuid;
+
+use super::storage::db_path;
+use super::storage::fnv1a;
+use super::storage::open_db;
+use super::storage::open_or_in_memory;
+use crate::models::Fatwa;
+use crate::models::FatwaSource;
+use crate::models::IslamicPrinciple;
+
+/// Environment variable with the path of the fatwa database; an in-memory one is used when unset
+pub const FATWA_DB_ENV: &str = "AVERROES_FATWA_DB";
+
+const BUILTIN_FATWAS: &str = include_str!("fatwas.json");
+
+lazy_static! {
+    /// Knowledge base at `AVERROES_FATWA_DB`, opened once and seeded with the bundled fatwas
+    static ref SHARED: FatwaKnowledgeBase = {
+        let knowledge_base =
+            open_or_in_memory("fatwa", FatwaKnowledgeBase::from_env, || FatwaKnowledgeBase::open(None));
+        match knowledge_base.import(ImportFormat::Json, BUILTIN_FATWAS) {
+            Ok(report) => info!("Seeded fatwa knowledge base: {:?}", report),
+            Err(e) => error!("Failed to seed fatwa knowledge base: {}", e),
+        }
+        knowledge_base
+    };
+}
+
+/// Bundle formats fatwas can be imported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Json,     // Array of `FatwaRecord`
+    Markdown, // `---` front matter, a `# Title` heading and the text, repeated per fatwa
+    Csv,      // Header row of `FatwaRecord` fields; principles and keywords separated by ';'
+}
+
+impl ImportFormat {
+    /// Format from a bundle's file extension
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Ok(Self::Json),
+            Some("md" | "markdown") => Ok(Self::Markdown),
+            Some("csv") => Ok(Self::Csv),
+            _ => Err(format!("{}: expected a .json, .md or .csv fatwa bundle", path.display())),
+        }
+    }
+}
+
+/// A fatwa as written in an import bundle or sent to the API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FatwaRecord {
+    pub title: String,
+    pub content: String,
+    pub source: String, // "MUI", "AAOIFI", "OIC", "Darul", "Scholar: <name>" or another issuer
+    #[serde(default)]
+    pub principles: Vec<IslamicPrinciple>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    pub language: String,
+    #[serde(default)]
+    pub issued_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub reference_number: Option<String>,
+}
+
+/// What an import or insert did with each record
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub added: Vec<Uuid>,   // New fatwas
+    pub revised: Vec<Uuid>, // New versions of a fatwa with the same source and title
+    pub duplicates: usize,  // Records identical to a fatwa already stored
+}
+
+/// Filters and terms for a knowledge base search
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FatwaQuery {
+    #[serde(default)]
+    pub terms: Vec<String>,
+    #[serde(default)]
+    pub principle: Option<IslamicPrinciple>,
+    #[serde(default)]
+    pub source: Option<String>, // Label, as in `FatwaRecord::source`
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Fatwas persisted in sled, with their versions and indexes
+#[derive(Clone)]
+pub struct FatwaKnowledgeBase {
+    db: Db,
+    fatwas: Tree,       // id -> fatwa JSON, every version
+    fingerprints: Tree, // content fingerprint -> id
+    lineages: Tree,     // source and title -> id of the version in force
+    index: Tree,        // "<field>\0<value>\0<id>" for versions in force
+}
+
+impl FatwaKnowledgeBase {
+    /// Database at `path`, or an in-memory one
+    pub fn open(path: Option<&str>) -> Result<Self, String> {
+        let db = open_db(path)?;
+        let tree = |name: &str| db.open_tree(name).map_err(|e| e.to_string());
+
+        Ok(Self {
+            fatwas: tree("fatwas")?,
+            fingerprints: tree("fatwa_fingerprints")?,
+            lineages: tree("fatwa_lineages")?,
+            index: tree("fatwa_index")?,
+            db,
+        })
+    }
+
+    /// Database at `AVERROES_FATWA_DB`, or an in-memory one when unset
+    pub fn from_env() -> Result<Self, String> {
+        Self::open(db_path(FATWA_DB_ENV).as_deref())
+    }
+
+    /// The process-wide knowledge base, seeded with the bundled fatwas
+    pub fn shared() -> Self {
+        SHARED.clone()
+    }
+
+    /// Import every record in a bundle; nothing is written unless every record is valid
+    pub fn import(
+        &self,
+        format: ImportFormat,
+        raw: &str,
+    ) -> Result<ImportReport, String> {
+        let records = match format {
+            ImportFormat::Json => serde_json::from_str::<Vec<FatwaRecord>>(raw).map_err(|e| e.to_string())?,
+            ImportFormat::Markdown => parse_markdown(raw)?,
+            ImportFormat::Csv => parse_csv(raw)?,
+        };
+        for (number, record) in records.iter().enumerate() {
+            validate(record).map_err(|e| format!("Record {}: {e}", number + 1))?;
+        }
+
+        let mut report = ImportReport::default();
+        for record in records {
+            self.store(record, &mut report)?;
+        }
+        self.db.flush().map_err(|e| e.to_string())?;
+        Ok(report)
+    }
+
+    /// Import a bundle file, its format taken from the extension
+    pub fn import_file(
+        &self,
+        path: &Path,
+    ) -> Result<ImportReport, String> {
+        let format = ImportFormat::from_path(path)?;
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        self.import(format, &raw).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Add one fatwa; a record with the source and title of a stored fatwa becomes its next version
+    pub fn insert(
+        &self,
+        record: FatwaRecord,
+    ) -> Result<ImportReport, String> {
+        let mut report = ImportReport::default();
+        self.store(record, &mut report)?;
+        self.db.flush().map_err(|e| e.to_string())?;
+        Ok(report)
+    }
+
+    /// Store a new version of the fatwa `id`, which must be the version in force
+    pub fn revise(
+        &self,
+        id: Uuid,
+        record: FatwaRecord,
+    ) -> Result<Fatwa, String> {
+        let current = self.get(id)?.ok_or_else(|| format!("Fatwa {id} does not exist"))?;
+        if let Some(next) = current.superseded_by {
+            return Err(format!("Fatwa {id} was superseded by {next}; revise that version"));
+        }
+        validate(&record)?;
+
+        let fatwa = self.write_version(Some(current), record)?;
+        self.db.flush().map_err(|e| e.to_string())?;
+        Ok(fatwa)
+    }
+
+    pub fn get(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<Fatwa>, String> {
+        match self.fatwas.get(id.as_bytes()).map_err(|e| e.to_string())? {
+            Some(raw) => serde_json::from_slice(&raw).map(Some).map_err(|e| e.to_string()),
+            None => Ok(None),
+        }
+    }
+
+    /// Every version of the fatwa `id` belongs to, oldest first
+    pub fn versions(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<Fatwa>, String> {
+        let Some(mut fatwa) = self.get(id)? else {
+            return Ok(Vec::new());
+        };
+        while let Some(previous) = fatwa.supersedes {
+            fatwa = self
+                .get(previous)?
+                .ok_or_else(|| format!("Fatwa {previous} is missing from the database"))?;
+        }
+
+        let mut versions = vec![fatwa];
+        while let Some(next) = versions[versions.len() - 1].superseded_by {
+            versions.push(
+                self.get(next)?
+                    .ok_or_else(|| format!("Fatwa {next} is missing from the database"))?,
+            );
+        }
+        Ok(versions)
+    }
+
+    /// Number of fatwas in force
+    pub fn len(&self) -> usize {
+        self.lineages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lineages.is_empty()
+    }
+
+    /// Fatwas in force that match the filters, ranked by how well they match the terms
+    pub fn search(
+        &self,
+        query: &FatwaQuery,
+    ) -> Result<Vec<Fatwa>, String> {
+        let filters = [
+            query
+                .principle
+                .as_ref()
+                .map(|principle| ("principle", format!("{principle:?}"))),
+            query
+                .source
+                .as_deref()
+                .map(|source| ("source", FatwaSource::from_label(source).label())),
+            query.language.as_deref().map(|language| ("language", language.to_owned())),
+        ];
+
+        let mut candidates: Option<HashSet<Uuid>> = None;
+        for (field, value) in filters.into_iter().flatten() {
+            let ids = self.indexed(field, &value)?;
+            candidates = Some(match candidates {
+                Some(candidates) => candidates.intersection(&ids).copied().collect(),
+                None => ids,
+            });
+        }
+        let candidates = match candidates {
+            Some(candidates) => candidates,
+            None => self.current_ids()?,
+        };
+
+        let terms = query
+            .terms
+            .iter()
+            .flat_map(|term| term.split_whitespace())
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+        let mut ranked = Vec::new();
+        for id in candidates {
+            let Some(mut fatwa) = self.get(id)? else {
+                continue;
+            };
+            let score = relevance(&fatwa, &terms);
+            if terms.is_empty() || score > 0.0 {
+                fatwa.confidence_score = Some(score);
+                ranked.push(fatwa);
+            }
+        }
+
+        // Best match first, then the most recently issued
+        ranked.sort_by(|a, b| {
+            b.confidence_score
+                .unwrap_or_default()
+                .total_cmp(&a.confidence_score.unwrap_or_default())
+                .then_with(|| b.issued_date.cmp(&a.issued_date))
+        });
+        ranked.truncate(query.limit.unwrap_or(20));
+        Ok(ranked)
+    }
+
+    fn store(
+        &self,
+        record: FatwaRecord,
+        report: &mut ImportReport,
+    ) -> Result<(), String> {
+        validate(&record)?;
+        if self
+            .fingerprints
+            .contains_key(fingerprint(&record))
+            .map_err(|e| e.to_string())?
+        {
+            report.duplicates += 1;
+            return Ok(());
+        }
+
+        let current = match self
+            .lineages
+            .get(lineage_key(&record.source, &record.title))
+            .map_err(|e| e.to_string())?
+        {
+            Some(id) => self.get(Uuid::from_slice(&id).map_err(|e| e.to_string())?)?,
+            None => None,
+        };
+        let revising = current.is_some();
+        let fatwa = self.write_version(current, record)?;
+        if revising {
+            report.revised.push(fatwa.id);
+        } else {
+            report.added.push(fatwa.id);
+        }
+        Ok(())
+    }
+
+    /// Write `record` as the version after `current`, moving the lineage and indexes to it
+    ///
+    /// Everything is written in one transaction, which fails without writing anything when `current` is no longer
+    /// the version in force.
+    fn write_version(
+        &self,
+        current: Option<Fatwa>,
+        record: FatwaRecord,
+    ) -> Result<Fatwa, String> {
+        let key = fingerprint(&record);
+        let mut fatwa = Fatwa::new(
+            record.title,
+            record.content,
+            FatwaSource::from_label(&record.source),
+            record.principles,
+            record.keywords,
+            record.language,
+        );
+        fatwa.issued_date = record
+            .issued_date
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|date| DateTime::<Utc>::from_naive_utc_and_offset(date, Utc));
+        fatwa.reference_number = record.reference_number;
+
+        let lineage = lineage_key(&fatwa.source.label(), &fatwa.title);
+        let previous = current.map(|mut previous| {
+            fatwa.version = previous.version + 1;
+            fatwa.supersedes = Some(previous.id);
+            previous.superseded_by = Some(fatwa.id);
+            previous
+        });
+        // The lineage `current` was read from, which must still point at it
+        let in_force_key = match &previous {
+            Some(previous) => lineage_key(&previous.source.label(), &previous.title),
+            None => lineage.clone(),
+        };
+        let raw = serde_json::to_vec(&fatwa).map_err(|e| e.to_string())?;
+        let previous_raw = previous
+            .as_ref()
+            .map(|previous| serde_json::to_vec(previous).map_err(|e| e.to_string()))
+            .transpose()?;
+
+        (&self.fatwas, &self.fingerprints, &self.lineages, &self.index)
+            .transaction(|(fatwas, fingerprints, lineages, index)| {
+                let in_force = lineages.get(&in_force_key)?;
+                if in_force.as_deref() != previous.as_ref().map(|previous| previous.id.as_bytes().as_slice()) {
+                    return abort(match &previous {
+                        Some(previous) => {
+                            format!("Fatwa {} was revised concurrently; revise the new version", previous.id)
+                        },
+                        None => format!("Fatwa '{}' was added concurrently", fatwa.title),
+                    });
+                }
+
+                if let (Some(previous), Some(previous_raw)) = (&previous, &previous_raw) {
+                    for key in index_keys(previous) {
+                        index.remove(key)?;
+                    }
+                    lineages.remove(in_force_key.as_slice())?;
+                    fatwas.insert(previous.id.as_bytes(), previous_raw.as_slice())?;
+                }
+                fatwas.insert(fatwa.id.as_bytes(), raw.as_slice())?;
+                fingerprints.insert(&key, fatwa.id.as_bytes())?;
+                lineages.insert(lineage.as_slice(), fatwa.id.as_bytes())?;
+                for key in index_keys(&fatwa) {
+                    index.insert(key, &[])?;
+                }
+                Ok(())
+            })
+            .map_err(|e| match e {
+                TransactionError::Abort(e) => e,
+                TransactionError::Storage(e) => e.to_string(),
+            })?;
+        Ok(fatwa)
+    }
+
+    /// Ids indexed under one field value
+    fn indexed(
+        &self,
+        field: &str,
+        value: &str,
+    ) -> Result<HashSet<Uuid>, String> {
+        let prefix = format!("{field}\0{}\0", value.to_lowercase());
+        self.index
+            .scan_prefix(prefix.as_bytes())
+            .map(|entry| {
+                let (key, _) = entry.map_err(|e| e.to_string())?;
+                Uuid::from_slice(&key[prefix.len()..]).map_err(|e| e.to_string())
+            })
+            .collect()
+    }
+
+    fn current_ids(&self) -> Result<HashSet<Uuid>, String> {
+        self.lineages
+            .iter()
+            .values()
+            .map(|id| Uuid::from_slice(&id.map_err(|e| e.to_string())?).map_err(|e| e.to_string()))
+            .collect()
+    }
+}
+
+/// Share of the terms a fatwa matches, counting keywords fully, titles by half and text by a quarter
+fn relevance(
+    fatwa: &Fatwa,
+    terms: &[String],
+) -> f64 {
+    if terms.is_empty() {
+        return 0.0;
+    }
+    let title = fatwa.title.to_lowercase();
+    let content = fatwa.content.to_lowercase();
+
+    let matched = terms
+        .iter()
+        .map(|term| {
+            if fatwa.keywords.iter().any(|keyword| keyword.to_lowercase() == *term) {
+                1.0
+            } else if title.contains(term.as_str()) {
+                0.5
+            } else if content.contains(term.as_str()) {
+                0.25
+            } else {
+                0.0
+            }
+        })
+        .sum::<f64>();
+    matched / terms.len() as f64
+}
+
+/// Index entries of a fatwa, as "<field>\0<value>\0" followed by the id's bytes
+fn index_keys(fatwa: &Fatwa) -> Vec<Vec<u8>> {
+    let values = fatwa
+        .principles_addressed
+        .iter()
+        .map(|principle| ("principle", format!("{principle:?}")))
+        .chain([("source", fatwa.source.label()), ("language", fatwa.language.clone())])
+        .chain(fatwa.keywords.iter().map(|keyword| ("keyword", keyword.clone())));
+
+    values
+        .map(|(field, value)| {
+            let mut key = format!("{field}\0{}\0", value.to_lowercase()).into_bytes();
+            key.extend_from_slice(fatwa.id.as_bytes());
+            key
+        })
+        .collect()
+}
+
+fn lineage_key(
+    source: &str,
+    title: &str,
+) -> Vec<u8> {
+    format!("{}\0{}", FatwaSource::from_label(source).label().to_lowercase(), normalize(title)).into_bytes()
+}
+
+/// FNV-1a over the normalized source, title and content, stable across builds and platforms
+fn fingerprint(record: &FatwaRecord) -> [u8; 8] {
+    let text = format!(
+        "{}\0{}\0{}",
+        FatwaSource::from_label(&record.source).label().to_lowercase(),
+        normalize(&record.title),
+        normalize(&record.content)
+    );
+    fnv1a(text.as_bytes()).to_be_bytes()
+}
+
+/// Lowercase with whitespace runs collapsed, so reformatting a fatwa does not make it new
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn parse_principles(raw: &str) -> Result<Vec<IslamicPrinciple>, String> {
+    split_list(raw)
+        .into_iter()
+        .map(|name| {
+            IslamicPrinciple::deserialize(name.as_str().into_deserializer())
+                .map_err(|e: serde::de::value::Error| format!("Unknown principle '{name}': {e}"))
+        })
+        .collect()
+}
+
+fn parse_date(raw: &str) -> Result<Option<NaiveDate>, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .map(Some)
+        .map_err(|e| format!("Issued date '{raw}': {e}"))
+}
+
+/// Items of a ';' or ',' separated list
+fn split_list(raw: &str) -> Vec<String> {
+    raw.split([';', ','])
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn non_empty(raw: &str) -> Option<String> {
+    Some(raw.trim()).filter(|raw| !raw.is_empty()).map(str::to_owned)
+}
+
+#[derive(Debug, Deserialize)]
+struct CsvRow {
+    title: String,
+    content: String,
+    source: String,
+    #[serde(default)]
+    principles: String,
+    #[serde(default)]
+    keywords: String,
+    language: String,
+    #[serde(default)]
+    issued_date: String,
+    #[serde(default)]
+    reference_number: String,
+}
+
+fn parse_csv(raw: &str) -> Result<Vec<FatwaRecord>, String> {
+    csv::Reader::from_reader(raw.as_bytes())
+        .deserialize::<CsvRow>()
+        .enumerate()
+        .map(|(index, row)| {
+            let row = row.map_err(|e| format!("Row {}: {e}", index + 1))?;
+            Ok(FatwaRecord {
+                principles: parse_principles(&row.principles).map_err(|e| format!("Row {}: {e}", index + 1))?,
+                keywords: split_list(&row.keywords),
+                issued_date: parse_date(&row.issued_date).map_err(|e| format!("Row {}: {e}", index + 1))?,
+                reference_number: non_empty(&row.reference_number),
+                title: row.title,
+                content: row.content,
+                source: row.source,
+                language: row.language,
+            })
+        })
+        .collect()
+}
+
+/// A record the knowledge base can store and index
+fn validate(record: &FatwaRecord) -> Result<(), String> {
+    if record.title.trim().is_empty() || record.content.trim().is_empty() {
+        return Err("A fatwa needs a title and content".to_owned());
+    }
+    if record.source.trim().is_empty() || record.language.trim().is_empty() {
+        return Err(format!("Fatwa '{}' needs a source and a language", record.title));
+    }
+    Ok(())
+}
+
+/// Fatwas written as front matter between `---` lines, a `# Title` heading and the text, one after another
+fn parse_markdown(raw: &str) -> Result<Vec<FatwaRecord>, String> {
+    let mut records = Vec::new();
+    let mut lines = raw.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if line.trim() != "---" {
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Err(format!("Expected '---' to open a fatwa's front matter, found '{line}'"));
+        }
+
+        let mut record = FatwaRecord {
+            title: String::new(),
+            content: String::new(),
+            source: String::new(),
+            principles: Vec::new(),
+            keywords: Vec::new(),
+            language: String::new(),
+            issued_date: None,
+            reference_number: None,
+        };
+        for line in lines.by_ref() {
+            if line.trim() == "---" {
+                break;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| format!("Front matter line '{line}' is not 'key: value'"))?;
+            match key.trim() {
+                "source" => value.trim().clone_into(&mut record.source),
+                "principles" => record.principles = parse_principles(value)?,
+                "keywords" => record.keywords = split_list(value),
+                "language" => value.trim().clone_into(&mut record.language),
+                "issued_date" => record.issued_date = parse_date(value)?,
+                "reference_number" => record.reference_number = non_empty(value),
+                other => return Err(format!("Unknown front matter key '{other}'")),
+            }
+        }
+
+        let mut body = Vec::new();
+        while let Some(line) = lines.next_if(|line| line.trim() != "---") {
+            match line.strip_prefix("# ") {
+                Some(title) if record.title.is_empty() => title.trim().clone_into(&mut record.title),
+                _ => body.push(line),
+            }
+        }
+        body.join("\n").trim().clone_into(&mut record.content);
+
+        if record.source.is_empty() || record.language.is_empty() {
+            return Err(format!("Fatwa '{}' needs a source and a language", record.title));
+        }
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARKDOWN: &str = "---
+source: MUI
+principles: Gharar; Maysir
+keywords: cryptocurrency, bitcoin
+language: id
+issued_date: 2021-11-11
+---
+# Hukum Cryptocurrency
+
+Penggunaan cryptocurrency sebagai mata uang hukumnya haram.
+
+---
+source: Scholar: Ustadz Contoh
+principles: Halal
+keywords: staking
+language: en
+---
+# Proof-of-stake staking
+
+Staking rewards paid for validation are permissible.
+";
+
+    #[test]
+    fn test_import_dedupes_and_versions() {
+        let knowledge_base = FatwaKnowledgeBase::open(None).unwrap();
+
+        let report = knowledge_base.import(ImportFormat::Markdown, MARKDOWN).unwrap();
+        assert_eq!(report.added.len(), 2);
+        assert_eq!(knowledge_base.len(), 2);
+
+        // Reformatting a fatwa does not make it new
+        let reformatted = MARKDOWN.replace("hukumnya haram.", "hukumnya   haram.");
+        let report = knowledge_base.import(ImportFormat::Markdown, &reformatted).unwrap();
+        assert_eq!(report.duplicates, 2);
+        assert!(report.added.is_empty() && report.revised.is_empty());
+
+        // The same source and title with new text supersedes the earlier version
+        let csv = concat!(
+            "title,content,source,principles,keywords,language,issued_date,reference_number\n",
+            "Hukum Cryptocurrency,\"Haram sebagai mata uang, sah sebagai komoditas yang memenuhi syarat sil'ah.\",",
+            "MUI,Gharar;Maysir,cryptocurrency;komoditas,id,2021-11-11,Ijtima Ulama VII\n",
+        );
+        let report = knowledge_base.import(ImportFormat::Csv, csv).unwrap();
+        assert_eq!(report.revised.len(), 1);
+        assert_eq!(knowledge_base.len(), 2);
+
+        let revised = knowledge_base.get(report.revised[0]).unwrap().unwrap();
+        assert_eq!(revised.version, 2);
+        assert_eq!(revised.reference_number.as_deref(), Some("Ijtima Ulama VII"));
+        let versions = knowledge_base.versions(revised.id).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].superseded_by, Some(revised.id));
+        assert_eq!(revised.supersedes, Some(versions[0].id));
+        assert!(
+            knowledge_base
+                .revise(versions[0].id, FatwaRecord {
+                    title: "Hukum Cryptocurrency".to_owned(),
+                    content: "Teks lain".to_owned(),
+                    source: "MUI".to_owned(),
+                    principles: vec![],
+                    keywords: vec![],
+                    language: "id".to_owned(),
+                    issued_date: None,
+                    reference_number: None,
+                })
+                .is_err()
+        );
+
+        // Only the version in force is indexed
+        let found = knowledge_base
+            .search(&FatwaQuery {
+                terms: vec!["komoditas".to_owned()],
+                source: Some("mui".to_owned()),
+                ..FatwaQuery::default()
+            })
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, revised.id);
+
+        assert!(knowledge_base.import(ImportFormat::Csv, "title,content\nx,y\n").is_err());
+
+        // One invalid record keeps the whole bundle out
+        let partly_invalid = r#"[
+            {"title": "Staking pool", "content": "Boleh.", "source": "MUI", "language": "id"},
+            {"title": "Untitled", "content": " ", "source": "MUI", "language": "id"}
+        ]"#;
+        let error = knowledge_base.import(ImportFormat::Json, partly_invalid).unwrap_err();
+        assert_eq!(error, "Record 2: A fatwa needs a title and content");
+        assert_eq!(knowledge_base.len(), 2);
+        assert!(knowledge_base.import(ImportFormat::Markdown, "# No front matter").is_err());
+    }
+
+    #[test]
+    fn test_stale_revision_writes_nothing() {
+        let knowledge_base = FatwaKnowledgeBase::open(None).unwrap();
+        let report = knowledge_base.import(ImportFormat::Markdown, MARKDOWN).unwrap();
+        let original = knowledge_base.get(report.added[0]).unwrap().unwrap();
+        let record = |content: &str| FatwaRecord {
+            title: original.title.clone(),
+            content: content.to_owned(),
+            source: original.source.label(),
+            principles: vec![],
+            keywords: vec!["revisi".to_owned()],
+            language: original.language.clone(),
+            issued_date: None,
+            reference_number: None,
+        };
+
+        // Two revisions read the same version in force; the second one to write loses
+        let revised = knowledge_base
+            .write_version(Some(original.clone()), record("Versi kedua."))
+            .unwrap();
+        let error = knowledge_base
+            .write_version(Some(original.clone()), record("Versi ketiga."))
+            .unwrap_err();
+        assert_eq!(error, format!("Fatwa {} was revised concurrently; revise the new version", original.id));
+
+        let versions = knowledge_base.versions(original.id).unwrap();
+        assert_eq!(versions.iter().map(|fatwa| fatwa.id).collect::<Vec<_>>(), vec![original.id, revised.id]);
+        assert_eq!(knowledge_base.len(), 2);
+        let found = knowledge_base
+            .search(&FatwaQuery {
+                terms: vec!["revisi".to_owned()],
+                ..FatwaQuery::default()
+            })
+            .unwrap();
+        assert_eq!(found.iter().map(|fatwa| fatwa.id).collect::<Vec<_>>(), vec![revised.id]);
+        assert!(
+            !knowledge_base
+                .fingerprints
+                .contains_key(fingerprint(&record("Versi ketiga.")))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_search_filters_and_ranks() {
+        let knowledge_base = FatwaKnowledgeBase::open(None).unwrap();
+        knowledge_base.import(ImportFormat::Json, BUILTIN_FATWAS).unwrap();
+        knowledge_base.import(ImportFormat::Markdown, MARKDOWN).unwrap();
+
+        let riba = knowledge_base
+            .search(&FatwaQuery {
+                principle: Some(IslamicPrinciple::Riba),
+                ..FatwaQuery::default()
+            })
+            .unwrap();
+        assert!(!riba.is_empty());
+        assert!(
+            riba.iter()
+                .all(|fatwa| fatwa.principles_addressed.contains(&IslamicPrinciple::Riba))
+        );
+
+        let ranked = knowledge_base
+            .search(&FatwaQuery {
+                terms: vec!["staking rewards".to_owned()],
+                language: Some("en".to_owned()),
+                ..FatwaQuery::default()
+            })
+            .unwrap();
+        assert_eq!(ranked[0].title, "Proof-of-stake staking");
+        assert!(
+            ranked
+                .windows(2)
+                .all(|pair| pair[0].confidence_score >= pair[1].confidence_score)
+        );
+        assert!(ranked.iter().all(|fatwa| fatwa.language == "en"));
+
+        let scholar = knowledge_base
+            .search(&FatwaQuery {
+                source: Some("Scholar: Ustadz Contoh".to_owned()),
+                ..FatwaQuery::default()
+            })
+            .unwrap();
+        assert_eq!(scholar.len(), 1);
+    }
+}
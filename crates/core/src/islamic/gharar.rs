@@ -0,0 +1,467 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::ratios::project_data_from_env;
+use crate::models::GhararAssessment;
+use crate::models::GhararFactor;
+use crate::models::GhararLevel;
+use crate::models::TokenExtension;
+use crate::models::TokenStandard;
+use crate::models::token::TokenHolders;
+use crate::models::token::UniversalTokenInfo as TokenInfo;
+
+/// Score at and above which gharar is excessive (fahish)
+pub const EXCESSIVE_GHARAR: f64 = 0.6;
+/// Score at and above which gharar is moderate
+pub const MODERATE_GHARAR: f64 = 0.3;
+/// Share of factor weight that must be measurable before the score is graded
+pub const MIN_GHARAR_COVERAGE: f64 = 0.4;
+
+/// Annualized volatility treated as the most uncertain a price can be
+const MAX_VOLATILITY: f64 = 1.5;
+/// Liquidity as a share of market cap at which exits are no longer in doubt
+const DEEP_LIQUIDITY: f64 = 0.1;
+/// Share of supply unlocking within a year that counts as a full overhang
+const MAX_UNLOCK_SHARE: f64 = 0.5;
+/// Transfer fee, in basis points, treated as taking away the whole point of holding the token
+const MAX_TRANSFER_FEE_BPS: f64 = 1000.0;
+const YEAR_SECONDS: u64 = 365 * 24 * 60 * 60;
+
+/// Information a buyer needs to know what they are getting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Disclosure {
+    Whitepaper,
+    Team,
+    Audit,
+    Tokenomics,
+    Treasury,
+}
+
+impl Disclosure {
+    pub const ALL: [Disclosure; 5] = [
+        Disclosure::Whitepaper,
+        Disclosure::Team,
+        Disclosure::Audit,
+        Disclosure::Tokenomics,
+        Disclosure::Treasury,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Disclosure::Whitepaper => "whitepaper",
+            Disclosure::Team => "team",
+            Disclosure::Audit => "audit",
+            Disclosure::Tokenomics => "tokenomics",
+            Disclosure::Treasury => "treasury",
+        }
+    }
+}
+
+/// Tokens released from a lock at one point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SupplyUnlock {
+    pub unlock_at: u64, // Unix timestamp in seconds
+    pub share: f64,     // 0-1 of total supply
+}
+
+/// Measurable inputs to the gharar score
+///
+/// Read from the same `<address>.json` / `<SYMBOL>.json` project file as the financials, then completed from
+/// market data. Anything left out is reported as unavailable and lowers the score's coverage.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GhararInputs {
+    /// Daily closing prices, oldest first
+    #[serde(default)]
+    pub price_history: Vec<f64>,
+    /// Percent, used when there is no price history
+    #[serde(default)]
+    pub price_change_24h: Option<f64>,
+    /// Percent of supply held by the ten largest holders
+    #[serde(default)]
+    pub top_10_holders_percentage: Option<f64>,
+    #[serde(default)]
+    pub liquidity_usd: Option<f64>,
+    #[serde(default)]
+    pub market_cap_usd: Option<f64>,
+    /// Locked supply and when it is released; empty when the whole supply circulates
+    #[serde(default)]
+    pub unlock_schedule: Option<Vec<SupplyUnlock>>,
+    #[serde(default)]
+    pub mint_authority_active: Option<bool>,
+    /// Token-2022 mint extensions; empty for a mint without any
+    #[serde(default)]
+    pub extensions: Option<Vec<TokenExtension>>,
+    /// What the project has published
+    #[serde(default)]
+    pub disclosures: Option<Vec<Disclosure>>,
+}
+
+impl GhararInputs {
+    pub fn from_json(raw: &str) -> Result<Self, String> {
+        serde_json::from_str(raw).map_err(|e| e.to_string())
+    }
+
+    /// Inputs from the token's project file in `AVERROES_PROJECT_DATA_DIR`
+    pub fn from_env(
+        address: &str,
+        symbol: &str,
+    ) -> Result<Option<Self>, String> {
+        project_data_from_env(address, symbol)
+    }
+
+    /// Fill inputs the project file left out from the token's market data
+    pub fn with_token(
+        mut self,
+        token_info: &TokenInfo,
+    ) -> Self {
+        if let Some(price_data) = &token_info.price_data {
+            self.price_change_24h.get_or_insert(price_data.price_change_24h);
+            if price_data.market_cap > 0 {
+                self.market_cap_usd.get_or_insert(price_data.market_cap as f64);
+            }
+        }
+        if self.liquidity_usd.is_none() && !token_info.liquidity_pools.is_empty() {
+            self.liquidity_usd = Some(token_info.liquidity_pools.iter().map(|pool| pool.liquidity_usd).sum());
+        }
+        if let Some(control) = &token_info.control {
+            self.mint_authority_active.get_or_insert(control.mint_authority.is_some());
+        }
+        // Only a mint read from the chain is known to be Token-2022, and so to have all its extensions listed
+        if matches!(token_info.metadata.token_standard, TokenStandard::Token2022) {
+            self.extensions.get_or_insert_with(|| token_info.metadata.extensions.clone());
+        }
+        self
+    }
+
+    pub fn with_holders(
+        mut self,
+        holders: &TokenHolders,
+    ) -> Self {
+        self.top_10_holders_percentage.get_or_insert(holders.top_10_holders_percentage);
+        self
+    }
+
+    /// Score the inputs as of `now` (Unix seconds)
+    pub fn assess(
+        &self,
+        now: u64,
+    ) -> GhararAssessment {
+        let measured = [
+            ("volatility", 0.2, self.volatility()),
+            ("holder_concentration", 0.15, self.concentration()),
+            ("liquidity_depth", 0.15, self.liquidity_depth()),
+            ("supply_unlocks", 0.15, self.supply_unlocks(now)),
+            ("mint_authority", 0.1, self.mint_authority()),
+            ("mint_extensions", 0.1, self.mint_extensions()),
+            ("missing_disclosures", 0.15, self.missing_disclosures()),
+        ];
+
+        let coverage = measured
+            .iter()
+            .filter(|(_, _, measure)| measure.is_some())
+            .map(|(_, weight, _)| weight)
+            .sum::<f64>();
+
+        let factors = measured
+            .into_iter()
+            .map(|(name, weight, measure)| match measure {
+                Some(Measure {
+                    value,
+                    risk,
+                    explanation,
+                }) => GhararFactor {
+                    name: name.to_owned(),
+                    value: Some(value),
+                    risk: Some(risk),
+                    weight,
+                    contribution: weight * risk / coverage,
+                    explanation,
+                },
+                None => GhararFactor {
+                    name: name.to_owned(),
+                    value: None,
+                    risk: None,
+                    weight,
+                    contribution: 0.0,
+                    explanation: "Not available".to_owned(),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let score = factors.iter().map(|factor| factor.contribution).sum::<f64>().clamp(0.0, 1.0);
+        let level = if coverage < MIN_GHARAR_COVERAGE {
+            GhararLevel::Undetermined
+        } else if score >= EXCESSIVE_GHARAR {
+            GhararLevel::Fahish
+        } else if score >= MODERATE_GHARAR {
+            GhararLevel::Mutawassit
+        } else {
+            GhararLevel::Yasir
+        };
+
+        GhararAssessment {
+            score,
+            level,
+            coverage,
+            factors,
+        }
+    }
+
+    /// Annualized realized volatility from daily log returns
+    fn volatility(&self) -> Option<Measure> {
+        let returns = self
+            .price_history
+            .windows(2)
+            .filter(|pair| pair[0] > 0.0 && pair[1] > 0.0)
+            .map(|pair| (pair[1] / pair[0]).ln())
+            .collect::<Vec<_>>();
+
+        let (volatility, source) = if !returns.is_empty() {
+            let mean_square = returns.iter().map(|r| r * r).sum::<f64>() / returns.len() as f64;
+            (mean_square.sqrt(), format!("{} daily closes", self.price_history.len()))
+        } else {
+            let change = self.price_change_24h.filter(|change| *change > -100.0)?;
+            ((change / 100.0).ln_1p().abs(), format!("a 24h move of {change:.1}%"))
+        };
+        let annualized = volatility * 365f64.sqrt();
+
+        Some(Measure {
+            value: annualized,
+            risk: (annualized / MAX_VOLATILITY).clamp(0.0, 1.0),
+            explanation: format!("Annualized volatility of {:.0}% from {source}", annualized * 100.0),
+        })
+    }
+
+    fn concentration(&self) -> Option<Measure> {
+        let percentage = self.top_10_holders_percentage?;
+
+        Some(Measure {
+            value: percentage,
+            // Up to a fifth of supply with the largest holders is ordinary; four fifths leaves the price in their hands
+            risk: ((percentage - 20.0) / 60.0).clamp(0.0, 1.0),
+            explanation: format!("Top 10 holders own {percentage:.1}% of supply"),
+        })
+    }
+
+    fn liquidity_depth(&self) -> Option<Measure> {
+        let liquidity = self.liquidity_usd?;
+        let market_cap = self.market_cap_usd.filter(|cap| *cap > 0.0)?;
+        let depth = liquidity / market_cap;
+
+        Some(Measure {
+            value: depth,
+            risk: (1.0 - depth / DEEP_LIQUIDITY).clamp(0.0, 1.0),
+            explanation: format!("Pool liquidity is {:.1}% of market cap", depth * 100.0),
+        })
+    }
+
+    fn supply_unlocks(
+        &self,
+        now: u64,
+    ) -> Option<Measure> {
+        let schedule = self.unlock_schedule.as_ref()?;
+        let upcoming = schedule
+            .iter()
+            .filter(|unlock| unlock.unlock_at > now && unlock.unlock_at <= now + YEAR_SECONDS)
+            .map(|unlock| unlock.share)
+            .sum::<f64>();
+
+        Some(Measure {
+            value: upcoming,
+            risk: (upcoming / MAX_UNLOCK_SHARE).clamp(0.0, 1.0),
+            explanation: format!("{:.1}% of supply unlocks within 12 months", upcoming * 100.0),
+        })
+    }
+
+    fn mint_authority(&self) -> Option<Measure> {
+        let active = self.mint_authority_active?;
+
+        Some(Measure {
+            value: if active {
+                1.0
+            } else {
+                0.0
+            },
+            risk: if active {
+                1.0
+            } else {
+                0.0
+            },
+            explanation: if active {
+                "Mint authority is active, so supply can be increased at will".to_owned()
+            } else {
+                "Mint authority is revoked".to_owned()
+            },
+        })
+    }
+
+    /// Extensions that leave holders' tokens at the issuer's discretion, scored by the most restrictive one
+    fn mint_extensions(&self) -> Option<Measure> {
+        let restrictions = self
+            .extensions
+            .as_ref()?
+            .iter()
+            .filter_map(extension_restriction)
+            .collect::<Vec<_>>();
+        let risk = restrictions.iter().map(|(risk, _)| *risk).fold(0.0, f64::max);
+
+        Some(Measure {
+            value: restrictions.len() as f64,
+            risk,
+            explanation: if restrictions.is_empty() {
+                "No mint extension restricts holders".to_owned()
+            } else {
+                restrictions
+                    .into_iter()
+                    .map(|(_, explanation)| explanation)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            },
+        })
+    }
+
+    fn missing_disclosures(&self) -> Option<Measure> {
+        let published = self.disclosures.as_ref()?;
+        let missing = Disclosure::ALL
+            .iter()
+            .filter(|disclosure| !published.contains(disclosure))
+            .map(|disclosure| disclosure.label())
+            .collect::<Vec<_>>();
+        let share = missing.len() as f64 / Disclosure::ALL.len() as f64;
+
+        Some(Measure {
+            value: missing.len() as f64,
+            risk: share,
+            explanation: if missing.is_empty() {
+                "All disclosures published".to_owned()
+            } else {
+                format!("Missing disclosures: {}", missing.join(", "))
+            },
+        })
+    }
+}
+
+/// How far an extension leaves holders at the issuer's discretion (0-1), and how; interest is left to riba
+fn extension_restriction(extension: &TokenExtension) -> Option<(f64, String)> {
+    match extension {
+        TokenExtension::PermanentDelegate {
+            delegate: Some(delegate),
+        } => Some((1.0, format!("Permanent delegate {delegate} can move or burn tokens from any holder"))),
+        TokenExtension::NonTransferable => {
+            Some((0.8, "Tokens cannot be transferred, so holders cannot sell what they buy".to_owned()))
+        },
+        TokenExtension::Pausable {
+            paused: true, ..
+        } => Some((1.0, "Transfers are paused by the issuer".to_owned())),
+        TokenExtension::Pausable {
+            authority: Some(_), ..
+        } => Some((0.6, "The issuer can pause all transfers".to_owned())),
+        TokenExtension::DefaultAccountState {
+            frozen: true,
+        } => Some((0.6, "New token accounts start frozen until the issuer thaws them".to_owned())),
+        TokenExtension::TransferHook {
+            program_id: Some(program_id), ..
+        } => Some((0.5, format!("Every transfer runs program {program_id}, which can refuse it"))),
+        TokenExtension::TransferFeeConfig {
+            fee_basis_points,
+            authority,
+            ..
+        } => {
+            let fee_risk = (f64::from(*fee_basis_points) / MAX_TRANSFER_FEE_BPS).clamp(0.0, 1.0);
+            let (risk, changeable) = match authority {
+                Some(_) => (fee_risk.max(0.3), ", which the issuer can change"),
+                None => (fee_risk, ""),
+            };
+            (risk > 0.0).then(|| {
+                (risk, format!("Transfers pay a {:.2}% fee{changeable}", f64::from(*fee_basis_points) / 100.0))
+            })
+        },
+        TokenExtension::ConfidentialTransferMint {
+            ..
+        } => Some((0.3, "Balances and transfer amounts can be hidden".to_owned())),
+        TokenExtension::ScaledUiAmount {
+            authority: Some(_), ..
+        } => Some((0.3, "The issuer can change the multiplier applied to displayed balances".to_owned())),
+        _ => None,
+    }
+}
+
+struct Measure {
+    value: f64,
+    risk: f64, // 0-1
+    explanation: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_750_000_000;
+
+    #[test]
+    fn test_scores_measured_factors_with_breakdown() {
+        let inputs = GhararInputs::from_json(&format!(
+            r#"{{
+                "price_history": [1.0, 1.2, 0.9, 1.1],
+                "top_10_holders_percentage": 80.0,
+                "liquidity_usd": 20000,
+                "market_cap_usd": 2000000,
+                "unlock_schedule": [
+                    {{ "unlock_at": {}, "share": 0.25 }},
+                    {{ "unlock_at": {}, "share": 0.25 }}
+                ],
+                "mint_authority_active": true,
+                "extensions": [
+                    {{ "TransferFeeConfig": {{ "fee_basis_points": 100, "maximum_fee": 0, "authority": null }} }},
+                    {{ "PermanentDelegate": {{ "delegate": "Delegate111" }} }}
+                ],
+                "disclosures": ["whitepaper"]
+            }}"#,
+            NOW + 86_400,
+            NOW + 2 * YEAR_SECONDS
+        ))
+        .unwrap();
+
+        let assessment = inputs.assess(NOW);
+        let factor = |name: &str| assessment.factors.iter().find(|factor| factor.name == name).unwrap();
+
+        assert_eq!(assessment.coverage, 1.0);
+        assert_eq!(assessment.level, GhararLevel::Fahish);
+        assert_eq!(factor("holder_concentration").risk, Some(1.0));
+        assert_eq!(factor("liquidity_depth").explanation, "Pool liquidity is 1.0% of market cap");
+        assert_eq!(factor("supply_unlocks").value, Some(0.25));
+        assert_eq!(factor("mint_extensions").risk, Some(1.0));
+        assert_eq!(
+            factor("mint_extensions").explanation,
+            "Transfers pay a 1.00% fee; Permanent delegate Delegate111 can move or burn tokens from any holder"
+        );
+        assert_eq!(factor("missing_disclosures").explanation, "Missing disclosures: team, audit, tokenomics, treasury");
+        assert!(
+            (assessment.factors.iter().map(|factor| factor.contribution).sum::<f64>() - assessment.score).abs() < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_unavailable_inputs_lower_coverage_not_score() {
+        let calm = GhararInputs {
+            price_change_24h: Some(0.5),
+            mint_authority_active: Some(false),
+            disclosures: Some(Disclosure::ALL.to_vec()),
+            ..Default::default()
+        };
+
+        let assessment = calm.assess(NOW);
+
+        assert!((assessment.coverage - 0.45).abs() < 1e-9);
+        assert_eq!(assessment.level, GhararLevel::Yasir);
+        assert_eq!(assessment.factors[1].explanation, "Not available");
+        assert_eq!(assessment.factors[1].contribution, 0.0);
+
+        let sparse = GhararInputs {
+            mint_authority_active: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(sparse.assess(NOW).level, GhararLevel::Undetermined);
+    }
+}
@@ -0,0 +1,122 @@
+//! Tabular (arithmetic) Islamic calendar, the civil variant with the 16th-year leap cycle.
+//!
+//! Months alternate 30 and 29 days and Dhu al-Hijjah gains a day in 11 years of every 30. Dates can
+//! differ by a day or two from sighting-based calendars, which is enough precision for the hawl.
+
+use crate::models::HijriDate;
+
+/// Julian day number of 1 Muharram 1 AH (16 July 622, Julian calendar)
+const EPOCH_JDN: i64 = 1_948_440;
+/// Julian day number of 1 January 1970
+const UNIX_EPOCH_JDN: i64 = 2_440_588;
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+/// The Hijri date of a Unix timestamp, in UTC
+pub fn hijri_from_unix(secs: u64) -> HijriDate {
+    from_jdn(secs as i64 / DAY_SECS + UNIX_EPOCH_JDN)
+}
+
+/// Midnight UTC at the start of a Hijri date
+pub fn unix_from_hijri(date: &HijriDate) -> u64 {
+    ((to_jdn(date) - UNIX_EPOCH_JDN) * DAY_SECS).max(0) as u64
+}
+
+/// The same date one lunar year later, on the month's last day when it has no such day
+pub fn lunar_year_after(date: &HijriDate) -> HijriDate {
+    let year = date.year + 1;
+    HijriDate {
+        year,
+        month: date.month,
+        day: date.day.min(month_length(year, date.month)),
+    }
+}
+
+pub fn month_length(
+    year: i32,
+    month: u32,
+) -> u32 {
+    let start = to_jdn(&HijriDate {
+        year,
+        month,
+        day: 1,
+    });
+    let next = if month == 12 {
+        HijriDate {
+            year: year + 1,
+            month: 1,
+            day: 1,
+        }
+    } else {
+        HijriDate {
+            year,
+            month: month + 1,
+            day: 1,
+        }
+    };
+    (to_jdn(&next) - start) as u32
+}
+
+fn to_jdn(date: &HijriDate) -> i64 {
+    let year = i64::from(date.year);
+    let month = i64::from(date.month);
+    i64::from(date.day) + (59 * (month - 1) + 1) / 2 + (year - 1) * 354 + (3 + 11 * year).div_euclid(30) + EPOCH_JDN - 1
+}
+
+fn from_jdn(jdn: i64) -> HijriDate {
+    let year = (30 * (jdn - EPOCH_JDN) + 10646).div_euclid(10631) as i32;
+    let new_year = to_jdn(&HijriDate {
+        year,
+        month: 1,
+        day: 1,
+    });
+    let month = ((2 * (jdn - 29 - new_year) + 58).div_euclid(59) + 1).clamp(1, 12) as u32;
+    let month_start = to_jdn(&HijriDate {
+        year,
+        month,
+        day: 1,
+    });
+
+    HijriDate {
+        year,
+        month,
+        day: (jdn - month_start + 1) as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_dates_round_trip() {
+        // 1 Ramadan 1446 fell on 1 March 2025
+        let ramadan = hijri_from_unix(1_740_787_200);
+        assert_eq!((ramadan.year, ramadan.month, ramadan.day), (1446, 9, 1));
+        assert_eq!(unix_from_hijri(&ramadan), 1_740_787_200);
+
+        // Every day of a leap year maps back to itself
+        let start = unix_from_hijri(&HijriDate {
+            year: 1445,
+            month: 1,
+            day: 1,
+        });
+        for day in 0..355 {
+            let secs = start + day * 86_400;
+            assert_eq!(unix_from_hijri(&hijri_from_unix(secs)), secs);
+        }
+        assert_eq!(month_length(1445, 12), 30);
+        assert_eq!(month_length(1446, 12), 29);
+        assert_eq!(
+            lunar_year_after(&HijriDate {
+                year: 1445,
+                month: 12,
+                day: 30
+            }),
+            HijriDate {
+                year: 1446,
+                month: 12,
+                day: 29
+            }
+        );
+    }
+}
@@ -0,0 +1,497 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::rules::load_from_env;
+use crate::models::Fatwa;
+use crate::models::FatwaSource;
+use crate::models::IkhtilafMap;
+use crate::models::IkhtilafPosition;
+use crate::models::IslamicPrinciple;
+use crate::models::RulingClass;
+use crate::models::SourceOpinion;
+use crate::models::analysis::ScrapedData;
+
+/// Environment variable pointing at a TOML file that replaces the bundled sources, phrases and bases
+pub const IKHTILAF_FILE_ENV: &str = "AVERROES_IKHTILAF_FILE";
+
+/// Longest excerpt kept from the sentence stating a ruling, in characters
+const EXCERPT_CHARS: usize = 240;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+const BUILTIN_MAPPER: &str = include_str!("ikhtilaf.toml");
+
+/// A site whose pages state a Sharia position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceProfile {
+    pub source: String, // Issuer label, as in `FatwaSource::from_label`
+    pub domains: Vec<String>,
+    pub credibility: f64,
+}
+
+/// Credibility of knowledge base fatwas by issuer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuerCredibility {
+    #[serde(default)]
+    pub issuers: HashMap<String, f64>, // By label
+    pub scholar: f64,
+    pub default: f64,
+}
+
+/// Phrases stating one ruling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulingPhrases {
+    pub principle: IslamicPrinciple,
+    pub phrases: Vec<String>,
+}
+
+/// A question positions turn on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DifferenceBasis {
+    pub id: String,
+    pub description: String,
+    pub terms: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MapperFile {
+    half_life_days: f64,
+    undated_recency: f64,
+    credibility: IssuerCredibility,
+    #[serde(default)]
+    sources: Vec<SourceProfile>,
+    #[serde(default)]
+    rulings: Vec<RulingPhrases>,
+    #[serde(default)]
+    bases: Vec<DifferenceBasis>,
+}
+
+/// An opinion with the text it was read from, for matching bases of difference
+struct ReadOpinion {
+    opinion: SourceOpinion,
+    text: String,
+}
+
+/// Groups the rulings stated by known sources and fatwas into majority and minority positions
+#[derive(Debug, Clone)]
+pub struct IkhtilafMapper {
+    half_life_days: f64,
+    undated_recency: f64,
+    credibility: IssuerCredibility,
+    sources: Vec<SourceProfile>,
+    phrases: Vec<(String, IslamicPrinciple)>, // Longest first
+    bases: Vec<DifferenceBasis>,
+}
+
+impl IkhtilafMapper {
+    /// Parse and validate the weights, `[[sources]]`, `[[rulings]]` and `[[bases]]` from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let file: MapperFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        if file.half_life_days <= 0.0 {
+            return Err("half_life_days must be positive".to_owned());
+        }
+        if !(0.0..=1.0).contains(&file.undated_recency) {
+            return Err("undated_recency must be between 0 and 1".to_owned());
+        }
+        let credibilities = [("scholar", file.credibility.scholar), ("default", file.credibility.default)]
+            .into_iter()
+            .chain(file.credibility.issuers.iter().map(|(label, value)| (label.as_str(), *value)))
+            .chain(
+                file.sources
+                    .iter()
+                    .map(|profile| (profile.source.as_str(), profile.credibility)),
+            );
+        for (name, credibility) in credibilities {
+            if !(0.0..=1.0).contains(&credibility) {
+                return Err(format!("Credibility of '{name}' must be between 0 and 1"));
+            }
+        }
+        for profile in &file.sources {
+            if profile.domains.is_empty() {
+                return Err(format!("Source '{}' needs at least one domain", profile.source));
+            }
+        }
+
+        let mut phrases: Vec<(String, IslamicPrinciple)> = Vec::new();
+        for ruling in &file.rulings {
+            for phrase in &ruling.phrases {
+                let phrase = phrase.trim().to_ascii_lowercase();
+                if phrase.is_empty() {
+                    return Err(format!("Empty phrase for {:?}", ruling.principle));
+                }
+                if phrases.iter().any(|(existing, _)| *existing == phrase) {
+                    return Err(format!("Phrase '{phrase}' is listed more than once"));
+                }
+                phrases.push((phrase, ruling.principle.clone()));
+            }
+        }
+        phrases.sort_by_key(|(phrase, _)| std::cmp::Reverse(phrase.len()));
+
+        for basis in &file.bases {
+            if basis.terms.is_empty() {
+                return Err(format!("Basis '{}' needs at least one term", basis.id));
+            }
+        }
+
+        Ok(Self {
+            half_life_days: file.half_life_days,
+            undated_recency: file.undated_recency,
+            credibility: file.credibility,
+            sources: file.sources,
+            phrases,
+            bases: file.bases,
+        })
+    }
+
+    /// The bundled sources, phrases and bases
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_MAPPER).expect("Bundled ikhtilaf mapper must be valid")
+    }
+
+    /// Sources, phrases and bases from `AVERROES_IKHTILAF_FILE`, or the bundled ones when unset
+    pub fn from_env() -> Result<Self, String> {
+        load_from_env(IKHTILAF_FILE_ENV, Self::from_toml, Self::builtin)
+    }
+
+    /// Positions of the known sources among `scraped` and of `fatwas`, one opinion per source; `None` when none
+    /// of them states a ruling
+    pub fn map(
+        &self,
+        scraped: &[ScrapedData],
+        fatwas: &[Fatwa],
+        now: u64,
+    ) -> Option<IkhtilafMap> {
+        let from_pages = scraped.iter().filter_map(|data| {
+            let profile = self.profile_for(&data.source_url)?;
+            self.read_opinion(
+                FatwaSource::from_label(&profile.source),
+                data.source_url.clone(),
+                &data.content,
+                None,
+                profile.credibility,
+                now,
+            )
+        });
+        let from_fatwas = fatwas.iter().filter_map(|fatwa| {
+            let reference = match &fatwa.reference_number {
+                Some(number) => format!("{} ({number})", fatwa.title),
+                None => fatwa.title.clone(),
+            };
+            self.read_opinion(
+                fatwa.source.clone(),
+                reference,
+                &fatwa.content,
+                fatwa.issued_date.map(|date| date.timestamp().max(0) as u64),
+                self.issuer_credibility(&fatwa.source),
+                now,
+            )
+        });
+
+        // A source that speaks more than once is counted once, by its heaviest opinion
+        let mut opinions: Vec<ReadOpinion> = Vec::new();
+        for read in from_pages.chain(from_fatwas) {
+            match opinions
+                .iter_mut()
+                .find(|existing| existing.opinion.source == read.opinion.source)
+            {
+                Some(existing) if existing.opinion.weight < read.opinion.weight => *existing = read,
+                Some(_) => {},
+                None => opinions.push(read),
+            }
+        }
+        if opinions.is_empty() {
+            return None;
+        }
+
+        let total: f64 = opinions.iter().map(|read| read.opinion.weight).sum();
+        let mut groups: Vec<(RulingClass, Vec<ReadOpinion>)> = Vec::new();
+        for read in opinions {
+            let class = read.opinion.ruling.class();
+            match groups.iter_mut().find(|(existing, _)| *existing == class) {
+                Some((_, members)) => members.push(read),
+                None => groups.push((class, vec![read])),
+            }
+        }
+        // Heaviest first; on equal weight the stricter position leads
+        groups.sort_by(|(class_a, a), (class_b, b)| {
+            let weight = |members: &[ReadOpinion]| members.iter().map(|read| read.opinion.weight).sum::<f64>();
+            weight(b).total_cmp(&weight(a)).then_with(|| class_b.cmp(class_a))
+        });
+
+        let mut raised: Vec<(usize, Vec<String>)> = Vec::new(); // Basis index, sources raising it
+        if groups.len() > 1 {
+            for (index, basis) in self.bases.iter().enumerate() {
+                let sources = groups
+                    .iter()
+                    .flat_map(|(_, members)| members.iter())
+                    .filter(|read| basis.terms.iter().any(|term| read.text.contains(&term.to_ascii_lowercase())))
+                    .map(|read| read.opinion.source.label())
+                    .collect::<Vec<_>>();
+                if !sources.is_empty() {
+                    raised.push((index, sources));
+                }
+            }
+        }
+        let basis_of_difference = raised
+            .into_iter()
+            .map(|(index, sources)| format!("{} (raised by {})", self.bases[index].description, sources.join(", ")))
+            .collect();
+
+        let mut positions = groups.into_iter().map(|(class, members)| position(class, members, total));
+        let majority_view = positions.next()?;
+        Some(IkhtilafMap {
+            majority_view,
+            minority_views: positions.collect(),
+            basis_of_difference,
+        })
+    }
+
+    /// The ruling `text` states and the sentence stating it, weighed for its source and date
+    fn read_opinion(
+        &self,
+        source: FatwaSource,
+        reference: String,
+        text: &str,
+        stated_at: Option<u64>,
+        credibility: f64,
+        now: u64,
+    ) -> Option<ReadOpinion> {
+        let text = text.to_ascii_lowercase();
+        let (ruling, at) = self.stated_ruling(&text)?;
+        let recency = match stated_at {
+            Some(stated_at) => {
+                let age_days = now.saturating_sub(stated_at) as f64 / SECONDS_PER_DAY;
+                0.5_f64.powf(age_days / self.half_life_days)
+            },
+            None => self.undated_recency,
+        };
+
+        Some(ReadOpinion {
+            opinion: SourceOpinion {
+                source,
+                reference,
+                ruling,
+                excerpt: sentence_at(&text, at),
+                stated_at,
+                credibility,
+                recency,
+                weight: credibility * recency,
+            },
+            text,
+        })
+    }
+
+    /// The ruling stated most often in lowercased `text`, and where it is first stated; on a tie, the one stated
+    /// first
+    fn stated_ruling(
+        &self,
+        text: &str,
+    ) -> Option<(IslamicPrinciple, usize)> {
+        let mut masked = text.to_owned();
+        let mut counts: Vec<(IslamicPrinciple, usize, usize)> = Vec::new(); // Ruling, count, first offset
+        for (phrase, principle) in &self.phrases {
+            for at in word_matches(&masked, phrase) {
+                masked.replace_range(at..at + phrase.len(), &" ".repeat(phrase.len()));
+                match counts.iter_mut().find(|(existing, ..)| existing == principle) {
+                    Some((_, count, first)) => {
+                        *count += 1;
+                        *first = (*first).min(at);
+                    },
+                    None => counts.push((principle.clone(), 1, at)),
+                }
+            }
+        }
+
+        counts
+            .into_iter()
+            .max_by(|(_, count_a, first_a), (_, count_b, first_b)| {
+                count_a.cmp(count_b).then_with(|| first_b.cmp(first_a))
+            })
+            .map(|(principle, _, first)| (principle, first))
+    }
+
+    fn profile_for(
+        &self,
+        url: &str,
+    ) -> Option<&SourceProfile> {
+        let host = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+        let host = host
+            .split(['/', '?', '#'])
+            .next()
+            .unwrap_or_default()
+            .split(':')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        self.sources.iter().find(|profile| {
+            profile.domains.iter().any(|domain| {
+                let domain = domain.to_ascii_lowercase();
+                host == domain || host.ends_with(&format!(".{domain}"))
+            })
+        })
+    }
+
+    fn issuer_credibility(
+        &self,
+        source: &FatwaSource,
+    ) -> f64 {
+        match self.credibility.issuers.get(&source.label()) {
+            Some(credibility) => *credibility,
+            None if matches!(source, FatwaSource::Scholar { .. }) => self.credibility.scholar,
+            None => self.credibility.default,
+        }
+    }
+}
+
+/// Offsets of `phrase` in `text` where it stands as whole words
+fn word_matches(
+    text: &str,
+    phrase: &str,
+) -> Vec<usize> {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric());
+    text.match_indices(phrase)
+        .map(|(at, _)| at)
+        .filter(|&at| !is_word(text[..at].chars().next_back()) && !is_word(text[at + phrase.len()..].chars().next()))
+        .collect()
+}
+
+/// The sentence around byte offset `at`, shortened to `EXCERPT_CHARS`
+fn sentence_at(
+    text: &str,
+    at: usize,
+) -> String {
+    let is_end = |c: char| matches!(c, '.' | '!' | '?' | '\n');
+    let start = text[..at].rfind(is_end).map(|end| end + 1).unwrap_or(0);
+    let end = text[at..].find(is_end).map(|end| at + end + 1).unwrap_or(text.len());
+    let sentence = text[start..end].trim();
+
+    match sentence.char_indices().nth(EXCERPT_CHARS) {
+        Some((cut, _)) => format!("{}…", &sentence[..cut]),
+        None => sentence.to_owned(),
+    }
+}
+
+fn position(
+    class: RulingClass,
+    mut members: Vec<ReadOpinion>,
+    total: f64,
+) -> IkhtilafPosition {
+    members.sort_by(|a, b| b.opinion.weight.total_cmp(&a.opinion.weight));
+    let weight: f64 = members.iter().map(|read| read.opinion.weight).sum();
+
+    let mut rulings: Vec<(IslamicPrinciple, f64)> = Vec::new();
+    for read in &members {
+        match rulings.iter_mut().find(|(ruling, _)| *ruling == read.opinion.ruling) {
+            Some((_, sum)) => *sum += read.opinion.weight,
+            None => rulings.push((read.opinion.ruling.clone(), read.opinion.weight)),
+        }
+    }
+    let ruling = rulings
+        .into_iter()
+        .reduce(|best, next| {
+            if next.1 > best.1 {
+                next
+            } else {
+                best
+            }
+        })
+        .map(|(ruling, _)| ruling)
+        .expect("A position has at least one opinion");
+
+    IkhtilafPosition {
+        class,
+        ruling,
+        weight_share: if total > 0.0 {
+            weight / total
+        } else {
+            0.0
+        },
+        opinions: members.into_iter().map(|read| read.opinion).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use chrono::Utc;
+
+    use super::*;
+    use crate::models::analysis::ScrapedDataType;
+
+    fn page(
+        url: &str,
+        content: &str,
+    ) -> ScrapedData {
+        ScrapedData {
+            source_url: url.to_owned(),
+            content: content.to_owned(),
+            data_type: ScrapedDataType::Official,
+            title: None,
+            relevance_score: 0.8,
+            scraped_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_longer_phrases_mask_the_rulings_they_contain() {
+        let mapper = IkhtilafMapper::builtin();
+
+        let (ruling, _) = mapper
+            .stated_ruling("this token is not halal. it is not halal because it is speculative, not halal at all.")
+            .unwrap();
+        assert_eq!(ruling, IslamicPrinciple::Haram);
+        let (ruling, at) = mapper
+            .stated_ruling("jual beli mata uang boleh dengan ketentuan. forward hukumnya haram.")
+            .unwrap();
+        assert_eq!(ruling, IslamicPrinciple::Halal);
+        assert_eq!(
+            sentence_at("jual beli mata uang boleh dengan ketentuan. forward", at),
+            "jual beli mata uang boleh dengan ketentuan."
+        );
+        assert!(mapper.stated_ruling("price went up today").is_none());
+    }
+
+    #[test]
+    fn test_weighs_sources_into_majority_and_minority_views() {
+        let mapper = IkhtilafMapper::builtin();
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let mut fatwa = Fatwa::new(
+            "Ruling on Dealing in Bitcoin".to_owned(),
+            "Trading in Bitcoin is impermissible: it is speculative and not backed by any issuing authority."
+                .to_owned(),
+            FatwaSource::Darul,
+            vec![IslamicPrinciple::Gharar],
+            vec!["bitcoin".to_owned()],
+            "en".to_owned(),
+        );
+        fatwa.issued_date = Some(now - chrono::Duration::days(1825));
+
+        let scraped = [
+            page("https://www.cryptohalal.cc/token/btc", "Bitcoin is halal as a digital commodity."),
+            page("https://cryptohalal.cc/analysis/btc", "Our older review found bitcoin questionable."),
+            page("https://example.com/btc", "Bitcoin is haram, says a forum post."),
+        ];
+        let map = mapper.map(&scraped, &[fatwa], now.timestamp() as u64).unwrap();
+
+        // Darul: 0.85 credibility at half recency outweighs CryptoHalal's 0.6 undated; the forum is not a source
+        assert_eq!(map.majority_view.class, RulingClass::Prohibited);
+        assert_eq!(map.majority_view.opinions[0].source, FatwaSource::Darul);
+        assert!((map.majority_view.opinions[0].weight - 0.425).abs() < 1e-9);
+        assert_eq!(map.minority_views.len(), 1);
+        assert_eq!(map.minority_views[0].ruling, IslamicPrinciple::Halal);
+        assert_eq!(map.minority_views[0].opinions.len(), 1);
+        assert!((map.majority_view.weight_share + map.minority_views[0].weight_share - 1.0).abs() < 1e-9);
+        assert!(!map.is_consensus());
+        assert!(
+            map.basis_of_difference.iter().any(
+                |basis| basis.starts_with("Whether a token without physical form") && basis.contains("CryptoHalal")
+            )
+        );
+
+        assert!(mapper.map(&scraped[2..], &[], now.timestamp() as u64).is_none());
+    }
+}
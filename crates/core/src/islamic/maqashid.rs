@@ -0,0 +1,260 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::rules::Condition;
+use super::rules::TokenFacts;
+use super::rules::evaluate_conditions;
+use super::rules::load_from_env;
+use super::rules::validate_conditions;
+use crate::models::MaqashidPrinciple;
+
+/// Environment variable pointing at a TOML file that replaces the bundled signals
+pub const MAQASHID_FILE_ENV: &str = "AVERROES_MAQASHID_FILE";
+
+const BUILTIN_SIGNALS: &str = include_str!("maqashid.toml");
+
+/// Score of an objective no signal bears on
+const NEUTRAL_SCORE: f64 = 0.5;
+
+/// The five objectives of the Sharia
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaqashidObjective {
+    Din,
+    Nafs,
+    Aql,
+    Nasl,
+    Mal,
+}
+
+impl MaqashidObjective {
+    pub const ALL: [MaqashidObjective; 5] = [
+        MaqashidObjective::Din,
+        MaqashidObjective::Nafs,
+        MaqashidObjective::Aql,
+        MaqashidObjective::Nasl,
+        MaqashidObjective::Mal,
+    ];
+
+    /// (name, category, description)
+    fn labels(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            MaqashidObjective::Din => {
+                ("Hifz al-Din (Preservation of Religion)", "Religion", "Keeping clear of what the religion forbids")
+            },
+            MaqashidObjective::Nafs => {
+                ("Hifz al-Nafs (Preservation of Life)", "Life", "Protecting life, health and livelihood")
+            },
+            MaqashidObjective::Aql => (
+                "Hifz al-Aql (Preservation of Intellect)",
+                "Intellect",
+                "Protecting sound judgement from intoxication and manipulation",
+            ),
+            MaqashidObjective::Nasl => {
+                ("Hifz al-Nasl (Preservation of Lineage)", "Lineage", "Protecting the family and future generations")
+            },
+            MaqashidObjective::Mal => (
+                "Hifz al-Mal (Preservation of Wealth)",
+                "Financial Security",
+                "Protecting wealth from harmful activities",
+            ),
+        }
+    }
+}
+
+/// Evidence that a token serves or harms one objective
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaqashidSignal {
+    pub id: String,
+    pub objective: MaqashidObjective,
+    /// Added to the objective's score when the signal holds; negative for harm
+    pub weight: f64,
+    pub rationale: String,
+    #[serde(default)]
+    pub evidence: Vec<String>,
+    #[serde(default)]
+    pub all: Vec<Condition>,
+    #[serde(default)]
+    pub any: Vec<Condition>,
+    #[serde(default)]
+    pub none: Vec<Condition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SignalsFile {
+    signals: Vec<MaqashidSignal>,
+}
+
+/// Scores a token against the five objectives and the balance of benefit and harm
+#[derive(Debug, Clone)]
+pub struct MaqashidAssessor {
+    signals: Vec<MaqashidSignal>,
+}
+
+impl MaqashidAssessor {
+    pub fn new(signals: Vec<MaqashidSignal>) -> Self {
+        Self {
+            signals,
+        }
+    }
+
+    /// Parse and validate `[[signals]]` from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let file: SignalsFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        for signal in &file.signals {
+            if !signal.weight.is_finite() || signal.weight.abs() > 1.0 {
+                return Err(format!("Signal '{}': weight must be between -1 and 1", signal.id));
+            }
+            validate_conditions(&format!("Signal '{}'", signal.id), &signal.all, &signal.any, &signal.none)?;
+        }
+
+        Ok(Self::new(file.signals))
+    }
+
+    /// The bundled signals
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_SIGNALS).expect("Bundled Maqashid signals must be valid")
+    }
+
+    /// Signals from `AVERROES_MAQASHID_FILE`, or the bundled ones when unset
+    pub fn from_env() -> Result<Self, String> {
+        load_from_env(MAQASHID_FILE_ENV, Self::from_toml, Self::builtin)
+    }
+
+    /// One entry per objective, in the classical order, followed by the maslahah/mafsadah balance
+    pub fn assess(
+        &self,
+        facts: &TokenFacts,
+    ) -> Vec<MaqashidPrinciple> {
+        let fired = self
+            .signals
+            .iter()
+            .filter_map(|signal| {
+                let matched = evaluate_conditions(&signal.all, &signal.any, &signal.none, facts)?;
+                Some((signal, matched))
+            })
+            .collect::<Vec<_>>();
+
+        let mut assessment = MaqashidObjective::ALL
+            .iter()
+            .map(|objective| {
+                let (name, category, description) = objective.labels();
+                let signals = fired
+                    .iter()
+                    .filter(|(signal, _)| signal.objective == *objective)
+                    .collect::<Vec<_>>();
+
+                let mut rationale = signals
+                    .iter()
+                    .map(|(signal, matched)| signal_line(signal, matched))
+                    .collect::<Vec<_>>();
+                if rationale.is_empty() {
+                    rationale.push("No evidence either way in the project description or on-chain data".to_owned());
+                }
+
+                MaqashidPrinciple {
+                    name: name.to_owned(),
+                    category: category.to_owned(),
+                    description: description.to_owned(),
+                    relevance_score: (NEUTRAL_SCORE + signals.iter().map(|(signal, _)| signal.weight).sum::<f64>())
+                        .clamp(0.0, 1.0),
+                    rationale,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        assessment.push(balance(&fired));
+        assessment
+    }
+}
+
+/// Maslahah against mafsadah; harm weighs double, as averting harm takes precedence over securing benefit
+fn balance(fired: &[(&MaqashidSignal, Vec<String>)]) -> MaqashidPrinciple {
+    let benefit = fired.iter().map(|(signal, _)| signal.weight.max(0.0)).sum::<f64>();
+    let harm = fired.iter().map(|(signal, _)| (-signal.weight).max(0.0)).sum::<f64>();
+
+    let mut rationale = vec![format!("Benefit {benefit:.2} against harm {harm:.2}")];
+    if harm > 0.0 {
+        rationale
+            .push("Legal maxim: dar' al-mafasid muqaddam 'ala jalb al-masalih (averting harm comes first)".to_owned());
+    }
+
+    MaqashidPrinciple {
+        name: "Maslahah/Mafsadah (Benefit and Harm)".to_owned(),
+        category: "Balance".to_owned(),
+        description: "Overall weighing of the benefit a token brings against the harm it causes".to_owned(),
+        relevance_score: (NEUTRAL_SCORE + benefit / 2.0 - harm).clamp(0.0, 1.0),
+        rationale,
+    }
+}
+
+/// e.g. `[mal.thin_liquidity] -0.10 (liquidity_usd = 2500 < 10000): With thin liquidity ... Evidence: ...`
+fn signal_line(
+    signal: &MaqashidSignal,
+    matched: &[String],
+) -> String {
+    let mut line = format!("[{}] {:+.2} ({}): {}", signal.id, signal.weight, matched.join("; "), signal.rationale);
+    if !signal.evidence.is_empty() {
+        line.push_str(&format!(" Evidence: {}", signal.evidence.join("; ")));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scores_all_objectives_from_description_and_chain_data() {
+        let facts = TokenFacts::new()
+            .text("text", "Online casino token with weekly jackpot, to the moon")
+            .number("holders", 40.0)
+            .number("turnover_24h", 3.5);
+
+        let assessment = MaqashidAssessor::builtin().assess(&facts);
+        let score = |category: &str| {
+            assessment
+                .iter()
+                .find(|principle| principle.category == category)
+                .unwrap()
+                .relevance_score
+        };
+
+        assert_eq!(assessment.len(), 6);
+        assert!((score("Religion") - 0.1).abs() < 1e-9);
+        assert!((score("Life") - 0.5).abs() < 1e-9);
+        assert!((score("Intellect") - 0.35).abs() < 1e-9);
+        assert!((score("Lineage") - 0.3).abs() < 1e-9);
+        assert_eq!(score("Financial Security"), 0.0);
+        assert_eq!(score("Balance"), 0.0);
+
+        let wealth = &assessment[4];
+        assert!(wealth.rationale.iter().any(|line| line.starts_with(
+            "[mal.frenzied_trading] -0.15 (turnover_24h = 3.5 > 1): Daily volume above the market cap points to \
+             speculative churn rather than use."
+        )));
+        assert_eq!(assessment[1].rationale, vec![
+            "No evidence either way in the project description or on-chain data".to_owned()
+        ]);
+    }
+
+    #[test]
+    fn test_benefit_outweighs_harm_for_useful_token() {
+        let facts = TokenFacts::new()
+            .text("text", "Open source research compute network for healthcare")
+            .number("holders", 25000.0)
+            .flag("verified", true);
+
+        let assessment = MaqashidAssessor::builtin().assess(&facts);
+
+        assert!(assessment.iter().all(|principle| principle.relevance_score >= 0.5));
+        assert!((assessment[5].relevance_score - 0.875).abs() < 1e-9);
+        assert_eq!(assessment[5].rationale, vec!["Benefit 0.75 against harm 0.00".to_owned()]);
+    }
+
+    #[test]
+    fn test_signal_without_conditions_is_rejected() {
+        let raw = "[[signals]]\nid = \"x\"\nobjective = \"Mal\"\nweight = 0.1\nrationale = \"x\"\n";
+        assert!(MaqashidAssessor::from_toml(raw).unwrap_err().contains("needs at least one"));
+    }
+}
@@ -0,0 +1,276 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::ratios::project_data_from_env;
+use super::rules::Condition;
+use super::rules::TokenFacts;
+use super::rules::evaluate_conditions;
+use super::rules::load_from_env;
+use super::rules::validate_conditions;
+use crate::models::MaysirAssessment;
+use crate::models::MaysirCategory;
+use crate::models::MaysirEvidence;
+
+/// Environment variable pointing at a TOML file that replaces the bundled programs and signals
+pub const MAYSIR_FILE_ENV: &str = "AVERROES_MAYSIR_FILE";
+
+/// Probability at and above which a token is taken to serve maysir
+pub const LIKELY_MAYSIR: f64 = 0.7;
+/// Probability at and above which maysir is flagged as a risk
+pub const POSSIBLE_MAYSIR: f64 = 0.4;
+
+/// Share of a token's transactions through a gambling program that counts as full evidence
+const SIGNIFICANT_SHARE: f64 = 0.1;
+
+const BUILTIN_DETECTOR: &str = include_str!("maysir.toml");
+
+/// On-chain programs whose use is gambling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GamblingProgram {
+    pub name: String,
+    pub category: MaysirCategory,
+    pub likelihood: f64,
+    #[serde(default)]
+    pub evidence: Vec<String>,
+    #[serde(default)]
+    pub program_ids: Vec<String>,
+    /// Matched case-insensitively against explorer labels
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+/// Token facts pointing to gambling tokenomics or pump dynamics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaysirSignal {
+    pub id: String,
+    pub category: MaysirCategory,
+    pub likelihood: f64,
+    pub rationale: String,
+    #[serde(default)]
+    pub evidence: Vec<String>,
+    #[serde(default)]
+    pub all: Vec<Condition>,
+    #[serde(default)]
+    pub any: Vec<Condition>,
+    #[serde(default)]
+    pub none: Vec<Condition>,
+}
+
+/// A program the token is used with, as reported in its project file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgramInteraction {
+    pub program_id: String,
+    /// Explorer label, e.g. "Coinflip Game"
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Share of the token's transactions, 0-1
+    #[serde(default)]
+    pub share: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProjectActivity {
+    #[serde(default)]
+    program_interactions: Vec<ProgramInteraction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DetectorFile {
+    #[serde(default)]
+    programs: Vec<GamblingProgram>,
+    #[serde(default)]
+    signals: Vec<MaysirSignal>,
+}
+
+/// Estimates how likely a token is to serve maysir from what it is used for
+#[derive(Debug, Clone)]
+pub struct MaysirDetector {
+    programs: Vec<GamblingProgram>,
+    signals: Vec<MaysirSignal>,
+}
+
+impl MaysirDetector {
+    pub fn new(
+        programs: Vec<GamblingProgram>,
+        signals: Vec<MaysirSignal>,
+    ) -> Self {
+        Self {
+            programs,
+            signals,
+        }
+    }
+
+    /// Parse and validate `[[programs]]` and `[[signals]]` from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let file: DetectorFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        for program in &file.programs {
+            if !(0.0..=1.0).contains(&program.likelihood) {
+                return Err(format!("Program '{}': likelihood must be between 0 and 1", program.name));
+            }
+            if program.program_ids.is_empty() && program.labels.is_empty() {
+                return Err(format!("Program '{}' needs at least one program id or label", program.name));
+            }
+        }
+        for signal in &file.signals {
+            if !(0.0..=1.0).contains(&signal.likelihood) {
+                return Err(format!("Signal '{}': likelihood must be between 0 and 1", signal.id));
+            }
+            validate_conditions(&format!("Signal '{}'", signal.id), &signal.all, &signal.any, &signal.none)?;
+        }
+
+        Ok(Self::new(file.programs, file.signals))
+    }
+
+    /// The bundled programs and signals
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_DETECTOR).expect("Bundled maysir detector must be valid")
+    }
+
+    /// Programs and signals from `AVERROES_MAYSIR_FILE`, or the bundled ones when unset
+    pub fn from_env() -> Result<Self, String> {
+        load_from_env(MAYSIR_FILE_ENV, Self::from_toml, Self::builtin)
+    }
+
+    /// Weigh program interactions and token facts as independent evidence
+    pub fn detect(
+        &self,
+        facts: &TokenFacts,
+        interactions: &[ProgramInteraction],
+    ) -> MaysirAssessment {
+        let mut evidence = interactions
+            .iter()
+            .filter_map(|interaction| self.program_evidence(interaction))
+            .collect::<Vec<_>>();
+
+        evidence.extend(self.signals.iter().filter_map(|signal| {
+            let matched = evaluate_conditions(&signal.all, &signal.any, &signal.none, facts)?;
+            Some(MaysirEvidence {
+                source: signal.id.clone(),
+                category: signal.category,
+                likelihood: signal.likelihood,
+                observed: matched.join("; "),
+                rationale: signal.rationale.clone(),
+                references: signal.evidence.clone(),
+            })
+        }));
+
+        let probability = 1.0 - evidence.iter().map(|item| 1.0 - item.likelihood).product::<f64>();
+
+        MaysirAssessment {
+            probability: probability.clamp(0.0, 1.0),
+            evidence,
+        }
+    }
+
+    fn program_evidence(
+        &self,
+        interaction: &ProgramInteraction,
+    ) -> Option<MaysirEvidence> {
+        let label = interaction.label.as_deref().unwrap_or_default().to_lowercase();
+        let program = self.programs.iter().find(|program| {
+            program.program_ids.contains(&interaction.program_id)
+                || program.labels.iter().any(|known| label.contains(&known.to_lowercase()))
+        })?;
+
+        // A program the token is barely used with says little about what the token is for
+        let weight = interaction
+            .share
+            .map(|share| (share / SIGNIFICANT_SHARE).clamp(0.0, 1.0))
+            .unwrap_or(1.0);
+        let share = interaction
+            .share
+            .map(|share| format!(", {:.1}% of transactions", share * 100.0))
+            .unwrap_or_default();
+
+        Some(MaysirEvidence {
+            source: program.name.clone(),
+            category: program.category,
+            likelihood: program.likelihood * weight,
+            observed: format!(
+                "Used with {} ({}{share})",
+                interaction.label.as_deref().unwrap_or("unlabelled program"),
+                interaction.program_id
+            ),
+            rationale: format!("The token moves through {}, where its use is gambling.", program.name.to_lowercase()),
+            references: program.evidence.clone(),
+        })
+    }
+}
+
+/// Program interactions from the token's project file in `AVERROES_PROJECT_DATA_DIR`
+pub fn program_interactions_from_env(
+    address: &str,
+    symbol: &str,
+) -> Result<Vec<ProgramInteraction>, String> {
+    let activity: Option<ProjectActivity> = project_data_from_env(address, symbol)?;
+    Ok(activity.unwrap_or_default().program_interactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jackpot_token_used_with_casino_is_likely_maysir() {
+        let facts = TokenFacts::new().text("text", "Community token with a 5% jackpot tax paid to one lucky buyer");
+        let interactions = vec![
+            ProgramInteraction {
+                program_id: "CoinF1ip111111111111111111111111111111111111".to_owned(),
+                label: Some("SolFlip Coinflip Game".to_owned()),
+                share: Some(0.3),
+            },
+            ProgramInteraction {
+                program_id: "Swap1111111111111111111111111111111111111111".to_owned(),
+                label: Some("DEX Aggregator".to_owned()),
+                share: Some(0.6),
+            },
+        ];
+
+        let assessment = MaysirDetector::builtin().detect(&facts, &interactions);
+
+        assert_eq!(assessment.evidence.len(), 2);
+        assert_eq!(assessment.evidence[0].category, MaysirCategory::Casino);
+        assert_eq!(
+            assessment.evidence[0].observed,
+            "Used with SolFlip Coinflip Game (CoinF1ip111111111111111111111111111111111111, 30.0% of transactions)"
+        );
+        assert_eq!(assessment.evidence[1].source, "tokenomics.jackpot_tax");
+        assert_eq!(assessment.evidence[1].observed, "text contains \"jackpot tax\"");
+        assert!((assessment.probability - 0.94).abs() < 1e-9);
+        assert!(assessment.probability >= LIKELY_MAYSIR);
+    }
+
+    #[test]
+    fn test_pump_dynamics_add_up_and_utility_scores_zero() {
+        let detector = MaysirDetector::builtin();
+
+        let meme = TokenFacts::new()
+            .text("text", "The memecoin of the summer")
+            .number("price_change_24h", 240.0)
+            .number("turnover_24h", 5.2);
+        let assessment = detector.detect(&meme, &[]);
+        assert!(assessment.evidence.iter().all(|item| item.category == MaysirCategory::Pump));
+        assert!((assessment.probability - 0.60625).abs() < 1e-9);
+
+        // A sliver of activity through a lottery barely counts
+        let utility = TokenFacts::new().text("text", "Utility token for decentralized storage payments");
+        let lottery = ProgramInteraction {
+            program_id: "x".to_owned(),
+            label: Some("Lotto Draw".to_owned()),
+            share: Some(0.01),
+        };
+        assert_eq!(detector.detect(&utility, &[]).probability, 0.0);
+        assert!((detector.detect(&utility, &[lottery]).probability - 0.07).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_program_without_ids_or_labels_is_rejected() {
+        let raw = "[[programs]]\nname = \"x\"\ncategory = \"Casino\"\nlikelihood = 0.5\n";
+        assert!(
+            MaysirDetector::from_toml(raw)
+                .unwrap_err()
+                .contains("at least one program id or label")
+        );
+    }
+}
@@ -0,0 +1,43 @@
+//! Rule-based Sharia screening, independent of any language model.
+
+pub mod analyzer;
+pub mod determination;
+pub mod documents;
+pub mod fatwas;
+pub mod gharar;
+pub mod hijri;
+pub mod ikhtilaf;
+pub mod maqashid;
+pub mod maysir;
+pub mod purification;
+pub mod ratios;
+pub mod review;
+pub mod riba;
+pub mod rules;
+pub mod schools;
+pub mod scripture;
+pub mod stablecoin;
+pub mod storage;
+pub mod taxonomy;
+pub mod zakat;
+
+pub use analyzer::*;
+pub use determination::*;
+pub use documents::*;
+pub use fatwas::*;
+pub use gharar::*;
+pub use hijri::*;
+pub use ikhtilaf::*;
+pub use maqashid::*;
+pub use maysir::*;
+pub use purification::*;
+pub use ratios::*;
+pub use review::*;
+pub use riba::*;
+pub use rules::*;
+pub use schools::*;
+pub use scripture::*;
+pub use stablecoin::*;
+pub use storage::*;
+pub use taxonomy::*;
+pub use zakat::*;
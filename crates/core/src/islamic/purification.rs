@@ -0,0 +1,319 @@
+use chrono::DateTime;
+use chrono::Months;
+
+use super::ratios::ProjectFinancials;
+use crate::AverroesError;
+use crate::models::PeriodPurification;
+use crate::models::PurificationLine;
+use crate::models::PurificationMethod;
+use crate::models::PurificationMethodology;
+use crate::models::PurificationPeriod;
+use crate::models::PurificationRequest;
+use crate::models::PurificationResult;
+use crate::models::RatioSource;
+use crate::models::ReceiptKind;
+
+const YEAR_SECS: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
+const PURIFICATION_REFERENCE: &str =
+    "AAOIFI Sharia Standard No. 21 (Financial Paper) - the impermissible portion of income is disposed of in charity";
+
+/// Purify a holding's income, exported to the mobile apps
+#[uniffi::export]
+pub fn calculate_purification(request: PurificationRequest) -> Result<PurificationResult, AverroesError> {
+    purify_from_env(&request).map_err(AverroesError::InvalidQuery)
+}
+
+/// Purify with the request's ratio, or the one in the token's financials in `AVERROES_PROJECT_DATA_DIR`
+pub fn purify_from_env(request: &PurificationRequest) -> Result<PurificationResult, String> {
+    let screened = match (&request.token, request.impermissible_ratio, &request.scholar_override) {
+        (Some(token), None, None) => {
+            ProjectFinancials::from_env(token, token)?.and_then(|financials| financials.impermissible_income_ratio())
+        },
+        _ => None,
+    };
+
+    purify(request, screened)
+}
+
+/// Amount to donate per period; a scholar's override wins over the request's ratio, which wins over screening
+pub fn purify(
+    request: &PurificationRequest,
+    screened_ratio: Option<f64>,
+) -> Result<PurificationResult, String> {
+    let (ratio, ratio_source, scholar) = match (&request.scholar_override, request.impermissible_ratio, screened_ratio)
+    {
+        (Some(scholar_override), ..) => {
+            (scholar_override.ratio, RatioSource::ScholarOverride, Some(scholar_override.scholar.clone()))
+        },
+        (None, Some(ratio), _) => (ratio, RatioSource::Request, None),
+        (None, None, Some(ratio)) => (ratio, RatioSource::Screening, None),
+        (None, None, None) => {
+            return Err("Give an impermissible-income ratio, a scholar override or a token with financials".to_owned());
+        },
+    };
+
+    if !(0.0..=1.0).contains(&ratio) {
+        return Err("The impermissible-income ratio must be between 0 and 1".to_owned());
+    }
+    if request.holding_value < 0.0 {
+        return Err("The holding value cannot be negative".to_owned());
+    }
+    if let Some(receipt) = request
+        .receipts
+        .iter()
+        .find(|receipt| receipt.amount < 0.0 || !(request.from..request.to).contains(&receipt.received_at))
+    {
+        return Err(format!(
+            "{:?} of {} received at {} is negative or outside the reporting window",
+            receipt.kind, receipt.amount, receipt.received_at
+        ));
+    }
+    let earnings_yield = match (request.method, request.annual_earnings_yield) {
+        (PurificationMethod::AttributableEarnings, None) => {
+            return Err("Purifying attributable earnings needs the project's annual earnings yield".to_owned());
+        },
+        (_, earnings_yield) => earnings_yield.unwrap_or_default(),
+    };
+
+    let periods = period_bounds(request.from, request.to, request.period)?
+        .into_iter()
+        .map(|(start, end)| {
+            let mut lines = Vec::new();
+
+            if request.method == PurificationMethod::AttributableEarnings {
+                let base = request.holding_value * earnings_yield * (end - start) as f64 / YEAR_SECS;
+                lines.push(PurificationLine {
+                    kind: None,
+                    base,
+                    amount: base * ratio,
+                    description: format!(
+                        "{:.2}% of {base:.2} in earnings attributable to the holding over {} days",
+                        ratio * 100.0,
+                        (end - start) / (24 * 60 * 60)
+                    ),
+                });
+            }
+
+            for kind in [
+                ReceiptKind::Dividend,
+                ReceiptKind::StakingReward,
+                ReceiptKind::Airdrop,
+                ReceiptKind::CapitalGain,
+            ] {
+                let received = request
+                    .receipts
+                    .iter()
+                    .filter(|receipt| receipt.kind == kind && (start..end).contains(&receipt.received_at))
+                    .collect::<Vec<_>>();
+                if received.is_empty() || !purifies(request.method, kind) {
+                    continue;
+                }
+
+                let base = received.iter().map(|receipt| receipt.amount).sum::<f64>();
+                lines.push(PurificationLine {
+                    kind: Some(kind),
+                    base,
+                    amount: base * ratio,
+                    description: format!("{:.2}% of {base:.2} in {}", ratio * 100.0, receipt_label(kind)),
+                });
+            }
+
+            PeriodPurification {
+                start,
+                end,
+                amount: lines.iter().map(|line| line.amount).sum(),
+                lines,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(PurificationResult {
+        methodology: PurificationMethodology {
+            method: request.method,
+            ratio,
+            ratio_source,
+            scholar,
+            description: describe(request.method).to_owned(),
+            references: references(request.method),
+        },
+        total: periods.iter().map(|period| period.amount).sum(),
+        periods,
+    })
+}
+
+/// Whether a method purifies a kind of income
+fn purifies(
+    method: PurificationMethod,
+    kind: ReceiptKind,
+) -> bool {
+    match kind {
+        ReceiptKind::StakingReward | ReceiptKind::Airdrop => true,
+        // Dividends are paid out of the earnings the attributable method already purifies
+        ReceiptKind::Dividend => method != PurificationMethod::AttributableEarnings,
+        ReceiptKind::CapitalGain => method == PurificationMethod::DistributionsAndGains,
+    }
+}
+
+fn receipt_label(kind: ReceiptKind) -> &'static str {
+    match kind {
+        ReceiptKind::Dividend => "dividends",
+        ReceiptKind::StakingReward => "staking rewards",
+        ReceiptKind::Airdrop => "airdrops",
+        ReceiptKind::CapitalGain => "realized capital gains",
+    }
+}
+
+fn describe(method: PurificationMethod) -> &'static str {
+    match method {
+        PurificationMethod::Distributions => {
+            "The impermissible share of every distribution received - dividends, staking rewards and airdrops - is \
+             donated. Capital gains are not purified."
+        },
+        PurificationMethod::DistributionsAndGains => {
+            "The impermissible share of distributions and of realized capital gains is donated, following scholars who \
+             hold that the price a token fetches reflects its impermissible earnings."
+        },
+        PurificationMethod::AttributableEarnings => {
+            "The impermissible share of the project's earnings attributable to the holding is donated whether or not \
+             they were distributed; dividends come out of those earnings and are not purified again. Staking rewards \
+             and airdrops are purified as received."
+        },
+    }
+}
+
+fn references(method: PurificationMethod) -> Vec<String> {
+    let mut references = vec![
+        PURIFICATION_REFERENCE.to_owned(),
+        "Sahih Muslim 1015 - Allah is good and accepts only what is good".to_owned(),
+    ];
+    if method == PurificationMethod::DistributionsAndGains {
+        references.push("Islamic Fiqh Academy Resolution No. 63 (1/7) on financial markets".to_owned());
+    }
+    references
+}
+
+/// Calendar periods from `from`, the last one cut short at `to`
+fn period_bounds(
+    from: u64,
+    to: u64,
+    period: PurificationPeriod,
+) -> Result<Vec<(u64, u64)>, String> {
+    if from >= to {
+        return Err("The reporting window must end after it starts".to_owned());
+    }
+    let months = match period {
+        PurificationPeriod::Monthly => 1,
+        PurificationPeriod::Quarterly => 3,
+        PurificationPeriod::Yearly => 12,
+    };
+    let first = DateTime::from_timestamp(from as i64, 0).ok_or_else(|| format!("Invalid timestamp {from}"))?;
+
+    let mut bounds = Vec::new();
+    let mut start = from;
+    // Counting from the first date keeps month ends from drifting, e.g. Jan 31, Feb 28, Mar 31
+    for index in 1.. {
+        let end = first
+            .checked_add_months(Months::new(months * index))
+            .ok_or_else(|| format!("Reporting window ending at {to} is out of range"))?;
+        let end = (end.timestamp() as u64).min(to);
+        bounds.push((start, end));
+        if end == to {
+            break;
+        }
+        start = end;
+    }
+    Ok(bounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::IncomeReceipt;
+    use crate::models::ScholarOverride;
+
+    // 2025-01-01 and 2025-07-01, UTC
+    const JAN_1: u64 = 1_735_689_600;
+    const JUL_1: u64 = 1_751_328_000;
+
+    fn receipt(
+        kind: ReceiptKind,
+        amount: f64,
+        received_at: u64,
+    ) -> IncomeReceipt {
+        IncomeReceipt {
+            kind,
+            amount,
+            received_at,
+        }
+    }
+
+    fn request(method: PurificationMethod) -> PurificationRequest {
+        PurificationRequest {
+            token: None,
+            holding_value: 10_000.0,
+            impermissible_ratio: Some(0.04),
+            scholar_override: None,
+            receipts: vec![
+                receipt(ReceiptKind::Dividend, 100.0, JAN_1 + 86_400),
+                receipt(ReceiptKind::StakingReward, 50.0, JAN_1 + 86_400 * 100),
+                receipt(ReceiptKind::StakingReward, 30.0, JAN_1 + 86_400 * 120),
+                receipt(ReceiptKind::CapitalGain, 2_000.0, JAN_1 + 86_400 * 150),
+            ],
+            annual_earnings_yield: Some(0.05),
+            method,
+            period: PurificationPeriod::Quarterly,
+            from: JAN_1,
+            to: JUL_1,
+        }
+    }
+
+    #[test]
+    fn test_distributions_are_purified_per_quarter() {
+        let result = purify(&request(PurificationMethod::Distributions), Some(0.5)).unwrap();
+
+        assert_eq!(result.methodology.ratio_source, RatioSource::Request);
+        assert_eq!(result.periods.len(), 2);
+        assert_eq!(result.periods[0].end, 1_743_465_600); // 2025-04-01
+        assert!((result.periods[0].amount - 4.0).abs() < 1e-9);
+        // The capital gain is left alone; both staking rewards fall in the second quarter
+        assert_eq!(result.periods[1].lines.len(), 1);
+        assert_eq!(result.periods[1].lines[0].description, "4.00% of 80.00 in staking rewards");
+        assert!((result.total - 7.2).abs() < 1e-9);
+
+        let with_gains = purify(&request(PurificationMethod::DistributionsAndGains), None).unwrap();
+        assert!((with_gains.total - 87.2).abs() < 1e-9);
+        assert_eq!(with_gains.methodology.references.len(), 3);
+    }
+
+    #[test]
+    fn test_attributable_earnings_and_scholar_override() {
+        let mut earnings = request(PurificationMethod::AttributableEarnings);
+        earnings.scholar_override = Some(ScholarOverride {
+            scholar: "Sheikh Example".to_owned(),
+            ratio: 0.1,
+            note: None,
+        });
+
+        let result = purify(&earnings, Some(0.02)).unwrap();
+        assert_eq!(result.methodology.ratio_source, RatioSource::ScholarOverride);
+        assert_eq!(result.methodology.scholar.as_deref(), Some("Sheikh Example"));
+        // 181 days of 5% earnings on 10,000 plus the staking rewards, without the dividend
+        let expected = (10_000.0 * 0.05 * 181.0 / 365.0 + 80.0) * 0.1;
+        assert!((result.total - expected).abs() < 1e-9);
+        assert!(
+            result.periods[0]
+                .lines
+                .iter()
+                .all(|line| line.kind != Some(ReceiptKind::Dividend))
+        );
+
+        earnings.annual_earnings_yield = None;
+        assert!(purify(&earnings, None).unwrap_err().contains("annual earnings yield"));
+
+        let mut unscreened = request(PurificationMethod::Distributions);
+        unscreened.impermissible_ratio = None;
+        assert!(purify(&unscreened, None).is_err());
+        assert_eq!(purify(&unscreened, Some(0.02)).unwrap().methodology.ratio_source, RatioSource::Screening);
+    }
+}
@@ -0,0 +1,415 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tracing::debug;
+use tracing::error;
+
+use super::rules::load_from_env;
+use super::storage::mint_key;
+use crate::models::RatioCheck;
+use crate::models::RatioScreening;
+use crate::models::RatioStatus;
+
+/// Environment variable pointing at a TOML file of extra `[[methodologies]]` entries
+pub const METHODOLOGIES_FILE_ENV: &str = "AVERROES_SCREENING_METHODOLOGIES";
+/// Environment variable naming the methodology to screen with
+pub const METHODOLOGY_ENV: &str = "AVERROES_SCREENING_METHODOLOGY";
+/// Environment variable naming a directory of `<address>.json` / `<SYMBOL>.json` project financials
+pub const PROJECT_DATA_DIR_ENV: &str = "AVERROES_PROJECT_DATA_DIR";
+
+pub const DEFAULT_METHODOLOGY: &str = "aaoifi";
+
+const BUILTIN_METHODOLOGIES: &str = include_str!("methodologies.toml");
+
+/// What debt and cash holdings are measured against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Denominator {
+    MarketCap,
+    TotalAssets,
+}
+
+impl Denominator {
+    fn label(self) -> &'static str {
+        match self {
+            Denominator::MarketCap => "market cap",
+            Denominator::TotalAssets => "total assets",
+        }
+    }
+}
+
+/// Ratio limits of one screening standard or index methodology
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScreeningMethodology {
+    pub name: String,
+    pub title: String,
+    pub denominator: Denominator,
+    pub max_interest_bearing_debt: f64,
+    pub max_cash_and_interest_securities: f64,
+    pub max_impermissible_income: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct MethodologiesFile {
+    #[serde(default)]
+    methodologies: Vec<ScreeningMethodology>,
+}
+
+/// Treasury and revenue figures reported by a project, in USD
+///
+/// Anything left out makes the ratios that need it unavailable rather than passing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProjectFinancials {
+    #[serde(default)]
+    pub market_cap_usd: Option<f64>,
+    #[serde(default)]
+    pub total_assets_usd: Option<f64>,
+    #[serde(default)]
+    pub interest_bearing_debt_usd: Option<f64>,
+    /// Cash plus interest-bearing deposits and securities held by the treasury
+    #[serde(default)]
+    pub cash_and_interest_securities_usd: Option<f64>,
+    #[serde(default)]
+    pub total_revenue_usd: Option<f64>,
+    /// Interest income and revenue from prohibited activities
+    #[serde(default)]
+    pub impermissible_revenue_usd: Option<f64>,
+    /// e.g. "2024-Q4"
+    #[serde(default)]
+    pub reporting_period: Option<String>,
+    /// Where the figures come from, e.g. a treasury report URL
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+impl ScreeningMethodology {
+    /// Parse `[[methodologies]]` entries from TOML
+    pub fn parse_all(raw: &str) -> Result<Vec<Self>, String> {
+        let file: MethodologiesFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        for methodology in &file.methodologies {
+            let limits = [
+                methodology.max_interest_bearing_debt,
+                methodology.max_cash_and_interest_securities,
+                methodology.max_impermissible_income,
+            ];
+            if limits.iter().any(|limit| !(0.0..=1.0).contains(limit)) {
+                return Err(format!("Methodology '{}': ratio limits must be between 0 and 1", methodology.name));
+            }
+        }
+
+        Ok(file.methodologies)
+    }
+
+    /// The bundled methodologies
+    pub fn builtin() -> Vec<Self> {
+        Self::parse_all(BUILTIN_METHODOLOGIES).expect("Bundled methodologies must be valid")
+    }
+
+    pub fn named(name: &str) -> Option<Self> {
+        Self::builtin().into_iter().find(|methodology| methodology.name == name)
+    }
+
+    /// The methodology named by `AVERROES_SCREENING_METHODOLOGY`, looking in
+    /// `AVERROES_SCREENING_METHODOLOGIES` before the bundled ones
+    pub fn from_env() -> Result<Self, String> {
+        let mut available = Self::builtin();
+        for methodology in load_from_env(METHODOLOGIES_FILE_ENV, Self::parse_all, Vec::new)? {
+            available.retain(|existing| existing.name != methodology.name);
+            available.push(methodology);
+        }
+
+        let name = std::env::var(METHODOLOGY_ENV)
+            .ok()
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| DEFAULT_METHODOLOGY.to_owned());
+        available
+            .into_iter()
+            .find(|methodology| methodology.name == name)
+            .ok_or_else(|| format!("Unknown screening methodology '{name}'"))
+    }
+
+    /// Check each ratio of `financials` against this methodology's limits
+    pub fn screen(
+        &self,
+        financials: &ProjectFinancials,
+    ) -> RatioScreening {
+        let denominator = match self.denominator {
+            Denominator::MarketCap => financials.market_cap_usd,
+            Denominator::TotalAssets => financials.total_assets_usd,
+        };
+
+        let ratios = vec![
+            check(
+                "interest_bearing_debt",
+                format!("Interest-bearing debt / {}", self.denominator.label()),
+                financials.interest_bearing_debt_usd,
+                denominator,
+                self.max_interest_bearing_debt,
+            ),
+            check(
+                "cash_and_interest_securities",
+                format!("Cash and interest-bearing securities / {}", self.denominator.label()),
+                financials.cash_and_interest_securities_usd,
+                denominator,
+                self.max_cash_and_interest_securities,
+            ),
+            check(
+                "impermissible_income",
+                "Impermissible income / total revenue".to_owned(),
+                financials.impermissible_revenue_usd,
+                financials.total_revenue_usd,
+                self.max_impermissible_income,
+            ),
+        ];
+
+        let status = if ratios.iter().any(|ratio| ratio.status == RatioStatus::Fail) {
+            RatioStatus::Fail
+        } else if ratios.iter().any(|ratio| ratio.status == RatioStatus::Unavailable) {
+            RatioStatus::Unavailable
+        } else {
+            RatioStatus::Pass
+        };
+
+        RatioScreening {
+            methodology: self.name.clone(),
+            ratios,
+            status,
+            reporting_period: financials.reporting_period.clone(),
+        }
+    }
+}
+
+impl ProjectFinancials {
+    pub fn from_json(raw: &str) -> Result<Self, String> {
+        serde_json::from_str(raw).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::from_json(&raw).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Financials for a token in `dir`, keyed by contract address first and upper-case symbol second
+    pub fn find(
+        dir: &Path,
+        address: &str,
+        symbol: &str,
+    ) -> Result<Option<Self>, String> {
+        project_file(dir, address, symbol).map(|path| Self::load(&path)).transpose()
+    }
+
+    /// Financials from `AVERROES_PROJECT_DATA_DIR`, or `None` when it is unset or has no entry
+    pub fn from_env(
+        address: &str,
+        symbol: &str,
+    ) -> Result<Option<Self>, String> {
+        match std::env::var(PROJECT_DATA_DIR_ENV) {
+            Ok(dir) if !dir.is_empty() => Self::find(Path::new(&dir), address, symbol),
+            _ => Ok(None),
+        }
+    }
+
+    /// Use the market's valuation when the project did not report one
+    pub fn with_market_cap_fallback(
+        mut self,
+        market_cap_usd: Option<f64>,
+    ) -> Self {
+        if self.market_cap_usd.is_none() {
+            self.market_cap_usd = market_cap_usd.filter(|cap| *cap > 0.0);
+        }
+        self
+    }
+
+    /// Impermissible revenue over total revenue, the share of income a holder purifies
+    pub fn impermissible_income_ratio(&self) -> Option<f64> {
+        match (self.impermissible_revenue_usd, self.total_revenue_usd) {
+            (Some(impermissible), _) if impermissible <= 0.0 => Some(0.0),
+            (Some(impermissible), Some(total)) if total > 0.0 => Some((impermissible / total).min(1.0)),
+            _ => None,
+        }
+    }
+}
+
+/// The file describing a token's project in `dir`: `<mint>.json` when `address` is a mint, `<SYMBOL>.json` otherwise
+///
+/// A token with a mint never falls back to its symbol, since anyone can mint a token under any symbol. Symbols that
+/// could name a path outside `dir` are refused.
+pub fn project_file(
+    dir: &Path,
+    address: &str,
+    symbol: &str,
+) -> Option<PathBuf> {
+    let key = match mint_key(address) {
+        Some(mint) => mint,
+        None if is_plain_symbol(symbol) => symbol.to_uppercase(),
+        None => return None,
+    };
+    Some(dir.join(format!("{key}.json"))).filter(|path| path.is_file())
+}
+
+fn is_plain_symbol(symbol: &str) -> bool {
+    !symbol.is_empty() && !symbol.contains(['/', '\\']) && !symbol.contains("..")
+}
+
+/// Read a token's project file from `AVERROES_PROJECT_DATA_DIR`, or `None` when it is unset or has no entry
+///
+/// The same file carries the financials and the inputs of the other measured screens, each reading its own fields.
+pub fn project_data_from_env<T: DeserializeOwned>(
+    address: &str,
+    symbol: &str,
+) -> Result<Option<T>, String> {
+    let dir = match std::env::var(PROJECT_DATA_DIR_ENV) {
+        Ok(dir) if !dir.is_empty() => dir,
+        _ => return Ok(None),
+    };
+    let Some(path) = project_file(Path::new(&dir), address, symbol) else {
+        return Ok(None);
+    };
+
+    let raw = std::fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    serde_json::from_str(&raw)
+        .map(Some)
+        .map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Screen a token's project with the configured methodology, when financials are on file
+pub fn screen_project(
+    address: &str,
+    symbol: &str,
+    market_cap_usd: Option<f64>,
+) -> Option<RatioScreening> {
+    let financials = match ProjectFinancials::from_env(address, symbol) {
+        Ok(Some(financials)) => financials.with_market_cap_fallback(market_cap_usd),
+        Ok(None) => return None,
+        Err(e) => {
+            error!("Failed to load project financials for {}: {}", symbol, e);
+            return None;
+        },
+    };
+
+    let methodology = ScreeningMethodology::from_env().unwrap_or_else(|e| {
+        error!("{}, screening with {}", e, DEFAULT_METHODOLOGY);
+        ScreeningMethodology::named(DEFAULT_METHODOLOGY).expect("Default methodology must be bundled")
+    });
+
+    let screening = methodology.screen(&financials);
+    debug!("Ratio screening of {} with {}: {:?}", symbol, methodology.name, screening.status);
+    Some(screening)
+}
+
+fn check(
+    name: &str,
+    description: String,
+    numerator: Option<f64>,
+    denominator: Option<f64>,
+    threshold: f64,
+) -> RatioCheck {
+    let value = match (numerator, denominator) {
+        // Nothing owed or earned is a zero ratio whatever the denominator
+        (Some(numerator), _) if numerator <= 0.0 => Some(0.0),
+        (Some(numerator), Some(denominator)) if denominator > 0.0 => Some(numerator / denominator),
+        _ => None,
+    };
+
+    RatioCheck {
+        name: name.to_owned(),
+        description,
+        value,
+        threshold,
+        status: match value {
+            None => RatioStatus::Unavailable,
+            Some(value) if value <= threshold => RatioStatus::Pass,
+            Some(_) => RatioStatus::Fail,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn treasury() -> ProjectFinancials {
+        ProjectFinancials::from_json(
+            r#"{
+                "total_assets_usd": 50000000,
+                "interest_bearing_debt_usd": 31000000,
+                "cash_and_interest_securities_usd": 12000000,
+                "total_revenue_usd": 8000000,
+                "impermissible_revenue_usd": 240000,
+                "reporting_period": "2024-Q4"
+            }"#,
+        )
+        .unwrap()
+        .with_market_cap_fallback(Some(100_000_000.0))
+    }
+
+    fn statuses(screening: &RatioScreening) -> Vec<RatioStatus> {
+        screening.ratios.iter().map(|ratio| ratio.status).collect()
+    }
+
+    #[test]
+    fn test_thresholds_differ_per_methodology() {
+        let aaoifi = ScreeningMethodology::named("aaoifi").unwrap().screen(&treasury());
+        assert_eq!(statuses(&aaoifi), vec![RatioStatus::Fail, RatioStatus::Pass, RatioStatus::Pass]);
+        assert_eq!(aaoifi.status, RatioStatus::Fail);
+        assert_eq!(aaoifi.ratios[0].value, Some(0.31));
+        assert_eq!(aaoifi.ratios[0].description, "Interest-bearing debt / market cap");
+        assert_eq!(aaoifi.ratios[2].value, Some(0.03));
+        assert_eq!(aaoifi.reporting_period.as_deref(), Some("2024-Q4"));
+
+        let djim = ScreeningMethodology::named("djim").unwrap().screen(&treasury());
+        assert_eq!(djim.status, RatioStatus::Pass);
+
+        // Measured against total assets the same debt is far over the limit
+        let msci = ScreeningMethodology::named("msci").unwrap().screen(&treasury());
+        assert_eq!(msci.ratios[0].value, Some(0.62));
+        assert_eq!(msci.status, RatioStatus::Fail);
+    }
+
+    #[test]
+    fn test_missing_figures_are_unavailable_not_passing() {
+        let financials = ProjectFinancials {
+            interest_bearing_debt_usd: Some(0.0),
+            cash_and_interest_securities_usd: Some(2_000_000.0),
+            ..Default::default()
+        };
+        let screening = ScreeningMethodology::named("aaoifi").unwrap().screen(&financials);
+
+        assert_eq!(statuses(&screening), vec![RatioStatus::Pass, RatioStatus::Unavailable, RatioStatus::Unavailable]);
+        assert_eq!(screening.status, RatioStatus::Unavailable);
+
+        let invalid = "[[methodologies]]\nname = \"x\"\ntitle = \"x\"\ndenominator = \
+                       \"market_cap\"\nmax_interest_bearing_debt = 30\nmax_cash_and_interest_securities = \
+                       0.3\nmax_impermissible_income = 0.05\n";
+        assert!(
+            ScreeningMethodology::parse_all(invalid)
+                .unwrap_err()
+                .contains("between 0 and 1")
+        );
+    }
+
+    #[test]
+    fn test_project_file_is_keyed_by_mint_before_symbol() {
+        let dir = tempfile::tempdir().unwrap();
+        let mint = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+        std::fs::write(dir.path().join("USDC.json"), "{}").unwrap();
+
+        // A token without a mint is found by its symbol
+        assert_eq!(project_file(dir.path(), "", "usdc"), Some(dir.path().join("USDC.json")));
+        // A mint without its own file does not borrow the file of whichever token uses the symbol
+        assert_eq!(project_file(dir.path(), mint, "USDC"), None);
+        std::fs::write(dir.path().join(format!("{mint}.json")), "{}").unwrap();
+        assert_eq!(project_file(dir.path(), mint, "USDC"), Some(dir.path().join(format!("{mint}.json"))));
+
+        std::fs::create_dir_all(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("nested").join("X.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("..json"), "{}").unwrap();
+        for symbol in ["nested/X", "nested\\X", "..", "../USDC"] {
+            assert_eq!(project_file(dir.path(), "", symbol), None, "{symbol}");
+        }
+    }
+}
@@ -0,0 +1,471 @@
+//! Scholar review of AI rulings: reviewer accounts and an append-only audit log of their decisions.
+//!
+//! Every decision is written once under the next sequence number and never changed or removed. A
+//! token's most recent approval or override decides whether an override is in force.
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serde::Serialize;
+use sled::Db;
+use sled::Tree;
+use tracing::error;
+use uuid::Uuid;
+
+use super::rules::load_from_env;
+use super::rules::load_or_else;
+use super::storage::db_path;
+use super::storage::mint_key;
+use super::storage::open_db;
+use super::storage::open_or_in_memory;
+use crate::models::DeterminationState;
+use crate::models::IslamicPrinciple;
+use crate::models::ReviewAction;
+use crate::models::ReviewDecision;
+use crate::models::RulingDetermination;
+use crate::models::RulingEvidence;
+
+/// Environment variable with the path of the review database; an in-memory one is used when unset
+pub const REVIEW_DB_ENV: &str = "AVERROES_REVIEW_DB";
+
+/// Environment variable pointing at a TOML file of reviewer accounts; nobody can review when unset
+pub const REVIEWERS_FILE_ENV: &str = "AVERROES_REVIEWERS_FILE";
+
+const MIN_ACCESS_TOKEN_LEN: usize = 16;
+
+lazy_static! {
+    /// Audit log at `AVERROES_REVIEW_DB`, opened once
+    static ref SHARED: ReviewLog = open_or_in_memory("review", ReviewLog::from_env, || ReviewLog::open(None));
+
+    /// Accounts from `AVERROES_REVIEWERS_FILE`
+    static ref ACCOUNTS: ReviewerRegistry =
+        load_or_else("reviewer accounts", ReviewerRegistry::from_env, ReviewerRegistry::default);
+}
+
+/// Why a review was not recorded
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ReviewError {
+    #[error("Account '{0}' does not have the scholar role")]
+    NotScholar(String),
+
+    #[error("Invalid review: {0}")]
+    Invalid(String),
+
+    #[error("Review storage error: {0}")]
+    Storage(String),
+}
+
+/// What an account may do
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountRole {
+    Member,  // Reads reviews
+    Scholar, // Approves, annotates and overrides rulings
+}
+
+/// An account as configured in the reviewers file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewerAccount {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub affiliation: Option<String>,
+    #[serde(skip_serializing)]
+    pub access_token: String, // Sent as `Authorization: Bearer <access_token>`
+    #[serde(default)]
+    pub roles: Vec<AccountRole>,
+}
+
+impl ReviewerAccount {
+    pub fn is_scholar(&self) -> bool {
+        self.roles.contains(&AccountRole::Scholar)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewerFile {
+    #[serde(default)]
+    accounts: Vec<ReviewerAccount>,
+}
+
+/// Accounts that may sign in to review rulings
+#[derive(Debug, Clone, Default)]
+pub struct ReviewerRegistry {
+    accounts: Vec<ReviewerAccount>,
+}
+
+impl ReviewerRegistry {
+    /// Parse and validate `[[accounts]]` from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let file: ReviewerFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        for (index, account) in file.accounts.iter().enumerate() {
+            if account.id.trim().is_empty() || account.name.trim().is_empty() {
+                return Err(format!("Account {index}: id and name must not be empty"));
+            }
+            if account.access_token.len() < MIN_ACCESS_TOKEN_LEN {
+                return Err(format!(
+                    "Account '{}': access_token must be at least {MIN_ACCESS_TOKEN_LEN} characters",
+                    account.id
+                ));
+            }
+            let earlier = &file.accounts[..index];
+            if earlier.iter().any(|other| other.id == account.id) {
+                return Err(format!("Duplicate account id '{}'", account.id));
+            }
+            if earlier.iter().any(|other| other.access_token == account.access_token) {
+                return Err(format!("Account '{}' reuses another account's access_token", account.id));
+            }
+        }
+
+        Ok(Self {
+            accounts: file.accounts,
+        })
+    }
+
+    /// Accounts from `AVERROES_REVIEWERS_FILE`, or none when unset
+    pub fn from_env() -> Result<Self, String> {
+        load_from_env(REVIEWERS_FILE_ENV, Self::from_toml, Self::default)
+    }
+
+    /// The process-wide registry
+    pub fn shared() -> Self {
+        ACCOUNTS.clone()
+    }
+
+    /// Account holding `access_token`, compared in constant time
+    pub fn authenticate(
+        &self,
+        access_token: &str,
+    ) -> Option<&ReviewerAccount> {
+        self.accounts
+            .iter()
+            .find(|account| constant_time_eq(account.access_token.as_bytes(), access_token.as_bytes()))
+    }
+}
+
+/// A review as submitted by a scholar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewSubmission {
+    pub token: String, // Mint address
+    #[serde(default)]
+    pub analysis_id: Option<String>, // Analysis the review responds to
+    pub action: ReviewAction,
+    #[serde(default)]
+    pub ruling: Option<IslamicPrinciple>, // Required to approve or override
+    pub rationale: String,
+    #[serde(default)]
+    pub references: Vec<String>,
+}
+
+/// Review decisions persisted in sled, in the order they were made
+#[derive(Clone)]
+pub struct ReviewLog {
+    db: Db,
+    decisions: Tree, // big-endian sequence -> decision JSON, written once
+    by_token: Tree,  // "<token>\0" and big-endian sequence -> empty
+}
+
+impl ReviewLog {
+    /// Database at `path`, or an in-memory one
+    pub fn open(path: Option<&str>) -> Result<Self, String> {
+        let db = open_db(path)?;
+        let tree = |name: &str| db.open_tree(name).map_err(|e| e.to_string());
+
+        Ok(Self {
+            decisions: tree("review_decisions")?,
+            by_token: tree("review_by_token")?,
+            db,
+        })
+    }
+
+    /// Database at `AVERROES_REVIEW_DB`, or an in-memory one when unset
+    pub fn from_env() -> Result<Self, String> {
+        Self::open(db_path(REVIEW_DB_ENV).as_deref())
+    }
+
+    /// The process-wide audit log
+    pub fn shared() -> Self {
+        SHARED.clone()
+    }
+
+    /// Validate a scholar's submission and append it to the log
+    pub fn record(
+        &self,
+        account: &ReviewerAccount,
+        submission: ReviewSubmission,
+    ) -> Result<ReviewDecision, ReviewError> {
+        if !account.is_scholar() {
+            return Err(ReviewError::NotScholar(account.id.clone()));
+        }
+        let token = mint_key(&submission.token)
+            .ok_or_else(|| ReviewError::Invalid("token must be a mint address".to_owned()))?;
+        let rationale = submission.rationale.trim().to_owned();
+        if rationale.is_empty() {
+            return Err(ReviewError::Invalid("rationale must not be empty".to_owned()));
+        }
+        let references = submission
+            .references
+            .iter()
+            .map(|reference| reference.trim().to_owned())
+            .filter(|reference| !reference.is_empty())
+            .collect::<Vec<_>>();
+        if references.is_empty() {
+            return Err(ReviewError::Invalid("at least one reference is required".to_owned()));
+        }
+        if submission.ruling.is_none() && submission.action != ReviewAction::Annotate {
+            return Err(ReviewError::Invalid(format!("a ruling is required to {:?}", submission.action)));
+        }
+
+        let storage = |e: sled::Error| ReviewError::Storage(e.to_string());
+        let sequence = self.db.generate_id().map_err(storage)?;
+        let decision = ReviewDecision {
+            id: Uuid::new_v4().to_string(),
+            sequence,
+            token: token.clone(),
+            analysis_id: submission.analysis_id,
+            action: submission.action,
+            ruling: submission.ruling,
+            scholar_id: account.id.clone(),
+            scholar_name: account.name.clone(),
+            rationale,
+            references,
+            decided_at: Utc::now().timestamp() as u64,
+        };
+        let json = serde_json::to_vec(&decision).map_err(|e| ReviewError::Storage(e.to_string()))?;
+
+        // Never overwrite: a sequence already in the log is an error, not a replacement
+        self.decisions
+            .compare_and_swap(sequence.to_be_bytes(), None as Option<&[u8]>, Some(json))
+            .map_err(storage)?
+            .map_err(|_| ReviewError::Storage(format!("Audit log entry {sequence} already exists")))?;
+        self.by_token.insert(token_index_key(&token, sequence), &[]).map_err(storage)?;
+        self.db.flush().map_err(storage)?;
+
+        Ok(decision)
+    }
+
+    /// Decisions about a mint, oldest first; none for anything that is not a mint address
+    pub fn history(
+        &self,
+        mint: &str,
+    ) -> Result<Vec<ReviewDecision>, String> {
+        let Some(mint) = mint_key(mint) else {
+            return Ok(Vec::new());
+        };
+        let prefix = format!("{mint}\0");
+        self.by_token
+            .scan_prefix(prefix.as_bytes())
+            .map(|entry| {
+                let (key, _) = entry.map_err(|e| e.to_string())?;
+                self.get(&key[prefix.len()..])
+            })
+            .collect()
+    }
+
+    /// Up to `limit` decisions made after sequence `after`, oldest first
+    pub fn log(
+        &self,
+        after: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<ReviewDecision>, String> {
+        let start = after.map(|sequence| sequence.saturating_add(1)).unwrap_or(0);
+        self.decisions
+            .range(start.to_be_bytes()..)
+            .take(limit)
+            .map(|entry| {
+                let (_, value) = entry.map_err(|e| e.to_string())?;
+                serde_json::from_slice(&value).map_err(|e| e.to_string())
+            })
+            .collect()
+    }
+
+    /// The override in force for a mint: its latest approval or override, when it is an override
+    pub fn override_for(
+        &self,
+        mint: &str,
+    ) -> Result<Option<ReviewDecision>, String> {
+        Ok(self
+            .history(mint)?
+            .into_iter()
+            .rev()
+            .find(|decision| decision.action != ReviewAction::Annotate)
+            .filter(|decision| decision.action == ReviewAction::Override))
+    }
+
+    fn get(
+        &self,
+        sequence: &[u8],
+    ) -> Result<ReviewDecision, String> {
+        let value = self
+            .decisions
+            .get(sequence)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Review index points at a missing decision".to_owned())?;
+        serde_json::from_slice(&value).map_err(|e| e.to_string())
+    }
+}
+
+/// Make a scholar's override ruling the determined one, whatever the evidence leaned towards or abstained on
+pub fn apply_override(
+    determination: &mut RulingDetermination,
+    review: &ReviewDecision,
+) {
+    let Some(ruling) = review.ruling.clone() else {
+        return;
+    };
+    determination.evidence.push(RulingEvidence {
+        source: format!("scholar:{}", review.scholar_id),
+        ruling: ruling.clone(),
+        confidence: 1.0,
+        decisive: true,
+    });
+    determination.state = DeterminationState::Determined;
+    determination.proposed = Some(ruling.clone());
+    determination.ruling = ruling;
+    determination.confidence = 1.0;
+    determination.abstentions.clear();
+}
+
+fn token_index_key(
+    token: &str,
+    sequence: u64,
+) -> Vec<u8> {
+    let mut key = format!("{token}\0").into_bytes();
+    key.extend_from_slice(&sequence.to_be_bytes());
+    key
+}
+
+fn constant_time_eq(
+    a: &[u8],
+    b: &[u8],
+) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0_u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINT: &str = "So11111111111111111111111111111111111111112";
+
+    const REVIEWERS: &str = r#"
+        [[accounts]]
+        id = "ustadz-ahmad"
+        name = "Ustadz Ahmad"
+        affiliation = "DSN-MUI"
+        access_token = "scholar-token-0123456789"
+        roles = ["scholar"]
+
+        [[accounts]]
+        id = "member-budi"
+        name = "Budi"
+        access_token = "member-token-0123456789"
+        roles = ["member"]
+    "#;
+
+    fn submission(
+        token: &str,
+        action: ReviewAction,
+        ruling: Option<IslamicPrinciple>,
+    ) -> ReviewSubmission {
+        ReviewSubmission {
+            token: token.to_owned(),
+            analysis_id: None,
+            action,
+            ruling,
+            rationale: "Staking rewards are fixed returns on a loan".to_owned(),
+            references: vec!["Fatwa DSN-MUI No. 116/2017".to_owned()],
+        }
+    }
+
+    #[test]
+    fn test_only_authenticated_scholars_record_complete_reviews() {
+        let registry = ReviewerRegistry::from_toml(REVIEWERS).unwrap();
+        let log = ReviewLog::open(None).unwrap();
+
+        assert!(registry.authenticate("wrong-token-0123456789").is_none());
+        let member = registry.authenticate("member-token-0123456789").unwrap();
+        let result = log.record(member, submission(MINT, ReviewAction::Override, Some(IslamicPrinciple::Riba)));
+        assert!(matches!(result, Err(ReviewError::NotScholar(_))));
+
+        let scholar = registry.authenticate("scholar-token-0123456789").unwrap();
+        let missing_references = ReviewSubmission {
+            references: vec![" ".to_owned()],
+            ..submission(MINT, ReviewAction::Annotate, None)
+        };
+        assert!(matches!(log.record(scholar, missing_references), Err(ReviewError::Invalid(_))));
+        let missing_ruling = submission(MINT, ReviewAction::Override, None);
+        assert!(matches!(log.record(scholar, missing_ruling), Err(ReviewError::Invalid(_))));
+        // A symbol names every token minted under it, so it cannot be reviewed
+        let by_symbol = submission("SOL", ReviewAction::Annotate, None);
+        assert!(matches!(log.record(scholar, by_symbol), Err(ReviewError::Invalid(_))));
+
+        let decision = log
+            .record(scholar, submission(&format!(" {MINT} "), ReviewAction::Annotate, None))
+            .unwrap();
+        assert_eq!(decision.token, MINT);
+        assert_eq!(decision.scholar_name, "Ustadz Ahmad");
+        assert_eq!(log.log(None, 10).unwrap(), vec![decision]);
+
+        assert!(
+            ReviewerRegistry::from_toml("[[accounts]]\nid = \"a\"\nname = \"A\"\naccess_token = \"short\"").is_err()
+        );
+    }
+
+    #[test]
+    fn test_latest_approval_or_override_decides_the_ruling() {
+        let registry = ReviewerRegistry::from_toml(REVIEWERS).unwrap();
+        let scholar = registry.authenticate("scholar-token-0123456789").unwrap();
+        let log = ReviewLog::open(None).unwrap();
+
+        let first = log
+            .record(scholar, submission(MINT, ReviewAction::Override, Some(IslamicPrinciple::Riba)))
+            .unwrap();
+        log.record(scholar, submission(MINT, ReviewAction::Annotate, None)).unwrap();
+        assert_eq!(log.override_for(MINT).unwrap(), Some(first.clone()));
+        assert_eq!(log.override_for("SOL").unwrap(), None);
+
+        // Approving the AI ruling lifts the override, but keeps it in the history
+        log.record(scholar, submission(MINT, ReviewAction::Approve, Some(IslamicPrinciple::Halal)))
+            .unwrap();
+        assert_eq!(log.override_for(MINT).unwrap(), None);
+        assert_eq!(log.history(MINT).unwrap()[0], first);
+
+        let entries = log.log(None, 10).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries.windows(2).all(|pair| pair[0].sequence < pair[1].sequence));
+        assert_eq!(log.log(Some(first.sequence), 10).unwrap()[..], entries[1..]);
+    }
+
+    #[test]
+    fn test_override_determines_a_syubhat_ruling() {
+        let registry = ReviewerRegistry::from_toml(REVIEWERS).unwrap();
+        let scholar = registry.authenticate("scholar-token-0123456789").unwrap();
+        let review = ReviewLog::open(None)
+            .unwrap()
+            .record(scholar, submission(MINT, ReviewAction::Override, Some(IslamicPrinciple::Haram)))
+            .unwrap();
+        let mut determination = RulingDetermination {
+            state: DeterminationState::Syubhat,
+            ruling: IslamicPrinciple::Syubhat,
+            proposed: Some(IslamicPrinciple::Halal),
+            confidence: 0.4,
+            evidence: Vec::new(),
+            abstentions: vec!["Bukti belum cukup".to_owned()],
+        };
+
+        apply_override(&mut determination, &review);
+        assert_eq!(determination.state, DeterminationState::Determined);
+        assert_eq!(determination.ruling, IslamicPrinciple::Haram);
+        assert_eq!(determination.proposed, Some(IslamicPrinciple::Haram));
+        assert_eq!(determination.confidence, 1.0);
+        assert!(determination.abstentions.is_empty());
+        assert_eq!(determination.evidence, vec![RulingEvidence {
+            source: "scholar:ustadz-ahmad".to_owned(),
+            ruling: IslamicPrinciple::Haram,
+            confidence: 1.0,
+            decisive: true,
+        }]);
+    }
+}
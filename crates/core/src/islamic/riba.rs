@@ -0,0 +1,353 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::ratios::project_data_from_env;
+use super::rules::Condition;
+use super::rules::TokenFacts;
+use super::rules::evaluate_conditions;
+use super::rules::load_from_env;
+use super::rules::validate_conditions;
+use crate::models::RibaAnalysis;
+use crate::models::RibaFinding;
+use crate::models::RibaKind;
+use crate::models::TokenExtension;
+use crate::models::YieldSource;
+use crate::models::token::UniversalTokenInfo as TokenInfo;
+
+/// Environment variable pointing at a TOML file that replaces the bundled protocols and signals
+pub const RIBA_FILE_ENV: &str = "AVERROES_RIBA_FILE";
+
+const BUILTIN_DETECTOR: &str = include_str!("riba.toml");
+
+/// Lending protocol whose receipt tokens carry interest to depositors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LendingProtocol {
+    pub name: String,
+    #[serde(default)]
+    pub evidence: Vec<String>,
+    /// Programs that mint the protocol's receipt tokens
+    #[serde(default)]
+    pub program_ids: Vec<String>,
+    #[serde(default)]
+    pub all: Vec<Condition>,
+    #[serde(default)]
+    pub any: Vec<Condition>,
+    #[serde(default)]
+    pub none: Vec<Condition>,
+}
+
+/// Riba exposure described in a token's own text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RibaSignal {
+    pub id: String,
+    pub kind: RibaKind,
+    pub explanation: String,
+    #[serde(default)]
+    pub evidence: Vec<String>,
+    #[serde(default)]
+    pub all: Vec<Condition>,
+    #[serde(default)]
+    pub any: Vec<Condition>,
+    #[serde(default)]
+    pub none: Vec<Condition>,
+}
+
+/// How holding the token pays a yield
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum YieldMechanism {
+    Rebasing,     // Balances grow
+    ExchangeRate, // Each token redeems for more of the underlying over time
+    Staking,      // Rewards for staking the token
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct YieldShare {
+    pub source: YieldSource,
+    pub share: f64, // 0-1 of the yield
+}
+
+/// On-chain and reported facts about how a token earns, read from its project file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RibaInputs {
+    #[serde(default)]
+    pub mint_authority: Option<String>,
+    /// Rate of a Token-2022 interest-bearing extension, in basis points a year
+    #[serde(default)]
+    pub interest_rate_bps: Option<i16>,
+    #[serde(default)]
+    pub yield_mechanism: Option<YieldMechanism>,
+    #[serde(default)]
+    pub yield_sources: Vec<YieldShare>,
+}
+
+impl RibaInputs {
+    /// Inputs from the token's project file in `AVERROES_PROJECT_DATA_DIR`
+    pub fn from_env(
+        address: &str,
+        symbol: &str,
+    ) -> Result<Option<Self>, String> {
+        project_data_from_env(address, symbol)
+    }
+
+    /// Fill the interest rate the project file left out from the mint's interest-bearing extension
+    pub fn with_token(
+        mut self,
+        token_info: &TokenInfo,
+    ) -> Self {
+        if self.interest_rate_bps.is_none() {
+            self.interest_rate_bps = token_info.metadata.extensions.iter().find_map(|extension| match extension {
+                TokenExtension::InterestBearingConfig {
+                    rate_bps, ..
+                } => Some(*rate_bps),
+                _ => None,
+            });
+        }
+        self
+    }
+
+    fn lending_share(&self) -> f64 {
+        self.yield_sources
+            .iter()
+            .filter(|yield_share| yield_share.source == YieldSource::Lending)
+            .map(|yield_share| yield_share.share)
+            .sum()
+    }
+
+    /// The source paying the largest share of the yield
+    fn main_source(&self) -> Option<YieldSource> {
+        self.yield_sources
+            .iter()
+            .max_by(|a, b| a.share.total_cmp(&b.share))
+            .map(|yield_share| yield_share.source)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DetectorFile {
+    #[serde(default)]
+    protocols: Vec<LendingProtocol>,
+    #[serde(default)]
+    signals: Vec<RibaSignal>,
+}
+
+/// Classifies how a token exposes its holder to riba
+#[derive(Debug, Clone)]
+pub struct RibaDetector {
+    protocols: Vec<LendingProtocol>,
+    signals: Vec<RibaSignal>,
+}
+
+impl RibaDetector {
+    pub fn new(
+        protocols: Vec<LendingProtocol>,
+        signals: Vec<RibaSignal>,
+    ) -> Self {
+        Self {
+            protocols,
+            signals,
+        }
+    }
+
+    /// Parse and validate `[[protocols]]` and `[[signals]]` from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let file: DetectorFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        for protocol in &file.protocols {
+            if protocol.program_ids.is_empty() || !protocol.all.is_empty() || !protocol.any.is_empty() {
+                validate_conditions(
+                    &format!("Protocol '{}'", protocol.name),
+                    &protocol.all,
+                    &protocol.any,
+                    &protocol.none,
+                )?;
+            }
+        }
+        for signal in &file.signals {
+            validate_conditions(&format!("Signal '{}'", signal.id), &signal.all, &signal.any, &signal.none)?;
+        }
+
+        Ok(Self::new(file.protocols, file.signals))
+    }
+
+    /// The bundled protocols and signals
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_DETECTOR).expect("Bundled riba detector must be valid")
+    }
+
+    /// Protocols and signals from `AVERROES_RIBA_FILE`, or the bundled ones when unset
+    pub fn from_env() -> Result<Self, String> {
+        load_from_env(RIBA_FILE_ENV, Self::from_toml, Self::builtin)
+    }
+
+    /// Structured findings from the token's mint, yield and description; text signals only add kinds
+    /// the structured data did not already establish
+    pub fn detect(
+        &self,
+        facts: &TokenFacts,
+        inputs: &RibaInputs,
+    ) -> RibaAnalysis {
+        let mut findings = Vec::new();
+
+        if let Some(finding) = self.lending_receipt(facts, inputs) {
+            findings.push(finding);
+        }
+
+        if let Some(rate) = inputs.interest_rate_bps.filter(|rate| *rate != 0) {
+            findings.push(RibaFinding {
+                kind: RibaKind::InterestBearingMint,
+                protocol: None,
+                observed: format!("Token-2022 interest-bearing extension at {:.2}% a year", f64::from(rate) / 100.0),
+                explanation: "The mint accrues its stated rate on every balance, which is interest by design."
+                    .to_owned(),
+                references: vec!["Quran 2:275 - Allah has permitted trade and forbidden riba".to_owned()],
+            });
+        }
+
+        let lending_share = inputs.lending_share();
+        if lending_share > 0.0 {
+            match inputs.yield_mechanism {
+                Some(YieldMechanism::Rebasing | YieldMechanism::ExchangeRate) => findings.push(RibaFinding {
+                    kind: RibaKind::RebasingLendingYield,
+                    protocol: None,
+                    observed: format!("{:.0}% of the yield comes from lending", lending_share * 100.0),
+                    explanation: "Holders' balances grow with interest paid by borrowers.".to_owned(),
+                    references: vec!["Quran 2:278-279 - give up what remains of riba".to_owned()],
+                }),
+                Some(YieldMechanism::Staking) => findings.push(RibaFinding {
+                    kind: RibaKind::StakingFromLending,
+                    protocol: None,
+                    observed: format!("{:.0}% of staking rewards come from lending", lending_share * 100.0),
+                    explanation: "Rewards paid from lending income are interest, not payment for validation."
+                        .to_owned(),
+                    references: vec![
+                        "Legal maxim: al-kharaj bil-daman (entitlement to yield follows liability for loss)".to_owned(),
+                    ],
+                }),
+                None => {},
+            }
+        }
+
+        for signal in &self.signals {
+            if findings.iter().any(|finding| finding.kind == signal.kind) {
+                continue;
+            }
+            if let Some(matched) = evaluate_conditions(&signal.all, &signal.any, &signal.none, facts) {
+                findings.push(RibaFinding {
+                    kind: signal.kind,
+                    protocol: None,
+                    observed: matched.join("; "),
+                    explanation: signal.explanation.clone(),
+                    references: signal.evidence.clone(),
+                });
+            }
+        }
+
+        RibaAnalysis {
+            findings,
+            staking_yield_source: match inputs.yield_mechanism {
+                Some(YieldMechanism::Staking) => inputs.main_source(),
+                _ => None,
+            },
+        }
+    }
+
+    fn lending_receipt(
+        &self,
+        facts: &TokenFacts,
+        inputs: &RibaInputs,
+    ) -> Option<RibaFinding> {
+        self.protocols.iter().find_map(|protocol| {
+            let observed = match &inputs.mint_authority {
+                Some(authority) if protocol.program_ids.contains(authority) => {
+                    format!("Minted by {} program {authority}", protocol.name)
+                },
+                _ if protocol.all.is_empty() && protocol.any.is_empty() => return None,
+                _ => evaluate_conditions(&protocol.all, &protocol.any, &protocol.none, facts)?.join("; "),
+            };
+
+            Some(RibaFinding {
+                kind: RibaKind::LendingReceipt,
+                protocol: Some(protocol.name.clone()),
+                observed,
+                explanation: format!(
+                    "The token is a receipt for a deposit lent out through {}; it grows with the interest borrowers \
+                     pay.",
+                    protocol.name
+                ),
+                references: protocol.evidence.clone(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(raw: &str) -> RibaInputs {
+        serde_json::from_str(raw).unwrap()
+    }
+
+    #[test]
+    fn test_receipts_and_interest_bearing_mints_are_typed() {
+        let detector = RibaDetector::builtin();
+
+        let receipt = TokenFacts::new().text("text", "Solend USDC cToken representing a deposit in the main pool");
+        let analysis = detector.detect(&receipt, &RibaInputs::default());
+        assert_eq!(analysis.findings.len(), 1);
+        assert_eq!(analysis.findings[0].kind, RibaKind::LendingReceipt);
+        assert_eq!(analysis.findings[0].protocol.as_deref(), Some("Solend"));
+        assert_eq!(analysis.findings[0].observed, "text contains \"solend\"; text contains \"ctoken\"");
+
+        let minted = RibaDetector::new(
+            vec![LendingProtocol {
+                name: "Example Lend".to_owned(),
+                evidence: vec![],
+                program_ids: vec!["Lend111".to_owned()],
+                all: vec![],
+                any: vec![],
+                none: vec![],
+            }],
+            vec![],
+        );
+        let inputs = RibaInputs {
+            mint_authority: Some("Lend111".to_owned()),
+            interest_rate_bps: Some(500),
+            ..Default::default()
+        };
+        let analysis = minted.detect(&TokenFacts::new(), &inputs);
+        let kinds = analysis.findings.iter().map(|finding| finding.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![RibaKind::LendingReceipt, RibaKind::InterestBearingMint]);
+        assert_eq!(analysis.findings[1].observed, "Token-2022 interest-bearing extension at 5.00% a year");
+    }
+
+    #[test]
+    fn test_staking_yield_source_decides_riba() {
+        let detector = RibaDetector::builtin();
+        let facts = TokenFacts::new().text("text", "Liquid staking token for Solana validators");
+
+        let validation = inputs(
+            r#"{"yield_mechanism": "Staking", "yield_sources": [
+                {"source": "Validation", "share": 0.85}, {"source": "Mev", "share": 0.15}
+            ]}"#,
+        );
+        let analysis = detector.detect(&facts, &validation);
+        assert!(analysis.findings.is_empty());
+        assert_eq!(analysis.staking_yield_source, Some(YieldSource::Validation));
+
+        let lending = inputs(
+            r#"{"yield_mechanism": "Staking", "yield_sources": [
+                {"source": "Validation", "share": 0.4}, {"source": "Lending", "share": 0.6}
+            ]}"#,
+        );
+        let analysis = detector.detect(&facts, &lending);
+        assert_eq!(analysis.findings[0].kind, RibaKind::StakingFromLending);
+        assert_eq!(analysis.findings[0].observed, "60% of staking rewards come from lending");
+        assert_eq!(analysis.staking_yield_source, Some(YieldSource::Lending));
+
+        // Described but unreported, the rebasing yield still surfaces from the text
+        let rebasing = TokenFacts::new().text("text", "Rebasing stablecoin earning yield from money market loans");
+        let analysis = detector.detect(&rebasing, &RibaInputs::default());
+        assert_eq!(analysis.findings[0].kind, RibaKind::RebasingLendingYield);
+    }
+}
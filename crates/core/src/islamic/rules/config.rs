@@ -0,0 +1,64 @@
+//! Loading the bundled TOML configuration that an environment variable can point elsewhere.
+
+use std::fmt::Display;
+
+use tracing::error;
+
+/// Parse the file named by `env` with `parse`, or use `builtin` when the variable is unset or empty
+///
+/// Read and parse errors are prefixed with the file's path.
+pub fn load_from_env<T, E: Display>(
+    env: &str,
+    parse: impl FnOnce(&str) -> Result<T, E>,
+    builtin: impl FnOnce() -> T,
+) -> Result<T, String> {
+    match std::env::var(env) {
+        Ok(path) if !path.is_empty() => load_file(&path, parse),
+        _ => Ok(builtin()),
+    }
+}
+
+fn load_file<T, E: Display>(
+    path: &str,
+    parse: impl FnOnce(&str) -> Result<T, E>,
+) -> Result<T, String> {
+    let raw = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    parse(&raw).map_err(|e| format!("{path}: {e}"))
+}
+
+/// The result of `load`, or `fallback` after logging why `what` could not be loaded
+pub fn load_or_else<T, E: Display>(
+    what: &str,
+    load: impl FnOnce() -> Result<T, E>,
+    fallback: impl FnOnce() -> T,
+) -> T {
+    load().unwrap_or_else(|e| {
+        error!("Failed to load {}, using the defaults: {}", what, e);
+        fallback()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &str) -> Result<u32, String> {
+        raw.trim().parse().map_err(|e| format!("invalid number: {e}"))
+    }
+
+    #[test]
+    fn test_config_loads_from_the_named_file_or_falls_back() {
+        assert_eq!(load_from_env("AVERROES_UNSET_TEST_FILE", parse, || 7), Ok(7));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let path_str = path.display().to_string();
+        std::fs::write(&path, "42\n").unwrap();
+        assert_eq!(load_file(&path_str, parse), Ok(42));
+
+        std::fs::write(&path, "many").unwrap();
+        let error = load_file(&path_str, parse).unwrap_err();
+        assert!(error.starts_with(&format!("{path_str}: invalid number")));
+        assert_eq!(load_or_else("test config", || load_file(&path_str, parse), || 3), 3);
+    }
+}
@@ -0,0 +1,323 @@
+use std::path::Path;
+
+use tracing::debug;
+
+use super::facts::TokenFacts;
+use super::pack::Rule;
+use super::pack::RulePack;
+use super::pack::Scoring;
+use crate::models::IslamicPrinciple;
+use crate::models::analysis::RuleHit;
+
+/// Environment variable naming a directory of extra `*.toml` rule packs
+pub const RULES_DIR_ENV: &str = "AVERROES_RULES_DIR";
+
+/// Pack used when none is configured
+const BUILTIN_PACK: &str = include_str!("core.toml");
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum RuleError {
+    #[error("Rule pack error: {0}")]
+    Pack(String),
+
+    #[error("Cannot read rules directory {path}: {message}")]
+    Directory {
+        path: String,
+        message: String,
+    },
+}
+
+/// Outcome of screening one token, with the rules that produced it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreeningResult {
+    pub ruling: IslamicPrinciple,
+    /// Compliance score in 0..=1 after all weights are applied
+    pub score: f64,
+    /// Rules that fired, in evaluation order
+    pub hits: Vec<RuleHit>,
+    pub rules_evaluated: usize,
+}
+
+impl ScreeningResult {
+    /// One line per fired rule, e.g.
+    /// `[maysir.gambling] Gambling or games of chance (Maysir, -0.60): text contains "casino"`
+    pub fn explain(&self) -> Vec<String> {
+        self.hits
+            .iter()
+            .map(|hit| {
+                format!(
+                    "[{}] {} ({:?}, {:+.2}): {}",
+                    hit.rule_id,
+                    hit.title,
+                    hit.principle,
+                    hit.weight,
+                    hit.matched.join("; ")
+                )
+            })
+            .collect()
+    }
+
+    /// Evidence cited by the fired rules, without duplicates
+    pub fn evidence(&self) -> Vec<String> {
+        let mut evidence: Vec<String> = Vec::new();
+        for reference in self.hits.iter().flat_map(|hit| &hit.evidence) {
+            if !evidence.contains(reference) {
+                evidence.push(reference.clone());
+            }
+        }
+        evidence
+    }
+}
+
+/// Evaluates token facts against one or more rule packs
+#[derive(Debug, Clone)]
+pub struct RuleEngine {
+    /// (pack name, rule) in evaluation order
+    rules: Vec<(String, Rule)>,
+    scoring: Scoring,
+}
+
+impl RuleEngine {
+    /// Combine packs in order; a rule whose id is already present replaces the earlier one
+    pub fn new(packs: Vec<RulePack>) -> Self {
+        let mut engine = Self {
+            rules: Vec::new(),
+            scoring: Scoring::default(),
+        };
+        for pack in packs {
+            engine.add_pack(pack);
+        }
+        engine
+    }
+
+    /// Only the bundled pack
+    pub fn builtin() -> Self {
+        Self::new(vec![RulePack::from_toml(BUILTIN_PACK).expect("Bundled rule pack must be valid")])
+    }
+
+    /// The bundled pack followed by every `*.toml` pack in `dir`, in file name order
+    pub fn with_dir(dir: &Path) -> Result<Self, RuleError> {
+        let directory_error = |e: std::io::Error| RuleError::Directory {
+            path: dir.display().to_string(),
+            message: e.to_string(),
+        };
+
+        let mut paths = std::fs::read_dir(dir)
+            .map_err(directory_error)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(directory_error)?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "toml"));
+        paths.sort();
+
+        let mut engine = Self::builtin();
+        for path in paths {
+            engine.add_pack(RulePack::load(&path).map_err(RuleError::Pack)?);
+        }
+        Ok(engine)
+    }
+
+    /// Packs from `AVERROES_RULES_DIR` on top of the bundled one, or just the bundled one when unset
+    pub fn from_env() -> Result<Self, RuleError> {
+        match std::env::var(RULES_DIR_ENV) {
+            Ok(dir) if !dir.is_empty() => Self::with_dir(Path::new(&dir)),
+            _ => Ok(Self::builtin()),
+        }
+    }
+
+    pub fn add_pack(
+        &mut self,
+        pack: RulePack,
+    ) {
+        debug!("Loading rule pack '{}' with {} rules", pack.pack.name, pack.rules.len());
+
+        if let Some(scoring) = pack.scoring {
+            self.scoring = scoring;
+        }
+        for rule in pack.rules {
+            match self.rules.iter_mut().find(|(_, existing)| existing.id == rule.id) {
+                Some(slot) => *slot = (pack.pack.name.clone(), rule),
+                None => self.rules.push((pack.pack.name.clone(), rule)),
+            }
+        }
+    }
+
+    pub fn scoring(&self) -> Scoring {
+        self.scoring
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Run every rule against `facts` and derive a ruling from the ones that fire
+    pub fn evaluate(
+        &self,
+        facts: &TokenFacts,
+    ) -> ScreeningResult {
+        let hits = self
+            .rules
+            .iter()
+            .filter_map(|(pack, rule)| {
+                let matched = rule.evaluate(facts)?;
+                Some(RuleHit {
+                    pack: pack.clone(),
+                    rule_id: rule.id.clone(),
+                    title: rule.title.clone(),
+                    principle: rule.principle.clone(),
+                    weight: rule.weight,
+                    decisive: rule.decisive,
+                    matched,
+                    evidence: rule.evidence.clone(),
+                    explanation: rule.explanation.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let score = (self.scoring.base_score + hits.iter().map(|hit| hit.weight).sum::<f64>()).clamp(0.0, 1.0);
+
+        ScreeningResult {
+            ruling: self.ruling(score, &hits),
+            score,
+            hits,
+            rules_evaluated: self.rules.len(),
+        }
+    }
+
+    fn ruling(
+        &self,
+        score: f64,
+        hits: &[RuleHit],
+    ) -> IslamicPrinciple {
+        let heaviest = |decisive_only: bool| {
+            hits.iter()
+                .filter(|hit| hit.weight < 0.0 && (hit.decisive || !decisive_only))
+                .min_by(|a, b| a.weight.total_cmp(&b.weight))
+                .map(|hit| prohibition(&hit.principle))
+        };
+
+        if let Some(principle) = heaviest(true) {
+            principle
+        } else if score >= self.scoring.halal_threshold {
+            IslamicPrinciple::Halal
+        } else if score <= self.scoring.haram_threshold {
+            heaviest(false).unwrap_or(IslamicPrinciple::Haram)
+        } else {
+            // Between the thresholds the evidence is mixed or thin
+            IslamicPrinciple::Syubhat
+        }
+    }
+}
+
+/// A negative rule tagged with a permissive principle still means a prohibition
+fn prohibition(principle: &IslamicPrinciple) -> IslamicPrinciple {
+    match principle {
+        IslamicPrinciple::Halal | IslamicPrinciple::Mubah | IslamicPrinciple::Mustahab => IslamicPrinciple::Haram,
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn description(text: &str) -> TokenFacts {
+        TokenFacts::new().text("text", text)
+    }
+
+    #[test]
+    fn test_builtin_pack_screens_and_explains() {
+        let engine = RuleEngine::builtin();
+
+        let casino = engine.evaluate(&description("Provably fair casino token with daily jackpot"));
+        assert_eq!(casino.ruling, IslamicPrinciple::Maysir);
+        assert_eq!(casino.hits[0].rule_id, "maysir.gambling");
+        assert_eq!(casino.hits[0].matched, vec!["text contains \"casino\""]);
+        assert!(casino.evidence().iter().any(|reference| reference.starts_with("Quran 5:90")));
+
+        let lending = engine.evaluate(&description("DeFi lending protocol paying fixed interest"));
+        assert_eq!(lending.ruling, IslamicPrinciple::Riba);
+
+        let utility = engine.evaluate(&description("Utility token for decentralized storage payments"));
+        assert_eq!(utility.ruling, IslamicPrinciple::Halal);
+        assert_eq!(utility.explain(), vec![
+            "[utility.network_access] Network utility (Halal, +0.20): text contains \"utility\"".to_owned()
+        ]);
+
+        // Gaming alone is neither halal nor haram; "interesting" is not "interest"
+        let game = engine.evaluate(&description("An interesting gaming community token"));
+        assert!(game.hits.is_empty());
+        assert_eq!(game.ruling, IslamicPrinciple::Syubhat);
+    }
+
+    #[test]
+    fn test_later_pack_overrides_rules_and_scoring() {
+        let scholar_pack = RulePack::from_toml(
+            r#"
+            [pack]
+            name = "board-review"
+            format_version = 1
+
+            [scoring]
+            base_score = 0.5
+            halal_threshold = 0.8
+            haram_threshold = 0.2
+
+            [[rules]]
+            id = "utility.network_access"
+            title = "Network utility, unverified"
+            principle = "Halal"
+            weight = 0.1
+            all = [
+                { fact = "text", contains_any = ["utility"] },
+                { fact = "verified", is = true },
+            ]
+
+            [[rules]]
+            id = "gharar.thin_liquidity"
+            title = "Thin liquidity"
+            principle = "Gharar"
+            weight = -0.15
+            evidence = ["Sahih Muslim 1513"]
+            any = [{ fact = "liquidity_usd", lt = 10000.0 }]
+            "#,
+        )
+        .unwrap();
+
+        let mut engine = RuleEngine::builtin();
+        let builtin_rules = engine.rule_count();
+        engine.add_pack(scholar_pack);
+        assert_eq!(engine.rule_count(), builtin_rules + 1);
+
+        let facts = description("Utility token")
+            .flag("verified", true)
+            .number("liquidity_usd", 2500.0);
+        let result = engine.evaluate(&facts);
+
+        assert_eq!(result.ruling, IslamicPrinciple::Syubhat);
+        assert!((result.score - 0.45).abs() < 1e-9);
+        assert_eq!(result.hits[0].pack, "board-review");
+        assert_eq!(result.hits[0].matched, vec!["text contains \"utility\"", "verified is true"]);
+        assert_eq!(result.hits[1].matched, vec!["liquidity_usd = 2500 < 10000"]);
+    }
+
+    #[test]
+    fn test_invalid_packs_are_rejected() {
+        let no_operator = r#"
+            [pack]
+            name = "broken"
+            format_version = 1
+
+            [[rules]]
+            id = "x"
+            title = "x"
+            principle = "Haram"
+            weight = -0.2
+            all = [{ fact = "text" }]
+        "#;
+        assert!(RulePack::from_toml(no_operator).unwrap_err().contains("has no operator"));
+
+        let future = "[pack]\nname = \"next\"\nformat_version = 2\n";
+        assert!(RulePack::from_toml(future).unwrap_err().contains("format_version 2"));
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// One observed property of a token
+#[derive(Debug, Clone, PartialEq)]
+pub enum FactValue {
+    Text(String),
+    Number(f64),
+    Flag(bool),
+}
+
+impl fmt::Display for FactValue {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            FactValue::Text(text) => write!(f, "\"{text}\""),
+            FactValue::Number(number) => write!(f, "{number}"),
+            FactValue::Flag(flag) => write!(f, "{flag}"),
+        }
+    }
+}
+
+/// Named facts a rule pack is evaluated against
+///
+/// Missing facts never satisfy a condition, so rules about data we do not have stay silent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenFacts {
+    values: BTreeMap<String, FactValue>,
+}
+
+impl TokenFacts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(
+        mut self,
+        name: &str,
+        value: &str,
+    ) -> Self {
+        self.values.insert(name.to_owned(), FactValue::Text(value.to_owned()));
+        self
+    }
+
+    pub fn number(
+        mut self,
+        name: &str,
+        value: f64,
+    ) -> Self {
+        self.values.insert(name.to_owned(), FactValue::Number(value));
+        self
+    }
+
+    pub fn flag(
+        mut self,
+        name: &str,
+        value: bool,
+    ) -> Self {
+        self.values.insert(name.to_owned(), FactValue::Flag(value));
+        self
+    }
+
+    /// Set a fact only when the value is known
+    pub fn maybe_number(
+        self,
+        name: &str,
+        value: Option<f64>,
+    ) -> Self {
+        match value {
+            Some(value) => self.number(name, value),
+            None => self,
+        }
+    }
+
+    pub fn get(
+        &self,
+        name: &str,
+    ) -> Option<&FactValue> {
+        self.values.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
@@ -0,0 +1,20 @@
+//! Declarative Sharia screening rules.
+//!
+//! Rules live in TOML rule packs rather than in code, so scholars can maintain them without touching Rust.
+//! Each rule names the facts it tests, the [`IslamicPrinciple`](crate::models::IslamicPrinciple) it bears on,
+//! a score weight and its evidence. [`RuleEngine::evaluate`] returns the ruling together with every rule
+//! that fired and why.
+//!
+//! The bundled `core.toml` pack is always loaded first; point `AVERROES_RULES_DIR` at a directory of
+//! `*.toml` packs to extend or override it. The other screening modules load their bundled TOML the same way
+//! through [`load_from_env`].
+
+pub mod config;
+pub mod engine;
+pub mod facts;
+pub mod pack;
+
+pub use config::*;
+pub use engine::*;
+pub use facts::*;
+pub use pack::*;
@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::facts::FactValue;
+use super::facts::TokenFacts;
+use crate::models::IslamicPrinciple;
+
+/// Rule pack format understood by this build
+pub const RULE_PACK_VERSION: u32 = 1;
+
+/// Numeric test applied as `fact <op> bound`
+type Comparison = fn(&f64, &f64) -> bool;
+
+/// A set of screening rules maintained outside the code, loaded from TOML
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulePack {
+    pub pack: PackInfo,
+    /// Overrides the thresholds of packs loaded before this one
+    #[serde(default)]
+    pub scoring: Option<Scoring>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackInfo {
+    pub name: String,
+    pub format_version: u32,
+    #[serde(default)]
+    pub description: String,
+    /// Who maintains the pack, e.g. the reviewing scholar or board
+    #[serde(default)]
+    pub maintainer: Option<String>,
+}
+
+/// How rule weights turn into a ruling
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Scoring {
+    /// Score before any rule fires
+    pub base_score: f64,
+    /// At or above this the token is screened halal
+    pub halal_threshold: f64,
+    /// At or below this the token takes the principle of the heaviest negative rule
+    pub haram_threshold: f64,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            base_score: 0.5,
+            halal_threshold: 0.6,
+            haram_threshold: 0.2,
+        }
+    }
+}
+
+/// One screening rule: when its conditions hold, `weight` is added to the score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    /// Stable id, e.g. `maysir.gambling`; a later pack with the same id replaces the rule
+    pub id: String,
+    pub title: String,
+    pub principle: IslamicPrinciple,
+    pub weight: f64,
+    /// Firing settles the ruling as `principle` regardless of the score
+    #[serde(default)]
+    pub decisive: bool,
+    /// Quran, hadith, standards or fatwas the rule rests on
+    #[serde(default)]
+    pub evidence: Vec<String>,
+    #[serde(default)]
+    pub explanation: String,
+    /// Every condition must hold
+    #[serde(default)]
+    pub all: Vec<Condition>,
+    /// At least one condition must hold, when any are given
+    #[serde(default)]
+    pub any: Vec<Condition>,
+    /// No condition may hold
+    #[serde(default)]
+    pub none: Vec<Condition>,
+}
+
+/// A test on one fact; when several operators are set they must all hold
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Condition {
+    pub fact: String,
+    /// Text contains one of these words or phrases, case-insensitively and on word boundaries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contains_any: Vec<String>,
+    /// Text equals one of these, case-insensitively
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub one_of: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gt: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gte: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lt: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lte: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is: Option<bool>,
+}
+
+impl RulePack {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::from_toml(&raw).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Parse and validate a pack from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let pack: RulePack = toml::from_str(raw).map_err(|e| e.to_string())?;
+        pack.validate()?;
+        Ok(pack)
+    }
+
+    /// Check version, ids, weights and conditions so mistakes surface at load time rather than mid-screening
+    pub fn validate(&self) -> Result<(), String> {
+        if self.pack.format_version != RULE_PACK_VERSION {
+            return Err(format!(
+                "Unsupported rule pack format_version {} (expected {RULE_PACK_VERSION})",
+                self.pack.format_version
+            ));
+        }
+
+        if let Some(scoring) = &self.scoring {
+            let ordered = 0.0 <= scoring.haram_threshold
+                && scoring.haram_threshold < scoring.halal_threshold
+                && scoring.halal_threshold <= 1.0;
+            if !ordered {
+                return Err("Scoring thresholds must satisfy 0 <= haram_threshold < halal_threshold <= 1".to_owned());
+            }
+        }
+
+        let mut ids = HashSet::new();
+        for rule in &self.rules {
+            if !ids.insert(rule.id.as_str()) {
+                return Err(format!("Duplicate rule id '{}'", rule.id));
+            }
+            if !rule.weight.is_finite() || rule.weight.abs() > 1.0 {
+                return Err(format!("Rule '{}': weight must be between -1 and 1", rule.id));
+            }
+            validate_conditions(&format!("Rule '{}'", rule.id), &rule.all, &rule.any, &rule.none)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Rule {
+    /// Descriptions of the conditions that made the rule fire, or `None` when it does not fire
+    pub fn evaluate(
+        &self,
+        facts: &TokenFacts,
+    ) -> Option<Vec<String>> {
+        evaluate_conditions(&self.all, &self.any, &self.none, facts)
+    }
+}
+
+/// Check that `all`, `any` and `none` conditions can fire and each has an operator; `owner` prefixes errors
+pub fn validate_conditions(
+    owner: &str,
+    all: &[Condition],
+    any: &[Condition],
+    none: &[Condition],
+) -> Result<(), String> {
+    if all.is_empty() && any.is_empty() {
+        return Err(format!("{owner} needs at least one 'all' or 'any' condition"));
+    }
+    for condition in all.iter().chain(any).chain(none) {
+        if !condition.has_operator() {
+            return Err(format!("{owner}: condition on '{}' has no operator", condition.fact));
+        }
+    }
+    Ok(())
+}
+
+/// Every `all` condition, one `any` condition when given, and no `none` condition must hold;
+/// returns the descriptions of the conditions that matched
+pub fn evaluate_conditions(
+    all: &[Condition],
+    any: &[Condition],
+    none: &[Condition],
+    facts: &TokenFacts,
+) -> Option<Vec<String>> {
+    let mut matched = all
+        .iter()
+        .map(|condition| condition.evaluate(facts))
+        .collect::<Option<Vec<_>>>()?;
+
+    if !any.is_empty() {
+        matched.push(any.iter().find_map(|condition| condition.evaluate(facts))?);
+    }
+
+    if none.iter().any(|condition| condition.evaluate(facts).is_some()) {
+        return None;
+    }
+
+    Some(matched)
+}
+
+impl Condition {
+    fn has_operator(&self) -> bool {
+        !self.contains_any.is_empty()
+            || !self.one_of.is_empty()
+            || self.gt.is_some()
+            || self.gte.is_some()
+            || self.lt.is_some()
+            || self.lte.is_some()
+            || self.is.is_some()
+    }
+
+    /// A readable account of why the condition holds, or `None` when it does not
+    pub fn evaluate(
+        &self,
+        facts: &TokenFacts,
+    ) -> Option<String> {
+        let value = facts.get(&self.fact)?;
+        let mut reasons = Vec::new();
+
+        if !self.contains_any.is_empty() {
+            let FactValue::Text(text) = value else {
+                return None;
+            };
+            let term = self.contains_any.iter().find(|term| contains_word(text, term))?;
+            reasons.push(format!("{} contains \"{term}\"", self.fact));
+        }
+
+        if !self.one_of.is_empty() {
+            let FactValue::Text(text) = value else {
+                return None;
+            };
+            let option = self.one_of.iter().find(|option| option.eq_ignore_ascii_case(text.trim()))?;
+            reasons.push(format!("{} is \"{option}\"", self.fact));
+        }
+
+        let comparisons: [(Option<f64>, &str, Comparison); 4] = [
+            (self.gt, ">", f64::gt),
+            (self.gte, ">=", f64::ge),
+            (self.lt, "<", f64::lt),
+            (self.lte, "<=", f64::le),
+        ];
+        for (bound, operator, holds) in comparisons {
+            let Some(bound) = bound else {
+                continue;
+            };
+            let FactValue::Number(number) = value else {
+                return None;
+            };
+            if !holds(number, &bound) {
+                return None;
+            }
+            reasons.push(format!("{} = {number} {operator} {bound}", self.fact));
+        }
+
+        if let Some(expected) = self.is {
+            if *value != FactValue::Flag(expected) {
+                return None;
+            }
+            reasons.push(format!("{} is {expected}", self.fact));
+        }
+
+        Some(reasons.join(" and "))
+    }
+}
+
+/// Whether `term` occurs in `text` as a whole word or phrase, ignoring case
+pub fn contains_word(
+    text: &str,
+    term: &str,
+) -> bool {
+    let text = text.to_lowercase();
+    let term = term.to_lowercase();
+    if term.is_empty() {
+        return false;
+    }
+
+    text.match_indices(&term).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + term.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
@@ -0,0 +1,288 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::ratios::ProjectFinancials;
+use super::ratios::ScreeningMethodology;
+use super::rules::PackInfo;
+use super::rules::RULE_PACK_VERSION;
+use super::rules::Rule;
+use super::rules::RuleEngine;
+use super::rules::RulePack;
+use super::rules::TokenFacts;
+use super::rules::load_from_env;
+use crate::models::IslamicPrinciple;
+use crate::models::Madhab;
+use crate::models::MethodologyRuling;
+use crate::models::RatioStatus;
+use crate::models::ShariaPreferences;
+use crate::models::ShariahStandard;
+use crate::models::analysis::RuleHit;
+
+/// Environment variable pointing at a TOML file that replaces the bundled positions
+pub const SCHOOLS_FILE_ENV: &str = "AVERROES_SCHOOLS_FILE";
+
+const BUILTIN_SCHOOLS: &str = include_str!("schools.toml");
+
+/// Where screening standards and madhabs depart from the core rules
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchoolPositions {
+    #[serde(default)]
+    pub standards: Vec<StandardPositions>,
+    #[serde(default)]
+    pub madhabs: Vec<MadhabPositions>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StandardPositions {
+    pub standard: ShariahStandard,
+    pub title: String,
+    /// Name of the financial-ratio methodology this body screens with
+    #[serde(default)]
+    pub ratio_methodology: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MadhabPositions {
+    pub madhab: Madhab,
+    pub title: String,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl SchoolPositions {
+    /// Parse and validate positions from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let positions: SchoolPositions = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        for standard in &positions.standards {
+            standard.pack().validate().map_err(|e| format!("{}: {e}", standard.title))?;
+            if let Some(name) = &standard.ratio_methodology {
+                if ScreeningMethodology::named(name).is_none() {
+                    return Err(format!("{}: unknown ratio methodology '{name}'", standard.title));
+                }
+            }
+        }
+        for madhab in &positions.madhabs {
+            madhab.pack().validate().map_err(|e| format!("{}: {e}", madhab.title))?;
+        }
+
+        Ok(positions)
+    }
+
+    /// The bundled positions
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_SCHOOLS).expect("Bundled school positions must be valid")
+    }
+
+    /// Positions from `AVERROES_SCHOOLS_FILE`, or the bundled ones when unset
+    pub fn from_env() -> Result<Self, String> {
+        load_from_env(SCHOOLS_FILE_ENV, Self::from_toml, Self::builtin)
+    }
+
+    pub fn standard(
+        &self,
+        standard: ShariahStandard,
+    ) -> Option<&StandardPositions> {
+        self.standards.iter().find(|positions| positions.standard == standard)
+    }
+
+    pub fn madhab(
+        &self,
+        madhab: Madhab,
+    ) -> Option<&MadhabPositions> {
+        self.madhabs.iter().find(|positions| positions.madhab == madhab)
+    }
+
+    /// One ruling per standard in `preferences`, each with `engine`'s rules overlaid by the standard's
+    /// positions and then the madhab's
+    pub fn rulings(
+        &self,
+        engine: &RuleEngine,
+        facts: &TokenFacts,
+        financials: Option<&ProjectFinancials>,
+        preferences: &ShariaPreferences,
+    ) -> Vec<MethodologyRuling> {
+        let baseline = engine.evaluate(facts);
+        let madhab = preferences.madhab.and_then(|madhab| self.madhab(madhab));
+
+        preferences
+            .standards
+            .iter()
+            .filter_map(|standard| self.standard(*standard))
+            .map(|standard| {
+                let mut overlay = engine.clone();
+                let mut packs = vec![standard.pack()];
+                packs.extend(madhab.map(MadhabPositions::pack));
+                for pack in &packs {
+                    overlay.add_pack(pack.clone());
+                }
+
+                let result = overlay.evaluate(facts);
+                let mut ruling = result.ruling.clone();
+                let mut basis = position_basis(&packs, &baseline.hits, &result.hits);
+
+                let ratio_screening = standard
+                    .ratio_methodology
+                    .as_deref()
+                    .and_then(ScreeningMethodology::named)
+                    .zip(financials)
+                    .map(|(methodology, financials)| methodology.screen(financials));
+                if let Some(screening) = &ratio_screening {
+                    if screening.status == RatioStatus::Fail {
+                        basis.push(format!("Financial ratios fail the {} limits", screening.methodology));
+                        if ruling.permits() {
+                            ruling = IslamicPrinciple::Haram;
+                        }
+                    }
+                }
+
+                MethodologyRuling {
+                    standard: standard.standard,
+                    madhab: madhab.map(|madhab| madhab.madhab),
+                    diverges: ruling != baseline.ruling,
+                    ruling,
+                    compliance_score: result.score,
+                    basis,
+                    ratio_status: ratio_screening.map(|screening| screening.status),
+                }
+            })
+            .collect()
+    }
+}
+
+impl StandardPositions {
+    fn pack(&self) -> RulePack {
+        overlay_pack(&format!("{:?}", self.standard), &self.title, &self.rules)
+    }
+}
+
+impl MadhabPositions {
+    fn pack(&self) -> RulePack {
+        overlay_pack(&format!("{:?}", self.madhab), &self.title, &self.rules)
+    }
+}
+
+fn overlay_pack(
+    name: &str,
+    title: &str,
+    rules: &[Rule],
+) -> RulePack {
+    RulePack {
+        pack: PackInfo {
+            name: name.to_owned(),
+            format_version: RULE_PACK_VERSION,
+            description: title.to_owned(),
+            maintainer: None,
+        },
+        scoring: None,
+        rules: rules.to_vec(),
+    }
+}
+
+/// Positions from `packs` that fired, and baseline rules they displaced without firing themselves
+fn position_basis(
+    packs: &[RulePack],
+    baseline: &[RuleHit],
+    hits: &[RuleHit],
+) -> Vec<String> {
+    let mut basis = Vec::new();
+
+    for hit in hits {
+        if let Some(pack) = packs.iter().find(|pack| pack.pack.name == hit.pack) {
+            basis.push(format!(
+                "{}: [{}] {} ({:?}) - {} Evidence: {}",
+                pack.pack.description,
+                hit.rule_id,
+                hit.title,
+                hit.principle,
+                hit.explanation,
+                hit.evidence.join("; ")
+            ));
+        }
+    }
+
+    for hit in baseline {
+        if hits.iter().any(|overlay_hit| overlay_hit.rule_id == hit.rule_id) {
+            continue;
+        }
+        let displaced_by = packs
+            .iter()
+            .rev()
+            .find(|pack| pack.rules.iter().any(|rule| rule.id == hit.rule_id));
+        if let Some(pack) = displaced_by {
+            basis.push(format!("{}: [{}] {} does not apply", pack.pack.description, hit.rule_id, hit.title));
+        }
+    }
+
+    basis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn description(text: &str) -> TokenFacts {
+        TokenFacts::new().text("text", text)
+    }
+
+    #[test]
+    fn test_standards_diverge_on_staking_with_basis() {
+        let schools = SchoolPositions::builtin();
+        let engine = RuleEngine::builtin();
+        let facts = description("Utility token for network staking and storage payments");
+
+        let rulings = schools.rulings(&engine, &facts, None, &ShariaPreferences::default());
+        let ruling_for = |standard| rulings.iter().find(|ruling| ruling.standard == standard).unwrap();
+
+        assert_eq!(rulings.len(), 3);
+        assert_eq!(ruling_for(ShariahStandard::Aaoifi).ruling, IslamicPrinciple::Syubhat);
+        assert!(ruling_for(ShariahStandard::Aaoifi).diverges);
+        assert!(ruling_for(ShariahStandard::Aaoifi).basis[0].starts_with("AAOIFI Sharia Standards: [staking.rewards]"));
+
+        assert_eq!(ruling_for(ShariahStandard::SacMalaysia).ruling, IslamicPrinciple::Halal);
+        assert!(!ruling_for(ShariahStandard::SacMalaysia).diverges);
+        assert_eq!(ruling_for(ShariahStandard::SacMalaysia).ratio_status, None);
+    }
+
+    #[test]
+    fn test_madhab_positions_and_failed_ratios_are_cited() {
+        let schools = SchoolPositions::builtin();
+        let engine = RuleEngine::builtin();
+        let facts = description("Fiat-backed stablecoin with audited reserves");
+        let financials = ProjectFinancials {
+            total_assets_usd: Some(100_000_000.0),
+            interest_bearing_debt_usd: Some(40_000_000.0),
+            ..Default::default()
+        };
+        let preferences = ShariaPreferences {
+            madhab: Some(Madhab::Shafii),
+            standards: vec![ShariahStandard::SacMalaysia],
+        };
+
+        let rulings = schools.rulings(&engine, &facts, Some(&financials), &preferences);
+
+        assert_eq!(rulings[0].madhab, Some(Madhab::Shafii));
+        assert_eq!(rulings[0].ratio_status, Some(RatioStatus::Fail));
+        assert_eq!(rulings[0].ruling, IslamicPrinciple::Haram);
+        assert!(rulings[0].diverges);
+        assert!(
+            rulings[0]
+                .basis
+                .iter()
+                .any(|line| line.starts_with("Shafi'i school: [stablecoin.asset_backed]"))
+        );
+        assert_eq!(rulings[0].basis.last().unwrap(), "Financial ratios fail the sac_malaysia limits");
+    }
+
+    #[test]
+    fn test_unknown_ratio_methodology_is_rejected() {
+        let raw = "[[standards]]\nstandard = \"Aaoifi\"\ntitle = \"x\"\nratio_methodology = \"nope\"\n";
+        assert!(
+            SchoolPositions::from_toml(raw)
+                .unwrap_err()
+                .contains("unknown ratio methodology 'nope'")
+        );
+    }
+}
@@ -0,0 +1,471 @@
+//! Bundled Quran and hadith text, so cited evidence resolves to exact wording in the user's language.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::rules::load_from_env;
+use crate::models::ScriptureReference;
+use crate::models::ScriptureSource;
+
+/// Environment variable pointing at a TOML file that replaces the bundled scripture library
+pub const SCRIPTURE_FILE_ENV: &str = "AVERROES_SCRIPTURE_FILE";
+/// Language every entry is translated into, used when the requested one is missing
+pub const DEFAULT_LANGUAGE: &str = "en";
+/// Hadith collections a citation may name; a citation of one the library lacks is unknown, not free text
+pub const HADITH_COLLECTIONS: &[&str] = &[
+    "Sahih al-Bukhari",
+    "Sahih Muslim",
+    "Sunan Abi Dawud",
+    "Jami at-Tirmidhi",
+    "Sunan an-Nasa'i",
+    "Sunan Ibn Majah",
+    "Muwatta Malik",
+    "Musnad Ahmad",
+];
+
+const SURAH_COUNT: u32 = 114;
+
+const BUILTIN_LIBRARY: &str = include_str!("scripture.toml");
+
+/// A parsed "Quran 2:275", "Quran 2:278-279" or "Sahih Muslim 1513"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptureCitation {
+    Quran {
+        surah: u32,
+        first: u32,
+        last: u32,
+    },
+    Hadith {
+        collection: &'static str,
+        number: u32,
+    },
+}
+
+impl ScriptureCitation {
+    /// Parse the citation at the start of an evidence line, before any " - " gloss; `None` when the line cites
+    /// neither the Quran nor a hadith collection
+    pub fn parse(evidence: &str) -> Result<Option<Self>, String> {
+        let citation = evidence
+            .split_once(" - ")
+            .map(|(citation, _)| citation)
+            .unwrap_or(evidence)
+            .trim();
+
+        if let Some(verses) = citation.strip_prefix("Quran ") {
+            let malformed = || format!("'{citation}' is not of the form 'Quran <surah>:<ayah>[-<ayah>]'");
+            let (surah, ayat) = verses.split_once(':').ok_or_else(malformed)?;
+            let (first, last) = ayat.split_once('-').unwrap_or((ayat, ayat));
+            let number = |raw: &str| raw.trim().parse::<u32>().ok().filter(|number| *number > 0);
+            let (Some(surah), Some(first), Some(last)) = (number(surah), number(first), number(last)) else {
+                return Err(malformed());
+            };
+            if surah > SURAH_COUNT || first > last {
+                return Err(malformed());
+            }
+            return Ok(Some(Self::Quran {
+                surah,
+                first,
+                last,
+            }));
+        }
+
+        for collection in HADITH_COLLECTIONS {
+            if let Some(number) = citation.strip_prefix(collection).and_then(|rest| rest.strip_prefix(' ')) {
+                let number = number
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|number| *number > 0)
+                    .ok_or_else(|| format!("'{citation}' is not of the form '{collection} <number>'"))?;
+                return Ok(Some(Self::Hadith {
+                    collection,
+                    number,
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// e.g. "Quran 2:278-279"
+    pub fn canonical(&self) -> String {
+        match self {
+            Self::Quran {
+                surah,
+                first,
+                last,
+            } if first == last => format!("Quran {surah}:{first}"),
+            Self::Quran {
+                surah,
+                first,
+                last,
+            } => format!("Quran {surah}:{first}-{last}"),
+            Self::Hadith {
+                collection,
+                number,
+            } => format!("{collection} {number}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuranVerse {
+    pub surah: u32,
+    pub ayah: u32,
+    pub surah_name: String,
+    pub arabic: String,
+    pub translations: HashMap<String, String>, // Language code to text
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hadith {
+    pub collection: String,
+    pub number: u32,
+    pub narrator: String,
+    pub arabic: String,
+    pub translations: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LibraryInfo {
+    version: String,
+    #[serde(default)]
+    translators: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LibraryFile {
+    library: LibraryInfo,
+    #[serde(default)]
+    quran: Vec<QuranVerse>,
+    #[serde(default)]
+    hadith: Vec<Hadith>,
+}
+
+/// Evidence lines checked against the library
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResolvedEvidence {
+    pub references: Vec<ScriptureReference>, // One per distinct citation, in order of appearance
+    pub rejected: Vec<(String, String)>,     // Evidence line and why it was rejected
+}
+
+/// Versioned store of Quran verses and hadith with their translations
+#[derive(Debug, Clone)]
+pub struct ScriptureLibrary {
+    version: String,
+    translators: HashMap<String, String>,
+    quran: BTreeMap<(u32, u32), QuranVerse>,
+    hadith: BTreeMap<(String, u32), Hadith>,
+}
+
+impl ScriptureLibrary {
+    /// Parse and validate `[library]`, `[[quran]]` and `[[hadith]]` from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let file: LibraryFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        let mut quran = BTreeMap::new();
+        for verse in file.quran {
+            let citation = format!("Quran {}:{}", verse.surah, verse.ayah);
+            if verse.surah == 0 || verse.surah > SURAH_COUNT || verse.ayah == 0 {
+                return Err(format!("{citation} does not exist"));
+            }
+            if !verse.translations.contains_key(DEFAULT_LANGUAGE) {
+                return Err(format!("{citation} has no '{DEFAULT_LANGUAGE}' translation"));
+            }
+            if quran.insert((verse.surah, verse.ayah), verse).is_some() {
+                return Err(format!("{citation} is given more than once"));
+            }
+        }
+
+        let mut hadith = BTreeMap::new();
+        for entry in file.hadith {
+            let citation = format!("{} {}", entry.collection, entry.number);
+            if !HADITH_COLLECTIONS.contains(&entry.collection.as_str()) {
+                return Err(format!("{citation} names an unknown collection"));
+            }
+            if !entry.translations.contains_key(DEFAULT_LANGUAGE) {
+                return Err(format!("{citation} has no '{DEFAULT_LANGUAGE}' translation"));
+            }
+            if hadith.insert((entry.collection.clone(), entry.number), entry).is_some() {
+                return Err(format!("{citation} is given more than once"));
+            }
+        }
+
+        Ok(Self {
+            version: file.library.version,
+            translators: file.library.translators,
+            quran,
+            hadith,
+        })
+    }
+
+    /// The bundled library
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_LIBRARY).expect("Bundled scripture library must be valid")
+    }
+
+    /// Library from `AVERROES_SCRIPTURE_FILE`, or the bundled one when unset
+    pub fn from_env() -> Result<Self, String> {
+        load_from_env(SCRIPTURE_FILE_ENV, Self::from_toml, Self::builtin)
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Text of a parsed citation, translated into `language` when every verse of it is
+    pub fn lookup(
+        &self,
+        citation: &ScriptureCitation,
+        language: &str,
+    ) -> Result<ScriptureReference, String> {
+        let missing = || format!("{} is not in scripture library {}", citation.canonical(), self.version);
+
+        let (source, title, entries) = match *citation {
+            ScriptureCitation::Quran {
+                surah,
+                first,
+                last,
+            } => {
+                let verses = (first..=last)
+                    .map(|ayah| self.quran.get(&(surah, ayah)))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(missing)?;
+                let entries = verses
+                    .iter()
+                    .map(|verse| (&verse.arabic, &verse.translations))
+                    .collect::<Vec<_>>();
+                (ScriptureSource::Quran, verses[0].surah_name.clone(), entries)
+            },
+            ScriptureCitation::Hadith {
+                collection,
+                number,
+            } => {
+                let hadith = self.hadith.get(&(collection.to_owned(), number)).ok_or_else(missing)?;
+                (ScriptureSource::Hadith, hadith.narrator.clone(), vec![(&hadith.arabic, &hadith.translations)])
+            },
+        };
+
+        let language = if entries.iter().all(|(_, translations)| translations.contains_key(language)) {
+            language
+        } else {
+            DEFAULT_LANGUAGE
+        };
+
+        Ok(ScriptureReference {
+            citation: citation.canonical(),
+            source,
+            title,
+            arabic: entries.iter().map(|(arabic, _)| arabic.as_str()).collect::<Vec<_>>().join(" "),
+            translation: entries
+                .iter()
+                .map(|(_, translations)| translations[language].as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            language: language.to_owned(),
+            translator: self.translators.get(language).cloned().unwrap_or_default(),
+            library_version: self.version.clone(),
+        })
+    }
+
+    /// Text of a citation such as "Quran 2:275 - Allah has permitted trade"; malformed and unknown citations, and
+    /// lines citing no scripture, are rejected
+    pub fn resolve(
+        &self,
+        citation: &str,
+        language: &str,
+    ) -> Result<ScriptureReference, String> {
+        match ScriptureCitation::parse(citation)? {
+            Some(parsed) => self.lookup(&parsed, language),
+            None => Err(format!("'{citation}' cites neither the Quran nor a hadith collection")),
+        }
+    }
+
+    /// Resolve the scripture among evidence lines; lines citing no scripture are neither resolved nor rejected
+    pub fn resolve_evidence(
+        &self,
+        evidence: &[String],
+        language: &str,
+    ) -> ResolvedEvidence {
+        let mut resolved = ResolvedEvidence::default();
+        for line in evidence {
+            match ScriptureCitation::parse(line)
+                .and_then(|parsed| parsed.map(|parsed| self.lookup(&parsed, language)).transpose())
+            {
+                Ok(Some(reference)) => {
+                    if !resolved
+                        .references
+                        .iter()
+                        .any(|existing| existing.citation == reference.citation)
+                    {
+                        resolved.references.push(reference);
+                    }
+                },
+                Ok(None) => {},
+                Err(e) => resolved.rejected.push((line.clone(), e)),
+            }
+        }
+        resolved
+    }
+
+    /// Entries whose citation, title, Arabic or translation contain the query's words, best matches first
+    pub fn search(
+        &self,
+        query: &str,
+        language: &str,
+    ) -> Vec<ScriptureReference> {
+        let terms = query.split_whitespace().map(str::to_lowercase).collect::<Vec<_>>();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let citations = self
+            .quran
+            .keys()
+            .map(|(surah, ayah)| ScriptureCitation::Quran {
+                surah: *surah,
+                first: *ayah,
+                last: *ayah,
+            })
+            .chain(self.hadith.keys().filter_map(|(collection, number)| {
+                let collection = HADITH_COLLECTIONS.iter().find(|known| **known == collection)?;
+                Some(ScriptureCitation::Hadith {
+                    collection,
+                    number: *number,
+                })
+            }));
+
+        let mut matches = citations
+            .filter_map(|citation| self.lookup(&citation, language).ok())
+            .filter_map(|reference| {
+                let text = format!(
+                    "{} {} {} {}",
+                    reference.citation, reference.title, reference.arabic, reference.translation
+                )
+                .to_lowercase();
+                let score = terms.iter().filter(|term| text.contains(term.as_str())).count();
+                (score > 0).then_some((score, reference))
+            })
+            .collect::<Vec<_>>();
+        // Stable, so equal scores keep library order
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        matches.into_iter().map(|(_, reference)| reference).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_citations_resolve_or_are_rejected() {
+        let library = ScriptureLibrary::builtin();
+
+        let range = library.resolve("Quran 2:278-279 - give up what remains of riba", "id").unwrap();
+        assert_eq!(range.citation, "Quran 2:278-279");
+        assert_eq!(range.source, ScriptureSource::Quran);
+        assert_eq!(range.title, "Al-Baqarah");
+        assert_eq!(range.language, "id");
+        assert!(range.translation.starts_with("Wahai orang-orang yang beriman"));
+        assert!(range.translation.contains("pokok hartamu"));
+
+        // No French translation is bundled, so English is returned
+        let trade = library.resolve("Quran 2:275", "fr").unwrap();
+        assert_eq!(trade.language, "en");
+        assert!(trade.translation.contains("Allah has permitted trade"));
+
+        let hadith = library.resolve("Sahih Muslim 1513", "en").unwrap();
+        assert_eq!(hadith.source, ScriptureSource::Hadith);
+        assert!(hadith.translation.contains("gharar"));
+
+        for malformed in [
+            "Quran 2",
+            "Quran 2:",
+            "Quran 115:1",
+            "Quran 2:279-278",
+            "Sahih Muslim 15a",
+            "Quran 0:1",
+        ] {
+            assert!(ScriptureCitation::parse(malformed).is_err(), "{malformed}");
+        }
+        assert!(library.resolve("Sahih Muslim 1", "en").is_err());
+        assert!(library.resolve("Quran 2:280", "en").is_err());
+        assert!(library.resolve("AAOIFI Sharia Standard No. 21", "en").is_err());
+
+        let evidence = vec![
+            "Quran 2:275 - Allah has permitted trade and forbidden riba".to_owned(),
+            "AAOIFI Sharia Standard No. 21".to_owned(),
+            "Quran 2:275".to_owned(),
+            "Quran 2:999".to_owned(),
+        ];
+        let resolved = library.resolve_evidence(&evidence, "en");
+        assert_eq!(resolved.references.len(), 1);
+        assert_eq!(resolved.rejected.len(), 1);
+        assert_eq!(resolved.rejected[0].0, "Quran 2:999");
+    }
+
+    #[test]
+    fn test_bundled_citations_are_in_library() {
+        fn strings<'a>(
+            value: &'a toml::Value,
+            out: &mut Vec<&'a str>,
+        ) {
+            match value {
+                toml::Value::String(text) => out.push(text),
+                toml::Value::Array(items) => {
+                    for item in items {
+                        strings(item, out);
+                    }
+                },
+                toml::Value::Table(table) => {
+                    for item in table.values() {
+                        strings(item, out);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        let library = ScriptureLibrary::builtin();
+        let bundled = [
+            include_str!("rules/core.toml"),
+            include_str!("maqashid.toml"),
+            include_str!("maysir.toml"),
+            include_str!("riba.toml"),
+            include_str!("schools.toml"),
+            include_str!("stablecoin.toml"),
+            include_str!("zakat.toml"),
+        ];
+
+        let mut cited = 0;
+        for raw in bundled {
+            let value = toml::from_str::<toml::Value>(raw).unwrap();
+            let mut texts = Vec::new();
+            strings(&value, &mut texts);
+            for text in texts {
+                if ScriptureCitation::parse(text).unwrap().is_some() {
+                    library.resolve(text, "id").unwrap();
+                    cited += 1;
+                }
+            }
+        }
+        assert!(cited > 0);
+    }
+
+    #[test]
+    fn test_search_ranks_matching_entries() {
+        let library = ScriptureLibrary::builtin();
+
+        let results = library.search("gambling intoxicants", "en");
+        assert_eq!(results[0].citation, "Quran 5:90");
+        assert!(results.iter().any(|reference| reference.citation == "Quran 2:219"));
+        assert!(!results.iter().any(|reference| reference.citation == "Quran 4:11"));
+
+        let results = library.search("judi", "id");
+        assert!(results.iter().all(|reference| reference.language == "id"));
+        assert!(results.iter().any(|reference| reference.citation == "Quran 5:91"));
+        assert!(library.search("  ", "en").is_empty());
+    }
+}
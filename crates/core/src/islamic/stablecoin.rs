@@ -0,0 +1,275 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::ratios::project_data_from_env;
+use super::rules::Condition;
+use super::rules::TokenFacts;
+use super::rules::evaluate_conditions;
+use super::rules::load_from_env;
+use super::rules::validate_conditions;
+use crate::models::PegMechanism;
+use crate::models::ReserveAttestation;
+use crate::models::StablecoinAnalysis;
+
+/// Environment variable pointing at a TOML file that replaces the bundled pegs and guidance
+pub const STABLECOIN_FILE_ENV: &str = "AVERROES_STABLECOIN_FILE";
+
+/// Share of reserves in interest-bearing debt above which stricter scholars object to the backing
+const MAJORITY_INTEREST_BEARING: f64 = 0.5;
+/// Share of reserves a commodity-backed token should hold in the commodity itself
+const FULL_COMMODITY_BACKING: f64 = 0.95;
+/// Age after which an attestation no longer vouches for today's reserves
+const STALE_ATTESTATION_SECS: u64 = 90 * 24 * 60 * 60;
+
+const BUILTIN_CLASSIFIER: &str = include_str!("stablecoin.toml");
+
+/// Token facts that identify a stablecoin and its peg mechanism
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PegSignal {
+    pub id: String,
+    pub mechanism: PegMechanism,
+    #[serde(default)]
+    pub all: Vec<Condition>,
+    #[serde(default)]
+    pub any: Vec<Condition>,
+    #[serde(default)]
+    pub none: Vec<Condition>,
+}
+
+/// What must hold for a kind of stablecoin to be permissible, and the uses that are not
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PegGuidance {
+    pub mechanism: PegMechanism,
+    #[serde(default)]
+    pub conditions: Vec<String>,
+    #[serde(default)]
+    pub caveats: Vec<String>,
+    #[serde(default)]
+    pub evidence: Vec<String>,
+}
+
+/// Peg mechanism and reserves as reported in the token's project file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StablecoinInputs {
+    #[serde(default)]
+    pub peg_mechanism: Option<PegMechanism>,
+    #[serde(default)]
+    pub reserve_attestation: Option<ReserveAttestation>,
+}
+
+impl StablecoinInputs {
+    /// Inputs from the token's project file in `AVERROES_PROJECT_DATA_DIR`
+    pub fn from_env(
+        address: &str,
+        symbol: &str,
+    ) -> Result<Option<Self>, String> {
+        project_data_from_env(address, symbol)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ClassifierFile {
+    #[serde(default)]
+    pegs: Vec<PegSignal>,
+    #[serde(default)]
+    guidance: Vec<PegGuidance>,
+}
+
+/// Classifies a stablecoin's peg and derives the conditions and caveats for its ruling
+#[derive(Debug, Clone)]
+pub struct StablecoinClassifier {
+    pegs: Vec<PegSignal>,
+    guidance: Vec<PegGuidance>,
+}
+
+impl StablecoinClassifier {
+    pub fn new(
+        pegs: Vec<PegSignal>,
+        guidance: Vec<PegGuidance>,
+    ) -> Self {
+        Self {
+            pegs,
+            guidance,
+        }
+    }
+
+    /// Parse and validate `[[pegs]]` and `[[guidance]]` from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let file: ClassifierFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        for peg in &file.pegs {
+            validate_conditions(&format!("Peg '{}'", peg.id), &peg.all, &peg.any, &peg.none)?;
+        }
+        for (index, guidance) in file.guidance.iter().enumerate() {
+            if file.guidance[..index]
+                .iter()
+                .any(|earlier| earlier.mechanism == guidance.mechanism)
+            {
+                return Err(format!("Guidance for {:?} is given more than once", guidance.mechanism));
+            }
+        }
+
+        Ok(Self::new(file.pegs, file.guidance))
+    }
+
+    /// The bundled pegs and guidance
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_CLASSIFIER).expect("Bundled stablecoin classifier must be valid")
+    }
+
+    /// Pegs and guidance from `AVERROES_STABLECOIN_FILE`, or the bundled ones when unset
+    pub fn from_env() -> Result<Self, String> {
+        load_from_env(STABLECOIN_FILE_ENV, Self::from_toml, Self::builtin)
+    }
+
+    /// The reported peg mechanism, or the first matching peg signal; `None` when the token is not a stablecoin
+    pub fn analyze(
+        &self,
+        facts: &TokenFacts,
+        inputs: &StablecoinInputs,
+        now: u64,
+    ) -> Option<StablecoinAnalysis> {
+        let (mechanism, observed) = match inputs.peg_mechanism {
+            Some(mechanism) => (mechanism, "Peg mechanism reported in the project file".to_owned()),
+            None => self.pegs.iter().find_map(|peg| {
+                evaluate_conditions(&peg.all, &peg.any, &peg.none, facts)
+                    .map(|matched| (peg.mechanism, matched.join("; ")))
+            })?,
+        };
+
+        let guidance = self.guidance.iter().find(|guidance| guidance.mechanism == mechanism);
+        let mut conditions = guidance.map(|guidance| guidance.conditions.clone()).unwrap_or_default();
+        let mut caveats = guidance.map(|guidance| guidance.caveats.clone()).unwrap_or_default();
+
+        match &inputs.reserve_attestation {
+            Some(reserves) => caveats.extend(reserve_caveats(mechanism, reserves, now)),
+            None if matches!(mechanism, PegMechanism::FiatBacked | PegMechanism::CommodityBacked) => {
+                conditions.push("Reserves are attested regularly by an independent auditor".to_owned());
+                caveats.push("No reserve attestation is on file, so the backing is unverified".to_owned());
+            },
+            None => {},
+        }
+
+        Some(StablecoinAnalysis {
+            mechanism,
+            observed,
+            reserves: inputs.reserve_attestation.clone(),
+            conditions,
+            caveats,
+            references: guidance.map(|guidance| guidance.evidence.clone()).unwrap_or_default(),
+        })
+    }
+}
+
+/// Caveats the attested reserves add to those of the mechanism
+fn reserve_caveats(
+    mechanism: PegMechanism,
+    reserves: &ReserveAttestation,
+    now: u64,
+) -> Vec<String> {
+    let mut caveats = Vec::new();
+
+    let interest_bearing = reserves.interest_bearing_share();
+    if interest_bearing > 0.0 {
+        caveats.push(format!(
+            "{:.0}% of reserves are in interest-bearing T-bills, repos or money market funds; the interest must stay \
+             with the issuer, and a version of the token that passes it to holders is riba",
+            interest_bearing * 100.0
+        ));
+    }
+    if interest_bearing > MAJORITY_INTEREST_BEARING {
+        caveats.push(
+            "Most reserves are interest-bearing debt; stricter scholars hold the token makruh while its backing earns \
+             riba"
+                .to_owned(),
+        );
+    }
+
+    if mechanism == PegMechanism::CommodityBacked && reserves.gold < FULL_COMMODITY_BACKING {
+        caveats.push(format!("Only {:.0}% of reserves are held in gold", reserves.gold * 100.0));
+    }
+
+    if let Some(ratio) = reserves.backing_ratio.filter(|ratio| *ratio < 1.0) {
+        caveats.push(format!("Reserves cover only {:.1}% of supply", ratio * 100.0));
+    }
+
+    if let Some(attested_at) = reserves.attested_at {
+        if now.saturating_sub(attested_at) > STALE_ATTESTATION_SECS {
+            caveats.push(format!(
+                "The latest reserve attestation is {} days old",
+                now.saturating_sub(attested_at) / (24 * 60 * 60)
+            ));
+        }
+    }
+
+    caveats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_760_000_000;
+
+    fn inputs(raw: &str) -> StablecoinInputs {
+        serde_json::from_str(raw).unwrap()
+    }
+
+    #[test]
+    fn test_pegs_are_classified_and_other_tokens_skipped() {
+        let classifier = StablecoinClassifier::builtin();
+        let none = StablecoinInputs::default();
+
+        let usdc = TokenFacts::new()
+            .text("symbol", "USDC")
+            .text("text", "USD Coin USDC USDC is a fully collateralized US dollar stablecoin.");
+        let analysis = classifier.analyze(&usdc, &none, NOW).unwrap();
+        assert_eq!(analysis.mechanism, PegMechanism::FiatBacked);
+        assert_eq!(analysis.observed, "symbol is \"USDC\"");
+        assert!(analysis.caveats.iter().any(|caveat| caveat.contains("unverified")));
+
+        let gold = TokenFacts::new()
+            .text("symbol", "GLD")
+            .text("text", "Each token is backed by physical gold held in a London vault");
+        let analysis = classifier.analyze(&gold, &none, NOW).unwrap();
+        assert_eq!(analysis.mechanism, PegMechanism::CommodityBacked);
+        assert!(analysis.conditions[0].contains("allocated and audited gold"));
+
+        let algorithmic = TokenFacts::new()
+            .text("symbol", "XUSD")
+            .text("text", "An algorithmic stablecoin held at $1 by seigniorage shares");
+        let analysis = classifier.analyze(&algorithmic, &none, NOW).unwrap();
+        assert_eq!(analysis.mechanism, PegMechanism::Algorithmic);
+        assert!(analysis.caveats[0].contains("excessive gharar"));
+
+        let utility = TokenFacts::new()
+            .text("symbol", "SOL")
+            .text("text", "Solana is a high-performance blockchain");
+        assert!(classifier.analyze(&utility, &none, NOW).is_none());
+    }
+
+    #[test]
+    fn test_attested_reserves_add_caveats() {
+        let classifier = StablecoinClassifier::builtin();
+        let facts = TokenFacts::new().text("symbol", "USDT");
+
+        let attested = inputs(
+            r#"{"reserve_attestation": {"attestor": "BDO", "attested_at": 1750000000, "backing_ratio": 0.98,
+                "cash": 0.05, "treasury_bills": 0.65, "repurchase_agreements": 0.15, "gold": 0.05, "crypto": 0.1}}"#,
+        );
+        let analysis = classifier.analyze(&facts, &attested, NOW).unwrap();
+
+        let derived = &analysis.caveats[2..];
+        assert!(derived[0].starts_with("80% of reserves are in interest-bearing T-bills"));
+        assert!(derived[1].contains("stricter scholars hold the token makruh"));
+        assert_eq!(derived[2], "Reserves cover only 98.0% of supply");
+        assert_eq!(derived[3], "The latest reserve attestation is 115 days old");
+        assert_eq!(analysis.reserves.as_ref().and_then(|reserves| reserves.attestor.as_deref()), Some("BDO"));
+
+        // A project that reports its mechanism is taken at its word
+        let reported = inputs(r#"{"peg_mechanism": "CommodityBacked", "reserve_attestation": {"gold": 0.8}}"#);
+        let analysis = classifier.analyze(&facts, &reported, NOW).unwrap();
+        assert_eq!(analysis.mechanism, PegMechanism::CommodityBacked);
+        assert_eq!(analysis.caveats.last().unwrap(), "Only 80% of reserves are held in gold");
+    }
+}
@@ -0,0 +1,69 @@
+//! Plumbing shared by the sled-backed stores: opening their databases, stable hashing and token keys.
+
+use sled::Db;
+use solana_program::pubkey::Pubkey;
+use tracing::error;
+
+/// Database at `path`, or a temporary in-memory one
+pub fn open_db(path: Option<&str>) -> Result<Db, String> {
+    match path {
+        Some(path) => sled::open(path).map_err(|e| format!("{path}: {e}")),
+        None => sled::Config::new().temporary(true).open().map_err(|e| e.to_string()),
+    }
+}
+
+/// Database path from `env`, or `None` when the variable is unset or empty
+pub fn db_path(env: &str) -> Option<String> {
+    std::env::var(env).ok().filter(|path| !path.is_empty())
+}
+
+/// The store from `open`, or an in-memory one after logging why the `what` database could not be opened
+pub fn open_or_in_memory<T>(
+    what: &str,
+    open: impl FnOnce() -> Result<T, String>,
+    in_memory: impl FnOnce() -> Result<T, String>,
+) -> T {
+    open().unwrap_or_else(|e| {
+        error!("Failed to open {} database, using an in-memory one: {}", what, e);
+        in_memory().unwrap_or_else(|e| panic!("In-memory {what} database must open: {e}"))
+    })
+}
+
+/// FNV-1a, which unlike the std hasher is fixed across Rust releases and platforms
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// A mint address in canonical form; symbols are refused because anyone can mint a token under any symbol
+///
+/// The default pubkey is refused too: it stands in for tokens whose mint is unknown.
+pub fn mint_key(token: &str) -> Option<String> {
+    token
+        .trim()
+        .parse::<Pubkey>()
+        .ok()
+        .filter(|mint| *mint != Pubkey::default())
+        .map(|mint| mint.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a_matches_the_reference_vectors() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn test_mint_key_accepts_only_mint_addresses() {
+        let mint = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+        assert_eq!(mint_key(&format!(" {mint}\n")), Some(mint.to_owned()));
+        assert_eq!(mint_key("USDC"), None);
+        assert_eq!(mint_key(&Pubkey::default().to_string()), None);
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::maysir::ProgramInteraction;
+use super::rules::FactValue;
+use super::rules::TokenFacts;
+use super::rules::contains_word;
+use super::rules::load_from_env;
+use crate::models::CategoryLabel;
+use crate::models::TokenCategory;
+use crate::models::analysis::ScrapedData;
+
+/// Environment variable pointing at a TOML file that replaces the bundled taxonomy
+pub const TAXONOMY_FILE_ENV: &str = "AVERROES_TAXONOMY_FILE";
+
+const BUILTIN_TAXONOMY: &str = include_str!("taxonomy.toml");
+
+/// Likelihood each kind of signal gives its category
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SignalLikelihoods {
+    pub mint: f64,
+    pub symbol: f64,
+    pub metadata: f64,
+    pub program: f64,
+    pub scraped: f64,
+}
+
+/// What places a token in one category
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryProfile {
+    pub category: TokenCategory,
+    #[serde(default)]
+    pub mints: Vec<String>,
+    /// Matched case-insensitively
+    #[serde(default)]
+    pub symbols: Vec<String>,
+    /// Matched on whole words in the token's metadata and scraped pages
+    #[serde(default)]
+    pub terms: Vec<String>,
+    #[serde(default)]
+    pub program_ids: Vec<String>,
+    /// Matched case-insensitively against explorer labels
+    #[serde(default)]
+    pub program_labels: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TaxonomyFile {
+    min_confidence: f64,
+    significant_share: f64,
+    likelihood: SignalLikelihoods,
+    #[serde(default)]
+    categories: Vec<CategoryProfile>,
+}
+
+/// Labels tokens with taxonomy categories from their metadata, program usage and scraped descriptions
+#[derive(Debug, Clone)]
+pub struct TokenClassifier {
+    min_confidence: f64,
+    significant_share: f64,
+    likelihood: SignalLikelihoods,
+    categories: Vec<CategoryProfile>,
+}
+
+impl TokenClassifier {
+    /// Parse and validate `[likelihood]` and `[[categories]]` from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let file: TaxonomyFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        let likelihood = file.likelihood;
+        for (name, value) in [
+            ("min_confidence", file.min_confidence),
+            ("likelihood.mint", likelihood.mint),
+            ("likelihood.symbol", likelihood.symbol),
+            ("likelihood.metadata", likelihood.metadata),
+            ("likelihood.program", likelihood.program),
+            ("likelihood.scraped", likelihood.scraped),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{name} must be between 0 and 1"));
+            }
+        }
+        if file.significant_share <= 0.0 || file.significant_share > 1.0 {
+            return Err("significant_share must be above 0 and at most 1".to_owned());
+        }
+        let mut seen = HashSet::new();
+        for profile in &file.categories {
+            if !seen.insert(profile.category) {
+                return Err(format!("{:?} is listed more than once", profile.category));
+            }
+            if profile.mints.is_empty()
+                && profile.symbols.is_empty()
+                && profile.terms.is_empty()
+                && profile.program_ids.is_empty()
+                && profile.program_labels.is_empty()
+            {
+                return Err(format!("{:?} needs at least one mint, symbol, term or program", profile.category));
+            }
+        }
+
+        Ok(Self {
+            min_confidence: file.min_confidence,
+            significant_share: file.significant_share,
+            likelihood,
+            categories: file.categories,
+        })
+    }
+
+    /// The bundled taxonomy
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_TAXONOMY).expect("Bundled token taxonomy must be valid")
+    }
+
+    /// Taxonomy from `AVERROES_TAXONOMY_FILE`, or the bundled one when unset
+    pub fn from_env() -> Result<Self, String> {
+        load_from_env(TAXONOMY_FILE_ENV, Self::from_toml, Self::builtin)
+    }
+
+    /// Categories whose signals add up to at least `min_confidence`, most confident first
+    pub fn classify(
+        &self,
+        address: &str,
+        facts: &TokenFacts,
+        interactions: &[ProgramInteraction],
+        scraped: &[ScrapedData],
+    ) -> Vec<CategoryLabel> {
+        let text = |name: &str| match facts.get(name) {
+            Some(FactValue::Text(text)) => text.as_str(),
+            _ => "",
+        };
+        let (symbol, metadata) = (text("symbol").trim(), text("text"));
+
+        let mut labels = self
+            .categories
+            .iter()
+            .filter_map(|profile| {
+                let mut signals: Vec<(f64, String)> = Vec::new();
+                if profile.mints.iter().any(|mint| mint == address) {
+                    signals.push((
+                        self.likelihood.mint,
+                        format!("Mint {address} is a known {:?} token", profile.category),
+                    ));
+                }
+                if !symbol.is_empty() && profile.symbols.iter().any(|known| known.eq_ignore_ascii_case(symbol)) {
+                    signals.push((
+                        self.likelihood.symbol,
+                        format!("Symbol {symbol} is a known {:?} symbol", profile.category),
+                    ));
+                }
+                if let Some(term) = profile.terms.iter().find(|term| contains_word(metadata, term)) {
+                    signals.push((self.likelihood.metadata, format!("Name or description mentions \"{term}\"")));
+                }
+                signals.extend(
+                    interactions
+                        .iter()
+                        .filter_map(|interaction| self.program_signal(profile, interaction)),
+                );
+                if let Some(signal) = self.scraped_signal(profile, scraped) {
+                    signals.push(signal);
+                }
+                if signals.is_empty() {
+                    return None;
+                }
+
+                let confidence = 1.0 - signals.iter().map(|(likelihood, _)| 1.0 - likelihood).product::<f64>();
+                Some(CategoryLabel {
+                    category: profile.category,
+                    confidence: confidence.clamp(0.0, 1.0),
+                    evidence: signals.into_iter().map(|(_, evidence)| evidence).collect(),
+                })
+            })
+            .filter(|label| label.confidence >= self.min_confidence)
+            .collect::<Vec<_>>();
+
+        labels.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then_with(|| a.category.cmp(&b.category)));
+        labels
+    }
+
+    fn program_signal(
+        &self,
+        profile: &CategoryProfile,
+        interaction: &ProgramInteraction,
+    ) -> Option<(f64, String)> {
+        let label = interaction.label.as_deref().unwrap_or_default().to_lowercase();
+        let known = profile.program_ids.contains(&interaction.program_id)
+            || profile.program_labels.iter().any(|known| label.contains(&known.to_lowercase()));
+        if !known {
+            return None;
+        }
+
+        // A program the token is barely used with says little about what the token is
+        let weight = interaction
+            .share
+            .map(|share| (share / self.significant_share).clamp(0.0, 1.0))
+            .unwrap_or(1.0);
+        let share = interaction
+            .share
+            .map(|share| format!(", {:.1}% of transactions", share * 100.0))
+            .unwrap_or_default();
+        Some((
+            self.likelihood.program * weight,
+            format!(
+                "Used with {} ({}{share})",
+                interaction.label.as_deref().unwrap_or("unlabelled program"),
+                interaction.program_id
+            ),
+        ))
+    }
+
+    /// One signal for all the pages that mention a term, however many there are
+    fn scraped_signal(
+        &self,
+        profile: &CategoryProfile,
+        scraped: &[ScrapedData],
+    ) -> Option<(f64, String)> {
+        let mentions = scraped
+            .iter()
+            .filter_map(|page| {
+                let text = format!("{} {}", page.title.as_deref().unwrap_or_default(), page.content);
+                profile.terms.iter().find(|term| contains_word(&text, term))
+            })
+            .collect::<Vec<_>>();
+        let term = mentions.first()?;
+
+        Some((self.likelihood.scraped, format!("{} scraped page(s) mention \"{term}\"", mentions.len())))
+    }
+}
+
+/// Name of the rule fact holding a category's confidence, e.g. `category_liquid_staking`
+pub fn category_fact(category: TokenCategory) -> String {
+    format!("{category:?}").chars().fold("category".to_owned(), |mut fact, c| {
+        if c.is_ascii_uppercase() {
+            fact.push('_');
+        }
+        fact.push(c.to_ascii_lowercase());
+        fact
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::analysis::ScrapedDataType;
+
+    fn page(content: &str) -> ScrapedData {
+        ScrapedData {
+            source_url: "https://example.com/token".to_owned(),
+            content: content.to_owned(),
+            data_type: ScrapedDataType::News,
+            title: None,
+            relevance_score: 1.0,
+            scraped_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_combines_metadata_programs_and_pages_into_confidences() {
+        let classifier = TokenClassifier::builtin();
+        let facts = TokenFacts::new()
+            .text("symbol", "MSOL")
+            .text("text", "Marinade staked SOL MSOL Liquid staking token earning validator rewards");
+        let interactions = vec![ProgramInteraction {
+            program_id: "MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD".to_owned(),
+            label: Some("Marinade Finance".to_owned()),
+            share: Some(0.05),
+        }];
+        let pages = vec![page("mSOL is the most used liquid staking token on Solana")];
+
+        let labels = classifier.classify("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So", &facts, &interactions, &pages);
+
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].category, TokenCategory::LiquidStaking);
+        assert_eq!(labels[0].evidence, vec![
+            "Mint mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So is a known LiquidStaking token".to_owned(),
+            "Symbol MSOL is a known LiquidStaking symbol".to_owned(),
+            "Name or description mentions \"liquid staking\"".to_owned(),
+            "Used with Marinade Finance (MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD, 5.0% of transactions)".to_owned(),
+            "1 scraped page(s) mention \"liquid staking\"".to_owned(),
+        ]);
+        let expected = 1.0 - 0.05 * 0.5 * 0.4 * (1.0 - 0.35) * (1.0 - 0.35);
+        assert!((labels[0].confidence - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_copied_symbols_alone_fall_short_and_labels_are_ranked() {
+        let classifier = TokenClassifier::builtin();
+
+        // A fresh mint calling itself USDC is not taken for a stablecoin on its symbol alone
+        let impostor = TokenFacts::new().text("symbol", "USDC").text("text", "USD Coin USDC");
+        let impostor = classifier.classify("Fake1111111111111111111111111111111111111111", &impostor, &[], &[]);
+        assert_eq!(impostor.len(), 1);
+        assert!((impostor[0].confidence - 0.5).abs() < 1e-9);
+
+        let meme = TokenFacts::new()
+            .text("symbol", "DOGQ")
+            .text("text", "DogQuest DOGQ The memecoin of play-to-earn gaming");
+        let labels = classifier.classify("DogQ111111111111111111111111111111111111111", &meme, &[], &[]);
+        let categories = labels.iter().map(|label| label.category).collect::<Vec<_>>();
+        assert_eq!(categories, vec![TokenCategory::Meme, TokenCategory::Gaming]);
+        assert!(labels.iter().all(|label| (label.confidence - 0.6).abs() < 1e-9));
+
+        assert_eq!(category_fact(TokenCategory::L1Native), "category_l1_native");
+        assert_eq!(category_fact(TokenCategory::LiquidStaking), "category_liquid_staking");
+    }
+}
@@ -0,0 +1,336 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::hijri::hijri_from_unix;
+use super::hijri::lunar_year_after;
+use super::rules::load_from_env;
+use crate::AverroesError;
+use crate::models::HoldingIntent;
+use crate::models::NisabStandard;
+use crate::models::ZakatAssetBreakdown;
+use crate::models::ZakatMethod;
+use crate::models::ZakatRequest;
+use crate::models::ZakatResult;
+
+/// Environment variable pointing at a TOML file that replaces the bundled rate, nisab and methods
+pub const ZAKAT_FILE_ENV: &str = "AVERROES_ZAKAT_FILE";
+
+const BUILTIN_CONFIG: &str = include_str!("zakat.toml");
+
+/// Nisab in grams of each metal, with optional prices to value it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NisabConfig {
+    pub gold_grams: f64,
+    pub silver_grams: f64,
+    #[serde(default)]
+    pub references: Vec<String>,
+    #[serde(default)]
+    pub gold_price_per_gram: Option<f64>,
+    #[serde(default)]
+    pub silver_price_per_gram: Option<f64>,
+}
+
+/// How a method values long-term holdings, and its basis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodGuidance {
+    pub method: ZakatMethod,
+    pub description: String,
+    #[serde(default)]
+    pub references: Vec<String>,
+}
+
+/// Zakat rate, nisab and methods
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZakatConfig {
+    pub rate: f64,
+    pub nisab: NisabConfig,
+    #[serde(default)]
+    pub methods: Vec<MethodGuidance>,
+}
+
+/// Zakat due on a portfolio, exported to the mobile apps
+#[uniffi::export]
+pub fn calculate_zakat(request: ZakatRequest) -> Result<ZakatResult, AverroesError> {
+    ZakatConfig::from_env()
+        .and_then(|config| config.calculate(&request))
+        .map_err(AverroesError::InvalidQuery)
+}
+
+impl ZakatConfig {
+    /// Parse and validate the rate, `[nisab]` and `[[methods]]` from TOML
+    pub fn from_toml(raw: &str) -> Result<Self, String> {
+        let config: Self = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        if !(config.rate > 0.0 && config.rate <= 1.0) {
+            return Err("rate must be above 0 and at most 1".to_owned());
+        }
+        if config.nisab.gold_grams <= 0.0 || config.nisab.silver_grams <= 0.0 {
+            return Err("Nisab weights must be positive".to_owned());
+        }
+        for (index, guidance) in config.methods.iter().enumerate() {
+            if config.methods[..index].iter().any(|earlier| earlier.method == guidance.method) {
+                return Err(format!("Method {:?} is described more than once", guidance.method));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// The bundled rate, nisab and methods
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_CONFIG).expect("Bundled zakat config must be valid")
+    }
+
+    /// Config from `AVERROES_ZAKAT_FILE`, or the bundled one when unset
+    pub fn from_env() -> Result<Self, String> {
+        load_from_env(ZAKAT_FILE_ENV, Self::from_toml, Self::builtin)
+    }
+
+    /// Value each holding at the zakat date, check its hawl and the nisab, and share out what is due
+    pub fn calculate(
+        &self,
+        request: &ZakatRequest,
+    ) -> Result<ZakatResult, String> {
+        let guidance = self
+            .methods
+            .iter()
+            .find(|guidance| guidance.method == request.method)
+            .ok_or_else(|| format!("No guidance is configured for {:?}", request.method))?;
+        let (nisab_standard, nisab_value) = self.nisab(request)?;
+        if request.debts_due < 0.0 {
+            return Err("Debts due cannot be negative".to_owned());
+        }
+
+        let zakat_date = hijri_from_unix(request.zakat_date);
+        let hawl_start = request.hawl_start.map(hijri_from_unix);
+
+        let mut assets = Vec::new();
+        for holding in &request.holdings {
+            if holding.quantity < 0.0 || holding.price < 0.0 || holding.income < 0.0 {
+                return Err(format!("{}: quantity, price and income cannot be negative", holding.asset));
+            }
+            if holding.acquired_at > request.zakat_date {
+                return Err(format!("{} was acquired after the zakat date", holding.asset));
+            }
+
+            let market_value = holding.quantity * holding.price;
+            let (zakatable_value, mut note) = match (holding.intent, request.method) {
+                (HoldingIntent::Trading, _) => (market_value, "Trading inventory at market value".to_owned()),
+                (HoldingIntent::LongTerm, ZakatMethod::MarketValue) => {
+                    (market_value, "Long-term holding at market value".to_owned())
+                },
+                (HoldingIntent::LongTerm, ZakatMethod::ZakatableAssets) => match holding.zakatable_ratio {
+                    Some(ratio) if (0.0..=1.0).contains(&ratio) => (
+                        market_value * ratio,
+                        format!("{:.0}% of market value backed by zakatable assets", ratio * 100.0),
+                    ),
+                    Some(_) => return Err(format!("{}: zakatable ratio must be between 0 and 1", holding.asset)),
+                    None => (market_value, "Market value, as no zakatable-asset share is reported".to_owned()),
+                },
+                (HoldingIntent::LongTerm, ZakatMethod::ReturnsOnly) => {
+                    (holding.income, "Income produced over the year".to_owned())
+                },
+            };
+
+            // Wealth gained after reaching nisab joins the hawl already running
+            let hawl_started = hawl_start.unwrap_or_else(|| hijri_from_unix(holding.acquired_at));
+            let hawl_completes = lunar_year_after(&hawl_started);
+            let hawl_complete = zakat_date >= hawl_completes;
+            if !hawl_complete {
+                note.push_str(&format!(
+                    "; hawl completes {}-{:02}-{:02} AH",
+                    hawl_completes.year, hawl_completes.month, hawl_completes.day
+                ));
+            }
+
+            assets.push(ZakatAssetBreakdown {
+                asset: holding.asset.clone(),
+                intent: holding.intent,
+                market_value,
+                zakatable_value,
+                hawl_started,
+                hawl_completes,
+                hawl_complete,
+                zakat_due: 0.0,
+                note,
+            });
+        }
+
+        let held_a_year = assets
+            .iter()
+            .filter(|asset| asset.hawl_complete)
+            .map(|asset| asset.zakatable_value)
+            .sum::<f64>();
+        let debts_deducted = request.debts_due.min(held_a_year);
+        let zakatable_wealth = held_a_year - debts_deducted;
+        let above_nisab = zakatable_wealth > 0.0 && zakatable_wealth >= nisab_value;
+
+        if above_nisab {
+            // Debts are deducted from each asset in proportion to its value
+            let net_share = zakatable_wealth / held_a_year;
+            for asset in assets.iter_mut().filter(|asset| asset.hawl_complete) {
+                asset.zakat_due = asset.zakatable_value * net_share * self.rate;
+            }
+        }
+
+        let mut references = guidance.references.clone();
+        references.extend(self.nisab.references.iter().cloned());
+
+        Ok(ZakatResult {
+            zakat_date,
+            method: request.method,
+            nisab_standard,
+            nisab_value,
+            zakatable_wealth,
+            debts_deducted,
+            above_nisab,
+            rate: self.rate,
+            zakat_due: assets.iter().map(|asset| asset.zakat_due).sum(),
+            assets,
+            methodology: guidance.description.clone(),
+            references,
+        })
+    }
+
+    /// The metal applied and the nisab's value in the reporting currency
+    fn nisab(
+        &self,
+        request: &ZakatRequest,
+    ) -> Result<(NisabStandard, f64), String> {
+        let gold = request
+            .gold_price_per_gram
+            .or(self.nisab.gold_price_per_gram)
+            .map(|price| price * self.nisab.gold_grams);
+        let silver = request
+            .silver_price_per_gram
+            .or(self.nisab.silver_price_per_gram)
+            .map(|price| price * self.nisab.silver_grams);
+        if gold.into_iter().chain(silver).any(|value| value <= 0.0) {
+            return Err("Metal prices must be positive".to_owned());
+        }
+
+        match (request.nisab_standard, gold, silver) {
+            (NisabStandard::Gold, Some(gold), _) => Ok((NisabStandard::Gold, gold)),
+            (NisabStandard::Silver, _, Some(silver)) => Ok((NisabStandard::Silver, silver)),
+            (NisabStandard::Lower, Some(gold), Some(silver)) if silver < gold => Ok((NisabStandard::Silver, silver)),
+            (NisabStandard::Lower, Some(gold), _) => Ok((NisabStandard::Gold, gold)),
+            (NisabStandard::Lower, None, Some(silver)) => Ok((NisabStandard::Silver, silver)),
+            (standard, ..) => Err(format!("A metal price per gram is needed for a {standard:?} nisab")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::islamic::hijri::unix_from_hijri;
+    use crate::models::HijriDate;
+    use crate::models::ZakatHolding;
+
+    fn day(
+        year: i32,
+        month: u32,
+        day: u32,
+    ) -> u64 {
+        unix_from_hijri(&HijriDate {
+            year,
+            month,
+            day,
+        })
+    }
+
+    fn holding(
+        asset: &str,
+        value: f64,
+        intent: HoldingIntent,
+        acquired_at: u64,
+    ) -> ZakatHolding {
+        ZakatHolding {
+            asset: asset.to_owned(),
+            quantity: 1.0,
+            price: value,
+            intent,
+            acquired_at,
+            zakatable_ratio: None,
+            income: 0.0,
+        }
+    }
+
+    fn request(
+        holdings: Vec<ZakatHolding>,
+        method: ZakatMethod,
+        nisab_standard: NisabStandard,
+    ) -> ZakatRequest {
+        ZakatRequest {
+            holdings,
+            zakat_date: day(1446, 9, 1),
+            method,
+            nisab_standard,
+            gold_price_per_gram: Some(100.0),
+            silver_price_per_gram: Some(1.0),
+            debts_due: 0.0,
+            hawl_start: None,
+        }
+    }
+
+    #[test]
+    fn test_breakdown_per_asset_with_hawl_and_debts() {
+        let mut long_term = holding("JUP", 1_000.0, HoldingIntent::LongTerm, day(1444, 2, 10));
+        long_term.zakatable_ratio = Some(0.4);
+        let mut portfolio = request(
+            vec![
+                holding("SOL", 1_500.0, HoldingIntent::Trading, day(1445, 9, 1)),
+                long_term,
+                holding("BONK", 500.0, HoldingIntent::Trading, day(1445, 10, 1)),
+            ],
+            ZakatMethod::ZakatableAssets,
+            NisabStandard::Silver,
+        );
+        portfolio.debts_due = 100.0;
+
+        let result = ZakatConfig::builtin().calculate(&portfolio).unwrap();
+
+        assert_eq!(result.nisab_value, 595.0);
+        assert_eq!(result.zakatable_wealth, 1_800.0);
+        assert!(result.above_nisab);
+        assert!((result.zakat_due - 45.0).abs() < 1e-9);
+        assert!((result.assets[0].zakat_due - 1_500.0 * 18.0 / 19.0 * 0.025).abs() < 1e-9);
+        assert_eq!(result.assets[1].zakatable_value, 400.0);
+        assert_eq!(result.assets[1].note, "40% of market value backed by zakatable assets");
+        assert!(!result.assets[2].hawl_complete);
+        assert_eq!(result.assets[2].note, "Trading inventory at market value; hawl completes 1446-10-01 AH");
+        assert_eq!(result.assets[2].zakat_due, 0.0);
+
+        // Under the gold nisab of 8,500 the same wealth pays nothing
+        portfolio.nisab_standard = NisabStandard::Gold;
+        let result = ZakatConfig::builtin().calculate(&portfolio).unwrap();
+        assert!(!result.above_nisab);
+        assert_eq!(result.zakat_due, 0.0);
+    }
+
+    #[test]
+    fn test_methods_nisab_choice_and_running_hawl() {
+        let mut staked = holding("SOL", 2_000.0, HoldingIntent::LongTerm, day(1446, 7, 1));
+        staked.income = 700.0;
+        let mut portfolio = request(vec![staked], ZakatMethod::ReturnsOnly, NisabStandard::Lower);
+
+        // Bought two months ago, the holding has no hawl of its own yet
+        let result = ZakatConfig::builtin().calculate(&portfolio).unwrap();
+        assert_eq!(result.nisab_standard, NisabStandard::Silver);
+        assert_eq!(result.zakat_due, 0.0);
+
+        // but joins the hawl of wealth that reached nisab the year before
+        portfolio.hawl_start = Some(day(1445, 6, 15));
+        let result = ZakatConfig::builtin().calculate(&portfolio).unwrap();
+        assert!((result.zakat_due - 700.0 * 0.025).abs() < 1e-9);
+        assert!(result.methodology.contains("income they produced"));
+
+        portfolio.method = ZakatMethod::MarketValue;
+        let result = ZakatConfig::builtin().calculate(&portfolio).unwrap();
+        assert!((result.zakat_due - 50.0).abs() < 1e-9);
+
+        portfolio.nisab_standard = NisabStandard::Gold;
+        portfolio.gold_price_per_gram = None;
+        assert!(ZakatConfig::builtin().calculate(&portfolio).unwrap_err().contains("Gold nisab"));
+    }
+}
@@ -1,14 +1,40 @@
 // UniFFI exports
 uniffi::setup_scaffolding!();
 
+pub mod actors;
+pub mod ai;
+pub mod analysis;
+pub mod api;
+pub mod error;
+pub mod fallback;
+pub mod http;
+pub mod islamic;
+pub mod mock;
+pub mod models;
+pub mod reasoning;
+pub mod solana;
+
+pub use islamic::calculate_purification;
+pub use islamic::calculate_zakat;
+
+use std::collections::VecDeque;
 use std::sync::Arc;
 use std::sync::Mutex;
 
-use rig::client::CompletionClient;
-use rig::completion::Prompt;
 use rig::providers::groq;
 use tokio::runtime::Runtime;
 
+use crate::ai::ChatClient;
+use crate::ai::ChatMessage;
+use crate::ai::ChatRequest;
+use crate::ai::ProviderConfig;
+use crate::http::HttpClient;
+use crate::http::HttpError;
+use crate::mock::ScriptedModel;
+use crate::reasoning::ModelOutput;
+use crate::reasoning::ReasoningStream;
+use crate::reasoning::completion_budget;
+
 // ============================================================================
 // SIMPLE FIQH AI DATA STRUCTURES
 // ============================================================================
@@ -87,13 +113,21 @@ pub struct AverroesSystem {
     agent_type: Mutex<AgentType>,
     // Shared Tokio runtime for all async operations (UniFFI best practice)
     runtime: Runtime,
+    // Reasoning split off model answers, kept only when AVERROES_RETAIN_REASONING is set
+    reasoning_traces: ReasoningTraces,
 }
 
+/// Most recent (query id, reasoning) pairs, oldest first
+type ReasoningTraces = Arc<Mutex<VecDeque<(String, String)>>>;
+
+const MAX_RETAINED_TRACES: usize = 50;
+
 // Simple enum to handle different agent types
-#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
 enum AgentType {
-    Groq(Arc<rig::agent::Agent<groq::CompletionModel>>),
-    Mock,
+    Groq(Arc<GroqAgent>),
+    // Answers come from the scenario in AVERROES_MOCK_SCENARIO, or the bundled one
+    Mock(Arc<ScriptedModel>),
 }
 
 #[uniffi::export]
@@ -109,13 +143,17 @@ impl AverroesSystem {
             .map_err(|e| AverroesError::InitializationError(format!("Failed to create Tokio runtime: {e}")))?;
 
         // Start with mock, upgrade to Groq via async method
-        let agent_type = Mutex::new(AgentType::Mock);
+        let mock_model = ScriptedModel::from_env_or_builtin()
+            .map_err(|e| AverroesError::InitializationError(format!("Failed to load mock scenario: {e}")))?;
+        log::warn!("🔥 RUST DEBUG: Using mock scenario '{}'", mock_model.name());
+        let agent_type = Mutex::new(AgentType::Mock(Arc::new(mock_model)));
 
         log::warn!("🔥 RUST DEBUG: Averroes system created with Mock agent (ready for upgrade)");
 
         Ok(Self {
             agent_type,
             runtime,
+            reasoning_traces: Arc::new(Mutex::new(VecDeque::new())),
         })
     }
 
@@ -169,33 +207,25 @@ impl AverroesSystem {
                 query_id: format!("filtered_{}", chrono::Utc::now().timestamp()),
                 response: error_message,
                 confidence: 0.0,
-                sources: vec!["Content Filter".to_string()],
+                sources: vec!["Content Filter".to_owned()],
                 timestamp: chrono::Utc::now().timestamp() as u64,
             });
         }
 
-        // Extract agent Arc or identify as Mock, outside the spawn
-        let (groq_agent, is_groq) = {
-            let agent_guard = self.agent_type.lock().unwrap();
-            match &*agent_guard {
-                AgentType::Groq(agent) => (Some(agent.clone()), true),
-                AgentType::Mock => (None, false),
-            }
-        }; // Guard is dropped here
+        // Clone the agent handle outside the spawn
+        let agent = self.agent_type.lock().unwrap().clone(); // Guard is dropped here
+        let reasoning_traces = self.reasoning_traces.clone();
+        let is_groq = matches!(agent, AgentType::Groq(_));
 
         // Use the shared runtime to spawn async task (UniFFI best practice)
         let handle = self.runtime.spawn(async move {
             log::info!("🔍 Analyzing user input: {}", user_input.chars().take(50).collect::<String>());
 
-            let response = match groq_agent {
-                Some(agent) => {
-                    log::info!("🤖 Using Groq AI for analysis...");
-
-                    // Detect language for response
-                    let detected_language = Self::detect_language(&user_input);
+            // Detect language for response
+            let detected_language = Self::detect_language(&user_input);
 
-                    let prompt = format!(
-                        "The user will ask the following: {user_input}
+            let prompt = format!(
+                "The user will ask the following: {user_input}
 
 Before answering the question, please analyze the following sources:
 1. https://www.cryptohalal.cc/currencies/
@@ -206,31 +236,44 @@ Before answering the question, please analyze the following sources:
 Now please answer the user's question based on your analysis of these sources and Islamic finance principles.
 
 IMPORTANT: Please respond in {detected_language} language, the same language the user used in their question."
-                    );
+            );
+
+            let output = match agent {
+                AgentType::Groq(agent) => {
+                    log::info!("🤖 Using Groq AI for analysis...");
 
-                    // Use direct agent prompting (following Rig examples)
                     match agent.prompt(&prompt).await {
-                        Ok(response) => response,
+                        Ok(output) => output,
                         Err(e) => {
                             log::error!("❌ Groq API error: {e}");
                             return Err(AverroesError::AIError(e.to_string()));
                         },
                     }
                 },
-                None => {
+                AgentType::Mock(model) => {
                     log::info!("🎭 Using Mock agent for analysis...");
-                    format!(
-                        "**Analysis Response**\n\n🔴 **Mock Response for Testing**\n\n**Input:** {}\n\n**Note:** This is a \
-                         simulated response for testing purposes. In the real implementation, this would provide Islamic \
-                         finance guidance based on your question. Please consult qualified Islamic scholars for actual \
-                         religious guidance.", user_input.chars().take(50).collect::<String>()
-                    )
+
+                    match model.complete(&prompt).await {
+                        Ok(response) => ModelOutput::from_text(&response),
+                        Err(e) => {
+                            log::error!("❌ Mock agent error: {e}");
+                            return Err(AverroesError::AIError(e.to_string()));
+                        },
+                    }
                 },
             };
 
+            // Only the answer goes back to the user
+            let query_id = format!(
+                "analysis_{}_{}",
+                chrono::Utc::now().timestamp(),
+                user_input.chars().take(10).collect::<String>()
+            );
+            retain_reasoning_trace(&reasoning_traces, &query_id, &output);
+
             Ok(QueryResponse {
-                query_id: format!("analysis_{}_{}", chrono::Utc::now().timestamp(), user_input.chars().take(10).collect::<String>()),
-                response,
+                query_id,
+                response: output.answer,
                 confidence: if is_groq {
                     0.9
                 } else {
@@ -272,33 +315,25 @@ IMPORTANT: Please respond in {detected_language} language, the same language the
                 query_id: format!("query_{}", chrono::Utc::now().timestamp()),
                 response: error_message,
                 confidence: 0.0,
-                sources: vec!["Content Filter".to_string()],
+                sources: vec!["Content Filter".to_owned()],
                 timestamp: chrono::Utc::now().timestamp() as u64,
             });
         }
 
-        // Extract agent Arc or identify as Mock, outside the spawn
-        let (groq_agent, is_groq) = {
-            let agent_guard = self.agent_type.lock().unwrap();
-            match &*agent_guard {
-                AgentType::Groq(agent) => (Some(agent.clone()), true),
-                AgentType::Mock => (None, false),
-            }
-        }; // Guard is dropped here
+        // Clone the agent handle outside the spawn
+        let agent = self.agent_type.lock().unwrap().clone(); // Guard is dropped here
+        let reasoning_traces = self.reasoning_traces.clone();
+        let is_groq = matches!(agent, AgentType::Groq(_));
 
         // Use the shared runtime to spawn async task (UniFFI best practice)
         let handle = self.runtime.spawn(async move {
             log::info!("🤔 Processing general query: {}", question.chars().take(50).collect::<String>());
 
-            let response = match groq_agent {
-                Some(agent) => {
-                    log::info!("🤖 Using Groq AI for query...");
-
-                    // Detect language for response
-                    let detected_language = Self::detect_language(&question);
+            // Detect language for response
+            let detected_language = Self::detect_language(&question);
 
-                    let prompt = format!(
-                        "The user will ask the following: {question}
+            let prompt = format!(
+                "The user will ask the following: {question}
 
 Before answering the question, please analyze the following sources:
 1. https://www.cryptohalal.cc/currencies/
@@ -309,29 +344,40 @@ Before answering the question, please analyze the following sources:
 Now please answer the user's question based on your analysis of these sources and Islamic finance principles.
 
 IMPORTANT: Please respond in {detected_language} language, the same language the user used in their question."
-                    );
+            );
+
+            let output = match agent {
+                AgentType::Groq(agent) => {
+                    log::info!("🤖 Using Groq AI for query...");
 
-                    // Use direct agent prompting (following Rig examples)
                     match agent.prompt(&prompt).await {
-                        Ok(response) => response,
+                        Ok(output) => output,
                         Err(e) => {
                             log::error!("❌ Groq API error: {e}");
                             return Err(AverroesError::AIError(e.to_string()));
                         },
                     }
                 },
-                None => {
+                AgentType::Mock(model) => {
                     log::info!("🎭 Using Mock agent for query...");
-                    "**Mock Response**\n\nThis is a simulated response for testing purposes. In the real \
-                     implementation, this would provide Islamic finance guidance based on your question. Please \
-                     consult qualified Islamic scholars for actual religious guidance."
-                        .to_owned()
+
+                    match model.complete(&prompt).await {
+                        Ok(response) => ModelOutput::from_text(&response),
+                        Err(e) => {
+                            log::error!("❌ Mock agent error: {e}");
+                            return Err(AverroesError::AIError(e.to_string()));
+                        },
+                    }
                 },
             };
 
+            // Only the answer goes back to the user
+            let query_id = format!("query_{}", chrono::Utc::now().timestamp());
+            retain_reasoning_trace(&reasoning_traces, &query_id, &output);
+
             Ok(QueryResponse {
-                query_id: format!("query_{}", chrono::Utc::now().timestamp()),
-                response,
+                query_id,
+                response: output.answer,
                 confidence: if is_groq {
                     0.9
                 } else {
@@ -369,14 +415,9 @@ IMPORTANT: Please respond in {detected_language} language, the same language the
 
         let query_id = format!("token_{token}_{}", chrono::Utc::now().timestamp());
 
-        // Extract agent Arc or identify as Mock, outside the spawn
-        let (groq_agent, _is_groq) = {
-            let agent_guard = self.agent_type.lock().unwrap();
-            match &*agent_guard {
-                AgentType::Groq(agent) => (Some(agent.clone()), true),
-                AgentType::Mock => (None, false),
-            }
-        }; // Guard is dropped here
+        // Clone the agent handle outside the spawn
+        let agent = self.agent_type.lock().unwrap().clone(); // Guard is dropped here
+        let reasoning_traces = self.reasoning_traces.clone();
 
         // Use the shared runtime to spawn async task (UniFFI best practice)
         // All callback usage must happen inside this task
@@ -384,22 +425,24 @@ IMPORTANT: Please respond in {detected_language} language, the same language the
             .spawn(async move {
                 log::info!("🔍 Analyzing token with streaming: {token}");
 
-                let result = match groq_agent {
-                    Some(agent) => {
-                        log::info!("🤖 Using Groq AI for streaming analysis...");
+                let prompt = format!(
+                    "Analyze the cryptocurrency '{token}' from an Islamic finance perspective. 
+                 Consider factors like: speculation, utility, volatility, underlying technology.
+                 Provide a clear halal/haram ruling with reasoning."
+                );
 
-                        let prompt = format!(
-                            "Analyze the cryptocurrency '{token}' from an Islamic finance perspective. 
-                         Consider factors like: speculation, utility, volatility, underlying technology.
-                         Provide a clear halal/haram ruling with reasoning."
-                        );
+                let result = match agent {
+                    AgentType::Groq(agent) => {
+                        log::info!("🤖 Using Groq AI for streaming analysis...");
 
                         // Use regular completion with simulated streaming
                         match agent.prompt(&prompt).await {
-                            Ok(response) => {
+                            Ok(output) => {
                                 log::info!("✅ Got full response, simulating streaming...");
+                                // Stream the answer only; the reasoning trace is never sent to the user
+                                retain_reasoning_trace(&reasoning_traces, &query_id, &output);
                                 // Clean and format the response for better readability
-                                let full_response = format_ai_response(&response);
+                                let full_response = format_ai_response(&output.answer);
 
                                 // Simulate streaming by breaking response into words
                                 let words: Vec<&str> = full_response.split_whitespace().collect();
@@ -445,53 +488,61 @@ IMPORTANT: Please respond in {detected_language} language, the same language the
                             },
                         }
                     },
-                    None => {
+                    AgentType::Mock(model) => {
                         log::info!("🎭 Using Mock agent for streaming analysis...");
 
-                        // Simulate streaming for mock response
-                        let mock_response = format!(
-                            "**{token} Analysis**\n\n🔴 **Ruling: Haram (Prohibited)**\n\n**Reasoning:** Excessive \
-                             volatility and speculation make {token} problematic under Islamic finance principles. \
-                             The lack of intrinsic value and speculative nature conflict with Sharia guidelines on \
-                             risk and uncertainty (gharar).\n\n**Recommendation:** Consult with qualified Islamic \
-                             scholars for personalized guidance."
-                        );
-
-                        // Simulate streaming by sending chunks with delays
-                        let words: Vec<&str> = mock_response.split_whitespace().collect();
-                        let mut accumulated = String::new();
-
-                        for (i, word) in words.iter().enumerate() {
-                            if i > 0 {
-                                accumulated.push(' ');
-                            }
-                            accumulated.push_str(word);
+                        // Stream the scripted reply with its scripted chunking and delays, holding back reasoning
+                        let mut reasoning_stream = ReasoningStream::new();
+                        let mut chunk_index = 0;
+                        let streamed = model
+                            .stream(&prompt, |_, content| {
+                                let visible = reasoning_stream.push(content);
+                                if !visible.is_empty() {
+                                    callback.on_chunk(StreamChunk {
+                                        query_id: query_id.clone(),
+                                        content: visible,
+                                        is_final: false,
+                                        chunk_index,
+                                    });
+                                    chunk_index += 1;
+                                }
+                            })
+                            .await;
 
+                        let (rest, output) = reasoning_stream.finish();
+                        if !rest.is_empty() {
                             callback.on_chunk(StreamChunk {
                                 query_id: query_id.clone(),
-                                content: format!("{word} "),
+                                content: rest,
                                 is_final: false,
-                                chunk_index: i as u32,
+                                chunk_index,
                             });
-
-                            // Small delay to simulate streaming
-                            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
                         }
+                        retain_reasoning_trace(&reasoning_traces, &query_id, &output);
 
-                        // Send final response
-                        let final_response = QueryResponse {
-                            query_id: query_id.clone(),
-                            response: accumulated,
-                            confidence: 0.8,
-                            sources: vec![
-                                "Islamic Finance Analysis".to_owned(),
-                                "Mock Response (Streaming)".to_owned(),
-                            ],
-                            timestamp: chrono::Utc::now().timestamp() as u64,
-                        };
-
-                        callback.on_complete(final_response);
-                        Ok(())
+                        match streamed {
+                            Ok(_) => {
+                                // Send final response
+                                let final_response = QueryResponse {
+                                    query_id: query_id.clone(),
+                                    response: output.answer,
+                                    confidence: 0.8,
+                                    sources: vec![
+                                        "Islamic Finance Analysis".to_owned(),
+                                        "Mock Response (Streaming)".to_owned(),
+                                    ],
+                                    timestamp: chrono::Utc::now().timestamp() as u64,
+                                };
+
+                                callback.on_complete(final_response);
+                                Ok(())
+                            },
+                            Err(e) => {
+                                log::error!("❌ Mock agent error: {e}");
+                                callback.on_error(format!("Mock agent error: {e}"));
+                                Err(AverroesError::AIError(e.to_string()))
+                            },
+                        }
                     },
                 };
 
@@ -519,14 +570,9 @@ IMPORTANT: Please respond in {detected_language} language, the same language the
 
         let query_id = format!("query_{}", chrono::Utc::now().timestamp());
 
-        // Extract agent Arc or identify as Mock, outside the spawn
-        let (groq_agent, _is_groq) = {
-            let agent_guard = self.agent_type.lock().unwrap();
-            match &*agent_guard {
-                AgentType::Groq(agent) => (Some(agent.clone()), true),
-                AgentType::Mock => (None, false),
-            }
-        }; // Guard is dropped here
+        // Clone the agent handle outside the spawn
+        let agent = self.agent_type.lock().unwrap().clone(); // Guard is dropped here
+        let reasoning_traces = self.reasoning_traces.clone();
 
         // Use the shared runtime to spawn async task (UniFFI best practice)
         // All callback usage must happen inside this task
@@ -534,22 +580,23 @@ IMPORTANT: Please respond in {detected_language} language, the same language the
             .spawn(async move {
                 log::info!("🤔 Processing query with streaming: {}", question.chars().take(50).collect::<String>());
 
-                let result = match groq_agent {
-                    Some(agent) => {
-                        log::info!("🤖 Using Groq AI for streaming query...");
+                let prompt = format!(
+                    "From an Islamic finance and Fiqh perspective, please answer: {question}
+                 Provide clear guidance based on Sharia principles and recommend consulting scholars when appropriate."
+                );
 
-                        let prompt = format!(
-                            "From an Islamic finance and Fiqh perspective, please answer: {question}
-                         Provide clear guidance based on Sharia principles and recommend consulting scholars when \
-                             appropriate."
-                        );
+                let result = match agent {
+                    AgentType::Groq(agent) => {
+                        log::info!("🤖 Using Groq AI for streaming query...");
 
                         // Use regular completion with simulated streaming
                         match agent.prompt(&prompt).await {
-                            Ok(response) => {
+                            Ok(output) => {
                                 log::info!("✅ Got full response, simulating streaming...");
+                                // Stream the answer only; the reasoning trace is never sent to the user
+                                retain_reasoning_trace(&reasoning_traces, &query_id, &output);
                                 // Clean and format the response for better readability
-                                let full_response = format_ai_response(&response);
+                                let full_response = format_ai_response(&output.answer);
 
                                 // Simulate streaming by breaking response into words
                                 let words: Vec<&str> = full_response.split_whitespace().collect();
@@ -595,50 +642,61 @@ IMPORTANT: Please respond in {detected_language} language, the same language the
                             },
                         }
                     },
-                    None => {
+                    AgentType::Mock(model) => {
                         log::info!("🎭 Using Mock agent for streaming query...");
 
-                        // Mock streaming response
-                        let mock_response = "**Mock Response**\n\nThis is a simulated streaming response for testing \
-                                             purposes. In the real implementation, this would provide Islamic finance \
-                                             guidance based on your question. Please consult qualified Islamic \
-                                             scholars for actual religious guidance.";
-
-                        // Simulate streaming by sending chunks with delays
-                        let words: Vec<&str> = mock_response.split_whitespace().collect();
-                        let mut accumulated = String::new();
-
-                        for (i, word) in words.iter().enumerate() {
-                            if i > 0 {
-                                accumulated.push(' ');
-                            }
-                            accumulated.push_str(word);
+                        // Stream the scripted reply with its scripted chunking and delays, holding back reasoning
+                        let mut reasoning_stream = ReasoningStream::new();
+                        let mut chunk_index = 0;
+                        let streamed = model
+                            .stream(&prompt, |_, content| {
+                                let visible = reasoning_stream.push(content);
+                                if !visible.is_empty() {
+                                    callback.on_chunk(StreamChunk {
+                                        query_id: query_id.clone(),
+                                        content: visible,
+                                        is_final: false,
+                                        chunk_index,
+                                    });
+                                    chunk_index += 1;
+                                }
+                            })
+                            .await;
 
+                        let (rest, output) = reasoning_stream.finish();
+                        if !rest.is_empty() {
                             callback.on_chunk(StreamChunk {
                                 query_id: query_id.clone(),
-                                content: format!("{word} "),
+                                content: rest,
                                 is_final: false,
-                                chunk_index: i as u32,
+                                chunk_index,
                             });
-
-                            // Small delay to simulate streaming
-                            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
                         }
+                        retain_reasoning_trace(&reasoning_traces, &query_id, &output);
 
-                        // Send final response
-                        let final_response = QueryResponse {
-                            query_id: query_id.clone(),
-                            response: accumulated,
-                            confidence: 0.7,
-                            sources: vec![
-                                "Islamic Finance Knowledge".to_owned(),
-                                "Mock Response (Streaming)".to_owned(),
-                            ],
-                            timestamp: chrono::Utc::now().timestamp() as u64,
-                        };
-
-                        callback.on_complete(final_response);
-                        Ok(())
+                        match streamed {
+                            Ok(_) => {
+                                // Send final response
+                                let final_response = QueryResponse {
+                                    query_id: query_id.clone(),
+                                    response: output.answer,
+                                    confidence: 0.7,
+                                    sources: vec![
+                                        "Islamic Finance Knowledge".to_owned(),
+                                        "Mock Response (Streaming)".to_owned(),
+                                    ],
+                                    timestamp: chrono::Utc::now().timestamp() as u64,
+                                };
+
+                                callback.on_complete(final_response);
+                                Ok(())
+                            },
+                            Err(e) => {
+                                log::error!("❌ Mock agent error: {e}");
+                                callback.on_error(format!("Mock agent error: {e}"));
+                                Err(AverroesError::AIError(e.to_string()))
+                            },
+                        }
                     },
                 };
 
@@ -662,13 +720,28 @@ IMPORTANT: Please respond in {detected_language} language, the same language the
 
         let info = match &*self.agent_type.lock().unwrap() {
             AgentType::Groq(_) => "Groq AI".to_owned(),
-            AgentType::Mock => "Mock Agent".to_owned(),
+            AgentType::Mock(_) => "Mock Agent".to_owned(),
         };
 
         log::warn!("🔥 RUST DEBUG: Returning agent info: {info}");
         info
     }
 
+    /// Reasoning behind a past answer, for audit and debugging
+    ///
+    /// Traces are only kept when `AVERROES_RETAIN_REASONING` is set; otherwise this is always `None`.
+    pub fn get_reasoning_trace(
+        &self,
+        query_id: String,
+    ) -> Option<String> {
+        let traces = self.reasoning_traces.lock().unwrap();
+        traces
+            .iter()
+            .rev()
+            .find(|(id, _)| *id == query_id)
+            .map(|(_, reasoning)| reasoning.clone())
+    }
+
     /// Check if real AI is active
     pub fn is_using_real_ai(&self) -> bool {
         log::warn!("🔥 RUST DEBUG: is_using_real_ai() called!");
@@ -695,7 +768,7 @@ impl AverroesSystem {
            input_lower.contains("make image") ||
            input_lower.contains("draw") ||
            input_lower.contains("picture") && (input_lower.contains("generate") || input_lower.contains("create")) {
-            return Some("I'm sorry, but I cannot generate images. I'm specialized in Islamic finance guidance and can help you with cryptocurrency analysis, halal investment questions, and Sharia compliance matters.".to_string());
+            return Some("I'm sorry, but I cannot generate images. I'm specialized in Islamic finance guidance and can help you with cryptocurrency analysis, halal investment questions, and Sharia compliance matters.".to_owned());
         }
 
         // Block code generation requests
@@ -703,14 +776,14 @@ impl AverroesSystem {
            input_lower.contains("write code") ||
            input_lower.contains("create code") ||
            input_lower.contains("programming") && (input_lower.contains("help") || input_lower.contains("write")) {
-            return Some("I'm sorry, but I cannot generate programming code. I'm specialized in Islamic finance guidance. I can help you with cryptocurrency analysis, halal investment questions, and Sharia compliance matters.".to_string());
+            return Some("I'm sorry, but I cannot generate programming code. I'm specialized in Islamic finance guidance. I can help you with cryptocurrency analysis, halal investment questions, and Sharia compliance matters.".to_owned());
         }
 
         // Block inappropriate content
         if input_lower.contains("hack") ||
            input_lower.contains("illegal") ||
            input_lower.contains("fraud") {
-            return Some("I cannot provide guidance on illegal or unethical activities. I'm here to help with Islamic finance questions and halal investment guidance.".to_string());
+            return Some("I cannot provide guidance on illegal or unethical activities. I'm here to help with Islamic finance questions and halal investment guidance.".to_owned());
         }
 
         None // No filtering needed
@@ -721,7 +794,7 @@ impl AverroesSystem {
         let input_lower = input.to_lowercase();
 
         // Arabic detection
-        if input.chars().any(|c| c >= '\u{0600}' && c <= '\u{06FF}') {
+        if input.chars().any(|c| ('\u{0600}'..='\u{06FF}').contains(&c)) {
             return "Arabic";
         }
 
@@ -736,30 +809,74 @@ impl AverroesSystem {
         "English"
     }
 
-    /// Create Groq agent following Rig example pattern
-    async fn create_groq_agent()
-    -> Result<rig::agent::Agent<groq::CompletionModel>, Box<dyn std::error::Error + Send + Sync>> {
-        // Following the Rig example pattern
-        let client = groq::Client::new("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");
-
-        let agent = client
-            .agent(groq::DEEPSEEK_R1_DISTILL_LLAMA_70B)
-            .preamble(
-                "You are an expert Islamic scholar specializing in Islamic finance and Fiqh. 
-                      Provide clear, balanced analysis based on Sharia principles. 
-                      Always include confidence levels and recommend consulting qualified scholars for important \
-                 decisions.",
-            )
-            .build();
-
-        Ok(agent)
+    /// Create the Groq agent from `GROQ_API_KEY`, sending requests through the cassette-aware HTTP client
+    async fn create_groq_agent() -> Result<GroqAgent, Box<dyn std::error::Error + Send + Sync>> {
+        let config = AverroesConfig::default();
+        if config.groq_api_key.is_empty() {
+            return Err("GROQ_API_KEY is not set".into());
+        }
+
+        let provider = ProviderConfig {
+            model: config.model_name.clone(),
+            ..ProviderConfig::groq(config.groq_api_key)
+        };
+        let http_client = HttpClient::from_env(reqwest::Client::new(), "groq_agent");
+
+        Ok(GroqAgent {
+            client: ChatClient::with_http_client(provider, http_client),
+            // Room for the reasoning trace on top of the answer
+            max_tokens: completion_budget(&config.model_name, 1500),
+        })
+    }
+}
+
+/// Groq chat model with the scholar preamble
+struct GroqAgent {
+    client: ChatClient,
+    max_tokens: u32,
+}
+
+impl GroqAgent {
+    const PREAMBLE: &str = "You are an expert Islamic scholar specializing in Islamic finance and Fiqh. Provide clear, \
+                            balanced analysis based on Sharia principles. Always include confidence levels and \
+                            recommend consulting qualified scholars for important decisions.";
+
+    async fn prompt(
+        &self,
+        prompt: &str,
+    ) -> Result<ModelOutput, HttpError> {
+        let request = ChatRequest::new(vec![ChatMessage::system(Self::PREAMBLE), ChatMessage::user(prompt)])
+            .with_max_tokens(self.max_tokens);
+        Ok(self.client.chat(&request).await?.output)
+    }
+}
+
+/// Keep a split-off reasoning trace when retention is enabled, dropping the oldest beyond the cap
+fn retain_reasoning_trace(
+    traces: &ReasoningTraces,
+    query_id: &str,
+    output: &ModelOutput,
+) {
+    log::info!(
+        "📊 Completion tokens: {} answer + {} reasoning",
+        output.usage.answer_tokens,
+        output.usage.reasoning_tokens
+    );
+
+    let Some(reasoning) = output.retained_reasoning() else {
+        return;
+    };
+
+    let mut traces = traces.lock().unwrap();
+    if traces.len() == MAX_RETAINED_TRACES {
+        traces.pop_front();
     }
+    traces.push_back((query_id.to_owned(), reasoning));
 }
 
-/// Format AI response with proper paragraphs and structure for better readability
-fn format_ai_response(raw_response: &str) -> String {
-    // Remove thinking tags and clean up
-    let cleaned = raw_response.replace("<think>", "").replace("</think>", "").trim().to_owned();
+/// Format the answer part of an AI response with proper paragraphs and structure for better readability
+fn format_ai_response(answer: &str) -> String {
+    let cleaned = answer.trim().to_owned();
 
     // Add paragraph breaks for better readability
     let formatted = cleaned
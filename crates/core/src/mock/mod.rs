@@ -0,0 +1,12 @@
+//! Scriptable mock language model.
+//!
+//! Responses come from a JSON scenario file instead of being hard-coded, so QA can drive the
+//! analyzer and chat flows through success, error, rate-limit and streaming paths without a network.
+//! Point `AVERROES_MOCK_SCENARIO` at a scenario file to use it; otherwise the bundled default scenario
+//! (the classic canned mock answers) is used.
+
+pub mod model;
+pub mod scenario;
+
+pub use model::*;
+pub use scenario::*;
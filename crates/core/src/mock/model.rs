@@ -0,0 +1,332 @@
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use regex::Captures;
+use regex::Regex;
+use tracing::debug;
+
+use super::scenario::MockScenario;
+use super::scenario::ScenarioStep;
+use super::scenario::StepOutcome;
+
+/// Environment variable pointing at a scenario file
+pub const MOCK_SCENARIO_ENV: &str = "AVERROES_MOCK_SCENARIO";
+
+/// Scenario used when none is configured; reproduces the classic canned mock answers
+const BUILTIN_SCENARIO: &str = include_str!("default_scenario.json");
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum MockModelError {
+    #[error("Scenario error: {0}")]
+    Scenario(String),
+
+    #[error("No scripted response for prompt: {0}")]
+    NoMatch(String),
+
+    #[error("Scripted failure: {0}")]
+    Scripted(String),
+
+    #[error("Rate limited, retry after {retry_after_ms}ms")]
+    RateLimited {
+        retry_after_ms: u64,
+    },
+}
+
+/// Language model that answers from a [`MockScenario`] instead of a provider
+#[derive(Debug)]
+pub struct ScriptedModel {
+    scenario: MockScenario,
+    patterns: Vec<Regex>,
+    cursors: Mutex<Vec<usize>>,
+    calls: Mutex<Vec<String>>,
+}
+
+impl ScriptedModel {
+    pub fn new(scenario: MockScenario) -> Result<Self, MockModelError> {
+        scenario.validate().map_err(MockModelError::Scenario)?;
+
+        let patterns = scenario
+            .rules
+            .iter()
+            .map(|rule| Regex::new(&rule.pattern))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| MockModelError::Scenario(e.to_string()))?;
+        let cursors = Mutex::new(vec![0; scenario.rules.len()]);
+
+        Ok(Self {
+            scenario,
+            patterns,
+            cursors,
+            calls: Mutex::new(Vec::new()),
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, MockModelError> {
+        Self::new(MockScenario::load(path.as_ref()).map_err(MockModelError::Scenario)?)
+    }
+
+    pub fn from_json(raw: &str) -> Result<Self, MockModelError> {
+        Self::new(MockScenario::from_json(raw).map_err(MockModelError::Scenario)?)
+    }
+
+    /// The bundled default scenario
+    pub fn builtin() -> Self {
+        Self::from_json(BUILTIN_SCENARIO).expect("Bundled mock scenario must be valid")
+    }
+
+    /// Scenario named by `AVERROES_MOCK_SCENARIO`, or `None` when the variable is unset
+    pub fn from_env() -> Result<Option<Self>, MockModelError> {
+        match std::env::var(MOCK_SCENARIO_ENV) {
+            Ok(path) if !path.is_empty() => Self::from_file(path).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Scenario from the environment, falling back to the bundled one
+    pub fn from_env_or_builtin() -> Result<Self, MockModelError> {
+        Ok(Self::from_env()?.unwrap_or_else(Self::builtin))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.scenario.name
+    }
+
+    /// Every prompt received so far, in order
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().map(|calls| calls.clone()).unwrap_or_default()
+    }
+
+    pub fn follow_up_questions(&self) -> Vec<String> {
+        self.scenario.follow_up_questions.clone()
+    }
+
+    /// Answer a prompt in one piece
+    pub async fn complete(
+        &self,
+        prompt: &str,
+    ) -> Result<String, MockModelError> {
+        let (step, text) = self.next_step(prompt)?;
+        sleep_ms(step.latency_ms).await;
+
+        match step.outcome {
+            // Replies always render to text
+            StepOutcome::Reply {
+                ..
+            } => Ok(text.unwrap_or_default()),
+            StepOutcome::Error {
+                message, ..
+            } => Err(MockModelError::Scripted(message)),
+            StepOutcome::RateLimited {
+                retry_after_ms,
+            } => Err(MockModelError::RateLimited {
+                retry_after_ms,
+            }),
+        }
+    }
+
+    /// Answer a prompt chunk by chunk, calling `on_chunk(index, content)` for each piece
+    pub async fn stream<F>(
+        &self,
+        prompt: &str,
+        mut on_chunk: F,
+    ) -> Result<String, MockModelError>
+    where
+        F: FnMut(u32, &str), {
+        let (step, text) = self.next_step(prompt)?;
+        sleep_ms(step.latency_ms).await;
+
+        let chunks = match &step.outcome {
+            StepOutcome::Reply {
+                chunks, ..
+            } if chunks.is_empty() => text
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(|word| format!("{word} "))
+                .collect(),
+            StepOutcome::Reply {
+                chunks, ..
+            }
+            | StepOutcome::Error {
+                chunks, ..
+            } => chunks.clone(),
+            StepOutcome::RateLimited {
+                ..
+            } => Vec::new(),
+        };
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            if index > 0 {
+                sleep_ms(step.chunk_delay_ms).await;
+            }
+            on_chunk(index as u32, chunk);
+        }
+
+        match step.outcome {
+            StepOutcome::Reply {
+                ..
+            } => Ok(text.unwrap_or_else(|| chunks.concat())),
+            StepOutcome::Error {
+                message, ..
+            } => Err(MockModelError::Scripted(message)),
+            StepOutcome::RateLimited {
+                retry_after_ms,
+            } => Err(MockModelError::RateLimited {
+                retry_after_ms,
+            }),
+        }
+    }
+
+    /// Pick the step answering `prompt` and render its reply text, advancing the rule's cursor
+    fn next_step(
+        &self,
+        prompt: &str,
+    ) -> Result<(ScenarioStep, Option<String>), MockModelError> {
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(prompt.to_owned());
+        }
+
+        let mut cursors = self.cursors.lock().map_err(|e| MockModelError::Scenario(e.to_string()))?;
+
+        for (index, (rule, pattern)) in self.scenario.rules.iter().zip(&self.patterns).enumerate() {
+            let Some(captures) = pattern.captures(prompt) else {
+                continue;
+            };
+
+            let position = cursors[index];
+            let step = match rule.steps.get(position) {
+                Some(step) => step,
+                None if rule.repeat_last => &rule.steps[rule.steps.len() - 1],
+                // Exhausted rules fall through so later rules can take over
+                None => continue,
+            };
+
+            cursors[index] += 1;
+            debug!("Mock scenario '{}' answering with rule {} step {}", self.scenario.name, index, position);
+
+            let text = render(step, prompt, Some((pattern, &captures)));
+            return Ok((step.clone(), text));
+        }
+
+        match &self.scenario.default {
+            Some(step) => Ok((step.clone(), render(step, prompt, None))),
+            None => Err(MockModelError::NoMatch(prompt.chars().take(80).collect())),
+        }
+    }
+}
+
+/// Fill `{{prompt}}` and `{{group}}` placeholders in a reply
+fn render(
+    step: &ScenarioStep,
+    prompt: &str,
+    captures: Option<(&Regex, &Captures<'_>)>,
+) -> Option<String> {
+    let StepOutcome::Reply {
+        text,
+        chunks,
+    } = &step.outcome
+    else {
+        return None;
+    };
+
+    let mut rendered = text.clone().unwrap_or_else(|| chunks.concat()).replace("{{prompt}}", prompt);
+
+    if let Some((pattern, captures)) = captures {
+        for name in pattern.capture_names().flatten() {
+            let value = captures.name(name).map(|m| m.as_str()).unwrap_or_default();
+            rendered = rendered.replace(&format!("{{{{{name}}}}}"), value);
+        }
+    }
+
+    Some(rendered)
+}
+
+async fn sleep_ms(ms: u64) {
+    if ms > 0 {
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENARIO: &str = r#"{
+        "version": 1,
+        "name": "unit",
+        "rules": [
+            {
+                "pattern": "Token: (?P<ticker>[A-Z]+)",
+                "steps": [
+                    { "kind": "rate_limited", "retry_after_ms": 1500 },
+                    { "kind": "reply", "text": "RULING: HALAL for {{ticker}}" }
+                ]
+            },
+            {
+                "pattern": "stream me",
+                "steps": [
+                    { "kind": "reply", "chunks": ["Hal", "al"] },
+                    { "kind": "error", "message": "connection reset", "chunks": ["Par"] }
+                ],
+                "repeat_last": false
+            }
+        ],
+        "follow_up_questions": ["Next?"]
+    }"#;
+
+    #[tokio::test]
+    async fn test_rule_steps_advance_and_repeat() {
+        let model = ScriptedModel::from_json(SCENARIO).unwrap();
+
+        let first = model.complete("Token: SOL").await.unwrap_err();
+        assert!(matches!(first, MockModelError::RateLimited {
+            retry_after_ms: 1500
+        }));
+        assert_eq!(model.complete("Token: SOL").await.unwrap(), "RULING: HALAL for SOL");
+        assert_eq!(model.complete("Token: BTC").await.unwrap(), "RULING: HALAL for BTC");
+        assert_eq!(model.calls().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_stream_chunks_and_partial_error() {
+        let model = ScriptedModel::from_json(SCENARIO).unwrap();
+
+        let mut received = Vec::new();
+        let text = model
+            .stream("stream me", |index, chunk| received.push((index, chunk.to_owned())))
+            .await;
+        assert_eq!(text.unwrap(), "Halal");
+        assert_eq!(received, vec![(0, "Hal".to_owned()), (1, "al".to_owned())]);
+
+        received.clear();
+        let err = model
+            .stream("stream me", |index, chunk| received.push((index, chunk.to_owned())))
+            .await;
+        assert!(matches!(err, Err(MockModelError::Scripted(_))));
+        assert_eq!(received, vec![(0, "Par".to_owned())]);
+
+        // Exhausted and not repeating, with no default: the prompt is unmatched
+        assert!(matches!(model.complete("stream me").await, Err(MockModelError::NoMatch(_))));
+    }
+
+    #[test]
+    fn test_builtin_scenario_is_valid() {
+        let model = ScriptedModel::builtin();
+        assert_eq!(model.name(), "builtin");
+        assert!(!model.follow_up_questions().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_scenario_rejected() {
+        let missing_text = r#"{ "version": 1, "name": "x", "rules": [
+            { "pattern": "a", "steps": [ { "kind": "reply" } ] }
+        ] }"#;
+        assert!(matches!(ScriptedModel::from_json(missing_text), Err(MockModelError::Scenario(_))));
+
+        let bad_pattern = r#"{ "version": 1, "name": "x", "rules": [
+            { "pattern": "(", "steps": [ { "kind": "reply", "text": "a" } ] }
+        ] }"#;
+        assert!(matches!(ScriptedModel::from_json(bad_pattern), Err(MockModelError::Scenario(_))));
+    }
+}
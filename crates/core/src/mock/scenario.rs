@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use regex::Regex;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Current scenario file format
+pub const SCENARIO_VERSION: u32 = 1;
+
+/// A scripted conversation: prompt patterns mapped to sequences of outcomes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockScenario {
+    pub version: u32,
+    pub name: String,
+    /// Rules are tried in order; the first whose pattern matches the prompt wins
+    #[serde(default)]
+    pub rules: Vec<ScenarioRule>,
+    /// Used when no rule matches; without it an unmatched prompt is an error
+    #[serde(default)]
+    pub default: Option<ScenarioStep>,
+    #[serde(default)]
+    pub follow_up_questions: Vec<String>,
+}
+
+/// A prompt pattern and the outcomes it produces on successive matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioRule {
+    /// Regex matched against the full prompt; named groups can be used as `{{name}}` in responses
+    pub pattern: String,
+    pub steps: Vec<ScenarioStep>,
+    /// Keep answering with the last step once the sequence is exhausted
+    #[serde(default = "default_repeat_last")]
+    pub repeat_last: bool,
+}
+
+/// One scripted answer with its timing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioStep {
+    #[serde(flatten)]
+    pub outcome: StepOutcome,
+    /// Delay before the first byte
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// Delay between streamed chunks
+    #[serde(default)]
+    pub chunk_delay_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StepOutcome {
+    /// A successful completion; `chunks` controls how it streams, `text` defaults to their concatenation
+    Reply {
+        #[serde(default)]
+        text: Option<String>,
+        #[serde(default)]
+        chunks: Vec<String>,
+    },
+    /// A provider failure, optionally after streaming some partial chunks
+    Error {
+        message: String,
+        #[serde(default)]
+        chunks: Vec<String>,
+    },
+    /// A 429-style rejection
+    RateLimited {
+        retry_after_ms: u64,
+    },
+}
+
+fn default_repeat_last() -> bool {
+    true
+}
+
+impl MockScenario {
+    /// Load and validate a scenario file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::from_json(&raw).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Parse and validate a scenario from JSON
+    pub fn from_json(raw: &str) -> Result<Self, String> {
+        let scenario: MockScenario = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Check version, patterns and steps so mistakes surface at load time rather than mid-test
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != SCENARIO_VERSION {
+            return Err(format!("unsupported scenario version {} (expected {SCENARIO_VERSION})", self.version));
+        }
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            Regex::new(&rule.pattern).map_err(|e| format!("rule {index}: invalid pattern: {e}"))?;
+
+            if rule.steps.is_empty() {
+                return Err(format!("rule {index}: at least one step is required"));
+            }
+
+            for step in &rule.steps {
+                step.validate().map_err(|e| format!("rule {index}: {e}"))?;
+            }
+        }
+
+        if let Some(step) = &self.default {
+            step.validate().map_err(|e| format!("default: {e}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ScenarioStep {
+    fn validate(&self) -> Result<(), String> {
+        match &self.outcome {
+            StepOutcome::Reply {
+                text: None,
+                chunks,
+            } if chunks.is_empty() => Err("reply needs `text` or `chunks`".to_owned()),
+            _ => Ok(()),
+        }
+    }
+}
@@ -45,6 +45,28 @@ pub struct ScrapedData {
     pub scraped_at: u64, // Unix timestamp for UniFFI
 }
 
+/// Instruction-like text found in untrusted content and neutralized before prompting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct InjectionFinding {
+    pub source_url: String,
+    pub rule: String,    // Name of the detection rule that fired
+    pub excerpt: String, // Offending text, truncated
+}
+
+/// A screening rule that fired, with the facts that triggered it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct RuleHit {
+    pub pack: String,    // Rule pack the rule came from
+    pub rule_id: String, // e.g. "maysir.gambling"
+    pub title: String,
+    pub principle: IslamicPrinciple,
+    pub weight: f64,          // Score adjustment applied
+    pub decisive: bool,       // Settled the ruling on its own
+    pub matched: Vec<String>, // Conditions that held, e.g. "text contains \"casino\""
+    pub evidence: Vec<String>,
+    pub explanation: String,
+}
+
 /// Backtest comparison result
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
 pub struct BacktestResult {
@@ -67,6 +89,9 @@ pub struct ConfidenceBreakdown {
     pub consensus_level: f64,
     pub data_freshness: f64,
     pub overall_confidence: f64,
+    /// Measured gharar of the token itself, 0-1, when market data was available
+    #[serde(default)]
+    pub gharar_score: Option<f64>,
 }
 
 /// User feedback on analysis
@@ -94,6 +119,14 @@ pub struct TokenAnalysis {
     pub confidence_breakdown: ConfidenceBreakdown,
     pub backtest_results: Vec<BacktestResult>,
     pub user_feedback: Option<UserFeedback>,
+    /// Set when scraped or user-supplied content tried to steer the model
+    #[serde(default)]
+    pub injection_detected: bool,
+    #[serde(default)]
+    pub injection_findings: Vec<InjectionFinding>,
+    /// Model reasoning behind the ruling, kept only when `AVERROES_RETAIN_REASONING` is set
+    #[serde(default)]
+    pub reasoning_trace: Option<String>,
 }
 
 impl TokenAnalysis {
@@ -103,13 +136,25 @@ impl TokenAnalysis {
             query_id: query_id.to_string(),
             token_info: None,
             islamic_analysis: IslamicAnalysis {
-                ruling: IslamicPrinciple::Mubah,
-                confidence: 0.5,
+                ruling: IslamicPrinciple::Syubhat, // Undecided until the analysis completes
+                confidence: 0.0,
                 reasoning: String::new(),
                 supporting_fatwas: Vec::new(),
                 risk_factors: Vec::new(),
                 recommendations: Vec::new(),
                 maqashid_assessment: Vec::new(),
+                ratio_screening: None,
+                methodology_rulings: vec![],
+                gharar: None,
+                maysir: None,
+                riba: None,
+                stablecoin: None,
+                scripture_references: vec![],
+                ikhtilaf: None,
+                determination: None,
+                review: None,
+                categories: vec![],
+                document_excerpts: vec![],
             },
             scraped_data: Vec::new(),
             status: AnalysisStatus::Pending,
@@ -123,9 +168,13 @@ impl TokenAnalysis {
                 consensus_level: 0.0,
                 data_freshness: 0.0,
                 overall_confidence: 0.0,
+                gharar_score: None,
             },
             backtest_results: Vec::new(),
             user_feedback: None,
+            injection_detected: false,
+            injection_findings: Vec::new(),
+            reasoning_trace: None,
         }
     }
 
@@ -284,6 +333,7 @@ impl Default for ConfidenceBreakdown {
             consensus_level: 0.0,
             data_freshness: 0.0,
             overall_confidence: 0.0,
+            gharar_score: None,
         }
     }
 }
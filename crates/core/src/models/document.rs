@@ -0,0 +1,76 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// File format of a project document
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Pdf,
+    Markdown, // Plain text is read as Markdown without headings
+    Html,
+}
+
+/// Part of a whitepaper the analysis reads closely
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, uniffi::Enum)]
+pub enum DocumentSectionKind {
+    Tokenomics,   // Supply, allocation, vesting and emissions
+    RevenueModel, // Where the project's income comes from
+    UseOfFunds,   // What raised money is spent on
+}
+
+/// A headed stretch of document text on a single page
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DocumentSection {
+    pub heading: Option<String>, // None for text before the first heading
+    pub level: u8,               // 1 for top-level headings, 0 without a heading
+    pub page: Option<u32>,       // 1-based; None for Markdown and HTML
+    pub continued: bool,         // Carries on the section from the previous page
+    pub text: String,
+}
+
+/// Consecutive words of one section, embedded for retrieval
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DocumentChunk {
+    pub index: usize,
+    pub heading: Option<String>,
+    pub page: Option<u32>,
+    pub text: String,
+    pub embedding: Vec<f32>, // Unit length
+}
+
+/// A key section of a project document, quoted into the analysis with where it was found
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct DocumentExcerpt {
+    pub kind: DocumentSectionKind,
+    pub document_id: String, // UUID as String for UniFFI
+    pub source: String,      // URL or uploaded file name
+    pub heading: Option<String>,
+    pub pages: Vec<u32>, // Pages the excerpt spans; empty for Markdown and HTML
+    pub text: String,
+}
+
+impl DocumentExcerpt {
+    /// Where the excerpt comes from, e.g. `whitepaper.pdf, pp. 4-5`
+    pub fn citation(&self) -> String {
+        let location = match (self.pages.first(), self.pages.last()) {
+            (Some(first), Some(last)) if first != last => format!("pp. {first}-{last}"),
+            (Some(page), _) => format!("p. {page}"),
+            _ => format!("\"{}\"", self.heading.as_deref().unwrap_or("untitled section")),
+        };
+        format!("{}, {location}", self.source)
+    }
+}
+
+/// A whitepaper or other project document, as ingested
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectDocument {
+    pub id: String,
+    pub token: String, // Mint address
+    pub source: String,
+    pub format: DocumentFormat,
+    pub title: Option<String>,
+    pub page_count: Option<u32>, // PDFs only
+    pub sections: Vec<DocumentSection>,
+    pub chunks: Vec<DocumentChunk>,
+    pub key_sections: Vec<DocumentExcerpt>,
+    pub ingested_at: u64, // Unix seconds
+}
@@ -4,7 +4,11 @@ use serde::Deserialize;
 use serde::Serialize;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Enum)]
+use super::document::DocumentExcerpt;
+use super::review::ReviewDecision;
+use super::token::CategoryLabel;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
 pub enum FatwaSource {
     MUI,    // Majelis Ulama Indonesia
     AAOIFI, // Accounting and Auditing Organization for Islamic Financial Institutions
@@ -38,7 +42,10 @@ pub struct MaqashidPrinciple {
     pub name: String,
     pub category: String, // e.g., "Hifz al-Mal" (Preservation of Wealth)
     pub description: String,
-    pub relevance_score: f64, // 0-1
+    pub relevance_score: f64, // 0-1, higher when the token serves the objective
+    /// Evidence behind the score, one line per signal
+    #[serde(default)]
+    pub rationale: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +62,20 @@ pub struct Fatwa {
     pub created_at: DateTime<Utc>,
     pub vector_embedding: Option<Vec<f32>>, // For vector search
     pub confidence_score: Option<f64>,      // Relevance to query
+    /// Issuer's own number, e.g. "116/DSN-MUI/IX/2017"
+    #[serde(default)]
+    pub reference_number: Option<String>,
+    /// 1 for the first text of a fatwa, one higher for each revision
+    #[serde(default = "first_version")]
+    pub version: u32,
+    #[serde(default)]
+    pub supersedes: Option<Uuid>, // Previous version
+    #[serde(default)]
+    pub superseded_by: Option<Uuid>, // Next version; `None` for the version in force
+}
+
+fn first_version() -> u32 {
+    1
 }
 
 /// Reference to supporting fatwa
@@ -76,6 +97,420 @@ pub struct IslamicAnalysis {
     pub risk_factors: Vec<String>,
     pub recommendations: Vec<String>,
     pub maqashid_assessment: Vec<MaqashidPrinciple>,
+    /// Financial-ratio screening, when project financials are available
+    #[serde(default)]
+    pub ratio_screening: Option<RatioScreening>,
+    /// One ruling per screening standard the user follows
+    #[serde(default)]
+    pub methodology_rulings: Vec<MethodologyRuling>,
+    /// Uncertainty measured from market and on-chain data
+    #[serde(default)]
+    pub gharar: Option<GhararAssessment>,
+    /// Likelihood that the token serves games of chance
+    #[serde(default)]
+    pub maysir: Option<MaysirAssessment>,
+    /// Structured riba exposure
+    #[serde(default)]
+    pub riba: Option<RibaAnalysis>,
+    /// Peg mechanism, reserves and usage conditions, when the token is a stablecoin
+    #[serde(default)]
+    pub stablecoin: Option<StablecoinAnalysis>,
+    /// Quran and hadith cited by the analysis, with their text
+    #[serde(default)]
+    pub scripture_references: Vec<ScriptureReference>,
+    /// Positions held by the sources consulted, when they stated one
+    #[serde(default)]
+    pub ikhtilaf: Option<IkhtilafMap>,
+    /// How the ruling was reached from the evidence, or why no ruling was reached
+    #[serde(default)]
+    pub determination: Option<RulingDetermination>,
+    /// Scholar override that set the ruling, when one is in force for the token
+    #[serde(default)]
+    pub review: Option<ReviewDecision>,
+    /// Categories of the token taxonomy, most confident first
+    #[serde(default)]
+    pub categories: Vec<CategoryLabel>,
+    /// Key whitepaper sections read for the analysis, with page citations
+    #[serde(default)]
+    pub document_excerpts: Vec<DocumentExcerpt>,
+}
+
+/// Outcome of one ratio check, or of a whole screening
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum RatioStatus {
+    Pass,
+    Fail,
+    Unavailable, // Figures needed for the ratio were not reported
+}
+
+/// One financial ratio checked against a methodology's limit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct RatioCheck {
+    pub name: String,        // e.g. "interest_bearing_debt"
+    pub description: String, // e.g. "Interest-bearing debt / market cap"
+    pub value: Option<f64>,  // 0-1, None when unavailable
+    pub threshold: f64,      // Maximum allowed, 0-1
+    pub status: RatioStatus,
+}
+
+/// Quantitative screening of a project's treasury and revenue
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct RatioScreening {
+    pub methodology: String, // e.g. "aaoifi"
+    pub ratios: Vec<RatioCheck>,
+    pub status: RatioStatus, // Fail if any ratio fails, Unavailable if any is missing
+    pub reporting_period: Option<String>,
+}
+
+/// How much uncertainty a token carries, in the classical grading
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum GhararLevel {
+    Yasir,        // Minor, tolerated in any sale
+    Mutawassit,   // Moderate, calls for caution
+    Fahish,       // Excessive, invalidates the sale
+    Undetermined, // Too few inputs were available to grade it
+}
+
+/// One measured source of gharar
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct GhararFactor {
+    pub name: String,        // e.g. "holder_concentration"
+    pub value: Option<f64>,  // Measured input, None when unavailable
+    pub risk: Option<f64>,   // 0-1, None when unavailable
+    pub weight: f64,         // Share of the score this factor carries when available
+    pub contribution: f64,   // Points added to the score
+    pub explanation: String, // e.g. "Top 10 holders own 65.0% of supply"
+}
+
+/// Gharar score computed from measurable inputs, with the factors behind it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct GhararAssessment {
+    pub score: f64, // 0-1, higher is more uncertain
+    pub level: GhararLevel,
+    pub coverage: f64, // 0-1, weight of the factors that could be measured
+    pub factors: Vec<GhararFactor>,
+}
+
+/// What kind of gambling a piece of maysir evidence points to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum MaysirCategory {
+    Casino,
+    Lottery,
+    PredictionMarket,
+    Tokenomics, // Lottery-like mechanics built into the token
+    Pump,       // Meme-coin pump dynamics
+}
+
+/// One observation suggesting a token serves maysir
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct MaysirEvidence {
+    pub source: String, // Signal id or gambling program name
+    pub category: MaysirCategory,
+    pub likelihood: f64,  // 0-1, from this observation alone
+    pub observed: String, // Conditions that held, or the program interaction
+    pub rationale: String,
+    pub references: Vec<String>,
+}
+
+/// Probability that a token is used for gambling, with the evidence behind it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct MaysirAssessment {
+    pub probability: f64, // 0-1
+    pub evidence: Vec<MaysirEvidence>,
+}
+
+/// How a token exposes its holder to riba
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum RibaKind {
+    LendingReceipt,       // Receipt for a deposit in a lending protocol (cToken/aToken style)
+    InterestBearingMint,  // SPL Token-2022 interest-bearing extension
+    RebasingLendingYield, // Balance or exchange rate grows from lending income
+    StakingFromLending,   // Staking rewards funded by lending rather than validation
+}
+
+/// Where a token's yield comes from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum YieldSource {
+    Validation, // Block rewards and fees for validating
+    Mev,        // Tips and MEV shared by validators
+    TradingFees,
+    Lending, // Interest paid by borrowers
+    Other,
+}
+
+/// One way a token exposes its holder to riba
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct RibaFinding {
+    pub kind: RibaKind,
+    pub protocol: Option<String>, // e.g. "Solend"
+    pub observed: String,         // What was found, e.g. "Interest rate of 5.00% a year"
+    pub explanation: String,
+    pub references: Vec<String>,
+}
+
+/// Structured riba analysis of a token
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct RibaAnalysis {
+    pub findings: Vec<RibaFinding>,
+    pub staking_yield_source: Option<YieldSource>, // Largest source of staking rewards, when reported
+}
+
+/// How a stablecoin holds its peg
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum PegMechanism {
+    FiatBacked,           // Redeemable for currency held in cash and short-term debt
+    CryptoCollateralized, // Minted against over-collateralized crypto positions
+    Algorithmic,          // Supply expands and contracts to hold the peg, with little or no collateral
+    CommodityBacked,      // Redeemable for allocated gold or another commodity
+}
+
+/// Reserve composition from the issuer's latest attestation
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct ReserveAttestation {
+    #[serde(default)]
+    pub attestor: Option<String>, // e.g. "Deloitte"
+    #[serde(default)]
+    pub attested_at: Option<u64>, // Unix seconds
+    #[serde(default)]
+    pub backing_ratio: Option<f64>, // Reserves over supply, e.g. 1.02
+    // Shares of reserves, 0-1
+    #[serde(default)]
+    pub cash: f64,
+    #[serde(default)]
+    pub treasury_bills: f64,
+    #[serde(default)]
+    pub repurchase_agreements: f64,
+    #[serde(default)]
+    pub money_market_funds: f64,
+    #[serde(default)]
+    pub gold: f64,
+    #[serde(default)]
+    pub crypto: f64,
+    #[serde(default)]
+    pub other: f64,
+}
+
+impl ReserveAttestation {
+    /// Share of reserves held in debt that pays interest
+    pub fn interest_bearing_share(&self) -> f64 {
+        self.treasury_bills + self.repurchase_agreements + self.money_market_funds
+    }
+}
+
+/// Peg mechanism and reserves of a stablecoin, with the conditions under which using it is permissible
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct StablecoinAnalysis {
+    pub mechanism: PegMechanism,
+    pub observed: String, // Why the mechanism was assigned
+    pub reserves: Option<ReserveAttestation>,
+    pub conditions: Vec<String>, // What must hold for holding and using the token to be permissible
+    pub caveats: Vec<String>,    // Uses and reserve facts that change the ruling
+    pub references: Vec<String>,
+}
+
+/// Whether a scripture reference is to the Quran or to a hadith collection
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum ScriptureSource {
+    Quran,
+    Hadith,
+}
+
+/// A Quran verse (or range) or hadith resolved against the bundled scripture library
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct ScriptureReference {
+    pub citation: String, // Canonical, e.g. "Quran 2:278-279" or "Sahih Muslim 1513"
+    pub source: ScriptureSource,
+    pub title: String, // Surah name, or the hadith's narrator
+    pub arabic: String,
+    pub translation: String,
+    pub language: String, // Language of the translation; English when the requested one is missing
+    pub translator: String,
+    pub library_version: String,
+}
+
+/// Broad verdict a ruling amounts to, used to group scholarly positions; ordered from least to most strict
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, uniffi::Enum)]
+pub enum RulingClass {
+    Permissible, // Halal, Mubah, Mustahab
+    Doubtful,    // Makruh, Syubhat
+    Prohibited,  // Haram, Riba, Gharar, Maysir
+}
+
+/// Ruling one source states for the token
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct SourceOpinion {
+    pub source: FatwaSource,
+    pub reference: String, // Page URL, or fatwa title and number
+    pub ruling: IslamicPrinciple,
+    pub excerpt: String,        // Sentence stating the ruling
+    pub stated_at: Option<u64>, // Unix seconds, when known
+    pub credibility: f64,       // 0-1
+    pub recency: f64,           // 0-1, halving every configured half-life
+    pub weight: f64,            // credibility × recency
+}
+
+/// Sources sharing one broad verdict
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct IkhtilafPosition {
+    pub class: RulingClass,
+    pub ruling: IslamicPrinciple,     // Carrying the most weight within the position
+    pub weight_share: f64,            // 0-1 of the weight of all opinions
+    pub opinions: Vec<SourceOpinion>, // Heaviest first
+}
+
+/// Map of agreement and difference (ikhtilaf) among the sources consulted
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct IkhtilafMap {
+    pub majority_view: IkhtilafPosition,
+    pub minority_views: Vec<IkhtilafPosition>, // Heaviest first; empty when the sources agree
+    pub basis_of_difference: Vec<String>,      // Questions the positions turn on
+}
+
+impl IkhtilafMap {
+    /// Whether every source falls in the same position
+    pub fn is_consensus(&self) -> bool {
+        self.minority_views.is_empty()
+    }
+}
+
+/// Where ruling determination ended
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum DeterminationState {
+    Undetermined, // No evidence at all; the ruling is Syubhat
+    Syubhat,      // Evidence short of the thresholds for its class, or conflicting; the ruling is Syubhat
+    Determined,   // Decisive evidence, or enough evidence for the ruling's class
+}
+
+/// One piece of evidence towards a ruling
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct RulingEvidence {
+    pub source: String, // e.g. "model", "standard:Aaoifi", "riba"
+    pub ruling: IslamicPrinciple,
+    pub confidence: f64, // 0-1
+    pub decisive: bool,  // Settles a prohibition on its own
+}
+
+/// Ruling reached from the evidence, with the reasons for abstaining when none was
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct RulingDetermination {
+    pub state: DeterminationState,
+    pub ruling: IslamicPrinciple,
+    pub proposed: Option<IslamicPrinciple>, // What the evidence leaned towards, when it leaned anywhere
+    pub confidence: f64,                    // Combined confidence of the evidence for the proposed ruling
+    pub evidence: Vec<RulingEvidence>,
+    pub abstentions: Vec<String>, // Why the proposed ruling was not adopted
+}
+
+/// Classical school of jurisprudence
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, uniffi::Enum)]
+pub enum Madhab {
+    Hanafi,
+    Maliki,
+    Shafii,
+    Hanbali,
+}
+
+/// Scholarly body whose screening standard a ruling follows
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, uniffi::Enum)]
+pub enum ShariahStandard {
+    Aaoifi,      // Accounting and Auditing Organization for Islamic Financial Institutions
+    MuiDsn,      // Dewan Syariah Nasional - Majelis Ulama Indonesia
+    SacMalaysia, // Shariah Advisory Council, Securities Commission Malaysia
+}
+
+impl IslamicPrinciple {
+    /// Whether the ruling still allows holding the token
+    pub fn permits(&self) -> bool {
+        matches!(
+            self,
+            IslamicPrinciple::Halal
+                | IslamicPrinciple::Mubah
+                | IslamicPrinciple::Mustahab
+                | IslamicPrinciple::Makruh
+                | IslamicPrinciple::Syubhat
+        )
+    }
+
+    /// Broad verdict the ruling amounts to
+    pub fn class(&self) -> RulingClass {
+        match self {
+            IslamicPrinciple::Halal | IslamicPrinciple::Mubah | IslamicPrinciple::Mustahab => RulingClass::Permissible,
+            IslamicPrinciple::Makruh | IslamicPrinciple::Syubhat => RulingClass::Doubtful,
+            IslamicPrinciple::Haram | IslamicPrinciple::Riba | IslamicPrinciple::Gharar | IslamicPrinciple::Maysir => {
+                RulingClass::Prohibited
+            },
+        }
+    }
+}
+
+impl ShariahStandard {
+    pub const ALL: [ShariahStandard; 3] =
+        [ShariahStandard::Aaoifi, ShariahStandard::MuiDsn, ShariahStandard::SacMalaysia];
+}
+
+/// Ruling under one screening standard, and optionally one madhab
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct MethodologyRuling {
+    pub standard: ShariahStandard,
+    pub madhab: Option<Madhab>,
+    pub ruling: IslamicPrinciple,
+    pub compliance_score: f64, // 0-1
+    pub diverges: bool,        // Differs from the baseline ruling
+    pub basis: Vec<String>,    // Positions of the standard or madhab that applied, with evidence
+    pub ratio_status: Option<RatioStatus>,
+}
+
+/// Which madhab and standards a user wants rulings for
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct ShariaPreferences {
+    pub madhab: Option<Madhab>,
+    pub standards: Vec<ShariahStandard>,
+}
+
+impl Default for ShariaPreferences {
+    fn default() -> Self {
+        Self {
+            madhab: None,
+            standards: ShariahStandard::ALL.to_vec(),
+        }
+    }
+}
+
+impl FatwaSource {
+    /// Parse "MUI", "AAOIFI", "OIC", "Darul", "Scholar: <name>" or any other issuer's name
+    pub fn from_label(label: &str) -> Self {
+        let label = label.trim();
+        match label.to_uppercase().as_str() {
+            "MUI" => FatwaSource::MUI,
+            "AAOIFI" => FatwaSource::AAOIFI,
+            "OIC" => FatwaSource::OIC,
+            "DARUL" => FatwaSource::Darul,
+            _ => match label.split_once(':') {
+                Some((kind, name)) if kind.trim().eq_ignore_ascii_case("scholar") => FatwaSource::Scholar {
+                    name: name.trim().to_owned(),
+                },
+                _ => FatwaSource::Custom {
+                    name: label.to_owned(),
+                },
+            },
+        }
+    }
+
+    /// Inverse of `from_label`
+    pub fn label(&self) -> String {
+        match self {
+            FatwaSource::MUI => "MUI".to_owned(),
+            FatwaSource::AAOIFI => "AAOIFI".to_owned(),
+            FatwaSource::OIC => "OIC".to_owned(),
+            FatwaSource::Darul => "Darul".to_owned(),
+            FatwaSource::Scholar {
+                name,
+            } => format!("Scholar: {name}"),
+            FatwaSource::Custom {
+                name,
+            } => name.clone(),
+        }
+    }
 }
 
 impl Fatwa {
@@ -100,6 +535,10 @@ impl Fatwa {
             created_at: Utc::now(),
             vector_embedding: None,
             confidence_score: None,
+            reference_number: None,
+            version: 1,
+            supersedes: None,
+            superseded_by: None,
         }
     }
 
@@ -124,13 +563,25 @@ impl Fatwa {
 impl Default for IslamicAnalysis {
     fn default() -> Self {
         Self {
-            ruling: IslamicPrinciple::Mubah,
+            ruling: IslamicPrinciple::Syubhat, // Nothing is known yet
             confidence: 0.0,
             reasoning: String::new(),
             supporting_fatwas: Vec::new(),
             risk_factors: Vec::new(),
             recommendations: Vec::new(),
             maqashid_assessment: Vec::new(),
+            ratio_screening: None,
+            methodology_rulings: vec![],
+            gharar: None,
+            maysir: None,
+            riba: None,
+            stablecoin: None,
+            scripture_references: vec![],
+            ikhtilaf: None,
+            determination: None,
+            review: None,
+            categories: vec![],
+            document_excerpts: vec![],
         }
     }
 }
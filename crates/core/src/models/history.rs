@@ -5,6 +5,7 @@ use serde::Deserialize;
 use serde::Serialize;
 use uuid::Uuid;
 
+use crate::models::CategoryLabel;
 use crate::models::IslamicPrinciple;
 use crate::models::Query;
 use crate::models::SolanaTokenInfo;
@@ -28,6 +29,8 @@ pub struct HistoryEntry {
     pub summary: String,
     pub analyzed_at: u64, // Unix timestamp for UniFFI
     pub token_info: Option<SolanaTokenInfo>,
+    #[serde(default)]
+    pub categories: Vec<CategoryLabel>,
 }
 
 /// User analysis statistics
@@ -319,10 +322,10 @@ impl AnalysisHistory {
     ) {
         // Extract token symbol from query
         let token_symbol = match &query.query_type {
-            crate::QueryType::TokenTicker {
+            crate::models::QueryType::TokenTicker {
                 ticker,
             } => ticker.clone(),
-            crate::QueryType::ContractAddress {
+            crate::models::QueryType::ContractAddress {
                 address,
             } => address.clone(),
             _ => "UNKNOWN".to_owned(),
@@ -336,6 +339,7 @@ impl AnalysisHistory {
             summary: analysis.islamic_analysis.reasoning.clone(),
             analyzed_at: analysis.created_at,
             token_info: analysis.token_info.clone(),
+            categories: analysis.islamic_analysis.categories.clone(),
         };
 
         self.entries.push(entry);
@@ -354,6 +358,7 @@ impl AnalysisHistory {
                     summary: entry.brief_reasoning,
                     analyzed_at: entry.timestamp.timestamp_millis() as u64,
                     token_info: None, // Would need to be populated from actual data
+                    categories: vec![],
                 }
             })
             .collect();
@@ -8,6 +8,7 @@ use crate::models::Fatwa;
 use crate::models::Query;
 use crate::models::QueryResponse;
 use crate::models::ScrapedData;
+use crate::models::ShariaPreferences;
 use crate::models::SolanaTokenInfo;
 use crate::models::TokenAnalysis;
 
@@ -110,6 +111,15 @@ pub enum HistoryMessage {
         days_to_keep: u32,
         respond_to: oneshot::Sender<Result<usize, HistoryError>>, // Returns number of deleted entries
     },
+    SavePreferences {
+        user_id: String,
+        preferences: ShariaPreferences,
+        respond_to: oneshot::Sender<Result<(), HistoryError>>,
+    },
+    GetPreferences {
+        user_id: String,
+        respond_to: oneshot::Sender<Result<Option<ShariaPreferences>, HistoryError>>,
+    },
 }
 
 // Error types for actor responses
@@ -183,6 +193,7 @@ pub enum SolanaError {
     RpcError(String),
     TokenNotFound(String),
     NetworkError(String),
+    InvalidAccountData(String),
 }
 
 impl std::fmt::Display for SolanaError {
@@ -195,6 +206,7 @@ impl std::fmt::Display for SolanaError {
             SolanaError::RpcError(msg) => write!(f, "RPC error: {msg}"),
             SolanaError::TokenNotFound(token) => write!(f, "Token not found: {token}"),
             SolanaError::NetworkError(msg) => write!(f, "Network error: {msg}"),
+            SolanaError::InvalidAccountData(msg) => write!(f, "Invalid account data: {msg}"),
         }
     }
 }
@@ -433,10 +445,10 @@ impl AnalyzerActorHandle {
         &self,
         symbol: &str,
     ) -> Result<crate::models::token::UniversalTokenInfo, Box<dyn std::error::Error + Send + Sync>> {
-        let client = reqwest::Client::new();
+        let client = crate::http::HttpClient::from_env(reqwest::Client::new(), "market_data");
         let url = format!("https://api.coingecko.com/api/v3/coins/{}", symbol.to_lowercase());
 
-        let response: serde_json::Value = client.get(&url).send().await?.json().await?;
+        let response: serde_json::Value = client.send(client.get(&url)).await?.json()?;
 
         Ok(crate::models::token::UniversalTokenInfo {
             address: format!("api_fetched_{}", symbol.to_lowercase()),
@@ -460,6 +472,7 @@ impl AnalyzerActorHandle {
             is_verified: true,
             risk_score: Some(0.3), // Default risk score
             blockchain: crate::models::token::BlockchainNetwork::Solana,
+            control: None,
         })
     }
 
@@ -490,6 +503,7 @@ impl AnalyzerActorHandle {
                 is_verified: true,
                 risk_score: Some(0.1),
                 blockchain: crate::models::token::BlockchainNetwork::Solana,
+                control: None,
             },
             "BTC" => crate::models::token::UniversalTokenInfo {
                 address: "9n4nbM75f5Ui33ZbPYXn59EwSgE8CGsHtAeTH5YFeJ9E".to_owned(),
@@ -513,6 +527,7 @@ impl AnalyzerActorHandle {
                 is_verified: true,
                 risk_score: Some(0.05),
                 blockchain: crate::models::token::BlockchainNetwork::Solana,
+                control: None,
             },
             "USDC" => crate::models::token::UniversalTokenInfo {
                 address: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_owned(),
@@ -536,6 +551,7 @@ impl AnalyzerActorHandle {
                 is_verified: true,
                 risk_score: Some(0.02),
                 blockchain: crate::models::token::BlockchainNetwork::Solana,
+                control: None,
             },
             _ => crate::models::token::UniversalTokenInfo {
                 address: format!("unknown_{}", symbol.to_lowercase()),
@@ -559,6 +575,7 @@ impl AnalyzerActorHandle {
                 is_verified: false,
                 risk_score: Some(0.8), // High risk for unknown tokens
                 blockchain: crate::models::token::BlockchainNetwork::Solana,
+                control: None,
             },
         }
     }
@@ -567,68 +584,8 @@ impl AnalyzerActorHandle {
         &self,
         token_info: &crate::models::token::UniversalTokenInfo,
     ) -> crate::actors::analyzer_actor::IslamicAnalysisResult {
-        let mut compliance_score: f32 = 0.5; // Start neutral
-        let mut reasoning = Vec::new();
-        let confidence = 0.7;
-
-        let description_lower = token_info.metadata.name.to_lowercase();
-
-        // Rule-based analysis
-        let prohibited_keywords = vec![
-            "gambling",
-            "casino",
-            "lottery",
-            "alcohol",
-            "pork",
-            "interest",
-            "usury",
-            "riba",
-            "lending",
-            "borrowing",
-            "leverage",
-            "margin",
-        ];
-
-        for keyword in &prohibited_keywords {
-            if description_lower.contains(keyword) {
-                compliance_score -= 0.2;
-                reasoning.push(format!("Contains prohibited keyword: {keyword}"));
-            }
-        }
-
-        // Check for DeFi/lending protocols (high risk)
-        if description_lower.contains("defi") || description_lower.contains("yield") {
-            compliance_score -= 0.3;
-            reasoning.push("DeFi protocols may involve riba (interest)".to_owned());
-        }
-
-        // Check for utility tokens (generally acceptable)
-        if description_lower.contains("utility") || description_lower.contains("infrastructure") {
-            compliance_score += 0.2;
-            reasoning.push("Utility tokens are generally permissible".to_owned());
-        }
-
-        // Check for stablecoins
-        if description_lower.contains("stablecoin") || description_lower.contains("stable") {
-            compliance_score += 0.1;
-            reasoning.push("Stablecoins may be permissible if properly backed".to_owned());
-        }
-
-        // Ensure score is between 0 and 1
-        compliance_score = compliance_score.clamp(0.0, 1.0);
-
-        let is_halal = compliance_score >= 0.6;
-
-        crate::actors::analyzer_actor::IslamicAnalysisResult {
-            is_halal,
-            compliance_score: compliance_score as f64,
-            confidence,
-            reasoning,
-            scholar_references: vec![
-                "Quran 2:275 - Allah has permitted trade and forbidden riba".to_owned(),
-                "AAOIFI Sharia Standard No. 17".to_owned(),
-            ],
-        }
+        // Screening rules live in TOML rule packs, see `islamic::rules`
+        crate::islamic::IslamicAnalyzer::new().analyze_token(token_info).await
     }
 
     async fn get_ai_analysis(
@@ -718,6 +675,37 @@ impl HistoryActorHandle {
         }))
     }
 
+    pub async fn save_preferences(
+        &self,
+        user_id: String,
+        preferences: ShariaPreferences,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let (tx, rx) = oneshot::channel();
+        self.sender
+            .send(HistoryMessage::SavePreferences {
+                user_id,
+                preferences,
+                respond_to: tx,
+            })
+            .await?;
+        Ok(rx.await??)
+    }
+
+    /// The user's saved madhab and standards, if they have chosen any
+    pub async fn get_preferences(
+        &self,
+        user_id: String,
+    ) -> Result<Option<ShariaPreferences>, Box<dyn std::error::Error + Send + Sync>> {
+        let (tx, rx) = oneshot::channel();
+        self.sender
+            .send(HistoryMessage::GetPreferences {
+                user_id,
+                respond_to: tx,
+            })
+            .await?;
+        Ok(rx.await??)
+    }
+
     pub async fn get_user_stats(
         &self,
         user_id: String,
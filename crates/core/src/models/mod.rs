@@ -1,13 +1,21 @@
 pub mod analysis;
+pub mod document;
 pub mod fatwa;
 pub mod history;
 pub mod messages;
+pub mod purification;
 pub mod query;
+pub mod review;
 pub mod token;
+pub mod zakat;
 
 pub use analysis::*;
+pub use document::*;
 pub use fatwa::*;
 pub use history::*;
 pub use messages::*;
+pub use purification::*;
 pub use query::*;
+pub use review::*;
 pub use token::*;
+pub use zakat::*;
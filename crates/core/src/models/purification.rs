@@ -0,0 +1,108 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Income received from a holding
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum ReceiptKind {
+    Dividend,      // Revenue share or buyback distribution
+    StakingReward, // Rewards for staking the token
+    Airdrop,
+    CapitalGain, // Realized on sale
+}
+
+/// One receipt to purify, valued in the reporting currency
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct IncomeReceipt {
+    pub kind: ReceiptKind,
+    pub amount: f64,
+    pub received_at: u64, // Unix seconds
+}
+
+/// Which income the impermissible ratio is applied to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum PurificationMethod {
+    Distributions,         // Dividends, staking rewards and airdrops received
+    DistributionsAndGains, // Distributions plus realized capital gains
+    AttributableEarnings,  // The project's earnings attributable to the holding, distributed or not
+}
+
+/// How often the user donates
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum PurificationPeriod {
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+/// Where the impermissible-income ratio came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum RatioSource {
+    Request,         // Given with the request
+    Screening,       // Impermissible revenue over total revenue in the project's financials
+    ScholarOverride, // Set by a scholar, replacing any other ratio
+}
+
+/// A scholar's ratio for a holding, replacing the screened one
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct ScholarOverride {
+    pub scholar: String,
+    pub ratio: f64, // 0-1
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// A holding and its income over a reporting window
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct PurificationRequest {
+    #[serde(default)]
+    pub token: Option<String>, // Address or symbol, to look up the ratio in the project's financials
+    pub holding_value: f64,
+    #[serde(default)]
+    pub impermissible_ratio: Option<f64>, // 0-1
+    #[serde(default)]
+    pub scholar_override: Option<ScholarOverride>,
+    #[serde(default)]
+    pub receipts: Vec<IncomeReceipt>,
+    #[serde(default)]
+    pub annual_earnings_yield: Option<f64>, // Project earnings over valuation, for AttributableEarnings
+    pub method: PurificationMethod,
+    pub period: PurificationPeriod,
+    pub from: u64, // Unix seconds, inclusive
+    pub to: u64,   // Unix seconds, exclusive
+}
+
+/// What was purified and how
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct PurificationMethodology {
+    pub method: PurificationMethod,
+    pub ratio: f64,
+    pub ratio_source: RatioSource,
+    pub scholar: Option<String>,
+    pub description: String,
+    pub references: Vec<String>,
+}
+
+/// One amount to donate, with the income it comes from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct PurificationLine {
+    pub kind: Option<ReceiptKind>, // None for attributable earnings
+    pub base: f64,                 // Income the ratio is applied to
+    pub amount: f64,
+    pub description: String,
+}
+
+/// Amount to donate for one period
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct PeriodPurification {
+    pub start: u64,
+    pub end: u64,
+    pub amount: f64,
+    pub lines: Vec<PurificationLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct PurificationResult {
+    pub methodology: PurificationMethodology,
+    pub periods: Vec<PeriodPurification>,
+    pub total: f64,
+}
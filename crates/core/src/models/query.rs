@@ -3,6 +3,9 @@ use serde::Deserialize;
 use serde::Serialize;
 use uuid::Uuid;
 
+use crate::models::IslamicPrinciple;
+use crate::models::ShariaPreferences;
+
 /// Different query input types
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Enum)]
 pub enum QueryType {
@@ -33,10 +36,13 @@ pub struct Query {
     pub timestamp: u64,          // Unix timestamp in milliseconds for UniFFI
     pub language: String,        // e.g., "en", "id", "ar"
     pub context: Option<String>, // Additional context for the query
+    /// Madhab and standards to rule by; the user's saved preferences apply when unset
+    #[serde(default)]
+    pub preferences: Option<ShariaPreferences>,
 }
 
 /// Response from query processing
-#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryResponse {
     pub query_id: String, // UUID as String for UniFFI
     pub response: String,
@@ -45,6 +51,8 @@ pub struct QueryResponse {
     pub follow_up_questions: Vec<String>,
     pub timestamp: u64,              // Unix timestamp in milliseconds for UniFFI
     pub analysis_id: Option<String>, // UUID as String for UniFFI
+    #[serde(default)]
+    pub ruling: Option<IslamicPrinciple>, // Final ruling, scholar override included; None when nothing was analyzed
 }
 
 impl Query {
@@ -62,6 +70,7 @@ impl Query {
             timestamp: Utc::now().timestamp_millis() as u64,
             language: language.unwrap_or_else(|| "id".to_owned()),
             context: None,
+            preferences: None,
         }
     }
 
@@ -79,6 +88,7 @@ impl Query {
             timestamp: Utc::now().timestamp_millis() as u64,
             language: language.unwrap_or_else(|| "id".to_owned()),
             context: None,
+            preferences: None,
         }
     }
 
@@ -96,6 +106,7 @@ impl Query {
             timestamp: Utc::now().timestamp_millis() as u64,
             language: language.unwrap_or_else(|| "id".to_owned()),
             context: None,
+            preferences: None,
         }
     }
 
@@ -113,6 +124,7 @@ impl Query {
             timestamp: Utc::now().timestamp_millis() as u64,
             language: language.unwrap_or_else(|| "id".to_owned()),
             context: None,
+            preferences: None,
         }
     }
 
@@ -132,6 +144,7 @@ impl Query {
             timestamp: Utc::now().timestamp_millis() as u64,
             language: language.unwrap_or_else(|| "id".to_owned()),
             context: None,
+            preferences: None,
         }
     }
 
@@ -163,6 +176,7 @@ impl QueryResponse {
             follow_up_questions,
             timestamp: Utc::now().timestamp_millis() as u64,
             analysis_id: analysis_id.map(|id| id.to_string()),
+            ruling: None,
         }
     }
 }
@@ -0,0 +1,28 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::fatwa::IslamicPrinciple;
+
+/// What a scholar decided about a token's ruling
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum ReviewAction {
+    Approve,  // The AI ruling stands; lifts an earlier override
+    Annotate, // A note on the ruling that leaves it unchanged
+    Override, // Replaces the AI ruling in later analyses and the halal index
+}
+
+/// One entry of the review audit log; never changed once written
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct ReviewDecision {
+    pub id: String,    // UUID as String for UniFFI
+    pub sequence: u64, // Position in the audit log
+    pub token: String, // Mint address
+    pub analysis_id: Option<String>,
+    pub action: ReviewAction,
+    pub ruling: Option<IslamicPrinciple>, // Approved or imposed ruling; optional for annotations
+    pub scholar_id: String,
+    pub scholar_name: String,
+    pub rationale: String,
+    pub references: Vec<String>, // Quran, hadith, fatwas or standards relied on
+    pub decided_at: u64,         // Unix seconds
+}
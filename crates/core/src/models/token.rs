@@ -6,13 +6,111 @@ use solana_pubkey::Pubkey;
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Enum)]
 pub enum TokenStandard {
-    SPL,
+    SPL,       // The original SPL Token program
+    Token2022, // SPL Token-2022, whose mints can carry extensions
     ERC20,
     Other {
         name: String,
     },
 }
 
+/// A Token-2022 mint extension, with the settings that bear on a ruling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Enum)]
+pub enum TokenExtension {
+    TransferFeeConfig {
+        fee_basis_points: u16, // The newer of the two configured fees, applying from its epoch on
+        maximum_fee: u64,      // In base units
+        authority: Option<String>,
+    },
+    MintCloseAuthority {
+        authority: Option<String>,
+    },
+    ConfidentialTransferMint {
+        authority: Option<String>,
+        auto_approve_new_accounts: bool,
+    },
+    DefaultAccountState {
+        frozen: bool, // New token accounts start frozen until the freeze authority thaws them
+    },
+    NonTransferable,
+    InterestBearingConfig {
+        rate_bps: i16, // Current rate, in basis points a year
+        authority: Option<String>,
+    },
+    PermanentDelegate {
+        delegate: Option<String>, // Can transfer or burn tokens from any account
+    },
+    TransferHook {
+        program_id: Option<String>, // Program invoked on every transfer
+        authority: Option<String>,
+    },
+    MetadataPointer {
+        metadata_address: Option<String>,
+        authority: Option<String>,
+    },
+    Metadata {
+        name: String,
+        symbol: String,
+        uri: String,
+        update_authority: Option<String>, // None once the metadata is immutable
+    },
+    ScaledUiAmount {
+        multiplier: f64, // Displayed amounts are base amounts times this
+        authority: Option<String>,
+    },
+    Pausable {
+        paused: bool,
+        authority: Option<String>,
+    },
+    Other {
+        extension_type: u16, // Read but not interpreted, e.g. token groups
+    },
+}
+
+impl TokenExtension {
+    pub fn name(&self) -> String {
+        match self {
+            TokenExtension::TransferFeeConfig {
+                ..
+            } => "TransferFeeConfig".to_owned(),
+            TokenExtension::MintCloseAuthority {
+                ..
+            } => "MintCloseAuthority".to_owned(),
+            TokenExtension::ConfidentialTransferMint {
+                ..
+            } => "ConfidentialTransferMint".to_owned(),
+            TokenExtension::DefaultAccountState {
+                ..
+            } => "DefaultAccountState".to_owned(),
+            TokenExtension::NonTransferable => "NonTransferable".to_owned(),
+            TokenExtension::InterestBearingConfig {
+                ..
+            } => "InterestBearingConfig".to_owned(),
+            TokenExtension::PermanentDelegate {
+                ..
+            } => "PermanentDelegate".to_owned(),
+            TokenExtension::TransferHook {
+                ..
+            } => "TransferHook".to_owned(),
+            TokenExtension::MetadataPointer {
+                ..
+            } => "MetadataPointer".to_owned(),
+            TokenExtension::Metadata {
+                ..
+            } => "Metadata".to_owned(),
+            TokenExtension::ScaledUiAmount {
+                ..
+            } => "ScaledUiAmount".to_owned(),
+            TokenExtension::Pausable {
+                ..
+            } => "Pausable".to_owned(),
+            TokenExtension::Other {
+                extension_type,
+            } => format!("Extension{extension_type}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Enum)]
 pub enum BlockchainNetwork {
     Solana,
@@ -37,6 +135,8 @@ pub struct TokenMetadata {
     pub verified: bool,
     pub token_standard: TokenStandard,
     pub blockchain: BlockchainNetwork, // NEW: Identifies the source chain
+    #[serde(default)]
+    pub extensions: Vec<TokenExtension>, // Token-2022 mints only
 }
 
 impl TokenMetadata {
@@ -68,6 +168,7 @@ impl TokenMetadata {
                 },
             },
             blockchain,
+            extensions: Vec::new(),
         }
     }
 }
@@ -103,6 +204,8 @@ pub struct UniversalTokenInfo {
     pub is_verified: bool,
     pub risk_score: Option<f64>,
     pub blockchain: BlockchainNetwork, // NEW: Explicit chain identification
+    #[serde(default)]
+    pub control: Option<TokenControl>, // Read from the chain for Solana mints
 }
 
 /// Solana-specific token information (for backward compatibility and Solana-specific features)
@@ -114,7 +217,73 @@ pub struct SolanaTokenInfo {
     pub holders: Option<u64>,
     pub liquidity_pools: Vec<LiquidityPool>,
     pub is_verified: bool,
-    pub risk_score: Option<f64>,
+    pub risk_score: Option<f64>, // 0-1, the control score when the mint was read from the chain
+    #[serde(default)]
+    pub control: Option<TokenControl>,
+}
+
+/// Sector of the token taxonomy
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, uniffi::Enum)]
+pub enum TokenCategory {
+    L1Native, // Native asset of a base-layer chain
+    Stablecoin,
+    DexGovernance, // Governance token of a decentralized exchange
+    Lending,
+    LiquidStaking,
+    Meme,
+    Gaming,
+    Nft,
+    Rwa, // Tokenized real-world assets
+    Privacy,
+    ExchangeToken, // Issued by a centralized exchange
+    Launchpad,
+}
+
+/// A category the token was placed in, with how sure the classifier is
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct CategoryLabel {
+    pub category: TokenCategory,
+    pub confidence: f64,       // 0-1
+    pub evidence: Vec<String>, // What placed the token in the category
+}
+
+/// Who holds an authority over a token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Enum)]
+pub enum AuthorityKind {
+    Wallet, // A single private key
+    Multisig {
+        threshold: u8, // Signatures required
+        signers: u8,
+    },
+    ProgramDerived, // An address without a private key, so only its program can sign, e.g. a DAO or Squads vault
+    Program,        // An executable program account
+}
+
+/// An address holding one of a token's authorities
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct TokenAuthority {
+    pub address: String,
+    pub kind: AuthorityKind,
+}
+
+/// One scored aspect of who controls a token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct ControlFactor {
+    pub name: String,      // e.g. "mint_authority"
+    pub risk: Option<f64>, // 0-1, None when unavailable
+    pub weight: f64,       // Share of the score this factor carries when available
+    pub explanation: String,
+}
+
+/// Who can still mint, freeze or rewrite a token, scored by how exposed that leaves holders
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct TokenControl {
+    pub mint_authority: Option<TokenAuthority>, // None once minting is disabled for good
+    pub freeze_authority: Option<TokenAuthority>,
+    pub update_authority: Option<TokenAuthority>, // Of the token's metadata
+    pub metadata_mutable: Option<bool>,           // None without on-chain metadata
+    pub score: f64,                               // 0-1, higher leaves holders more exposed
+    pub factors: Vec<ControlFactor>,
 }
 
 // Internal structs (not exposed to UniFFI)
@@ -136,6 +305,20 @@ pub struct TokenMetadataInternal {
     pub coinmarketcap_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub last_updated: DateTime<Utc>,
+    #[serde(default)]
+    pub mint_authority: Option<String>, // None once minting is disabled for good
+    #[serde(default)]
+    pub freeze_authority: Option<String>, // Can freeze holders' token accounts
+    #[serde(default)]
+    pub update_authority: Option<String>, // Can change the Metaplex metadata
+    #[serde(default)]
+    pub is_mutable: Option<bool>, // Whether the metadata can still change; None without a metadata account
+    #[serde(default)]
+    pub metadata_uri: Option<String>, // Off-chain JSON the metadata account points to
+    #[serde(default)]
+    pub creators: Vec<String>, // Verified Metaplex creators
+    #[serde(default)]
+    pub extensions: Vec<TokenExtension>, // Token-2022 mints only
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -168,7 +351,9 @@ pub struct SolanaTokenInfoInternal {
     pub holders: Option<TokenHolders>,
     pub liquidity_pools: Vec<String>, // Pool addresses
     pub is_verified: bool,
-    pub risk_score: Option<f64>, // 0-100, lower is safer
+    pub risk_score: Option<f64>, // 0-1, lower is safer
+    #[serde(default)]
+    pub control: Option<TokenControl>,
 }
 
 impl TokenMetadataInternal {
@@ -196,6 +381,13 @@ impl TokenMetadataInternal {
             coinmarketcap_id: None,
             created_at: now,
             last_updated: now,
+            mint_authority: None,
+            freeze_authority: None,
+            update_authority: None,
+            is_mutable: None,
+            metadata_uri: None,
+            creators: Vec::new(),
+            extensions: Vec::new(),
         }
     }
 }
@@ -212,6 +404,7 @@ impl SolanaTokenInfoInternal {
             liquidity_pools: Vec::new(),
             is_verified: false,
             risk_score: None,
+            control: None,
         }
     }
 }
@@ -231,12 +424,14 @@ impl SolanaTokenInfo {
                 verified: false,
                 token_standard: TokenStandard::SPL,
                 blockchain: BlockchainNetwork::Solana,
+                extensions: vec![],
             },
             price_data: None,
             holders: None,
             liquidity_pools: vec![],
             is_verified: false,
             risk_score: None,
+            control: None,
         }
     }
 
@@ -250,10 +445,11 @@ impl SolanaTokenInfo {
                 decimals: internal.metadata.decimals as u32,
                 description: internal.metadata.description,
                 image_url: internal.metadata.logo_uri,
-                creator: None,
+                creator: internal.metadata.creators.first().cloned(),
                 verified: internal.is_verified,
                 token_standard: internal.metadata.token_standard,
                 blockchain: BlockchainNetwork::Solana,
+                extensions: internal.metadata.extensions,
             },
             price_data: internal.price_data.map(|pd| TokenPriceData {
                 price_usd: pd.price_usd,
@@ -276,6 +472,22 @@ impl SolanaTokenInfo {
                 .collect(),
             is_verified: internal.is_verified,
             risk_score: internal.risk_score,
+            control: internal.control,
+        }
+    }
+
+    /// The chain-agnostic view used by the screening rules
+    pub fn to_universal(&self) -> UniversalTokenInfo {
+        UniversalTokenInfo {
+            address: self.pubkey.clone(),
+            metadata: self.metadata.clone(),
+            price_data: self.price_data.clone(),
+            holders: self.holders,
+            liquidity_pools: self.liquidity_pools.clone(),
+            is_verified: self.is_verified,
+            risk_score: self.risk_score,
+            blockchain: BlockchainNetwork::Solana,
+            control: self.control.clone(),
         }
     }
 }
@@ -0,0 +1,94 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Date in the tabular Islamic calendar
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, uniffi::Record)]
+pub struct HijriDate {
+    pub year: i32,
+    pub month: u32, // 1 = Muharram
+    pub day: u32,
+}
+
+/// Metal the nisab is measured in
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum NisabStandard {
+    Gold,
+    Silver,
+    Lower, // Whichever is lower, so more wealth pays zakat
+}
+
+/// Why an asset is held
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, uniffi::Enum)]
+pub enum HoldingIntent {
+    Trading,  // Bought to sell at a profit
+    LongTerm, // Held for growth or use
+}
+
+/// How long-term holdings are valued; trading inventory is always zakatable at market value
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, uniffi::Enum)]
+pub enum ZakatMethod {
+    MarketValue,     // Long-term holdings at full market value too
+    ZakatableAssets, // Long-term holdings at the zakatable share of the project's assets
+    ReturnsOnly,     // Long-term holdings pay only on the income they produced
+}
+
+/// One asset held on the zakat date
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct ZakatHolding {
+    pub asset: String, // Symbol or address
+    pub quantity: f64,
+    pub price: f64, // Per unit on the zakat date, in the reporting currency
+    pub intent: HoldingIntent,
+    pub acquired_at: u64, // Unix seconds
+    #[serde(default)]
+    pub zakatable_ratio: Option<f64>, // 0-1, zakatable share of the project's assets, for ZakatableAssets
+    #[serde(default)]
+    pub income: f64, // Staking rewards and distributions over the year, for ReturnsOnly
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct ZakatRequest {
+    pub holdings: Vec<ZakatHolding>,
+    pub zakat_date: u64, // Unix seconds
+    pub method: ZakatMethod,
+    pub nisab_standard: NisabStandard,
+    #[serde(default)]
+    pub gold_price_per_gram: Option<f64>, // Falls back to the configured price
+    #[serde(default)]
+    pub silver_price_per_gram: Option<f64>,
+    #[serde(default)]
+    pub debts_due: f64, // Owed now, deducted before the nisab test
+    /// When the holder's wealth first reached nisab; assets bought since share its hawl (Hanafi)
+    #[serde(default)]
+    pub hawl_start: Option<u64>,
+}
+
+/// Zakat on one asset
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct ZakatAssetBreakdown {
+    pub asset: String,
+    pub intent: HoldingIntent,
+    pub market_value: f64,
+    pub zakatable_value: f64,
+    pub hawl_started: HijriDate,
+    pub hawl_completes: HijriDate,
+    pub hawl_complete: bool,
+    pub zakat_due: f64,
+    pub note: String, // How the zakatable value was reached
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, uniffi::Record)]
+pub struct ZakatResult {
+    pub zakat_date: HijriDate,
+    pub method: ZakatMethod,
+    pub nisab_standard: NisabStandard, // Gold or Silver, the one applied
+    pub nisab_value: f64,
+    pub zakatable_wealth: f64, // Hawl-complete zakatable value less debts
+    pub debts_deducted: f64,
+    pub above_nisab: bool,
+    pub rate: f64,
+    pub zakat_due: f64,
+    pub assets: Vec<ZakatAssetBreakdown>,
+    pub methodology: String,
+    pub references: Vec<String>,
+}
@@ -0,0 +1,138 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Extra completion tokens reserved for a reasoning model's thinking, on top of the answer budget
+pub const REASONING_HEADROOM_TOKENS: u32 = 2048;
+
+/// Substrings identifying model families that emit a reasoning trace
+const REASONING_MODEL_MARKERS: &[&str] = &["deepseek-r1", "r1-distill", "qwq", "gpt-oss", "magistral", "reasoner"];
+
+/// `OpenAI` o-series prefixes
+const REASONING_MODEL_PREFIXES: &[&str] = &["o1", "o3", "o4"];
+
+/// Whether `model` is known to think before answering
+pub fn is_reasoning_model(model: &str) -> bool {
+    let model = model.to_lowercase();
+    let name = model.rsplit('/').next().unwrap_or(&model);
+
+    REASONING_MODEL_MARKERS.iter().any(|marker| name.contains(marker))
+        || REASONING_MODEL_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// `max_tokens` to request so that thinking does not eat the answer budget
+pub fn completion_budget(
+    model: &str,
+    answer_tokens: u32,
+) -> u32 {
+    if is_reasoning_model(model) {
+        answer_tokens + REASONING_HEADROOM_TOKENS
+    } else {
+        answer_tokens
+    }
+}
+
+/// Rough token count for text without a tokenizer, about four characters per token
+pub fn estimate_tokens(text: &str) -> u32 {
+    text.chars().count().div_ceil(4) as u32
+}
+
+/// Tokens spent on one completion, with reasoning counted separately from the answer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub answer_tokens: u32,
+    pub reasoning_tokens: u32,
+}
+
+impl TokenUsage {
+    /// Estimate usage from the text alone, for providers that do not report it
+    pub fn estimate(
+        answer: &str,
+        reasoning: Option<&str>,
+    ) -> Self {
+        Self {
+            prompt_tokens: 0,
+            answer_tokens: estimate_tokens(answer),
+            reasoning_tokens: reasoning.map(estimate_tokens).unwrap_or(0),
+        }
+    }
+
+    /// Read an OpenAI-style `usage` object, estimating whatever it leaves out
+    ///
+    /// Providers count reasoning inside `completion_tokens`; the split comes from
+    /// `completion_tokens_details.reasoning_tokens` when present, otherwise from the trace length.
+    pub fn from_usage_json(
+        usage: &serde_json::Value,
+        answer: &str,
+        reasoning: Option<&str>,
+    ) -> Self {
+        let Some(completion_tokens) = usage["completion_tokens"].as_u64() else {
+            return Self::estimate(answer, reasoning);
+        };
+        let completion_tokens = completion_tokens as u32;
+
+        let reasoning_tokens = usage["completion_tokens_details"]["reasoning_tokens"]
+            .as_u64()
+            .map(|tokens| tokens as u32)
+            .unwrap_or_else(|| reasoning.map(estimate_tokens).unwrap_or(0))
+            .min(completion_tokens);
+
+        Self {
+            prompt_tokens: usage["prompt_tokens"].as_u64().unwrap_or(0) as u32,
+            answer_tokens: completion_tokens - reasoning_tokens,
+            reasoning_tokens,
+        }
+    }
+
+    /// Answer plus reasoning, what the provider bills as completion
+    pub fn completion_tokens(&self) -> u32 {
+        self.answer_tokens + self.reasoning_tokens
+    }
+
+    pub fn total(&self) -> u32 {
+        self.prompt_tokens + self.completion_tokens()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_reasoning_models_get_headroom() {
+        assert!(is_reasoning_model("deepseek-r1-distill-llama-70b"));
+        assert!(is_reasoning_model("openai/gpt-oss-120b"));
+        assert!(is_reasoning_model("o3-mini"));
+        assert!(!is_reasoning_model("llama3-8b-8192"));
+        assert!(!is_reasoning_model("grok-beta"));
+
+        assert_eq!(completion_budget("llama3-8b-8192", 1000), 1000);
+        assert_eq!(completion_budget("deepseek-r1-distill-llama-70b", 1000), 1000 + REASONING_HEADROOM_TOKENS);
+    }
+
+    #[test]
+    fn test_usage_counts_both_parts() {
+        let usage = json!({
+            "prompt_tokens": 120,
+            "completion_tokens": 900,
+            "completion_tokens_details": { "reasoning_tokens": 700 }
+        });
+        let parsed = TokenUsage::from_usage_json(&usage, "answer", Some("thinking"));
+        assert_eq!(parsed, TokenUsage {
+            prompt_tokens: 120,
+            answer_tokens: 200,
+            reasoning_tokens: 700,
+        });
+        assert_eq!(parsed.total(), 1020);
+
+        // Without details the trace length decides the split
+        let parsed = TokenUsage::from_usage_json(&json!({ "completion_tokens": 10 }), "abcd", Some("12345678"));
+        assert_eq!((parsed.answer_tokens, parsed.reasoning_tokens), (8, 2));
+
+        // Without usage everything is estimated
+        let parsed = TokenUsage::from_usage_json(&serde_json::Value::Null, "abcd", Some("12345678"));
+        assert_eq!(parsed.completion_tokens(), 3);
+    }
+}
@@ -0,0 +1,14 @@
+//! Reasoning-trace handling for models that think out loud.
+//!
+//! Reasoning models (`DeepSeek` R1 and its distills, `QwQ`, the o-series, ...) emit their chain of thought
+//! either inline in `<think>` blocks or in a separate `reasoning_content` field. [`ModelOutput`] keeps
+//! that trace apart from the answer so only the answer is parsed, shown or streamed.
+//!
+//! Traces are dropped once split off unless `AVERROES_RETAIN_REASONING` is set, in which case they are
+//! kept next to stored results for audit and debugging. Token budgets count both parts, see [`budget`].
+
+pub mod budget;
+pub mod trace;
+
+pub use budget::*;
+pub use trace::*;
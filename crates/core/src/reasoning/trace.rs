@@ -0,0 +1,298 @@
+use super::budget::TokenUsage;
+
+/// Environment variable that keeps reasoning traces with stored results
+pub const RETAIN_REASONING_ENV: &str = "AVERROES_RETAIN_REASONING";
+
+/// Opening and closing tags that wrap inline reasoning
+const REASONING_TAGS: &[(&str, &str)] = &[
+    ("<think>", "</think>"),
+    ("<thinking>", "</thinking>"),
+    ("<reasoning>", "</reasoning>"),
+];
+
+/// Whether traces should be kept for audit and debugging
+pub fn retain_reasoning() -> bool {
+    std::env::var(RETAIN_REASONING_ENV)
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// A completion split into the user-facing answer and the model's reasoning
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelOutput {
+    pub answer: String,
+    pub reasoning: Option<String>,
+    pub usage: TokenUsage,
+}
+
+impl ModelOutput {
+    /// Output with no reasoning, e.g. a canned or error message
+    pub fn answer_only(answer: impl Into<String>) -> Self {
+        let answer = answer.into();
+        Self {
+            usage: TokenUsage::estimate(&answer, None),
+            answer,
+            reasoning: None,
+        }
+    }
+
+    /// Split raw completion text, estimating its usage
+    pub fn from_text(raw: &str) -> Self {
+        let (answer, reasoning) = split_reasoning(raw);
+        Self {
+            usage: TokenUsage::estimate(&answer, reasoning.as_deref()),
+            answer,
+            reasoning,
+        }
+    }
+
+    /// Read an OpenAI-compatible chat completion response
+    ///
+    /// Reasoning may arrive inline in the content, in `reasoning_content`/`reasoning`, or both.
+    pub fn from_chat_completion(response: &serde_json::Value) -> Self {
+        let message = &response["choices"][0]["message"];
+        let (answer, inline) = split_reasoning(message["content"].as_str().unwrap_or_default());
+
+        let separate = message["reasoning_content"]
+            .as_str()
+            .or_else(|| message["reasoning"].as_str())
+            .map(str::trim)
+            .filter(|text| !text.is_empty());
+        let reasoning = match (separate, inline) {
+            (Some(separate), Some(inline)) => Some(format!("{separate}\n\n{inline}")),
+            (Some(separate), None) => Some(separate.to_owned()),
+            (None, inline) => inline,
+        };
+
+        Self {
+            usage: TokenUsage::from_usage_json(&response["usage"], &answer, reasoning.as_deref()),
+            answer,
+            reasoning,
+        }
+    }
+
+    /// Use `fallback` when the model produced reasoning but no answer
+    pub fn or_answer(
+        mut self,
+        fallback: &str,
+    ) -> Self {
+        if self.answer.is_empty() {
+            fallback.clone_into(&mut self.answer);
+        }
+        self
+    }
+
+    /// The trace to store with results; `None` unless retention is enabled
+    pub fn retained_reasoning(&self) -> Option<String> {
+        if retain_reasoning() {
+            self.reasoning.clone()
+        } else {
+            None
+        }
+    }
+}
+
+/// Separate reasoning from the answer in a complete response
+///
+/// A closing tag with no opening one means the chat template opened the block before generation began,
+/// so everything before it is reasoning. An unterminated block means the output was cut off mid-thought.
+pub fn split_reasoning(raw: &str) -> (String, Option<String>) {
+    let mut leading = None;
+    let mut rest = raw;
+
+    let first_open = REASONING_TAGS.iter().filter_map(|(open, _)| raw.find(open)).min();
+    let orphan_close = REASONING_TAGS
+        .iter()
+        .filter_map(|(_, close)| raw.find(close).map(|pos| (pos, *close)))
+        .min_by_key(|(pos, _)| *pos);
+    if let Some((pos, close)) = orphan_close {
+        if first_open.is_none_or(|open| pos < open) {
+            leading = Some(raw[..pos].to_owned());
+            rest = &raw[pos + close.len()..];
+        }
+    }
+
+    let mut stream = ReasoningStream::new();
+    stream.push(rest);
+    let (_, output) = stream.finish();
+
+    let blocks = leading.into_iter().chain(output.reasoning).collect::<Vec<_>>();
+    (output.answer, join_blocks(&blocks))
+}
+
+/// Splits a streamed completion on the fly so reasoning never reaches the user
+///
+/// Text that could be the start of a tag is held back until the next chunk settles it.
+#[derive(Debug, Default)]
+pub struct ReasoningStream {
+    /// Closing tag being waited for while inside a reasoning block
+    closing: Option<&'static str>,
+    pending: String,
+    answer: String,
+    current: String,
+    blocks: Vec<String>,
+}
+
+impl ReasoningStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk and get back the part that may be shown, possibly empty
+    pub fn push(
+        &mut self,
+        chunk: &str,
+    ) -> String {
+        self.pending.push_str(chunk);
+        let mut visible = String::new();
+
+        loop {
+            match self.closing {
+                Some(close) => match self.pending.find(close) {
+                    Some(end) => {
+                        self.current.push_str(&self.pending[..end]);
+                        self.blocks.push(std::mem::take(&mut self.current));
+                        self.pending.drain(..end + close.len());
+                        self.closing = None;
+                    },
+                    None => {
+                        let settled = self.pending.len() - partial_tag_len(&self.pending, &[close]);
+                        self.current.push_str(&self.pending[..settled]);
+                        self.pending.drain(..settled);
+                        break;
+                    },
+                },
+                None => {
+                    let next_open = REASONING_TAGS
+                        .iter()
+                        .filter_map(|(open, close)| self.pending.find(open).map(|pos| (pos, *open, *close)))
+                        .min_by_key(|(pos, ..)| *pos);
+
+                    match next_open {
+                        Some((pos, open, close)) => {
+                            visible.push_str(&self.pending[..pos]);
+                            self.pending.drain(..pos + open.len());
+                            self.closing = Some(close);
+                        },
+                        None => {
+                            let opens = REASONING_TAGS.iter().map(|(open, _)| *open).collect::<Vec<_>>();
+                            let settled = self.pending.len() - partial_tag_len(&self.pending, &opens);
+                            visible.push_str(&self.pending[..settled]);
+                            self.pending.drain(..settled);
+                            break;
+                        },
+                    }
+                },
+            }
+        }
+
+        self.answer.push_str(&visible);
+        visible
+    }
+
+    /// Flush held-back text, returning the last visible piece and the full output
+    pub fn finish(mut self) -> (String, ModelOutput) {
+        let pending = std::mem::take(&mut self.pending);
+        let visible = if self.closing.is_some() {
+            self.current.push_str(&pending);
+            self.blocks.push(std::mem::take(&mut self.current));
+            String::new()
+        } else {
+            self.answer.push_str(&pending);
+            pending
+        };
+
+        let answer = self.answer.trim().to_owned();
+        let reasoning = join_blocks(&self.blocks);
+
+        (visible, ModelOutput {
+            usage: TokenUsage::estimate(&answer, reasoning.as_deref()),
+            answer,
+            reasoning,
+        })
+    }
+}
+
+/// Length of the longest suffix of `text` that begins one of `tags` without completing it
+fn partial_tag_len(
+    text: &str,
+    tags: &[&str],
+) -> usize {
+    text.char_indices()
+        .filter(|(_, c)| *c == '<')
+        .map(|(index, _)| &text[index..])
+        .find(|suffix| tags.iter().any(|tag| tag.len() > suffix.len() && tag.starts_with(suffix)))
+        .map(str::len)
+        .unwrap_or(0)
+}
+
+fn join_blocks(blocks: &[String]) -> Option<String> {
+    let blocks = blocks
+        .iter()
+        .map(|block| block.trim())
+        .filter(|block| !block.is_empty())
+        .collect::<Vec<_>>();
+
+    if blocks.is_empty() {
+        None
+    } else {
+        Some(blocks.join("\n\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_split_inline_and_orphan_blocks() {
+        let (answer, reasoning) = split_reasoning("<think>\nRiba? No interest.\n</think>\n\nRULING: HALAL");
+        assert_eq!(answer, "RULING: HALAL");
+        assert_eq!(reasoning.as_deref(), Some("Riba? No interest."));
+
+        // Template already opened the block
+        let (answer, reasoning) = split_reasoning("Check gharar first.</think>RULING: HARAM");
+        assert_eq!(answer, "RULING: HARAM");
+        assert_eq!(reasoning.as_deref(), Some("Check gharar first."));
+
+        // Cut off while thinking: no answer at all
+        let (answer, reasoning) = split_reasoning("<think>Still weighing the");
+        assert_eq!(answer, "");
+        assert_eq!(reasoning.as_deref(), Some("Still weighing the"));
+
+        assert_eq!(split_reasoning("Plain answer"), ("Plain answer".to_owned(), None));
+    }
+
+    #[test]
+    fn test_stream_never_emits_reasoning() {
+        let chunks = ["Answer: ", "<th", "ink>secret ", "chain</thi", "nk> HALAL", " <", "b>ok</b>"];
+        let mut stream = ReasoningStream::new();
+
+        let mut shown = chunks.iter().map(|chunk| stream.push(chunk)).collect::<String>();
+        let (rest, output) = stream.finish();
+        shown.push_str(&rest);
+
+        assert_eq!(shown, "Answer:  HALAL <b>ok</b>");
+        assert_eq!(output.answer, "Answer:  HALAL <b>ok</b>");
+        assert_eq!(output.reasoning.as_deref(), Some("secret chain"));
+    }
+
+    #[test]
+    fn test_chat_completion_with_reasoning_field() {
+        let response = json!({
+            "choices": [{ "message": {
+                "content": "RULING: HALAL",
+                "reasoning_content": "Utility token, no riba."
+            } }],
+            "usage": { "prompt_tokens": 50, "completion_tokens": 30 }
+        });
+        let output = ModelOutput::from_chat_completion(&response);
+
+        assert_eq!(output.answer, "RULING: HALAL");
+        assert_eq!(output.reasoning.as_deref(), Some("Utility token, no riba."));
+        assert_eq!(output.usage.completion_tokens(), 30);
+        assert_eq!(output.usage.reasoning_tokens, 6);
+    }
+}
@@ -0,0 +1,242 @@
+//! Who controls a token: whether its mint and freeze authorities are still set, whether its metadata can
+//! still change, and whether those powers sit with a single key, a multisig or a program.
+
+use solana_account::Account;
+use solana_pubkey::Pubkey;
+
+use super::metadata::TOKEN_2022_PROGRAM_ID;
+use super::metadata::TOKEN_PROGRAM_ID;
+use crate::models::AuthorityKind;
+use crate::models::ControlFactor;
+use crate::models::TokenAuthority;
+use crate::models::TokenControl;
+use crate::models::TokenMetadataInternal;
+
+/// Control score at and above which a token is flagged as centrally controlled
+pub const CENTRALIZED_CONTROL: f64 = 0.5;
+
+/// Size of an SPL Token multisig account: threshold, signer count, initialized flag and 11 signer slots
+const MULTISIG_LEN: usize = 3 + 11 * 32;
+
+/// Kind of the holder of `address`, from its account; addresses without an account are wallets or PDAs
+pub fn classify_authority(
+    address: &Pubkey,
+    account: Option<&Account>,
+) -> AuthorityKind {
+    match account {
+        Some(account) if account.executable => AuthorityKind::Program,
+        Some(account)
+            if (account.owner == TOKEN_PROGRAM_ID || account.owner == TOKEN_2022_PROGRAM_ID)
+                && account.data.len() == MULTISIG_LEN
+                && account.data[2] == 1 =>
+        {
+            AuthorityKind::Multisig {
+                threshold: account.data[0],
+                signers: account.data[1],
+            }
+        },
+        _ if !address.is_on_curve() => AuthorityKind::ProgramDerived,
+        _ => AuthorityKind::Wallet,
+    }
+}
+
+/// Score the token's authorities, using `kind_of` to tell who holds each one
+///
+/// The mint authority weighs most, since it can dilute every holder; the freeze authority can lock holders
+/// out of selling; a mutable metadata account can rename the token after it was bought. Metadata counts only
+/// when the token has any.
+pub fn assess_control(
+    metadata: &TokenMetadataInternal,
+    kind_of: impl Fn(&str) -> AuthorityKind,
+) -> TokenControl {
+    let authority = |address: &Option<String>| {
+        address.as_ref().map(|address| TokenAuthority {
+            address: address.clone(),
+            kind: kind_of(address),
+        })
+    };
+    let mint_authority = authority(&metadata.mint_authority);
+    let freeze_authority = authority(&metadata.freeze_authority);
+    let update_authority = authority(&metadata.update_authority);
+
+    let factors = vec![
+        authority_factor(
+            "mint_authority",
+            "Mint authority",
+            0.5,
+            mint_authority.as_ref(),
+            "increase the supply at will",
+        ),
+        authority_factor(
+            "freeze_authority",
+            "Freeze authority",
+            0.3,
+            freeze_authority.as_ref(),
+            "freeze any holder's tokens",
+        ),
+        match metadata.is_mutable {
+            Some(true) => authority_factor(
+                "metadata_mutability",
+                "Update authority",
+                0.2,
+                update_authority.as_ref(),
+                "change the token's name, symbol and links",
+            ),
+            Some(false) => ControlFactor {
+                name: "metadata_mutability".to_owned(),
+                risk: Some(0.0),
+                weight: 0.2,
+                explanation: "Metadata is immutable".to_owned(),
+            },
+            None => ControlFactor {
+                name: "metadata_mutability".to_owned(),
+                risk: None,
+                weight: 0.2,
+                explanation: "Not available".to_owned(),
+            },
+        },
+    ];
+
+    let coverage = factors
+        .iter()
+        .filter(|factor| factor.risk.is_some())
+        .map(|factor| factor.weight)
+        .sum::<f64>();
+    let score = factors
+        .iter()
+        .filter_map(|factor| factor.risk.map(|risk| factor.weight * risk / coverage))
+        .sum::<f64>()
+        .clamp(0.0, 1.0);
+
+    TokenControl {
+        mint_authority,
+        freeze_authority,
+        update_authority,
+        metadata_mutable: metadata.is_mutable,
+        score,
+        factors,
+    }
+}
+
+/// How far holders are exposed to whoever holds an authority: a single key most, a program least
+fn authority_risk(kind: &AuthorityKind) -> f64 {
+    match kind {
+        AuthorityKind::Wallet => 1.0,
+        // A 1-of-n multisig is as good as a single key
+        AuthorityKind::Multisig {
+            threshold, ..
+        } if *threshold <= 1 => 1.0,
+        AuthorityKind::Multisig {
+            ..
+        } => 0.5,
+        AuthorityKind::ProgramDerived => 0.6,
+        AuthorityKind::Program => 0.4,
+    }
+}
+
+fn authority_factor(
+    name: &str,
+    label: &str,
+    weight: f64,
+    authority: Option<&TokenAuthority>,
+    power: &str,
+) -> ControlFactor {
+    let (risk, explanation) = match authority {
+        None => (0.0, format!("{label} is revoked")),
+        Some(authority) => {
+            let holder = match &authority.kind {
+                AuthorityKind::Wallet => "a single wallet".to_owned(),
+                AuthorityKind::Multisig {
+                    threshold,
+                    signers,
+                } => format!("a {threshold}-of-{signers} multisig"),
+                AuthorityKind::ProgramDerived => "a program-derived address".to_owned(),
+                AuthorityKind::Program => "a program".to_owned(),
+            };
+            (
+                authority_risk(&authority.kind),
+                format!("{label} {} is held by {holder}, which can {power}", authority.address),
+            )
+        },
+    };
+
+    ControlFactor {
+        name: name.to_owned(),
+        risk: Some(risk),
+        weight,
+        explanation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(
+        owner: Pubkey,
+        data: Vec<u8>,
+        executable: bool,
+    ) -> Account {
+        Account {
+            lamports: 1,
+            data,
+            owner,
+            executable,
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn test_authorities_are_classified() {
+        let wallet = Pubkey::new_from_array([1; 32]);
+        assert!(wallet.is_on_curve());
+        assert_eq!(classify_authority(&wallet, None), AuthorityKind::Wallet);
+
+        let mut multisig = vec![2, 3, 1];
+        multisig.resize(MULTISIG_LEN, 0);
+        let multisig = account(TOKEN_PROGRAM_ID, multisig, false);
+        assert_eq!(classify_authority(&wallet, Some(&multisig)), AuthorityKind::Multisig {
+            threshold: 2,
+            signers: 3,
+        });
+
+        let program = account(Pubkey::new_from_array([2; 32]), vec![], true);
+        assert_eq!(classify_authority(&wallet, Some(&program)), AuthorityKind::Program);
+
+        let (vault, _) = Pubkey::find_program_address(&[b"vault"], &Pubkey::new_from_array([3; 32]));
+        assert_eq!(classify_authority(&vault, None), AuthorityKind::ProgramDerived);
+    }
+
+    #[test]
+    fn test_control_score_weighs_each_authority() {
+        let mut metadata = TokenMetadataInternal::new("mint".to_owned(), "Example".to_owned(), "EXM".to_owned(), 6);
+        metadata.mint_authority = Some("Minter".to_owned());
+        metadata.freeze_authority = Some("Freezer".to_owned());
+
+        let control = assess_control(&metadata, |address| match address {
+            "Minter" => AuthorityKind::Wallet,
+            _ => AuthorityKind::Multisig {
+                threshold: 3,
+                signers: 5,
+            },
+        });
+        // Without metadata only the mint (0.5) and freeze (0.3) authorities are weighed
+        assert!((control.score - (0.5 * 1.0 + 0.3 * 0.5) / 0.8).abs() < 1e-9);
+        assert_eq!(
+            control.factors[1].explanation,
+            "Freeze authority Freezer is held by a 3-of-5 multisig, which can freeze any holder's tokens"
+        );
+        assert_eq!(control.factors[2].risk, None);
+        assert!(control.score >= CENTRALIZED_CONTROL);
+
+        // Revoked authorities and immutable metadata leave nothing to control
+        metadata.mint_authority = None;
+        metadata.freeze_authority = None;
+        metadata.update_authority = Some("Updater".to_owned());
+        metadata.is_mutable = Some(false);
+        let control = assess_control(&metadata, |_| AuthorityKind::Wallet);
+        assert_eq!(control.score, 0.0);
+        assert_eq!(control.factors[0].explanation, "Mint authority is revoked");
+        assert_eq!(control.update_authority.unwrap().kind, AuthorityKind::Wallet);
+    }
+}
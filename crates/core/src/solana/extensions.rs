@@ -0,0 +1,203 @@
+//! Token-2022 mint extensions.
+//!
+//! An extended mint is the 82-byte base mint, padding up to the 165-byte token account size, an
+//! account type byte, and then a list of type-length-value entries, one per extension.
+
+use solana_pubkey::Pubkey;
+
+use super::metadata::Reader;
+use super::metadata::TOKEN_ACCOUNT_LEN;
+use crate::models::TokenExtension;
+
+/// `AccountState::Frozen` in the default account state extension
+const FROZEN_ACCOUNT_STATE: u8 = 2;
+
+/// Every extension on a Token-2022 mint, in the order they are stored; empty for a mint without extensions
+pub fn decode_extensions(data: &[u8]) -> Result<Vec<TokenExtension>, String> {
+    let Some(entries) = data.get(TOKEN_ACCOUNT_LEN + 1..) else {
+        return Ok(Vec::new());
+    };
+
+    let mut reader = Reader::new(entries);
+    let mut extensions = Vec::new();
+    while !reader.is_empty() {
+        let extension_type = reader.u16()?;
+        // Unused space after the last entry is zeroed, which reads as the `Uninitialized` type
+        if extension_type == 0 {
+            break;
+        }
+        let len = reader.u16()?;
+        let value = reader.take(usize::from(len))?;
+        let extension =
+            decode_extension(extension_type, value).map_err(|e| format!("extension {extension_type}: {e}"))?;
+        extensions.push(extension);
+    }
+
+    Ok(extensions)
+}
+
+fn decode_extension(
+    extension_type: u16,
+    value: &[u8],
+) -> Result<TokenExtension, String> {
+    let mut reader = Reader::new(value);
+    let address = |pubkey: Option<Pubkey>| pubkey.map(|pubkey| pubkey.to_string());
+
+    let extension = match extension_type {
+        1 => {
+            let authority = reader.optional_pubkey()?;
+            reader.take(32 + 8)?; // Withdraw authority and withheld amount
+            reader.take(8 + 8 + 2)?; // Older fee: epoch, maximum fee, basis points
+            reader.u64()?; // Newer fee's epoch
+            let maximum_fee = reader.u64()?;
+            TokenExtension::TransferFeeConfig {
+                fee_basis_points: reader.u16()?,
+                maximum_fee,
+                authority: address(authority),
+            }
+        },
+        3 => TokenExtension::MintCloseAuthority {
+            authority: address(reader.optional_pubkey()?),
+        },
+        4 => TokenExtension::ConfidentialTransferMint {
+            authority: address(reader.optional_pubkey()?),
+            auto_approve_new_accounts: reader.bool()?,
+        },
+        6 => TokenExtension::DefaultAccountState {
+            frozen: reader.u8()? == FROZEN_ACCOUNT_STATE,
+        },
+        9 => TokenExtension::NonTransferable,
+        10 => {
+            let authority = reader.optional_pubkey()?;
+            reader.take(8 + 2 + 8)?; // Initialization timestamp, average rate before the last update, its timestamp
+            TokenExtension::InterestBearingConfig {
+                rate_bps: reader.i16()?,
+                authority: address(authority),
+            }
+        },
+        12 => TokenExtension::PermanentDelegate {
+            delegate: address(reader.optional_pubkey()?),
+        },
+        14 => {
+            let authority = reader.optional_pubkey()?;
+            TokenExtension::TransferHook {
+                program_id: address(reader.optional_pubkey()?),
+                authority: address(authority),
+            }
+        },
+        18 => {
+            let authority = reader.optional_pubkey()?;
+            TokenExtension::MetadataPointer {
+                metadata_address: address(reader.optional_pubkey()?),
+                authority: address(authority),
+            }
+        },
+        19 => {
+            let update_authority = reader.optional_pubkey()?;
+            reader.pubkey()?; // Mint
+            TokenExtension::Metadata {
+                name: reader.string()?,
+                symbol: reader.string()?,
+                uri: reader.string()?,
+                update_authority: address(update_authority),
+            }
+        },
+        25 => {
+            let authority = reader.optional_pubkey()?;
+            TokenExtension::ScaledUiAmount {
+                multiplier: reader.f64()?,
+                authority: address(authority),
+            }
+        },
+        26 => {
+            let authority = reader.optional_pubkey()?;
+            TokenExtension::Pausable {
+                paused: reader.bool()?,
+                authority: address(authority),
+            }
+        },
+        _ => TokenExtension::Other {
+            extension_type,
+        },
+    };
+
+    Ok(extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        extension_type: u16,
+        value: &[u8],
+    ) -> Vec<u8> {
+        let mut bytes = extension_type.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(value);
+        bytes
+    }
+
+    #[test]
+    fn test_mint_extensions_are_decoded() {
+        let authority = Pubkey::new_from_array([7; 32]);
+        let hook = Pubkey::new_from_array([8; 32]);
+
+        let mut mint = vec![0; TOKEN_ACCOUNT_LEN];
+        mint.push(1);
+
+        let mut interest = authority.to_bytes().to_vec();
+        interest.extend_from_slice(&[0; 18]);
+        interest.extend_from_slice(&500_i16.to_le_bytes());
+        mint.extend(entry(10, &interest));
+
+        let mut fees = authority.to_bytes().to_vec();
+        fees.extend_from_slice(&[0; 32 + 8 + 18 + 8]);
+        fees.extend_from_slice(&1_000_000_u64.to_le_bytes());
+        fees.extend_from_slice(&250_u16.to_le_bytes());
+        mint.extend(entry(1, &fees));
+
+        mint.extend(entry(12, authority.as_ref()));
+        let mut transfer_hook = [0; 32].to_vec();
+        transfer_hook.extend_from_slice(hook.as_ref());
+        mint.extend(entry(14, &transfer_hook));
+        mint.extend(entry(6, &[FROZEN_ACCOUNT_STATE]));
+        mint.extend(entry(9, &[]));
+        mint.extend(entry(21, &[0; 80]));
+        // Zeroed space left for extensions added later
+        mint.extend_from_slice(&[0; 12]);
+
+        let extensions = decode_extensions(&mint).unwrap();
+        assert_eq!(extensions, vec![
+            TokenExtension::InterestBearingConfig {
+                rate_bps: 500,
+                authority: Some(authority.to_string()),
+            },
+            TokenExtension::TransferFeeConfig {
+                fee_basis_points: 250,
+                maximum_fee: 1_000_000,
+                authority: Some(authority.to_string()),
+            },
+            TokenExtension::PermanentDelegate {
+                delegate: Some(authority.to_string()),
+            },
+            TokenExtension::TransferHook {
+                program_id: Some(hook.to_string()),
+                authority: None,
+            },
+            TokenExtension::DefaultAccountState {
+                frozen: true,
+            },
+            TokenExtension::NonTransferable,
+            TokenExtension::Other {
+                extension_type: 21,
+            },
+        ]);
+
+        // A base-size mint has no extensions, and a truncated entry is an error
+        assert!(decode_extensions(&mint[..82]).unwrap().is_empty());
+        let mut truncated = mint[..=TOKEN_ACCOUNT_LEN].to_vec();
+        truncated.extend_from_slice(&entry(12, authority.as_ref())[..20]);
+        assert!(decode_extensions(&truncated).is_err());
+    }
+}
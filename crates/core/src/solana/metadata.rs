@@ -0,0 +1,653 @@
+//! Token metadata read from the chain: the mint account, the Metaplex Token Metadata account derived
+//! from it, and the off-chain JSON document that account points to.
+//!
+//! Accounts are decoded by hand from their fixed layouts, so SPL Token and Token-2022 mints are
+//! read the same way. Token-2022 mints also carry their extensions, and their name and symbol when
+//! they keep metadata on the mint rather than in a Metaplex account.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde_json::Value;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_pubkey::Pubkey;
+use tracing::debug;
+use tracing::warn;
+
+use super::control::assess_control;
+use super::control::classify_authority;
+use super::extensions::decode_extensions;
+use super::rpc::rpc_client;
+use crate::http::HttpClient;
+use crate::models::AuthorityKind;
+use crate::models::SolanaError;
+use crate::models::SolanaTokenInfoInternal;
+use crate::models::TokenControl;
+use crate::models::TokenExtension;
+use crate::models::TokenMetadataInternal;
+use crate::models::TokenStandard;
+
+pub const TOKEN_PROGRAM_ID: Pubkey = Pubkey::from_str_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const METADATA_PROGRAM_ID: Pubkey = Pubkey::from_str_const("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Size of the base mint layout, shared by SPL Token and Token-2022
+pub const MINT_LEN: usize = 82;
+
+/// Size of a token account; Token-2022 puts its account type byte right after it
+pub(super) const TOKEN_ACCOUNT_LEN: usize = 165;
+const TOKEN_2022_MINT_TYPE: u8 = 1;
+
+/// First byte of a Metaplex `MetadataV1` account
+const METADATA_V1_KEY: u8 = 4;
+
+/// How long resolved metadata is served from the cache
+pub const METADATA_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+const MAX_OFF_CHAIN_BYTES: usize = 1024 * 1024;
+
+/// A mint account's base fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MintAccount {
+    pub mint_authority: Option<Pubkey>,
+    pub supply: u64, // In base units
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<Pubkey>,
+}
+
+/// A creator listed in Metaplex metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataCreator {
+    pub address: Pubkey,
+    pub verified: bool, // Signed by the creator, not just claimed by the update authority
+    pub share: u8,      // Percent of royalties
+}
+
+/// The leading fields of a Metaplex Token Metadata account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetaplexMetadata {
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Vec<MetadataCreator>,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+}
+
+/// What a token's off-chain JSON adds to the on-chain metadata
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OffChainMetadata {
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub website: Option<String>,
+    pub twitter: Option<String>,
+    pub telegram: Option<String>,
+    pub discord: Option<String>,
+}
+
+/// Resolves mint addresses to token metadata, caching what it found for [`METADATA_CACHE_TTL`]
+pub struct TokenMetadataResolver {
+    rpc_client: RpcClient,
+    http: HttpClient,
+    ttl: Duration,
+    cache: Arc<RwLock<HashMap<Pubkey, (Instant, SolanaTokenInfoInternal)>>>,
+}
+
+impl TokenMetadataResolver {
+    pub fn new(rpc_url: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        let http = HttpClient::from_env(client, "token_metadata");
+        Self::with_clients(rpc_client(http.clone(), rpc_url), http)
+    }
+
+    /// Read the chain with `rpc_client` and fetch off-chain JSON with `http`
+    pub fn with_clients(
+        rpc_client: RpcClient,
+        http: HttpClient,
+    ) -> Self {
+        Self {
+            rpc_client,
+            http,
+            ttl: METADATA_CACHE_TTL,
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Override how long resolved metadata is cached
+    pub fn with_ttl(
+        mut self,
+        ttl: Duration,
+    ) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Mint and Metaplex metadata of a token, completed from its off-chain JSON when that can be fetched and
+    /// scored by who controls it
+    pub async fn resolve(
+        &self,
+        mint: &Pubkey,
+    ) -> Result<SolanaTokenInfoInternal, SolanaError> {
+        if let Some(cached) = self.cached(mint) {
+            debug!("Token metadata cache hit for {}", mint);
+            return Ok(cached);
+        }
+
+        let metadata_address = metadata_address(mint);
+        let accounts = self
+            .rpc_client
+            .get_multiple_accounts(&[*mint, metadata_address])
+            .await
+            .map_err(|e| SolanaError::RpcError(e.to_string()))?;
+        let mut accounts = accounts.into_iter();
+        let mint_account = accounts
+            .next()
+            .flatten()
+            .ok_or_else(|| SolanaError::TokenNotFound(mint.to_string()))?;
+
+        let token_standard = if mint_account.owner == TOKEN_PROGRAM_ID {
+            TokenStandard::SPL
+        } else if mint_account.owner == TOKEN_2022_PROGRAM_ID {
+            TokenStandard::Token2022
+        } else {
+            return Err(SolanaError::InvalidAddress(format!("{mint} is not owned by a token program")));
+        };
+        if !is_mint_layout(&mint_account.owner, &mint_account.data) {
+            return Err(SolanaError::InvalidAddress(format!("{mint} is not a mint account")));
+        }
+        let base = decode_mint(&mint_account.data).map_err(SolanaError::InvalidAccountData)?;
+
+        // Tokens without a metadata account keep the placeholder name, as before
+        let mut metadata =
+            TokenMetadataInternal::new(mint.to_string(), "Unknown Token".to_owned(), "UNK".to_owned(), base.decimals);
+        metadata.total_supply = Some(base.supply);
+        metadata.mint_authority = base.mint_authority.map(|authority| authority.to_string());
+        metadata.freeze_authority = base.freeze_authority.map(|authority| authority.to_string());
+        if matches!(token_standard, TokenStandard::Token2022) {
+            metadata.extensions = decode_extensions(&mint_account.data).map_err(SolanaError::InvalidAccountData)?;
+        }
+        metadata.token_standard = token_standard;
+
+        let on_chain = accounts
+            .next()
+            .flatten()
+            .filter(|account| account.owner == METADATA_PROGRAM_ID)
+            .map(|account| decode_metadata(&account.data))
+            .transpose()
+            .unwrap_or_else(|e| {
+                warn!("Ignoring undecodable metadata account {} of {}: {}", metadata_address, mint, e);
+                None
+            })
+            .filter(|on_chain| on_chain.mint == *mint);
+        let embedded = metadata.extensions.iter().find_map(|extension| match extension {
+            TokenExtension::Metadata {
+                name,
+                symbol,
+                uri,
+                update_authority,
+            } => Some((name.clone(), symbol.clone(), uri.clone(), update_authority.clone())),
+            _ => None,
+        });
+        if let Some(on_chain) = on_chain {
+            metadata.update_authority = Some(on_chain.update_authority.to_string());
+            metadata.is_mutable = Some(on_chain.is_mutable);
+            metadata.creators = on_chain
+                .creators
+                .iter()
+                .filter(|creator| creator.verified)
+                .map(|creator| creator.address.to_string())
+                .collect();
+            self.describe(&mut metadata, on_chain.name, on_chain.symbol, on_chain.uri).await;
+        } else if let Some((name, symbol, uri, update_authority)) = embedded {
+            // Token-2022 metadata kept on the mint itself can change for as long as it has an update authority
+            metadata.is_mutable = Some(update_authority.is_some());
+            metadata.update_authority = update_authority;
+            self.describe(&mut metadata, name, symbol, uri).await;
+        }
+
+        let control = self.control(&metadata).await;
+        let resolved = SolanaTokenInfoInternal {
+            risk_score: Some(control.score),
+            control: Some(control),
+            ..SolanaTokenInfoInternal::from_metadata(metadata)
+        };
+        if let Ok(mut cache) = self.cache.write() {
+            cache.insert(*mint, (Instant::now(), resolved.clone()));
+        }
+        Ok(resolved)
+    }
+
+    /// Name and symbol from on-chain metadata, then whatever its off-chain JSON adds
+    async fn describe(
+        &self,
+        metadata: &mut TokenMetadataInternal,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) {
+        if !name.is_empty() {
+            metadata.name = name;
+        }
+        if !symbol.is_empty() {
+            metadata.symbol = symbol;
+        }
+        if !uri.is_empty() {
+            if let Some(off_chain) = self.off_chain(&uri).await {
+                apply_off_chain(metadata, off_chain);
+            }
+            metadata.metadata_uri = Some(uri);
+        }
+    }
+
+    /// Who holds the token's authorities; when their accounts cannot be read, holders are told apart by
+    /// address alone, which takes a multisig for a wallet and so errs towards more risk
+    async fn control(
+        &self,
+        metadata: &TokenMetadataInternal,
+    ) -> TokenControl {
+        let mut addresses = [&metadata.mint_authority, &metadata.freeze_authority, &metadata.update_authority]
+            .into_iter()
+            .flatten()
+            .filter_map(|address| address.parse::<Pubkey>().ok())
+            .collect::<Vec<_>>();
+        addresses.sort();
+        addresses.dedup();
+
+        let accounts = if addresses.is_empty() {
+            Vec::new()
+        } else {
+            self.rpc_client.get_multiple_accounts(&addresses).await.unwrap_or_else(|e| {
+                warn!("Failed to read authority accounts of {}: {}", metadata.mint_address, e);
+                Vec::new()
+            })
+        };
+        let kinds = addresses
+            .iter()
+            .enumerate()
+            .map(|(index, address)| {
+                let account = accounts.get(index).and_then(Option::as_ref);
+                (address.to_string(), classify_authority(address, account))
+            })
+            .collect::<HashMap<_, _>>();
+
+        assess_control(metadata, |address| kinds.get(address).cloned().unwrap_or(AuthorityKind::Wallet))
+    }
+
+    fn cached(
+        &self,
+        mint: &Pubkey,
+    ) -> Option<SolanaTokenInfoInternal> {
+        let cache = self.cache.read().ok()?;
+        let (resolved_at, resolved) = cache.get(mint)?;
+        (resolved_at.elapsed() < self.ttl).then(|| resolved.clone())
+    }
+
+    /// The off-chain JSON; a token still resolves without it, so failures are only logged
+    async fn off_chain(
+        &self,
+        uri: &str,
+    ) -> Option<OffChainMetadata> {
+        let url = gateway_url(uri)?;
+        // The URI is whatever the update authority wrote, so it may point inside our own network
+        let response = match self
+            .http
+            .get_untrusted(&url, MAX_OFF_CHAIN_BYTES)
+            .await
+            .and_then(|r| r.error_for_status())
+        {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Failed to fetch off-chain token metadata {}: {}", url, e);
+                return None;
+            },
+        };
+
+        match response.json::<Value>() {
+            Ok(json) => Some(parse_off_chain(&json)),
+            Err(e) => {
+                warn!("Off-chain token metadata {} is not JSON: {}", url, e);
+                None
+            },
+        }
+    }
+}
+
+/// Address of a mint's Metaplex metadata account: the PDA of `["metadata", program, mint]`
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    let seeds: &[&[u8]] = &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()];
+    Pubkey::find_program_address(seeds, &METADATA_PROGRAM_ID).0
+}
+
+/// Whether account data owned by a token program is a mint rather than a token account
+pub fn is_mint_layout(
+    program: &Pubkey,
+    data: &[u8],
+) -> bool {
+    if *program == TOKEN_2022_PROGRAM_ID && data.len() > TOKEN_ACCOUNT_LEN {
+        // Extended Token-2022 accounts are padded to the token account size and then typed
+        return data[TOKEN_ACCOUNT_LEN] == TOKEN_2022_MINT_TYPE;
+    }
+    data.len() == MINT_LEN
+}
+
+/// Decode the base mint layout: authorities are `COption`s with a four-byte tag
+pub fn decode_mint(data: &[u8]) -> Result<MintAccount, String> {
+    let mut reader = Reader::new(data);
+    let mint = MintAccount {
+        mint_authority: reader.coption_pubkey()?,
+        supply: reader.u64()?,
+        decimals: reader.u8()?,
+        is_initialized: reader.bool()?,
+        freeze_authority: reader.coption_pubkey()?,
+    };
+    if !mint.is_initialized {
+        return Err("mint is not initialized".to_owned());
+    }
+    Ok(mint)
+}
+
+/// Decode the Borsh-encoded fields of a `MetadataV1` account up to `is_mutable`
+///
+/// Names, symbols and URIs are stored padded with NULs, which are trimmed.
+pub fn decode_metadata(data: &[u8]) -> Result<MetaplexMetadata, String> {
+    let mut reader = Reader::new(data);
+    let key = reader.u8()?;
+    if key != METADATA_V1_KEY {
+        return Err(format!("account key {key} is not MetadataV1"));
+    }
+
+    let update_authority = reader.pubkey()?;
+    let mint = reader.pubkey()?;
+    let name = reader.string()?;
+    let symbol = reader.string()?;
+    let uri = reader.string()?;
+    let seller_fee_basis_points = reader.u16()?;
+    let creators = if reader.bool()? {
+        (0..reader.u32()?)
+            .map(|_| {
+                Ok(MetadataCreator {
+                    address: reader.pubkey()?,
+                    verified: reader.bool()?,
+                    share: reader.u8()?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?
+    } else {
+        Vec::new()
+    };
+
+    Ok(MetaplexMetadata {
+        update_authority,
+        mint,
+        name,
+        symbol,
+        uri,
+        seller_fee_basis_points,
+        creators,
+        primary_sale_happened: reader.bool()?,
+        is_mutable: reader.bool()?,
+    })
+}
+
+/// Description, image and links from the JSON a metadata URI points to
+///
+/// Links are read from the top level, then from `extensions` and `links`, as token launchers differ.
+pub fn parse_off_chain(json: &Value) -> OffChainMetadata {
+    let text = |value: &Value| value.as_str().map(str::trim).filter(|text| !text.is_empty()).map(str::to_owned);
+    let link = |names: &[&str]| {
+        [json, &json["extensions"], &json["links"]]
+            .into_iter()
+            .flat_map(|object| names.iter().map(move |name| &object[*name]))
+            .find_map(text)
+    };
+
+    OffChainMetadata {
+        name: text(&json["name"]),
+        symbol: text(&json["symbol"]),
+        description: text(&json["description"]),
+        image: text(&json["image"]),
+        website: link(&["website", "external_url"]),
+        twitter: link(&["twitter"]),
+        telegram: link(&["telegram"]),
+        discord: link(&["discord"]),
+    }
+}
+
+/// An HTTP URL for a metadata URI, going through public gateways for IPFS and Arweave
+fn gateway_url(uri: &str) -> Option<String> {
+    let uri = uri.trim();
+    if let Some(path) = uri.strip_prefix("ipfs://") {
+        return Some(format!("https://ipfs.io/ipfs/{}", path.trim_start_matches("ipfs/")));
+    }
+    if let Some(path) = uri.strip_prefix("ar://") {
+        return Some(format!("https://arweave.net/{path}"));
+    }
+    (uri.starts_with("https://") || uri.starts_with("http://")).then(|| uri.to_owned())
+}
+
+/// On-chain name and symbol win; the JSON only fills what the chain leaves out
+fn apply_off_chain(
+    metadata: &mut TokenMetadataInternal,
+    off_chain: OffChainMetadata,
+) {
+    if metadata.name == "Unknown Token" {
+        if let Some(name) = off_chain.name {
+            metadata.name = name;
+        }
+    }
+    if metadata.symbol == "UNK" {
+        if let Some(symbol) = off_chain.symbol {
+            metadata.symbol = symbol;
+        }
+    }
+    metadata.description = off_chain.description;
+    metadata.logo_uri = off_chain.image;
+    metadata.website = off_chain.website;
+    metadata.twitter = off_chain.twitter;
+    metadata.telegram = off_chain.telegram;
+    metadata.discord = off_chain.discord;
+}
+
+/// Little-endian cursor over account data
+pub(super) struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+        }
+    }
+
+    pub(super) fn take(
+        &mut self,
+        len: usize,
+    ) -> Result<&'a [u8], String> {
+        let end = self.offset.checked_add(len).filter(|end| *end <= self.data.len());
+        let end = end.ok_or_else(|| format!("account data ends before byte {}", self.offset + len))?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    pub(super) fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    pub(super) fn bool(&mut self) -> Result<bool, String> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(format!("invalid bool {other} at byte {}", self.offset - 1)),
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    pub(super) fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    pub(super) fn i16(&mut self) -> Result<i16, String> {
+        Ok(i16::from_le_bytes(self.array()?))
+    }
+
+    pub(super) fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub(super) fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    pub(super) fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    pub(super) fn pubkey(&mut self) -> Result<Pubkey, String> {
+        Ok(Pubkey::new_from_array(self.array()?))
+    }
+
+    /// A Token-2022 optional pubkey, where all zeroes mean none
+    pub(super) fn optional_pubkey(&mut self) -> Result<Option<Pubkey>, String> {
+        let pubkey = self.pubkey()?;
+        Ok((pubkey != Pubkey::default()).then_some(pubkey))
+    }
+
+    pub(super) fn coption_pubkey(&mut self) -> Result<Option<Pubkey>, String> {
+        let tag = self.u32()?;
+        let pubkey = self.pubkey()?;
+        match tag {
+            0 => Ok(None),
+            1 => Ok(Some(pubkey)),
+            other => Err(format!("invalid COption tag {other}")),
+        }
+    }
+
+    pub(super) fn string(&mut self) -> Result<String, String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        Ok(String::from_utf8_lossy(bytes).trim_matches('\0').trim().to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(value: &str) -> Vec<u8> {
+        let mut bytes = (value.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(value.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_mint_and_metadata_accounts_are_decoded() {
+        let authority = Pubkey::new_from_array([7; 32]);
+        let mint_address = Pubkey::new_from_array([9; 32]);
+
+        let mut mint = Vec::new();
+        mint.extend_from_slice(&1_u32.to_le_bytes());
+        mint.extend_from_slice(authority.as_ref());
+        mint.extend_from_slice(&1_000_000_000_u64.to_le_bytes());
+        mint.extend_from_slice(&[6, 1]);
+        mint.extend_from_slice(&[0; 36]);
+        assert!(is_mint_layout(&TOKEN_PROGRAM_ID, &mint));
+        assert_eq!(decode_mint(&mint).unwrap(), MintAccount {
+            mint_authority: Some(authority),
+            supply: 1_000_000_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: None,
+        });
+        // A token account is not a mint, and Token-2022 mints with extensions are typed after byte 165
+        assert!(!is_mint_layout(&TOKEN_PROGRAM_ID, &[0; TOKEN_ACCOUNT_LEN]));
+        let mut extended = mint.clone();
+        extended.resize(TOKEN_ACCOUNT_LEN, 0);
+        extended.push(TOKEN_2022_MINT_TYPE);
+        assert!(is_mint_layout(&TOKEN_2022_PROGRAM_ID, &extended));
+        assert_eq!(decode_mint(&extended).unwrap().decimals, 6);
+
+        let mut metadata = vec![METADATA_V1_KEY];
+        metadata.extend_from_slice(authority.as_ref());
+        metadata.extend_from_slice(mint_address.as_ref());
+        metadata.extend(string("Halal Swap\0\0\0\0\0\0"));
+        metadata.extend(string("HSW\0\0\0\0\0"));
+        metadata.extend(string("ipfs://bafyhsw/metadata.json\0\0\0"));
+        metadata.extend_from_slice(&250_u16.to_le_bytes());
+        metadata.push(1);
+        metadata.extend_from_slice(&2_u32.to_le_bytes());
+        metadata.extend_from_slice(authority.as_ref());
+        metadata.extend_from_slice(&[1, 100]);
+        metadata.extend_from_slice(mint_address.as_ref());
+        metadata.extend_from_slice(&[0, 0]);
+        metadata.extend_from_slice(&[1, 0]);
+        // Later fields such as the edition nonce and collection are not read
+        metadata.extend_from_slice(&[1, 255, 0]);
+
+        let decoded = decode_metadata(&metadata).unwrap();
+        assert_eq!(decoded.name, "Halal Swap");
+        assert_eq!(decoded.symbol, "HSW");
+        assert_eq!(decoded.uri, "ipfs://bafyhsw/metadata.json");
+        assert_eq!(decoded.mint, mint_address);
+        assert_eq!(decoded.creators.len(), 2);
+        assert!(decoded.creators[0].verified && !decoded.creators[1].verified);
+        assert!(decoded.primary_sale_happened);
+        assert!(!decoded.is_mutable);
+
+        assert!(decode_metadata(&metadata[..40]).is_err());
+        assert_ne!(metadata_address(&mint_address), metadata_address(&authority));
+    }
+
+    #[test]
+    fn test_off_chain_json_fills_description_image_and_links() {
+        let json = serde_json::json!({
+            "name": "Halal Swap",
+            "symbol": "HSW",
+            "description": "Payments for halal merchants",
+            "image": "https://arweave.net/hsw.png",
+            "twitter": "https://x.com/halalswap",
+            "extensions": { "website": "https://halalswap.example", "telegram": " " },
+            "links": { "telegram": "https://t.me/halalswap" }
+        });
+        let off_chain = parse_off_chain(&json);
+        assert_eq!(off_chain.website.as_deref(), Some("https://halalswap.example"));
+        assert_eq!(off_chain.twitter.as_deref(), Some("https://x.com/halalswap"));
+        assert_eq!(off_chain.telegram.as_deref(), Some("https://t.me/halalswap"));
+        assert_eq!(off_chain.discord, None);
+
+        let mut metadata =
+            TokenMetadataInternal::new("mint".to_owned(), "Unknown Token".to_owned(), "HSW".to_owned(), 6);
+        apply_off_chain(&mut metadata, off_chain);
+        assert_eq!(metadata.name, "Halal Swap");
+        assert_eq!(metadata.description.as_deref(), Some("Payments for halal merchants"));
+        assert_eq!(metadata.logo_uri.as_deref(), Some("https://arweave.net/hsw.png"));
+
+        assert_eq!(gateway_url("ipfs://ipfs/bafyhsw").as_deref(), Some("https://ipfs.io/ipfs/bafyhsw"));
+        assert_eq!(gateway_url("ar://hsw").as_deref(), Some("https://arweave.net/hsw"));
+        assert_eq!(gateway_url("file:///etc/passwd"), None);
+    }
+}
@@ -1,53 +1,90 @@
+pub mod control;
+pub mod extensions;
+pub mod metadata;
+pub mod rpc;
+pub mod token_fetcher;
+
+pub use control::*;
+pub use extensions::*;
+pub use metadata::*;
+pub use rpc::*;
 use serde_json::Value;
-use solana_client::rpc_client::RpcClient;
 use solana_program::pubkey::Pubkey;
+pub use token_fetcher::*;
+
+use crate::http::HttpClient;
+use crate::http::HttpError;
+use crate::models::SolanaError;
+use crate::models::SolanaTokenInfo;
+use crate::models::TokenMetadataInternal;
+use crate::models::TokenPriceData;
+use crate::models::UniversalTokenInfo;
+
+const MAINNET_RPC_URL: &str = "https://api.mainnet-beta.solana.com";
 
 pub struct SolanaTokenAnalyzer {
-    rpc_client: RpcClient,
-    coingecko_client: reqwest::Client,
+    coingecko_client: HttpClient,
+    metadata_resolver: TokenMetadataResolver,
+}
+
+impl Default for SolanaTokenAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SolanaTokenAnalyzer {
     pub fn new() -> Self {
         Self {
-            rpc_client: RpcClient::new("https://api.mainnet-beta.solana.com"),
-            coingecko_client: reqwest::Client::new(),
+            coingecko_client: HttpClient::from_env(reqwest::Client::new(), "market_data"),
+            metadata_resolver: TokenMetadataResolver::new(MAINNET_RPC_URL),
         }
     }
 
+    /// Token metadata resolved from the chain, priced by its symbol when the market data API lists it
     pub async fn get_token_info(
         &self,
         mint_address: &str,
-    ) -> Result<TokenInfo, SolanaError> {
-        let mint_pubkey = mint_address.parse::<Pubkey>()?;
+    ) -> Result<UniversalTokenInfo, SolanaError> {
+        let mint_pubkey = mint_address
+            .parse::<Pubkey>()
+            .map_err(|e| SolanaError::InvalidAddress(format!("{mint_address}: {e}")))?;
+        let resolved = self.metadata_resolver.resolve(&mint_pubkey).await?;
 
-        // Get token supply
-        let supply = self.rpc_client.get_token_supply(&mint_pubkey)?;
+        // Get price from CoinGecko
+        let price = self.get_token_price(&resolved.metadata.symbol).await.unwrap_or(0.0);
+        let total_supply = resolved
+            .metadata
+            .total_supply
+            .map(|supply| supply as f64 / 10_f64.powi(i32::from(resolved.metadata.decimals)));
 
-        // Get metadata account
-        let metadata = self.get_token_metadata(&mint_pubkey).await?;
+        let mut token_info = SolanaTokenInfo::from_internal(resolved).to_universal();
+        token_info.price_data = Some(TokenPriceData {
+            price_usd: price,
+            price_change_24h: 0.0,
+            volume_24h: 0,
+            market_cap: 0,
+            total_supply,
+            last_updated: chrono::Utc::now().timestamp_millis() as u64,
+        });
+        Ok(token_info)
+    }
 
-        // Get price from CoinGecko
-        let price = self.get_token_price(&metadata.symbol).await.unwrap_or(0.0);
-
-        Ok(TokenInfo {
-            mint_address: mint_address.to_string(),
-            name: metadata.name,
-            symbol: metadata.symbol,
-            decimals: metadata.decimals,
-            total_supply: supply.ui_amount.unwrap_or(0.0),
-            current_price: price,
-            description: metadata.description,
-        })
+    /// Mint and Metaplex metadata of a token, see [`TokenMetadataResolver`]
+    pub async fn get_token_metadata(
+        &self,
+        mint: &Pubkey,
+    ) -> Result<TokenMetadataInternal, SolanaError> {
+        Ok(self.metadata_resolver.resolve(mint).await?.metadata)
     }
 
     async fn get_token_price(
         &self,
         symbol: &str,
-    ) -> Result<f64, reqwest::Error> {
+    ) -> Result<f64, HttpError> {
         let url =
             format!("https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies=usd", symbol.to_lowercase());
-        let response: Value = self.coingecko_client.get(&url).send().await?.json().await?;
+        let response: Value = self.coingecko_client.send(self.coingecko_client.get(&url)).await?.json()?;
 
         Ok(response[symbol.to_lowercase()]["usd"].as_f64().unwrap_or(0.0))
     }
@@ -0,0 +1,154 @@
+//! Solana JSON-RPC over [`HttpClient`], so RPC calls are recorded and replayed like every other request.
+
+use std::sync::RwLock;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use solana_client::client_error::ClientErrorKind;
+use solana_client::client_error::Result as ClientResult;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::RpcClientConfig;
+use solana_client::rpc_request::RpcError;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_request::RpcResponseErrorData;
+use solana_client::rpc_sender::RpcSender;
+use solana_client::rpc_sender::RpcTransportStats;
+
+use crate::http::HttpClient;
+use crate::http::MatchConfig;
+use crate::http::MatchOn;
+
+/// Every call carries the same id, so a recorded request body matches its replay
+const REQUEST_ID: u64 = 1;
+
+/// Sends each RPC call as one JSON-RPC POST through an [`HttpClient`]
+pub struct HttpRpcSender {
+    http: HttpClient,
+    url: String,
+    stats: RwLock<RpcTransportStats>,
+}
+
+impl HttpRpcSender {
+    /// Calls all go to the same URL, so replay tells them apart by body as well
+    pub fn new(
+        http: HttpClient,
+        url: &str,
+    ) -> Self {
+        Self {
+            http: http.with_match_config(MatchConfig {
+                match_on: vec![MatchOn::Method, MatchOn::Url, MatchOn::Body],
+                allow_repeats: false,
+            }),
+            url: url.to_owned(),
+            stats: RwLock::new(RpcTransportStats::default()),
+        }
+    }
+}
+
+#[async_trait]
+impl RpcSender for HttpRpcSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: Value,
+    ) -> ClientResult<Value> {
+        let started = Instant::now();
+        let body = request.build_request_json(REQUEST_ID, params);
+        let response = self.http.send(self.http.post(&self.url).json(&body)).await;
+        if let Ok(mut stats) = self.stats.write() {
+            stats.request_count += 1;
+            stats.elapsed_time += started.elapsed();
+        }
+
+        let mut json = response
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json::<Value>())
+            .map_err(|e| ClientErrorKind::Custom(e.to_string()))?;
+        if json["error"].is_object() {
+            return Err(RpcError::RpcResponseError {
+                code: json["error"]["code"].as_i64().unwrap_or_default(),
+                message: json["error"]["message"].as_str().unwrap_or_default().to_owned(),
+                data: RpcResponseErrorData::Empty,
+            }
+            .into());
+        }
+        Ok(json["result"].take())
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.stats.read().map(|stats| stats.clone()).unwrap_or_default()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+}
+
+/// RPC client for `url` whose requests go through `http`
+pub fn rpc_client(
+    http: HttpClient,
+    url: &str,
+) -> RpcClient {
+    RpcClient::new_sender(HttpRpcSender::new(http, url), RpcClientConfig::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use solana_pubkey::Pubkey;
+
+    use super::*;
+    use crate::http::Cassette;
+    use crate::http::Interaction;
+    use crate::http::RecordedRequest;
+    use crate::http::RecordedResponse;
+    use crate::http::Scrubber;
+
+    #[tokio::test]
+    async fn test_rpc_calls_replay_from_a_cassette() {
+        let mint = Pubkey::new_unique();
+        let body = RpcRequest::GetMultipleAccounts
+            .build_request_json(
+                REQUEST_ID,
+                json!([
+                    [mint.to_string()],
+                    {"commitment": "finalized", "dataSlice": null, "encoding": "base64+zstd", "minContextSlot": null}
+                ]),
+            )
+            .to_string();
+        let mut cassette = Cassette::default();
+        for result in [
+            json!({"jsonrpc": "2.0", "id": 1, "result": {"context": {"slot": 7}, "value": [null]}}),
+            json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32005, "message": "Node is behind"}}),
+        ] {
+            cassette.record(Interaction {
+                request: RecordedRequest::new(
+                    "POST",
+                    "https://rpc.example/",
+                    Vec::new(),
+                    Some(body.as_bytes()),
+                    &Scrubber::default(),
+                ),
+                response: RecordedResponse::new(
+                    200,
+                    &Default::default(),
+                    result.to_string().as_bytes(),
+                    &Scrubber::default(),
+                ),
+            });
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let cassette_path = dir.path().join("rpc.json");
+        cassette.save(&cassette_path).unwrap();
+
+        let client = rpc_client(HttpClient::replaying(&cassette_path).unwrap(), "https://rpc.example/");
+        assert_eq!(client.get_multiple_accounts(&[mint]).await.unwrap(), vec![None]);
+        let behind = client.get_multiple_accounts(&[mint]).await.unwrap_err();
+        assert!(behind.to_string().contains("Node is behind"));
+
+        // A call the cassette never saw is a miss rather than a network request
+        let other = client.get_multiple_accounts(&[Pubkey::new_unique()]).await.unwrap_err();
+        assert!(other.to_string().contains("No recorded interaction"));
+    }
+}
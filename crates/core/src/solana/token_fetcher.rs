@@ -1,31 +1,53 @@
-use reqwest::Client;
 use serde_json::Value;
 
+use crate::http::HttpClient;
+
+/// A token as the market data API lists it
+#[derive(Debug, Clone)]
+pub struct MarketTokenInfo {
+    pub symbol: String,
+    pub name: String,
+    pub current_price: f64,
+    pub description: String,
+    pub total_supply: f64,
+    pub market_cap: f64,
+}
+
 pub struct TokenFetcher {
-    client: Client,
+    client: HttpClient,
+}
+
+impl Default for TokenFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TokenFetcher {
     pub fn new() -> Self {
+        Self::with_http_client(HttpClient::from_env(reqwest::Client::new(), "market_data"))
+    }
+
+    pub fn with_http_client(client: HttpClient) -> Self {
         Self {
-            client: Client::new(),
+            client,
         }
     }
 
     pub async fn get_token_info(
         &self,
         symbol: &str,
-    ) -> Result<TokenInfo, Box<dyn std::error::Error>> {
+    ) -> Result<MarketTokenInfo, Box<dyn std::error::Error>> {
         // Use CoinGecko free API (no key required)
         let url = format!("https://api.coingecko.com/api/v3/coins/{}", symbol.to_lowercase());
 
-        let response: Value = self.client.get(&url).send().await?.json().await?;
+        let response: Value = self.client.send(self.client.get(&url)).await?.json()?;
 
-        Ok(TokenInfo {
+        Ok(MarketTokenInfo {
             symbol: symbol.to_uppercase(),
-            name: response["name"].as_str().unwrap_or("Unknown").to_string(),
+            name: response["name"].as_str().unwrap_or("Unknown").to_owned(),
             current_price: response["market_data"]["current_price"]["usd"].as_f64().unwrap_or(0.0),
-            description: response["description"]["en"].as_str().unwrap_or("").to_string(),
+            description: response["description"]["en"].as_str().unwrap_or("").to_owned(),
             total_supply: response["market_data"]["total_supply"].as_f64().unwrap_or(0.0),
             market_cap: response["market_data"]["market_cap"]["usd"].as_f64().unwrap_or(0.0),
         })